@@ -0,0 +1,180 @@
+//! Shared setup for the protocol benchmarks.
+//!
+//! Each protocol has a corpus of messages recorded in the SAWP file format
+//! under `benches/corpus`, see the README there. Benchmarks report
+//! throughput in bytes and in messages per second.
+
+// Not every benchmark uses every helper
+#![allow(dead_code)]
+
+use criterion::{black_box, Criterion, Throughput};
+use sawp::error::{Error, ErrorKind};
+use sawp::parser::{Direction, Parse};
+use sawp_file::format::{self, Call, Reader};
+use std::fs::File;
+
+/// Size of the segments fed to the stream reassembly benchmark
+pub const SEGMENT_SIZE: usize = 64;
+
+/// Recorded calls to `parse` for one protocol.
+pub struct Corpus {
+    pub calls: Vec<(Direction, Vec<u8>)>,
+}
+
+impl Corpus {
+    /// Loads `benches/corpus/<name>.sawp`.
+    pub fn load(name: &str) -> Self {
+        let path = format!(
+            "{}/benches/corpus/{}.sawp",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let file = File::open(&path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+        let reader = Reader::new(file).unwrap_or_else(|err| panic!("{}: {:?}", path, err));
+        let calls = reader
+            .filter_map(|call| match call {
+                Call::Parse(data) => Some((direction(data.direction), data.data)),
+                Call::Gap(_) => None,
+            })
+            .collect();
+        Corpus { calls }
+    }
+
+    /// Total size of the calls in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.calls.iter().map(|(_, data)| data.len() as u64).sum()
+    }
+
+    /// Data of the calls in `direction` as one stream.
+    pub fn stream(&self, direction: &Direction) -> Vec<u8> {
+        self.calls
+            .iter()
+            .filter(|(d, _)| d == direction)
+            .flat_map(|(_, data)| data.iter().copied())
+            .collect()
+    }
+}
+
+fn direction(direction: format::Direction) -> Direction {
+    match direction {
+        format::Direction::ToServer => Direction::ToServer,
+        format::Direction::ToClient => Direction::ToClient,
+        format::Direction::Unknown => Direction::Unknown,
+    }
+}
+
+/// Parses each call as an input of its own and returns the number of
+/// messages.
+fn parse_calls<P>(parser: &P, corpus: &Corpus) -> u64
+where
+    P: for<'a> Parse<'a>,
+{
+    let mut messages = 0;
+    for (direction, data) in &corpus.calls {
+        let mut input = black_box(data.as_slice());
+        while !input.is_empty() {
+            match parser.parse(input, direction.clone()) {
+                Ok((rest, message)) => {
+                    if black_box(message).is_some() {
+                        messages += 1;
+                    }
+                    input = rest;
+                }
+                Err(err) => panic!("{:?} parsing {:?}", err, data),
+            }
+        }
+    }
+    messages
+}
+
+/// Feeds `stream` to the parser in segments of [`SEGMENT_SIZE`], buffering
+/// incomplete messages until the next segment arrives, and returns the number
+/// of messages.
+fn parse_stream<P>(parser: &P, stream: &[u8], direction: &Direction) -> u64
+where
+    P: for<'a> Parse<'a>,
+{
+    let mut messages = 0;
+    let mut buffer = Vec::with_capacity(SEGMENT_SIZE * 2);
+    for segment in stream.chunks(SEGMENT_SIZE) {
+        buffer.extend_from_slice(black_box(segment));
+        let mut consumed = 0;
+        while consumed < buffer.len() {
+            match parser.parse(&buffer[consumed..], direction.clone()) {
+                Ok((rest, message)) => {
+                    if black_box(message).is_some() {
+                        messages += 1;
+                    }
+                    consumed = buffer.len() - rest.len();
+                }
+                Err(Error {
+                    kind: ErrorKind::Incomplete(_),
+                }) => break,
+                Err(err) => panic!("{:?} at offset {}", err, consumed),
+            }
+        }
+        buffer.drain(..consumed);
+    }
+    assert!(buffer.is_empty(), "{} bytes left over", buffer.len());
+    messages
+}
+
+fn bench<F>(c: &mut Criterion, name: &str, id: &str, bytes: u64, messages: u64, mut f: F)
+where
+    F: FnMut() -> u64,
+{
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_function(format!("{}/bytes", id), |b| b.iter(&mut f));
+    group.throughput(Throughput::Elements(messages));
+    group.bench_function(format!("{}/messages", id), |b| b.iter(&mut f));
+    group.finish();
+}
+
+/// Benchmarks parsing every call of the corpus as a complete input, the way
+/// datagrams are parsed.
+pub fn bench_parse<P>(c: &mut Criterion, name: &str, new: fn() -> P, corpus: &Corpus)
+where
+    P: for<'a> Parse<'a>,
+{
+    let messages = parse_calls(&new(), corpus);
+    assert_eq!(messages, corpus.calls.len() as u64);
+
+    bench(c, name, "parse", corpus.bytes(), messages, || {
+        parse_calls(&new(), corpus)
+    });
+}
+
+/// Benchmarks the stream reassembly path: the calls in each of `directions`
+/// are concatenated and fed to a parser in segments of [`SEGMENT_SIZE`].
+pub fn bench_stream<P>(
+    c: &mut Criterion,
+    name: &str,
+    new: fn() -> P,
+    corpus: &Corpus,
+    directions: &[Direction],
+) where
+    P: for<'a> Parse<'a>,
+{
+    let streams: Vec<(Direction, Vec<u8>)> = directions
+        .iter()
+        .map(|direction| (direction.clone(), corpus.stream(direction)))
+        .collect();
+
+    // Reassembly must give the same messages as parsing each call
+    let mut messages = 0;
+    for (direction, stream) in &streams {
+        let expected = corpus.calls.iter().filter(|(d, _)| d == direction).count();
+        assert_ne!(expected, 0, "no calls in {:?}", direction);
+        assert_eq!(parse_stream(&new(), stream, direction), expected as u64);
+        messages += expected as u64;
+    }
+    let bytes = streams.iter().map(|(_, stream)| stream.len() as u64).sum();
+
+    bench(c, name, "stream", bytes, messages, || {
+        streams
+            .iter()
+            .map(|(direction, stream)| parse_stream(&new(), stream, direction))
+            .sum()
+    });
+}
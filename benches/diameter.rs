@@ -0,0 +1,20 @@
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sawp::parser::Direction;
+use sawp_diameter::Diameter;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let corpus = common::Corpus::load("diameter");
+    common::bench_parse(c, "diameter", Diameter::default, &corpus);
+    common::bench_stream(
+        c,
+        "diameter",
+        Diameter::default,
+        &corpus,
+        &[Direction::Unknown, Direction::ToServer, Direction::ToClient],
+    );
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,12 @@
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sawp_gre::Gre;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let corpus = common::Corpus::load("gre");
+    common::bench_parse(c, "gre", Gre::default, &corpus);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,12 +1,19 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+mod common;
 
-// TODO: benchmark modbus protocol parsing
-fn modbus(n: u64) -> bool {
-    n == 20
-}
+use criterion::{criterion_group, criterion_main, Criterion};
+use sawp::parser::Direction;
+use sawp_modbus::Modbus;
 
 fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("modbus", |b| b.iter(|| modbus(black_box(20))));
+    let corpus = common::Corpus::load("modbus");
+    common::bench_parse(c, "modbus", Modbus::default, &corpus);
+    common::bench_stream(
+        c,
+        "modbus",
+        Modbus::default,
+        &corpus,
+        &[Direction::Unknown, Direction::ToServer, Direction::ToClient],
+    );
 }
 
 criterion_group!(benches, criterion_benchmark);
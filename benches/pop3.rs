@@ -0,0 +1,14 @@
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sawp::parser::Direction;
+use sawp_pop3::POP3;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let corpus = common::Corpus::load("pop3");
+    common::bench_parse(c, "pop3", POP3::default, &corpus);
+    common::bench_stream(c, "pop3", POP3::default, &corpus, &[Direction::ToServer]);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
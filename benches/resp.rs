@@ -0,0 +1,20 @@
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sawp::parser::Direction;
+use sawp_resp::Resp;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let corpus = common::Corpus::load("resp");
+    common::bench_parse(c, "resp", Resp::default, &corpus);
+    common::bench_stream(
+        c,
+        "resp",
+        Resp::default,
+        &corpus,
+        &[Direction::Unknown, Direction::ToServer, Direction::ToClient],
+    );
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
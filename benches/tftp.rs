@@ -0,0 +1,12 @@
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sawp_tftp::TFTP;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let corpus = common::Corpus::load("tftp");
+    common::bench_parse(c, "tftp", TFTP::default, &corpus);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,12 +1,9 @@
 #![no_main]
 use libfuzzer_sys::fuzz_target;
 
-use sawp_diameter::Diameter;
-use sawp::parser::{Parse, Direction};
+use sawp_fuzz::targets::{self, DiameterFrame};
+use sawp_fuzz::Input;
 
-fuzz_target!(|data: &[u8]| {
-    let parser = Diameter {};
-    if let Err(e) = parser.parse(data, Direction::Unknown) {
-        eprintln!("Diameter: Error parsing {:?}", e);
-    }
+fuzz_target!(|input: Input<DiameterFrame>| {
+    targets::diameter(&input);
 });
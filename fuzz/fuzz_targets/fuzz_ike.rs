@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use sawp_fuzz::targets::{self, IkeFrame};
+use sawp_fuzz::Input;
+
+fuzz_target!(|input: Input<IkeFrame>| {
+    targets::ike(&input);
+});
@@ -1,12 +1,9 @@
 #![no_main]
 use libfuzzer_sys::fuzz_target;
 
-use sawp_modbus::Modbus;
-use sawp::parser::{Parse, Direction};
+use sawp_fuzz::targets::{self, ModbusFrame};
+use sawp_fuzz::Input;
 
-fuzz_target!(|data: &[u8]| {
-    let parser = Modbus::default();
-    if let Err(e) = parser.parse(data, Direction::Unknown) {
-        eprintln!("Modbus: Error parsing {:?}", e);
-    }
+fuzz_target!(|input: Input<ModbusFrame>| {
+    targets::modbus(&input);
 });
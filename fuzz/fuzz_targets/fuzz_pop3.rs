@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use sawp_fuzz::targets::{self, Pop3Frame};
+use sawp_fuzz::Input;
+
+fuzz_target!(|input: Input<Pop3Frame>| {
+    targets::pop3(&input);
+});
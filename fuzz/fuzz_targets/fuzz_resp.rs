@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use sawp_fuzz::targets::{self, RespFrame};
+use sawp_fuzz::Input;
+
+fuzz_target!(|input: Input<RespFrame>| {
+    targets::resp(&input);
+});
@@ -0,0 +1,268 @@
+//! Oracles and input generators shared by the fuzz targets.
+//!
+//! Every target parses its input with [`check_parse`] from `sawp::testing`,
+//! which checks the invariants of [`Parse`] on each call:
+//!
+//! - `Ok` consumes at least one byte and returns a suffix of its input.
+//! - `Incomplete` doesn't consume anything, parsing restarts from the same
+//!   bytes once more data is available.
+//!
+//! Stream protocols are also checked with [`check_split`]: feeding the input
+//! in two chunks, waiting for more data on `Incomplete`, must give the same
+//! messages as feeding it whole. Datagram protocols are excluded since they
+//! consume whatever input they are given, as are protocols which end messages
+//! at the end of the input (see the notes on each target).
+//!
+//! Protocols which can encode their messages are checked with
+//! [`check_round_trip`].
+//!
+//! Targets take an [`Input`], either raw bytes or frames of the protocol
+//! built by the generators in [`targets`], so the fuzzer can reach past
+//! length and count checks.
+
+use arbitrary::Arbitrary;
+
+use sawp::metrics::Flagged;
+use sawp::parser::{Direction, Parse};
+use sawp::protocol::Protocol;
+pub use sawp::testing::{check_parse, parse_stream, End, Stream};
+
+use std::fmt::Debug;
+
+pub mod targets;
+
+/// Direction of the fuzzed input.
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum FuzzDirection {
+    ToClient,
+    ToServer,
+    Unknown,
+}
+
+impl From<FuzzDirection> for Direction {
+    fn from(direction: FuzzDirection) -> Self {
+        match direction {
+            FuzzDirection::ToClient => Direction::ToClient,
+            FuzzDirection::ToServer => Direction::ToServer,
+            FuzzDirection::Unknown => Direction::Unknown,
+        }
+    }
+}
+
+/// Data of a fuzz target.
+#[derive(Arbitrary, Debug)]
+pub enum Data<F> {
+    Raw(Vec<u8>),
+    Frames(Vec<F>),
+}
+
+/// Input of a fuzz target.
+#[derive(Arbitrary, Debug)]
+pub struct Input<F> {
+    pub data: Data<F>,
+    pub direction: FuzzDirection,
+    /// Offset where the data is split in two chunks, modulo its length
+    pub split: usize,
+}
+
+/// Frames which can be written to their wire format.
+pub trait Frame {
+    fn write(&self, out: &mut Vec<u8>);
+}
+
+impl<F: Frame> Input<F> {
+    /// The data as bytes, frames are written one after another.
+    pub fn bytes(&self) -> Vec<u8> {
+        match &self.data {
+            Data::Raw(data) => data.clone(),
+            Data::Frames(frames) => {
+                let mut out = Vec::new();
+                for frame in frames {
+                    frame.write(&mut out);
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Checks that parsing `input` split at `split` gives the same messages as
+/// parsing it whole. Each stream uses a new parser from `new`.
+///
+/// Parsers may reject truncated input or return it with error flags, so the
+/// streams are only compared up to the first error or message with error
+/// flags. A message without error flags must be the same in both streams.
+pub fn check_split<P, M>(new: impl Fn() -> P, input: &[u8], split: usize, direction: Direction)
+where
+    P: for<'a> Parse<'a> + for<'a> Protocol<'a, Message = M>,
+    M: Debug + PartialEq + Flagged,
+{
+    let split = split % (input.len() + 1);
+    let whole = parse_stream(&new(), &[input], direction.clone());
+    let chunked = parse_stream(&new(), &[&input[..split], &input[split..]], direction);
+
+    for (whole_message, chunked_message) in whole.messages.iter().zip(&chunked.messages) {
+        if whole_message.1.error_flags() != 0 || chunked_message.1.error_flags() != 0 {
+            return;
+        }
+        assert_eq!(whole_message, chunked_message, "split at {}", split);
+    }
+    if matches!(chunked.end, End::Error(_)) || matches!(whole.end, End::Error(_)) {
+        return;
+    }
+    assert_eq!(whole, chunked, "split at {}", split);
+}
+
+/// Checks that `message` is parsed back from its encoding by `encode`.
+/// Whitespace may be left over, such as the delimiter ending a JSON number.
+pub fn check_round_trip<P, M>(parser: &P, message: &M, encode: impl Fn(&M) -> Vec<u8>)
+where
+    P: for<'a> Parse<'a> + for<'a> Protocol<'a, Message = M>,
+    M: Debug + PartialEq,
+{
+    let encoded = encode(message);
+    match check_parse(parser, &encoded, Direction::Unknown) {
+        Ok((rest, Some(decoded))) => {
+            assert!(
+                rest.iter().all(u8::is_ascii_whitespace),
+                "{} bytes left",
+                rest.len()
+            );
+            assert_eq!(&decoded, message);
+        }
+        result => panic!("{:?} encoded as {:?} gave {:?}", message, encoded, result),
+    }
+}
+
+/// Runs the oracles of a fuzz target over `input`.
+///
+/// - `new` creates the parser.
+/// - `stream` enables [`check_split`].
+pub fn fuzz<P, M, F>(input: &Input<F>, new: impl Fn() -> P, stream: bool)
+where
+    P: for<'a> Parse<'a> + for<'a> Protocol<'a, Message = M>,
+    M: Debug + PartialEq + Flagged,
+    F: Frame,
+{
+    let data = input.bytes();
+    let direction = Direction::from(input.direction);
+    if stream {
+        check_split(new, &data, input.split, direction);
+    } else {
+        let parser = new();
+        let mut rest = &data[..];
+        while let Ok((next, _)) = check_parse(&parser, rest, direction.clone()) {
+            rest = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbitrary::Unstructured;
+    use rstest::rstest;
+    use sawp::error::Error;
+
+    #[derive(Debug, PartialEq)]
+    struct Payload(Vec<u8>);
+
+    impl Flagged for Payload {
+        fn error_flags(&self) -> u64 {
+            0
+        }
+    }
+
+    /// Messages are a length byte followed by the payload. If `lenient`, a
+    /// truncated payload is returned as is instead of waiting for more data.
+    struct Test {
+        lenient: bool,
+    }
+
+    impl<'a> Protocol<'a> for Test {
+        type Message = Payload;
+
+        fn name() -> &'static str {
+            "fuzz_test"
+        }
+    }
+
+    impl<'a> Parse<'a> for Test {
+        fn parse(
+            &self,
+            input: &'a [u8],
+            _direction: Direction,
+        ) -> sawp::error::Result<(&'a [u8], Option<Payload>)> {
+            match input {
+                [] => Err(Error::incomplete_needed(1)),
+                [len, rest @ ..] if rest.len() < usize::from(*len) && self.lenient => {
+                    Ok((&[], Some(Payload(rest.to_vec()))))
+                }
+                [len, rest @ ..] if rest.len() < usize::from(*len) => {
+                    Err(Error::incomplete_needed(usize::from(*len) - rest.len()))
+                }
+                [len, rest @ ..] => {
+                    let (payload, rest) = rest.split_at(usize::from(*len));
+                    Ok((rest, Some(Payload(payload.to_vec()))))
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        check_split(
+            || Test { lenient: false },
+            b"\x01a\x02bc",
+            3,
+            Direction::Unknown,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "split at 3")]
+    fn test_split_lenient() {
+        check_split(
+            || Test { lenient: true },
+            b"\x01a\x02bc",
+            3,
+            Direction::Unknown,
+        );
+    }
+
+    /// Runs `target` over pseudo random inputs.
+    fn smoke<F>(target: fn(&Input<F>))
+    where
+        F: for<'a> Arbitrary<'a>,
+    {
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut bytes = vec![0u8; 4096];
+        for _ in 0..500 {
+            for byte in bytes.iter_mut() {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                *byte = seed as u8;
+            }
+            if let Ok(input) = Input::<F>::arbitrary(&mut Unstructured::new(&bytes)) {
+                target(&input);
+            }
+        }
+    }
+
+    #[rstest(
+        target,
+        case::diameter(|| smoke(targets::diameter)),
+        case::dns(|| smoke(targets::dns)),
+        case::gre(|| smoke(targets::gre)),
+        case::ike(|| smoke(targets::ike)),
+        case::json(|| smoke(targets::json)),
+        case::modbus(|| smoke(targets::modbus)),
+        case::pop3(|| smoke(targets::pop3)),
+        case::resp(|| smoke(targets::resp)),
+        case::tftp(|| smoke(targets::tftp))
+    )]
+    fn test_targets(target: fn()) {
+        target();
+    }
+}
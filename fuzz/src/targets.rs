@@ -0,0 +1,541 @@
+//! Frame generators and fuzz targets of each protocol.
+//!
+//! Generators fill in lengths, counts and terminators from their contents so
+//! the frames get past the framing checks of the parsers. Malformed framing is
+//! covered by the raw inputs.
+
+use arbitrary::Arbitrary;
+
+use crate::{check_parse, check_round_trip, fuzz, Frame, Input};
+
+use sawp::parser::Direction;
+
+/// Removes the bytes of `exclude` from `text`.
+fn clean(text: &str, exclude: &[char]) -> String {
+    text.chars().filter(|c| !exclude.contains(c)).collect()
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct DiameterAvp {
+    code: u32,
+    flags: u8,
+    vendor_id: u32,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct DiameterFrame {
+    version: u8,
+    flags: u8,
+    code: u32,
+    app_id: u32,
+    hop_id: u32,
+    end_id: u32,
+    avps: Vec<DiameterAvp>,
+}
+
+impl Frame for DiameterFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut avps = Vec::new();
+        for avp in &self.avps {
+            let vendor = avp.flags & 0x80 != 0;
+            let header = if vendor { 12 } else { 8 };
+            let data = &avp.data[..avp.data.len().min(0xff_ffff - header)];
+            avps.extend_from_slice(&avp.code.to_be_bytes());
+            avps.push(avp.flags);
+            avps.extend_from_slice(&((header + data.len()) as u32).to_be_bytes()[1..]);
+            if vendor {
+                avps.extend_from_slice(&avp.vendor_id.to_be_bytes());
+            }
+            avps.extend_from_slice(data);
+            avps.resize(avps.len() + (4 - data.len() % 4) % 4, 0);
+        }
+        out.push(self.version);
+        out.extend_from_slice(&((20 + avps.len()) as u32).to_be_bytes()[1..]);
+        out.push(self.flags);
+        out.extend_from_slice(&self.code.to_be_bytes()[1..]);
+        out.extend_from_slice(&self.app_id.to_be_bytes());
+        out.extend_from_slice(&self.hop_id.to_be_bytes());
+        out.extend_from_slice(&self.end_id.to_be_bytes());
+        out.extend_from_slice(&avps);
+    }
+}
+
+pub fn diameter(input: &Input<DiameterFrame>) {
+    fuzz(input, sawp_diameter::Diameter::default, true);
+}
+
+/// Domain name as labels, optionally ending with a compression pointer.
+#[derive(Arbitrary, Debug)]
+pub struct DnsName {
+    labels: Vec<Vec<u8>>,
+    pointer: Option<u16>,
+}
+
+impl DnsName {
+    fn write(&self, out: &mut Vec<u8>) {
+        for label in &self.labels {
+            let label = &label[..label.len().min(63)];
+            out.push(label.len() as u8);
+            out.extend_from_slice(label);
+        }
+        match self.pointer {
+            Some(pointer) => out.extend_from_slice(&(0xc000 | pointer).to_be_bytes()),
+            None => out.push(0),
+        }
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct DnsQuestion {
+    name: DnsName,
+    record_type: u16,
+    class: u16,
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct DnsRecord {
+    name: DnsName,
+    record_type: u16,
+    class: u16,
+    ttl: u32,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct DnsFrame {
+    id: u16,
+    flags: u16,
+    questions: Vec<DnsQuestion>,
+    answers: Vec<DnsRecord>,
+    authorities: Vec<DnsRecord>,
+    additionals: Vec<DnsRecord>,
+}
+
+impl Frame for DnsFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.id.to_be_bytes());
+        out.extend_from_slice(&self.flags.to_be_bytes());
+        for count in [
+            self.questions.len(),
+            self.answers.len(),
+            self.authorities.len(),
+            self.additionals.len(),
+        ] {
+            out.extend_from_slice(&(count as u16).to_be_bytes());
+        }
+        for question in &self.questions {
+            question.name.write(out);
+            out.extend_from_slice(&question.record_type.to_be_bytes());
+            out.extend_from_slice(&question.class.to_be_bytes());
+        }
+        for record in self
+            .answers
+            .iter()
+            .chain(&self.authorities)
+            .chain(&self.additionals)
+        {
+            let data = &record.data[..record.data.len().min(0xffff)];
+            record.name.write(out);
+            out.extend_from_slice(&record.record_type.to_be_bytes());
+            out.extend_from_slice(&record.class.to_be_bytes());
+            out.extend_from_slice(&record.ttl.to_be_bytes());
+            out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            out.extend_from_slice(data);
+        }
+    }
+}
+
+pub fn dns(input: &Input<DnsFrame>) {
+    fuzz(input, sawp_dns::Dns::default, true);
+    let tcp = || sawp_dns::Dns {
+        transport: sawp_dns::Transport::Tcp,
+        ..Default::default()
+    };
+    fuzz(input, tcp, true);
+    let domain_names = || sawp_dns::Dns {
+        domain_names: true,
+        ..Default::default()
+    };
+    fuzz(input, domain_names, true);
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct GreSourceRoute {
+    address_family: u16,
+    offset: u8,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct GreFrame {
+    flags: u16,
+    protocol_type: u16,
+    /// Checksum and offset, key, sequence and acknowledgement numbers,
+    /// written if the flags require them
+    fields: [u32; 4],
+    routing: Vec<GreSourceRoute>,
+    payload: Vec<u8>,
+}
+
+impl Frame for GreFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        let version = self.flags & 0x7;
+        out.extend_from_slice(&self.flags.to_be_bytes());
+        out.extend_from_slice(&self.protocol_type.to_be_bytes());
+        if self.flags & 0xc000 != 0 {
+            out.extend_from_slice(&self.fields[0].to_be_bytes());
+        }
+        if self.flags & 0x2000 != 0 {
+            out.extend_from_slice(&self.fields[1].to_be_bytes());
+        }
+        if self.flags & 0x1000 != 0 {
+            out.extend_from_slice(&self.fields[2].to_be_bytes());
+        }
+        if version == 1 && self.flags & 0x0080 != 0 {
+            out.extend_from_slice(&self.fields[3].to_be_bytes());
+        }
+        if self.flags & 0x4000 != 0 {
+            for route in &self.routing {
+                let data = &route.data[..route.data.len().min(0xff)];
+                out.extend_from_slice(&route.address_family.to_be_bytes());
+                out.push(route.offset);
+                out.push(data.len() as u8);
+                out.extend_from_slice(data);
+            }
+            out.extend_from_slice(&[0; 4]);
+        }
+        out.extend_from_slice(&self.payload);
+    }
+}
+
+/// Datagram protocol, each input is a single packet.
+pub fn gre(input: &Input<GreFrame>) {
+    fuzz(input, sawp_gre::Gre::default, false);
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct IkePayload {
+    payload_type: u8,
+    reserved: u8,
+    data: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct IkeFrame {
+    non_esp_marker: bool,
+    initiator_spi: u64,
+    responder_spi: u64,
+    /// Major version 1 or 2, followed by the minor version
+    v2: bool,
+    minor_version: u8,
+    exchange_type: u8,
+    flags: u8,
+    message_id: u32,
+    payloads: Vec<IkePayload>,
+}
+
+impl Frame for IkeFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        let mut payloads = Vec::new();
+        for (index, payload) in self.payloads.iter().enumerate() {
+            let data = &payload.data[..payload.data.len().min(0xffff - 4)];
+            let next = self
+                .payloads
+                .get(index + 1)
+                .map_or(0, |next| next.payload_type);
+            payloads.push(next);
+            payloads.push(payload.reserved);
+            payloads.extend_from_slice(&((4 + data.len()) as u16).to_be_bytes());
+            payloads.extend_from_slice(data);
+        }
+        if self.non_esp_marker {
+            out.extend_from_slice(&[0; 4]);
+        }
+        out.extend_from_slice(&self.initiator_spi.to_be_bytes());
+        out.extend_from_slice(&self.responder_spi.to_be_bytes());
+        out.push(self.payloads.first().map_or(0, |first| first.payload_type));
+        out.push(if self.v2 { 0x20 } else { 0x10 } | (self.minor_version & 0xf));
+        out.push(self.exchange_type);
+        out.push(self.flags);
+        out.extend_from_slice(&self.message_id.to_be_bytes());
+        out.extend_from_slice(&((28 + payloads.len()) as u32).to_be_bytes());
+        out.extend_from_slice(&payloads);
+    }
+}
+
+pub fn ike(input: &Input<IkeFrame>) {
+    fuzz(input, sawp_ike::Ike::default, true);
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum JsonFrame {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<JsonFrame>),
+    Object(Vec<(String, JsonFrame)>),
+}
+
+impl JsonFrame {
+    fn value(&self) -> serde_json::Value {
+        use serde_json::Value;
+
+        match self {
+            JsonFrame::Null => Value::Null,
+            JsonFrame::Bool(value) => Value::from(*value),
+            JsonFrame::Integer(value) => Value::from(*value),
+            JsonFrame::Float(value) => Value::from(*value),
+            JsonFrame::String(value) => Value::from(value.as_str()),
+            JsonFrame::Array(values) => values.iter().map(JsonFrame::value).collect(),
+            JsonFrame::Object(members) => Value::Object(
+                members
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.value()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl Frame for JsonFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        serde_json::to_writer(out, &self.value()).unwrap();
+    }
+}
+
+/// Parsed messages are also checked to round-trip through `serde_json`.
+pub fn json(input: &Input<JsonFrame>) {
+    fuzz(input, sawp_json::Json::default, true);
+
+    let parser = sawp_json::Json::default();
+    let data = input.bytes();
+    let mut rest = &data[..];
+    while let Ok((next, message)) = check_parse(&parser, rest, Direction::Unknown) {
+        if let Some(message) = message {
+            check_round_trip(&parser, &message, |message| {
+                // Numbers only end once followed by another character
+                let mut encoded = serde_json::to_vec(&message.value).unwrap();
+                encoded.push(b'\n');
+                encoded
+            });
+        }
+        rest = next;
+    }
+}
+
+#[derive(Arbitrary, Debug)]
+pub struct ModbusFrame {
+    transaction_id: u16,
+    protocol_id: u16,
+    unit_id: u8,
+    function: u8,
+    data: Vec<u8>,
+}
+
+impl Frame for ModbusFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        let data = &self.data[..self.data.len().min(0xffff - 2)];
+        out.extend_from_slice(&self.transaction_id.to_be_bytes());
+        out.extend_from_slice(&self.protocol_id.to_be_bytes());
+        out.extend_from_slice(&((2 + data.len()) as u16).to_be_bytes());
+        out.push(self.unit_id);
+        out.push(self.function);
+        out.extend_from_slice(data);
+    }
+}
+
+pub fn modbus(input: &Input<ModbusFrame>) {
+    fuzz(input, sawp_modbus::Modbus::default, true);
+    // RTU frames of unknown functions end with the input, they can't be split
+    let rtu = || sawp_modbus::Modbus {
+        framing: sawp_modbus::Framing::Rtu,
+        ..Default::default()
+    };
+    fuzz(input, rtu, false);
+    let ascii = || sawp_modbus::Modbus {
+        framing: sawp_modbus::Framing::Ascii,
+        ..Default::default()
+    };
+    fuzz(input, ascii, true);
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum Pop3Keyword {
+    Known(u8),
+    Other(String),
+}
+
+const POP3_KEYWORDS: &[&str] = &[
+    "STAT", "LIST", "RETR", "DELE", "NOOP", "RSET", "QUIT", "APOP", "TOP", "UIDL", "USER", "PASS",
+    "CAPA", "STLS", "AUTH", "SASL",
+];
+
+#[derive(Arbitrary, Debug)]
+pub enum Pop3Frame {
+    Command {
+        keyword: Pop3Keyword,
+        args: Vec<String>,
+    },
+    Response {
+        ok: bool,
+        header: String,
+        /// Lines of a multi-line response, dot-stuffed when written
+        lines: Option<Vec<String>>,
+    },
+}
+
+impl Frame for Pop3Frame {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Pop3Frame::Command { keyword, args } => {
+                let keyword = match keyword {
+                    Pop3Keyword::Known(index) => {
+                        POP3_KEYWORDS[usize::from(*index) % POP3_KEYWORDS.len()].to_string()
+                    }
+                    Pop3Keyword::Other(keyword) => clean(keyword, &[' ', '\r', '\n']),
+                };
+                out.extend_from_slice(keyword.as_bytes());
+                for arg in args {
+                    out.push(b' ');
+                    out.extend_from_slice(clean(arg, &[' ', '\r', '\n']).as_bytes());
+                }
+            }
+            Pop3Frame::Response { ok, header, lines } => {
+                out.extend_from_slice(if *ok { b"+OK " } else { b"-ERR " });
+                out.extend_from_slice(clean(header, &['\r', '\n']).as_bytes());
+                if let Some(lines) = lines {
+                    for line in lines {
+                        out.extend_from_slice(b"\r\n");
+                        if line.starts_with('.') {
+                            out.push(b'.');
+                        }
+                        out.extend_from_slice(clean(line, &['\r', '\n']).as_bytes());
+                    }
+                    out.extend_from_slice(b"\r\n.");
+                }
+            }
+        }
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+/// Single and multi-line responses can't be told apart without the command,
+/// a response is complete at the end of the input if it could be single
+/// line. Input isn't split since the result depends on where chunks end.
+pub fn pop3(input: &Input<Pop3Frame>) {
+    fuzz(input, sawp_pop3::POP3::default, false);
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum RespFrame {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<RespFrame>>),
+}
+
+impl Frame for RespFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            RespFrame::Simple(text) => {
+                out.push(b'+');
+                out.extend_from_slice(clean(text, &['\r', '\n']).as_bytes());
+            }
+            RespFrame::Error(text) => {
+                out.push(b'-');
+                out.extend_from_slice(clean(text, &['\r', '\n']).as_bytes());
+            }
+            RespFrame::Integer(value) => out.extend_from_slice(format!(":{}", value).as_bytes()),
+            RespFrame::Bulk(None) => out.extend_from_slice(b"$-1"),
+            RespFrame::Bulk(Some(data)) => {
+                out.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+                out.extend_from_slice(data);
+            }
+            RespFrame::Array(None) => out.extend_from_slice(b"*-1"),
+            RespFrame::Array(Some(values)) => {
+                out.extend_from_slice(format!("*{}", values.len()).as_bytes());
+                for value in values {
+                    out.extend_from_slice(b"\r\n");
+                    value.write(out);
+                }
+                return;
+            }
+        }
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+pub fn resp(input: &Input<RespFrame>) {
+    fuzz(input, sawp_resp::Resp::default, true);
+}
+
+#[derive(Arbitrary, Debug)]
+pub enum TftpFrame {
+    Request {
+        write: bool,
+        filename: String,
+        mode: String,
+        options: Vec<(String, String)>,
+    },
+    Data {
+        block: u16,
+        data: Vec<u8>,
+    },
+    Ack(u16),
+    Error {
+        code: u16,
+        message: String,
+    },
+    OptionAck(Vec<(String, String)>),
+}
+
+fn write_strings<'a>(out: &mut Vec<u8>, strings: impl IntoIterator<Item = &'a String>) {
+    for string in strings {
+        out.extend_from_slice(clean(string, &['\0']).as_bytes());
+        out.push(0);
+    }
+}
+
+impl Frame for TftpFrame {
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            TftpFrame::Request {
+                write,
+                filename,
+                mode,
+                options,
+            } => {
+                out.extend_from_slice(if *write { &[0, 2] } else { &[0, 1] });
+                write_strings(out, [filename, mode]);
+                write_strings(out, options.iter().flat_map(|(name, value)| [name, value]));
+            }
+            TftpFrame::Data { block, data } => {
+                out.extend_from_slice(&[0, 3]);
+                out.extend_from_slice(&block.to_be_bytes());
+                out.extend_from_slice(data);
+            }
+            TftpFrame::Ack(block) => {
+                out.extend_from_slice(&[0, 4]);
+                out.extend_from_slice(&block.to_be_bytes());
+            }
+            TftpFrame::Error { code, message } => {
+                out.extend_from_slice(&[0, 5]);
+                out.extend_from_slice(&code.to_be_bytes());
+                write_strings(out, [message]);
+            }
+            TftpFrame::OptionAck(options) => {
+                out.extend_from_slice(&[0, 6]);
+                write_strings(out, options.iter().flat_map(|(name, value)| [name, value]));
+            }
+        }
+    }
+}
+
+/// Datagram protocol, each input is a single packet.
+pub fn tftp(input: &Input<TftpFrame>) {
+    fuzz(input, sawp_tftp::TFTP::default, false);
+}
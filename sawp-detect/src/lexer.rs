@@ -0,0 +1,209 @@
+//! Splits a rule expression into tokens.
+
+use crate::Error;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    /// Field path or identifier, e.g. `dns.queries[*].name` or `WrMultCoils`
+    Word(String),
+    Integer(i128),
+    Float(f64),
+    String(Vec<u8>),
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Not,
+    LeftParen,
+    RightParen,
+}
+
+/// Returns the tokens of `input` along with their byte offset.
+pub fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, Error> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while position < bytes.len() {
+        let start = position;
+        let two = bytes.get(position..position + 2);
+        let token = match bytes[position] {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                position += 1;
+                continue;
+            }
+            _ if two == Some(b"==") => Token::Eq,
+            _ if two == Some(b"!=") => Token::Ne,
+            _ if two == Some(b"<=") => Token::Le,
+            _ if two == Some(b">=") => Token::Ge,
+            _ if two == Some(b"&&") => Token::And,
+            _ if two == Some(b"||") => Token::Or,
+            b'<' => Token::Lt,
+            b'>' => Token::Gt,
+            b'!' => Token::Not,
+            b'(' => Token::LeftParen,
+            b')' => Token::RightParen,
+            b'"' => {
+                let (string, end) = string(bytes, position)?;
+                position = end;
+                tokens.push((start, Token::String(string)));
+                continue;
+            }
+            b'0'..=b'9' | b'-' => {
+                let end = find_end(bytes, position + 1, |c| {
+                    c.is_ascii_alphanumeric() || c == b'.' || c == b'_'
+                });
+                tokens.push((start, number(&input[position..end], start)?));
+                position = end;
+                continue;
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b'[' => {
+                let end = find_end(bytes, position, |c| {
+                    c.is_ascii_alphanumeric() || b"_.[]*".contains(&c)
+                });
+                tokens.push((start, Token::Word(input[position..end].to_string())));
+                position = end;
+                continue;
+            }
+            _ => return Err(Error::UnexpectedCharacter(position)),
+        };
+        position += match token {
+            Token::Eq | Token::Ne | Token::Le | Token::Ge | Token::And | Token::Or => 2,
+            _ => 1,
+        };
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+fn find_end(bytes: &[u8], start: usize, accept: impl Fn(u8) -> bool) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&c| !accept(c))
+        .map_or(bytes.len(), |offset| start + offset)
+}
+
+fn number(input: &str, position: usize) -> Result<Token, Error> {
+    let (negative, digits) = match input.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, input),
+    };
+    let digits = digits.replace('_', "");
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16)
+    } else if digits.contains('.') {
+        return input
+            .replace('_', "")
+            .parse()
+            .map(Token::Float)
+            .map_err(|_| Error::InvalidNumber(position));
+    } else {
+        digits.parse()
+    };
+    value
+        .map(|value| Token::Integer(if negative { -value } else { value }))
+        .map_err(|_| Error::InvalidNumber(position))
+}
+
+/// Parses the string literal starting at `start` and returns its content and
+/// the position following the closing quote.
+fn string(bytes: &[u8], start: usize) -> Result<(Vec<u8>, usize), Error> {
+    let mut result = Vec::new();
+    let mut position = start + 1;
+    loop {
+        match bytes.get(position) {
+            None => return Err(Error::UnterminatedString(start)),
+            Some(b'"') => return Ok((result, position + 1)),
+            Some(b'\\') => {
+                let escaped = match bytes.get(position + 1) {
+                    Some(b'\\') => b'\\',
+                    Some(b'"') => b'"',
+                    Some(b'n') => b'\n',
+                    Some(b'r') => b'\r',
+                    Some(b't') => b'\t',
+                    Some(b'0') => b'\0',
+                    Some(b'x') => {
+                        let hex = bytes
+                            .get(position + 2..position + 4)
+                            .and_then(|hex| std::str::from_utf8(hex).ok())
+                            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                            .ok_or(Error::InvalidEscape(position))?;
+                        position += 2;
+                        hex
+                    }
+                    _ => return Err(Error::InvalidEscape(position)),
+                };
+                result.push(escaped);
+                position += 2;
+            }
+            Some(&c) => {
+                result.push(c);
+                position += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        input,
+        expected,
+        case::empty("", Ok(vec![])),
+        case::comparison(
+            "dns.queries[*].name == \"a\"",
+            Ok(vec![
+                (0, Token::Word("dns.queries[*].name".into())),
+                (20, Token::Eq),
+                (23, Token::String(b"a".to_vec())),
+            ])
+        ),
+        case::operators(
+            "!(a<=1||b>-2)&&c!=d",
+            Ok(vec![
+                (0, Token::Not),
+                (1, Token::LeftParen),
+                (2, Token::Word("a".into())),
+                (3, Token::Le),
+                (5, Token::Integer(1)),
+                (6, Token::Or),
+                (8, Token::Word("b".into())),
+                (9, Token::Gt),
+                (10, Token::Integer(-2)),
+                (12, Token::RightParen),
+                (13, Token::And),
+                (15, Token::Word("c".into())),
+                (16, Token::Ne),
+                (18, Token::Word("d".into())),
+            ])
+        ),
+        case::numbers(
+            "0x1F 1_000 1.5",
+            Ok(vec![
+                (0, Token::Integer(31)),
+                (5, Token::Integer(1000)),
+                (11, Token::Float(1.5)),
+            ])
+        ),
+        case::escapes(
+            r#""\"\\\x41\n""#,
+            Ok(vec![(0, Token::String(b"\"\\A\n".to_vec()))])
+        ),
+        case::unterminated("a == \"b", Err(Error::UnterminatedString(5))),
+        case::invalid_escape(r#""\q""#, Err(Error::InvalidEscape(1))),
+        case::invalid_hex_escape(r#""\xZZ""#, Err(Error::InvalidEscape(1))),
+        case::invalid_number("a == 12ab", Err(Error::InvalidNumber(5))),
+        case::unexpected("a = 1", Err(Error::UnexpectedCharacter(2)))
+    )]
+    fn test_tokenize(input: &str, expected: Result<Vec<(usize, Token)>, Error>) {
+        assert_eq!(tokenize(input), expected);
+    }
+}
@@ -0,0 +1,311 @@
+//! Rule based detection over parsed messages.
+//!
+//! Rules are boolean expressions over the fields of a message, as exposed by
+//! [`sawp_visit`]. Every path starts with the name of the protocol the rule
+//! applies to:
+//!
+//! ```text
+//! modbus.function.code == WrMultCoils && modbus.unit_id == 5
+//! dns.queries[*].name endswith ".onion"
+//! ```
+//!
+//! # Syntax
+//!
+//! - `path` is true if the field has at least one value.
+//! - `path <op> literal` compares the values of the field to a literal. It is
+//!   true if at least one of the values matches.
+//! - Operators are `==`, `!=`, `<`, `<=`, `>`, `>=`, `contains`,
+//!   `startswith` and `endswith`.
+//! - Literals are integers (`5`, `-1`, `0x1f`), floats (`1.5`), strings
+//!   (`"a\x00"`), booleans (`true`, `false`) and identifiers naming an enum
+//!   variant or a flag (`WrMultCoils`).
+//! - Strings match text and bytes fields. They also match IP address fields
+//!   when they hold a valid address.
+//! - Flag fields are tested with `contains`, e.g.
+//!   `modbus.error_flags contains DATA_LENGTH`.
+//! - Expressions are combined with `!`, `&&`, `||` and parentheses.
+//!
+//! # Example
+//! ```
+//! use sawp_detect::Ruleset;
+//! use sawp_visit::Visit;
+//!
+//! #[derive(Visit)]
+//! struct Question {
+//!     name: Vec<u8>,
+//! }
+//!
+//! #[derive(Visit)]
+//! struct Message {
+//!     queries: Vec<Question>,
+//! }
+//!
+//! let rules = Ruleset::parse(
+//!     r#"
+//!     ## Comments and empty lines are ignored
+//!     onion: dns.queries[*].name endswith ".onion"
+//!     empty: !dns.queries
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! let message = Message {
+//!     queries: vec![Question {
+//!         name: b"example.onion".to_vec(),
+//!     }],
+//! };
+//! let hits = rules.matches_protocol("dns", &message);
+//! assert_eq!(hits.len(), 1);
+//! assert_eq!(hits[0].rule.name(), "onion");
+//! assert_eq!(rules.hit_count("onion"), Some(1));
+//! ```
+
+mod lexer;
+mod rule;
+
+use rule::{Expr, Parser};
+
+use sawp::protocol::Protocol;
+use sawp_visit::Visit;
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Errors encountered while compiling rules.
+///
+/// Positions are byte offsets in the rule expression.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    UnexpectedCharacter(usize),
+    UnterminatedString(usize),
+    InvalidEscape(usize),
+    InvalidNumber(usize),
+    UnexpectedToken(usize),
+    UnexpectedEnd,
+    InvalidPath(usize, sawp_visit::Error),
+    /// The path doesn't start with a protocol name
+    MissingProtocol(usize),
+    /// The path is only a protocol name
+    MissingField(usize),
+    /// The path refers to another protocol than the rest of the rule
+    MixedProtocols(usize),
+    /// The literal can't be used with the operator
+    InvalidOperand(usize),
+    /// A rule with this name already exists
+    DuplicateRule(String),
+    /// A ruleset line isn't of the form `name: expression`
+    InvalidLine(usize),
+    /// A rule of a ruleset failed to compile
+    Line(usize, Box<Error>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedCharacter(position) => {
+                write!(f, "unexpected character at {}", position)
+            }
+            Error::UnterminatedString(position) => {
+                write!(f, "unterminated string at {}", position)
+            }
+            Error::InvalidEscape(position) => write!(f, "invalid escape at {}", position),
+            Error::InvalidNumber(position) => write!(f, "invalid number at {}", position),
+            Error::UnexpectedToken(position) => write!(f, "unexpected token at {}", position),
+            Error::UnexpectedEnd => write!(f, "unexpected end of rule"),
+            Error::InvalidPath(position, error) => {
+                write!(f, "invalid path at {}: {}", position, error)
+            }
+            Error::MissingProtocol(position) => {
+                write!(f, "path at {} doesn't start with a protocol", position)
+            }
+            Error::MissingField(position) => write!(f, "path at {} has no field", position),
+            Error::MixedProtocols(position) => {
+                write!(f, "path at {} refers to another protocol", position)
+            }
+            Error::InvalidOperand(position) => {
+                write!(f, "invalid operand for operator at {}", position)
+            }
+            Error::DuplicateRule(name) => write!(f, "duplicate rule {}", name),
+            Error::InvalidLine(line) => write!(f, "line {}: expected `name: expression`", line),
+            Error::Line(line, error) => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A compiled rule.
+#[derive(Debug)]
+pub struct Rule {
+    name: String,
+    protocol: String,
+    source: String,
+    expr: Expr,
+}
+
+impl Rule {
+    /// Compiles the expression `source` into a rule called `name`.
+    pub fn compile(name: &str, source: &str) -> Result<Self, Error> {
+        let tokens = lexer::tokenize(source)?;
+        let (protocol, expr) = Parser::new(&tokens).parse()?;
+        Ok(Rule {
+            name: name.to_string(),
+            protocol,
+            source: source.to_string(),
+            expr,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of the protocol the rule applies to.
+    pub fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    /// Expression the rule was compiled from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the rule over `message`, regardless of its protocol.
+    pub fn matches(&self, message: &dyn Visit) -> bool {
+        self.expr.matches(message)
+    }
+}
+
+/// A rule which matched a message.
+#[derive(Debug)]
+pub struct Hit<'r> {
+    pub rule: &'r Rule,
+}
+
+/// A set of rules evaluated together, counting how many times each one hit.
+#[derive(Debug, Default)]
+pub struct Ruleset {
+    rules: Vec<Rule>,
+    hits: Vec<AtomicU64>,
+}
+
+impl Ruleset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a ruleset with one `name: expression` rule per line.
+    ///
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let mut ruleset = Ruleset::new();
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, source) = line
+                .split_once(':')
+                .ok_or(Error::InvalidLine(line_number))?;
+            ruleset
+                .add(name.trim(), source.trim())
+                .map_err(|error| Error::Line(line_number, Box::new(error)))?;
+        }
+        Ok(ruleset)
+    }
+
+    /// Compiles and adds a rule.
+    pub fn add(&mut self, name: &str, source: &str) -> Result<(), Error> {
+        if self.rules.iter().any(|rule| rule.name == name) {
+            return Err(Error::DuplicateRule(name.to_string()));
+        }
+        self.rules.push(Rule::compile(name, source)?);
+        self.hits.push(AtomicU64::new(0));
+        Ok(())
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Evaluates the rules of the protocol `P` over `message`.
+    pub fn matches<'a, P>(&self, message: &P::Message) -> Vec<Hit<'_>>
+    where
+        P: Protocol<'a>,
+        P::Message: Visit,
+    {
+        self.matches_protocol(P::name(), message)
+    }
+
+    /// Evaluates the rules of `protocol` over `message`.
+    pub fn matches_protocol(&self, protocol: &str, message: &dyn Visit) -> Vec<Hit<'_>> {
+        self.rules
+            .iter()
+            .zip(&self.hits)
+            .filter(|(rule, _)| rule.protocol == protocol && rule.matches(message))
+            .map(|(rule, hits)| {
+                hits.fetch_add(1, Ordering::Relaxed);
+                Hit { rule }
+            })
+            .collect()
+    }
+
+    /// Number of messages the rule called `name` matched.
+    pub fn hit_count(&self, name: &str) -> Option<u64> {
+        self.rules
+            .iter()
+            .position(|rule| rule.name == name)
+            .map(|index| self.hits[index].load(Ordering::Relaxed))
+    }
+
+    /// Number of messages each rule matched, in the order rules were added.
+    pub fn hit_counts(&self) -> Vec<(&Rule, u64)> {
+        self.rules
+            .iter()
+            .zip(&self.hits)
+            .map(|(rule, hits)| (rule, hits.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        input,
+        expected,
+        case::invalid_line("a: p.a\nb", Error::InvalidLine(2)),
+        case::duplicate("a: p.a\na: p.b", Error::Line(2, Box::new(Error::DuplicateRule("a".into())))),
+        case::invalid_rule("a: p.a ==", Error::Line(1, Box::new(Error::UnexpectedEnd)))
+    )]
+    fn test_parse_error(input: &str, expected: Error) {
+        assert_eq!(Ruleset::parse(input).unwrap_err(), expected);
+    }
+
+    #[test]
+    fn test_protocol_filter() {
+        #[derive(Visit)]
+        struct Message {
+            a: u8,
+        }
+
+        let rules = Ruleset::parse("p: p.a == 1\nq: q.a == 1").unwrap();
+        let hits = rules.matches_protocol("p", &Message { a: 1 });
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].rule.name(), "p");
+        assert_eq!(hits[0].rule.protocol(), "p");
+        assert_eq!(hits[0].rule.source(), "p.a == 1");
+        assert_eq!(
+            rules
+                .hit_counts()
+                .iter()
+                .map(|(rule, hits)| (rule.name(), *hits))
+                .collect::<Vec<_>>(),
+            vec![("p", 1), ("q", 0)]
+        );
+        assert_eq!(rules.hit_count("r"), None);
+    }
+}
@@ -0,0 +1,438 @@
+//! Rule expressions: parsing and evaluation.
+
+use crate::lexer::Token;
+use crate::Error;
+
+use sawp_visit::{Path, Segment, Value, Visit};
+
+use std::cmp::Ordering;
+use std::net::IpAddr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+impl Op {
+    fn ordering(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains | Op::StartsWith | Op::EndsWith => false,
+        }
+    }
+
+    fn equality(self, equal: bool) -> bool {
+        match self {
+            Op::Eq => equal,
+            Op::Ne => !equal,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    Integer(i128),
+    Float(f64),
+    Bool(bool),
+    /// String literal, `ip` is set if it is a valid IP address
+    String {
+        bytes: Vec<u8>,
+        ip: Option<IpAddr>,
+    },
+    /// Name of an enum variant or flag
+    Ident(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    /// The path has at least one value
+    Exists(Path),
+    /// At least one value of the path compares true to the literal
+    Compare(Path, Op, Literal),
+}
+
+impl Expr {
+    /// Evaluates the expression over `message`. Paths are relative to the
+    /// message, without protocol name.
+    pub fn matches(&self, message: &dyn Visit) -> bool {
+        match self {
+            Expr::And(left, right) => left.matches(message) && right.matches(message),
+            Expr::Or(left, right) => left.matches(message) || right.matches(message),
+            Expr::Not(expr) => !expr.matches(message),
+            Expr::Exists(path) => !path.query(message).is_empty(),
+            Expr::Compare(path, op, literal) => path
+                .query(message)
+                .into_iter()
+                .any(|value| compare(value, *op, literal)),
+        }
+    }
+}
+
+fn compare(value: Value, op: Op, literal: &Literal) -> bool {
+    match (value, literal) {
+        (Value::Uint(value), Literal::Integer(literal)) => {
+            op.ordering(i128::from(value).cmp(literal))
+        }
+        (Value::Int(value), Literal::Integer(literal)) => {
+            op.ordering(i128::from(value).cmp(literal))
+        }
+        (Value::Uint(value), Literal::Float(literal)) => compare_float(value as f64, op, *literal),
+        (Value::Int(value), Literal::Float(literal)) => compare_float(value as f64, op, *literal),
+        (Value::Float(value), Literal::Integer(literal)) => {
+            compare_float(value, op, *literal as f64)
+        }
+        (Value::Float(value), Literal::Float(literal)) => compare_float(value, op, *literal),
+        (Value::Bool(value), Literal::Bool(literal)) => op.equality(value == *literal),
+        (Value::Str(value), Literal::String { bytes, .. }) => {
+            compare_bytes(value.as_bytes(), op, bytes)
+        }
+        (Value::Bytes(value), Literal::String { bytes, .. }) => compare_bytes(value, op, bytes),
+        (Value::Ip(value), Literal::String { ip: Some(ip), .. }) => op.equality(value == *ip),
+        (Value::Variant(value), Literal::Ident(literal)) => op.equality(value == literal),
+        (Value::Variant(value), Literal::String { bytes, .. }) => {
+            op.equality(value.as_bytes() == bytes.as_slice())
+        }
+        (Value::Flags(flags), Literal::Ident(name)) => match op {
+            Op::Contains => flags.contains(name),
+            _ => matches!(flags.flag(name), Some(flag) if op.equality(flags.bits() == flag)),
+        },
+        (Value::Flags(flags), Literal::Integer(literal)) => match op {
+            Op::Contains => i128::from(flags.bits()) & literal == *literal,
+            _ => op.ordering(i128::from(flags.bits()).cmp(literal)),
+        },
+        _ => false,
+    }
+}
+
+fn compare_float(value: f64, op: Op, literal: f64) -> bool {
+    matches!(value.partial_cmp(&literal), Some(ordering) if op.ordering(ordering))
+}
+
+fn compare_bytes(value: &[u8], op: Op, literal: &[u8]) -> bool {
+    match op {
+        Op::Contains => {
+            literal.is_empty() || value.windows(literal.len()).any(|window| window == literal)
+        }
+        Op::StartsWith => value.starts_with(literal),
+        Op::EndsWith => value.ends_with(literal),
+        _ => op.ordering(value.cmp(literal)),
+    }
+}
+
+/// Recursive descent parser of rule expressions.
+///
+/// ```text
+/// or         := and ("||" and)*
+/// and        := unary ("&&" unary)*
+/// unary      := "!" unary | "(" or ")" | comparison
+/// comparison := path [operator literal]
+/// ```
+pub struct Parser<'t> {
+    tokens: &'t [(usize, Token)],
+    index: usize,
+    protocol: Option<String>,
+}
+
+impl<'t> Parser<'t> {
+    pub fn new(tokens: &'t [(usize, Token)]) -> Self {
+        Parser {
+            tokens,
+            index: 0,
+            protocol: None,
+        }
+    }
+
+    /// Parses the whole expression and returns it with the protocol its
+    /// paths refer to.
+    pub fn parse(mut self) -> Result<(String, Expr), Error> {
+        let expr = self.or()?;
+        if let Some((position, _)) = self.peek() {
+            return Err(Error::UnexpectedToken(*position));
+        }
+        // At least one path is parsed if the expression is valid
+        Ok((self.protocol.unwrap_or_default(), expr))
+    }
+
+    fn peek(&self) -> Option<&'t (usize, Token)> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Result<&'t (usize, Token), Error> {
+        let token = self.peek().ok_or(Error::UnexpectedEnd)?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, token: &Token) -> bool {
+        match self.peek() {
+            Some((_, next)) if next == token => {
+                self.index += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.and()?;
+        while self.accept(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.unary()?;
+        while self.accept(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        if self.accept(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.accept(&Token::LeftParen) {
+            let expr = self.or()?;
+            return match self.next()? {
+                (_, Token::RightParen) => Ok(expr),
+                (position, _) => Err(Error::UnexpectedToken(*position)),
+            };
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, Error> {
+        let path = match self.next()? {
+            (position, Token::Word(path)) => self.path(*position, path)?,
+            (position, _) => return Err(Error::UnexpectedToken(*position)),
+        };
+
+        let op = match self.peek() {
+            Some((_, Token::Eq)) => Op::Eq,
+            Some((_, Token::Ne)) => Op::Ne,
+            Some((_, Token::Lt)) => Op::Lt,
+            Some((_, Token::Le)) => Op::Le,
+            Some((_, Token::Gt)) => Op::Gt,
+            Some((_, Token::Ge)) => Op::Ge,
+            Some((_, Token::Word(word))) if word == "contains" => Op::Contains,
+            Some((_, Token::Word(word))) if word == "startswith" => Op::StartsWith,
+            Some((_, Token::Word(word))) if word == "endswith" => Op::EndsWith,
+            _ => return Ok(Expr::Exists(path)),
+        };
+        self.index += 1;
+
+        let (position, literal) = self.next()?;
+        let literal = match literal {
+            Token::Integer(value) => Literal::Integer(*value),
+            Token::Float(value) => Literal::Float(*value),
+            Token::String(bytes) => Literal::String {
+                bytes: bytes.clone(),
+                ip: std::str::from_utf8(bytes)
+                    .ok()
+                    .and_then(|ip| ip.parse().ok()),
+            },
+            Token::Word(word) if word == "true" => Literal::Bool(true),
+            Token::Word(word) if word == "false" => Literal::Bool(false),
+            Token::Word(word) if !word.contains(|c| ".[]*".contains(c)) => {
+                Literal::Ident(word.clone())
+            }
+            _ => return Err(Error::UnexpectedToken(*position)),
+        };
+
+        let valid = match op {
+            Op::StartsWith | Op::EndsWith => matches!(literal, Literal::String { .. }),
+            Op::Contains => matches!(
+                literal,
+                Literal::String { .. } | Literal::Ident(_) | Literal::Integer(_)
+            ),
+            Op::Lt | Op::Le | Op::Gt | Op::Ge => !matches!(literal, Literal::Bool(_)),
+            Op::Eq | Op::Ne => true,
+        };
+        if !valid {
+            return Err(Error::InvalidOperand(*position));
+        }
+
+        Ok(Expr::Compare(path, op, literal))
+    }
+
+    /// Parses a path made of a protocol name followed by the path of a field
+    /// within messages of that protocol.
+    fn path(&mut self, position: usize, path: &str) -> Result<Path, Error> {
+        let path: Path = path
+            .parse()
+            .map_err(|error| Error::InvalidPath(position, error))?;
+        let protocol = match path.segments() {
+            [Segment::Field(protocol), _, ..] => protocol,
+            [Segment::Field(_)] => return Err(Error::MissingField(position)),
+            _ => return Err(Error::MissingProtocol(position)),
+        };
+        match &self.protocol {
+            Some(expected) if expected != protocol => Err(Error::MixedProtocols(position)),
+            _ => {
+                let relative = path.strip_prefix(protocol);
+                self.protocol = Some(protocol.clone());
+                // strip_prefix can't fail, the first segment is the protocol
+                relative.ok_or(Error::MissingProtocol(position))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::tokenize;
+    use rstest::rstest;
+
+    fn parse(input: &str) -> Result<(String, Expr), Error> {
+        Parser::new(&tokenize(input)?).parse()
+    }
+
+    fn path(path: &str) -> Path {
+        path.parse().unwrap()
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::exists("p.a", Ok(Expr::Exists(path("a")))),
+        case::compare(
+            "p.a[*].b == 1",
+            Ok(Expr::Compare(path("a[*].b"), Op::Eq, Literal::Integer(1)))
+        ),
+        case::precedence(
+            "p.a || p.b && !p.c",
+            Ok(Expr::Or(
+                Box::new(Expr::Exists(path("a"))),
+                Box::new(Expr::And(
+                    Box::new(Expr::Exists(path("b"))),
+                    Box::new(Expr::Not(Box::new(Expr::Exists(path("c"))))),
+                )),
+            ))
+        ),
+        case::parentheses(
+            "(p.a || p.b) && p.c contains X",
+            Ok(Expr::And(
+                Box::new(Expr::Or(
+                    Box::new(Expr::Exists(path("a"))),
+                    Box::new(Expr::Exists(path("b"))),
+                )),
+                Box::new(Expr::Compare(
+                    path("c"),
+                    Op::Contains,
+                    Literal::Ident("X".into())
+                )),
+            ))
+        ),
+        case::ip(
+            "p.a == \"::1\"",
+            Ok(Expr::Compare(
+                path("a"),
+                Op::Eq,
+                Literal::String {
+                    bytes: b"::1".to_vec(),
+                    ip: Some("::1".parse().unwrap())
+                }
+            ))
+        ),
+        case::bool("p.a != false", Ok(Expr::Compare(path("a"), Op::Ne, Literal::Bool(false)))),
+        case::empty("", Err(Error::UnexpectedEnd)),
+        case::missing_literal("p.a ==", Err(Error::UnexpectedEnd)),
+        case::missing_paren("(p.a", Err(Error::UnexpectedEnd)),
+        case::trailing("p.a p.b", Err(Error::UnexpectedToken(4))),
+        case::literal_path("p.a == p.b", Err(Error::UnexpectedToken(7))),
+        case::missing_field("p == 1", Err(Error::MissingField(0))),
+        case::missing_protocol("[0].a == 1", Err(Error::MissingProtocol(0))),
+        case::mixed("p.a && q.b", Err(Error::MixedProtocols(7))),
+        case::invalid_path("p.a[x]", Err(Error::InvalidPath(0, sawp_visit::Error::InvalidIndex(3)))),
+        case::invalid_operand("p.a startswith 1", Err(Error::InvalidOperand(15))),
+        case::invalid_ordering("p.a < true", Err(Error::InvalidOperand(6)))
+    )]
+    fn test_parse(input: &str, expected: Result<Expr, Error>) {
+        assert_eq!(parse(input).map(|(_, expr)| expr), expected);
+    }
+
+    #[test]
+    fn test_protocol() {
+        assert_eq!(parse("dns.a && !dns.b").unwrap().0, "dns");
+    }
+
+    #[derive(sawp_visit::Visit)]
+    enum Kind {
+        First,
+    }
+
+    #[derive(sawp_visit::Visit)]
+    struct Test {
+        uint: u16,
+        int: i32,
+        float: f32,
+        bool: bool,
+        string: String,
+        bytes: Vec<u8>,
+        ip: IpAddr,
+        kind: Kind,
+        nested: Vec<Vec<u16>>,
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::uint("t.uint == 10", true),
+        case::uint_ordering("t.uint > 9 && t.uint <= 10", true),
+        case::uint_float("t.uint < 10.5", true),
+        case::int("t.int == -1", true),
+        case::int_ne("t.int != -1", false),
+        case::float("t.float >= 0.5", true),
+        case::bool("t.bool == true", true),
+        case::string("t.string == \"hello\"", true),
+        case::string_contains("t.string contains \"ell\"", true),
+        case::bytes_starts("t.bytes startswith \"\\x00\\x01\"", true),
+        case::bytes_ends("t.bytes endswith \"\\x01\"", false),
+        case::ip("t.ip == \"10.0.0.1\"", true),
+        case::ip_not_an_ip("t.ip == \"host\"", false),
+        case::variant("t.kind == First", true),
+        case::variant_string("t.kind == \"First\"", true),
+        case::variant_other("t.kind == Second", false),
+        case::any("t.nested[*] == 3", true),
+        case::index("t.nested[0] == 3", false),
+        case::exists("t.nested[1]", true),
+        case::missing("t.missing", false),
+        case::type_mismatch("t.string == 1", false),
+        case::not("!(t.uint == 1)", true)
+    )]
+    fn test_matches(input: &str, expected: bool) {
+        let test = Test {
+            uint: 10,
+            int: -1,
+            float: 0.5,
+            bool: true,
+            string: "hello".into(),
+            bytes: vec![0, 1, 2],
+            ip: "10.0.0.1".parse().unwrap(),
+            kind: Kind::First,
+            nested: vec![vec![1, 2], vec![3]],
+        };
+        let (_, expr) = parse(input).unwrap();
+        assert_eq!(expr.matches(&test), expected);
+    }
+}
@@ -0,0 +1,97 @@
+use sawp::parser::{Direction, Parse};
+use sawp_detect::Ruleset;
+
+fn ruleset() -> Ruleset {
+    Ruleset::parse(
+        r#"
+        coils: modbus.function.code == WrMultCoils && modbus.unit_id == 5
+        modbus_errors: modbus.error_flags contains DATA_LENGTH || modbus.error_flags contains FUNC_CODE
+        onion: dns.queries[*].name endswith ".onion"
+        ike_v1: ike.header.major_version == 1 && ike.header.exchange_type == IdentityProtection
+        ike_vendor: ike.payloads[*].next_payload == V1VendorID
+        ike_reserved: ike.error_flags contains NonZeroReserved
+        ike_length: ike.error_flags contains InvalidLength
+        "#,
+    )
+    .unwrap()
+}
+
+fn hits(ruleset: &Ruleset, protocol: &str, message: &dyn sawp_visit::Visit) -> Vec<String> {
+    ruleset
+        .matches_protocol(protocol, message)
+        .iter()
+        .map(|hit| hit.rule.name().to_string())
+        .collect()
+}
+
+#[test]
+fn test_modbus() {
+    let input = [
+        // Transaction ID: 0, Protocol ID: 0, Length: 9, Unit ID: 5
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x09, 0x05,
+        // Function Code: Write Multiple Coils (15)
+        0x0f, // Start Address: 19, Quantity: 10, Byte Count: 2
+        0x00, 0x13, 0x00, 0x0a, 0x02, // Value
+        0xcd, 0x01,
+    ];
+    let modbus = sawp_modbus::Modbus::default();
+    let (_, message) = modbus.parse(&input, Direction::ToServer).unwrap();
+    let message = message.unwrap();
+
+    let rules = ruleset();
+    let matched = rules.matches::<sawp_modbus::Modbus>(&message);
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].rule.name(), "coils");
+
+    // Same request with another unit ID
+    let mut input = input;
+    input[6] = 1;
+    let (_, message) = modbus.parse(&input, Direction::ToServer).unwrap();
+    assert!(hits(&rules, "modbus", &message.unwrap()).is_empty());
+    assert_eq!(rules.hit_count("coils"), Some(1));
+}
+
+#[test]
+fn test_dns() {
+    let input = [
+        0x12, 0x34, // Transaction ID: 0x1234
+        0x01, 0x00, // Flags: recursion desired
+        0x00, 0x01, // QDCOUNT: 1
+        0x00, 0x00, // ANCOUNT: 0
+        0x00, 0x00, // NSCOUNT: 0
+        0x00, 0x00, // ARCOUNT: 0
+        0x07, 0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x05, 0x6f, 0x6e, 0x69, 0x6f, 0x6e,
+        0x00, // question: example.onion
+        0x00, 0x01, // RType: A
+        0x00, 0x01, // RClass: IN
+    ];
+    let dns = sawp_dns::Dns::default();
+    let (_, message) = dns.parse(&input, Direction::ToServer).unwrap();
+
+    let rules = ruleset();
+    assert_eq!(hits(&rules, "dns", &message.unwrap()), vec!["onion"]);
+    assert_eq!(rules.hit_count("onion"), Some(1));
+}
+
+#[test]
+fn test_ike() {
+    // Retrieved from https://github.com/vathpela/wireshark/blob/master/test/captures/ikev1-certs.pcap
+    let input = [
+        0xfa, 0xfa, 0xeb, 0x49, 0x38, 0x2a, 0x76, 0x3c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x01, 0x10, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x74, 0x0d, 0x01,
+        0x00, 0x34, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x28, 0x00,
+        0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x20, 0x00, 0x01, 0x00, 0x00, 0x80, 0x0b, 0x00, 0x01,
+        0x80, 0x0c, 0x70, 0x80, 0x80, 0x01, 0x00, 0x05, 0x80, 0x02, 0x00, 0x01, 0x80, 0x03, 0x00,
+        0x03, 0x80, 0x04, 0x00, 0x02, 0x0d, 0x00, 0x00, 0x10, 0x4f, 0x45, 0x68, 0x79, 0x4c, 0x64,
+        0x41, 0x43, 0x65, 0x63, 0x66, 0x61, 0x00, 0x00, 0x00, 0x14, 0xaf, 0xca, 0xd7, 0x13, 0x68,
+        0xa1, 0xf1, 0xc9, 0x6b, 0x86, 0x96, 0xfc, 0x77, 0x57, 0x01, 0x00,
+    ];
+    let ike = sawp_ike::Ike::default();
+    let (_, message) = ike.parse(&input, Direction::Unknown).unwrap();
+
+    let rules = ruleset();
+    assert_eq!(
+        hits(&rules, "ike", &message.unwrap()),
+        vec!["ike_v1", "ike_vendor", "ike_reserved"]
+    );
+}
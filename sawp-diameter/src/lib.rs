@@ -2,6 +2,8 @@
 //!     https://tools.ietf.org/html/rfc6733
 
 use sawp::error::{NomError, Result};
+use sawp::limits::{Limits, Tracker};
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::Probe;
 use sawp::protocol::Protocol;
@@ -11,7 +13,6 @@ use nom::bytes::streaming::tag;
 use nom::bytes::streaming::take;
 use nom::combinator;
 use nom::error::ErrorKind;
-use nom::multi::many0;
 use nom::number::streaming::{be_u24, be_u32, be_u64, be_u8};
 use nom::IResult;
 
@@ -19,9 +20,17 @@ use num_enum::TryFromPrimitive;
 use std::convert::TryFrom;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-#[derive(Debug)]
-pub struct Diameter {}
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
+#[derive(Debug, Default)]
+pub struct Diameter {
+    /// Resource limits applied to every message. `max_depth` bounds the
+    /// nesting of grouped AVPs and `max_elements` the total number of AVPs.
+    pub limits: Limits,
+}
+
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Header {
     version: u8,
@@ -34,6 +43,7 @@ pub struct Header {
 }
 
 /// AVP Attribute Names as stated in the [protocol reference](https://tools.ietf.org/html/rfc6733#section-4.5)
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u32)]
 pub enum AttributeCode {
@@ -89,6 +99,7 @@ pub enum AttributeCode {
     VendorSpecificApplicationId = 260,
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Attribute {
     /// Value of the code in AVP header
@@ -107,6 +118,7 @@ impl Attribute {
 }
 
 /// AVP Data Format as specified in the [protocol reference](https://tools.ietf.org/html/rfc6733#section-4.2)
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq)]
 pub enum Value {
     Unhandled(Vec<u8>),
@@ -127,9 +139,13 @@ pub enum Value {
 }
 
 impl Value {
+    /// `depth` is the nesting level of the AVP holding this value, top-level
+    /// AVPs being at depth 0.
     pub fn new<'a>(
         code: &AttributeCode,
         data: &'a [u8],
+        depth: usize,
+        tracker: &Tracker,
     ) -> IResult<&'a [u8], (Self, Flags<ErrorFlags>)> {
         match code {
             AttributeCode::AcctSessionId | AttributeCode::ProxyState => {
@@ -175,7 +191,16 @@ impl Value {
             | AttributeCode::FailedAVP
             | AttributeCode::ProxyInfo
             | AttributeCode::VendorSpecificApplicationId => {
-                let (input, (avps, error_flags)) = parse_avps(data)?;
+                if !tracker.depth(depth + 1) {
+                    return Ok((
+                        &[],
+                        (
+                            Value::Unhandled(data.into()),
+                            ErrorFlags::LimitExceeded.into(),
+                        ),
+                    ));
+                }
+                let (input, (avps, error_flags)) = parse_avps(data, depth + 1, tracker)?;
                 Ok((input, (Value::Grouped(avps), error_flags)))
             }
             AttributeCode::AcctMultiSessionId
@@ -245,6 +270,7 @@ impl Value {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq)]
 pub struct AVP {
     attribute: Attribute,
@@ -266,8 +292,13 @@ pub enum ErrorFlags {
     DataLength = 0b0000_0010,
     NonZeroReserved = 0b0000_0100,
     NonZeroPadding = 0b0000_1000,
+    /// A resource limit was reached. Grouped AVPs nested too deeply are left
+    /// unhandled, AVPs past the element limit are dropped and messages larger
+    /// than the allocation limit have no AVPs parsed.
+    LimitExceeded = 0b0001_0000,
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq)]
 pub struct Message {
     pub header: Header,
@@ -444,7 +475,11 @@ impl AVP {
         self.flags & Self::RESERVED_MASK
     }
 
-    pub fn parse(input: &[u8]) -> IResult<&[u8], (Self, Flags<ErrorFlags>)> {
+    pub fn parse<'a>(
+        input: &'a [u8],
+        depth: usize,
+        tracker: &Tracker,
+    ) -> IResult<&'a [u8], (Self, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
         let (input, raw_code) = be_u32(input)?;
         let (input, flags) = be_u8(input)?;
@@ -477,7 +512,7 @@ impl AVP {
             error_flags |= ErrorFlags::NonZeroPadding;
         }
         let attribute = Attribute::new(raw_code);
-        let value = match Value::new(&attribute.code, data) {
+        let value = match Value::new(&attribute.code, data, depth, tracker) {
             Ok((rest, (value, flags))) => {
                 if !rest.is_empty() {
                     error_flags |= ErrorFlags::DataLength;
@@ -524,21 +559,40 @@ impl Protocol<'_> for Diameter {
     }
 }
 
-fn parse_avps(input: &[u8]) -> IResult<&[u8], (Vec<AVP>, Flags<ErrorFlags>)> {
-    let (rest, avps_flags) = many0(combinator::complete(AVP::parse))(input)?;
-    if !rest.is_empty() {
-        // many0 will stop if subparser fails, but should read all
-        Err(nom::Err::Error(NomError::new(input, ErrorKind::Many0)))
-    } else {
-        let mut error_flags = ErrorFlags::none();
-        let mut avps = Vec::new();
-        for (avp, flag) in avps_flags {
-            error_flags |= flag;
-            avps.push(avp)
-        }
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
 
-        Ok((rest, (avps, error_flags)))
+fn parse_avps<'a>(
+    input: &'a [u8],
+    depth: usize,
+    tracker: &Tracker,
+) -> IResult<&'a [u8], (Vec<AVP>, Flags<ErrorFlags>)> {
+    let mut error_flags = ErrorFlags::none();
+    let mut avps = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        if !tracker.element() {
+            error_flags |= ErrorFlags::LimitExceeded;
+            return Ok((&[], (avps, error_flags)));
+        }
+        match combinator::complete(|i| AVP::parse(i, depth, tracker))(rest) {
+            Ok((i, (avp, flag))) => {
+                error_flags |= flag;
+                avps.push(avp);
+                rest = i;
+            }
+            // The AVPs should fill the whole input
+            Err(nom::Err::Error(_)) => {
+                return Err(nom::Err::Error(NomError::new(input, ErrorKind::Many0)))
+            }
+            Err(e) => return Err(e),
+        }
     }
+
+    Ok((rest, (avps, error_flags)))
 }
 
 impl<'a> Parse<'a> for Diameter {
@@ -554,8 +608,15 @@ impl<'a> Parse<'a> for Diameter {
         // Don't have to worry about splitting slice causing incomplete
         // Because we have verified the length in Header::parse
         let (input, avps_input) = combinator::complete(take(header.length()))(input)?;
-        let (_, (avps, flags)) = parse_avps(avps_input)?;
-        error_flags |= flags;
+        let tracker = self.limits.tracker();
+        let avps = if tracker.alloc(header.length()) {
+            let (_, (avps, flags)) = parse_avps(avps_input, 0, &tracker)?;
+            error_flags |= flags;
+            avps
+        } else {
+            error_flags |= ErrorFlags::LimitExceeded;
+            Vec::new()
+        };
         Ok((
             input,
             Some(Message {
@@ -575,6 +636,7 @@ mod tests {
     use rstest::rstest;
     use sawp::error;
     use sawp::probe::Status;
+    use sawp::testing::assert_incremental;
 
     #[test]
     fn test_name() {
@@ -1208,7 +1270,51 @@ mod tests {
         )
     )]
     fn test_avp(input: &[u8], expected: IResult<&[u8], (AVP, Flags<ErrorFlags>)>) {
-        assert_eq!(AVP::parse(input), expected);
+        assert_eq!(AVP::parse(input, 0, &Limits::default().tracker()), expected);
+    }
+
+    /// Wraps `avp` in an ExperimentalResult grouped AVP.
+    fn grouped(avp: &[u8]) -> Vec<u8> {
+        let length = (8 + avp.len()) as u32;
+        let mut group = vec![0x00, 0x00, 0x01, 0x29, 0x00];
+        group.extend_from_slice(&length.to_be_bytes()[1..]);
+        group.extend_from_slice(avp);
+        group
+    }
+
+    #[rstest(
+        depth,
+        limits,
+        expected_flags,
+        case::default(3, Limits::default(), ErrorFlags::none()),
+        case::max_depth(
+            3,
+            Limits {
+                max_depth: 2,
+                ..Limits::default()
+            },
+            ErrorFlags::LimitExceeded.into()
+        ),
+        case::max_elements(
+            3,
+            Limits {
+                max_elements: 2,
+                ..Limits::default()
+            },
+            ErrorFlags::LimitExceeded.into()
+        )
+    )]
+    fn test_avp_limits(depth: usize, limits: Limits, expected_flags: Flags<ErrorFlags>) {
+        // Code: 44 (AcctSessionId), Length: 12, Data: 0x01020304
+        let mut input = vec![
+            0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x0c, 0x01, 0x02, 0x03, 0x04,
+        ];
+        for _ in 0..depth {
+            input = grouped(&input);
+        }
+        let (rest, (_, flags)) = AVP::parse(&input, 0, &limits.tracker()).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(flags, expected_flags);
     }
 
     #[rstest(
@@ -1424,9 +1530,10 @@ mod tests {
         ),
     )]
     fn test_parse(input: &[u8], expected: Result<(&[u8], Option<Message>)>) {
-        let diameter = Diameter {};
+        let diameter = Diameter::default();
 
         assert_eq!(diameter.parse(input, Direction::Unknown), expected);
+        assert_incremental(Diameter::default, input, Direction::Unknown);
     }
 
     #[rstest(
@@ -1455,7 +1562,7 @@ mod tests {
         ),
     )]
     fn test_probe(input: &[u8], expected: Status) {
-        let diameter = Diameter {};
+        let diameter = Diameter::default();
 
         assert_eq!(diameter.probe(input, Direction::Unknown), expected);
     }
@@ -2,24 +2,29 @@ use nom::bytes::streaming::take;
 use nom::number::complete::be_u32;
 use nom::number::streaming::be_u16;
 
+use nom::error::ErrorKind;
+use sawp::error::NomError;
+use sawp::limits::Tracker;
 use sawp_flags::{Flag, Flags};
 
+use alloc::{vec, vec::Vec};
+
 use crate::enums::{RecordClass, RecordType};
 use crate::rdata::RDataType;
-use crate::{custom_count, ErrorFlags, IResult, Name};
+use crate::{custom_count, DomainName, ErrorFlags, IResult, Name};
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
-/// Per RFC1035/RFC4408: max RDATA len = 65535 octets. Since TXT RDATA includes a length byte before
-/// each TXT string, min size per TXT is 2 bytes, leaving maximum of 65535/2 parser runs needed.
-const MAX_TXT_PARSES: usize = 32767;
 /// First three bytes of an OPT AR - determines whether an AR should be parsed with special "OPT logic".
 const OPT_RR_START: [u8; 3] = [0, 0, 41];
 
 /// A parsed DNS answer
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Answer {
     pub name: Vec<u8>,
@@ -31,14 +36,20 @@ pub struct Answer {
     pub rclass_raw: u16,
     pub ttl: u32,
     pub data: RDataType,
+    /// The name with its labels, if enabled by [`crate::Dns::domain_names`]
+    #[cfg_attr(feature = "ffi", sawp_ffi(skip))]
+    pub domain_name: Option<DomainName>,
 }
 
 impl Answer {
     fn parse<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
+        domain_names: bool,
     ) -> IResult<'a, (Answer, Flags<ErrorFlags>)> {
-        let (input, (name, mut error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (name, domain_name, mut error_flags)) =
+            Name::parse_with_domain_name(reference_bytes, tracker, domain_names)(input)?;
 
         let (input, working_rtype) = be_u16(input)?;
         let rtype = RecordType::from_raw(working_rtype);
@@ -62,24 +73,28 @@ impl Answer {
             rclass_raw: working_rclass,
             ttl,
             data: RDataType::UNKNOWN(vec![]),
+            domain_name,
         };
 
         let (input, data_len) = be_u16(input)?;
         let (rem, local_data) = take(data_len)(input)?;
 
+        let domain_name_len = answer.domain_name.as_ref().map_or(0, DomainName::len);
+        if !tracker.alloc(answer.name.len() + domain_name_len + usize::from(data_len)) {
+            return Err(nom::Err::Error(NomError::new(input, ErrorKind::Count)));
+        }
+
         // always call once
         let (mut local_data, (mut rdata, inner_error_flags)) =
-            RDataType::parse(local_data, reference_bytes, rtype)?;
+            RDataType::parse(local_data, reference_bytes, rtype, tracker)?;
         error_flags |= inner_error_flags;
 
         // get ref to buffer we will extend first, if TXT
         if let RDataType::TXT(ref mut current_rdata) = rdata {
-            for _ in 0..MAX_TXT_PARSES - 1 {
-                if local_data.is_empty() {
-                    break;
-                }
+            // Each TXT string counts as an element
+            while !local_data.is_empty() && tracker.element() {
                 let (new_data, (rdata, inner_error_flags)) =
-                    RDataType::parse(local_data, reference_bytes, rtype)?;
+                    RDataType::parse(local_data, reference_bytes, rtype, tracker)?;
                 error_flags |= inner_error_flags;
                 if let RDataType::TXT(new_rdata) = rdata {
                     current_rdata.extend(new_rdata);
@@ -96,9 +111,17 @@ impl Answer {
     fn parse_additional<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
+        domain_names: bool,
     ) -> IResult<'a, (Answer, Flags<ErrorFlags>, bool)> {
         let mut opt_rr_present = false;
         if input.len() >= 3 && input[0..3] == OPT_RR_START[0..3] {
+            let domain_name = if domain_names {
+                let (_, (domain_name, _)) = DomainName::parse(reference_bytes, tracker)(input)?;
+                Some(domain_name)
+            } else {
+                None
+            };
             let (input, (data, inner_error_flags)) = RDataType::parse_rdata_opt(&input[3..])?;
             opt_rr_present = true;
             Ok((
@@ -112,13 +135,15 @@ impl Answer {
                         rclass_raw: 254,
                         ttl: 0, // OPT RRs do not contain a TTL
                         data,
+                        domain_name,
                     },
                     inner_error_flags,
                     opt_rr_present,
                 ),
             ))
         } else {
-            let (input, (answer, inner_error_flags)) = Answer::parse(input, reference_bytes)?;
+            let (input, (answer, inner_error_flags)) =
+                Answer::parse(input, reference_bytes, tracker, domain_names)?;
             Ok((input, (answer, inner_error_flags, opt_rr_present)))
         }
     }
@@ -127,13 +152,18 @@ impl Answer {
         input: &'a [u8],
         reference_bytes: &'a [u8],
         acnt: usize,
+        tracker: &Tracker,
+        domain_names: bool,
     ) -> IResult<'a, (Vec<Answer>, Flags<ErrorFlags>)> {
         let mut opt_rr_present = false;
         let mut error_flags = ErrorFlags::none();
         let (input, answers) = custom_count(
             |input, reference_bytes| {
+                if !tracker.element() {
+                    return Err(nom::Err::Error(NomError::new(input, ErrorKind::Count)));
+                }
                 let (input, (answer, inner_error_flags, inner_opt_rr_present)) =
-                    Answer::parse_additional(input, reference_bytes)?;
+                    Answer::parse_additional(input, reference_bytes, tracker, domain_names)?;
                 if inner_opt_rr_present {
                     if opt_rr_present {
                         error_flags |= ErrorFlags::ExtraOptRr;
@@ -154,11 +184,17 @@ impl Answer {
         input: &'a [u8],
         reference_bytes: &'a [u8],
         acnt: usize,
+        tracker: &Tracker,
+        domain_names: bool,
     ) -> IResult<'a, (Vec<Answer>, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
         let (input, answers) = custom_count(
             |input, reference_bytes| {
-                let (input, (answer, inner_error_flags)) = Answer::parse(input, reference_bytes)?;
+                if !tracker.element() {
+                    return Err(nom::Err::Error(NomError::new(input, ErrorKind::Count)));
+                }
+                let (input, (answer, inner_error_flags)) =
+                    Answer::parse(input, reference_bytes, tracker, domain_names)?;
                 error_flags |= inner_error_flags;
                 Ok((input, answer))
             },
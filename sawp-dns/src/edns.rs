@@ -15,12 +15,17 @@ use num_enum::TryFromPrimitive;
 
 use sawp_flags::{Flag, Flags};
 
-use std::convert::TryFrom;
+use core::convert::TryFrom;
+
+use alloc::{vec, vec::Vec};
 
 use crate::{custom_many0, ErrorFlags, IResult};
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum OptionCode {
@@ -69,6 +74,7 @@ impl OptionCode {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct EdnsOption {
     #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
@@ -77,7 +83,7 @@ pub struct EdnsOption {
 }
 
 impl EdnsOption {
-    pub fn parse(input: &[u8]) -> IResult<(EdnsOption, Flags<ErrorFlags>)> {
+    pub fn parse(input: &[u8]) -> IResult<'_, (EdnsOption, Flags<ErrorFlags>)> {
         let (input, (code, inner_error_flags)) = EdnsOption::parse_option_code(input)?;
         let (input, option_length) = be_u16(input)?;
         let (input, data) = take(option_length)(input)?;
@@ -94,7 +100,7 @@ impl EdnsOption {
         ))
     }
 
-    fn parse_option_code(input: &[u8]) -> IResult<(OptionCode, Flags<ErrorFlags>)> {
+    fn parse_option_code(input: &[u8]) -> IResult<'_, (OptionCode, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
 
         let (input, raw_option_code) = be_u16(input)?;
@@ -108,7 +114,7 @@ impl EdnsOption {
     pub fn parse_options(
         input: &[u8],
         data_len: u16,
-    ) -> IResult<(Vec<EdnsOption>, Flags<ErrorFlags>)> {
+    ) -> IResult<'_, (Vec<EdnsOption>, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
         if data_len < 4 {
             return Ok((input, (vec![], error_flags)));
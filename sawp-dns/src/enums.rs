@@ -1,7 +1,11 @@
 use num_enum::TryFromPrimitive;
 
-use std::convert::TryFrom;
+use core::convert::TryFrom;
 
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
+
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum RecordType {
@@ -192,6 +196,7 @@ impl RecordType {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum RecordClass {
@@ -209,6 +214,7 @@ impl RecordClass {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum OpCode {
@@ -227,6 +233,7 @@ impl OpCode {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum ResponseCode {
@@ -261,6 +268,7 @@ impl ResponseCode {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum OptResponseCode {
@@ -299,6 +307,7 @@ impl OptResponseCode {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum TSigResponseCode {
@@ -350,6 +359,7 @@ impl TSigResponseCode {
 }
 
 /// Indicates whether the message is a query or response.
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[repr(u8)]
 pub enum QueryResponse {
@@ -366,6 +376,7 @@ pub enum AnswerType {
     Additional = 2,
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum SshfpAlgorithm {
@@ -383,6 +394,7 @@ impl SshfpAlgorithm {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum SshfpFingerprint {
@@ -398,6 +410,7 @@ impl SshfpFingerprint {
     }
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum TkeyMode {
@@ -415,3 +428,73 @@ impl TkeyMode {
         TkeyMode::try_from(val).unwrap_or(TkeyMode::UNKNOWN)
     }
 }
+
+/// DNSSEC algorithm of DNSKEY, RRSIG, DS and CDS records
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum DnssecAlgorithm {
+    RESERVED = 0,
+    /// Deprecated
+    RSAMD5 = 1,
+    DH = 2,
+    /// Deprecated
+    DSA = 3,
+    RSASHA1 = 5,
+    /// Deprecated
+    DSANSEC3SHA1 = 6,
+    RSASHA1NSEC3SHA1 = 7,
+    RSASHA256 = 8,
+    RSASHA512 = 10,
+    /// Deprecated
+    ECCGOST = 12,
+    ECDSAP256SHA256 = 13,
+    ECDSAP384SHA384 = 14,
+    ED25519 = 15,
+    ED448 = 16,
+    INDIRECT = 252,
+    PRIVATEDNS = 253,
+    PRIVATEOID = 254,
+    UNKNOWN,
+}
+
+impl DnssecAlgorithm {
+    pub fn from_raw(val: u8) -> Self {
+        DnssecAlgorithm::try_from(val).unwrap_or(DnssecAlgorithm::UNKNOWN)
+    }
+}
+
+/// Digest type of DS and CDS records
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum DigestType {
+    RESERVED = 0,
+    SHA1 = 1,
+    SHA256 = 2,
+    GOST = 3,
+    SHA384 = 4,
+    UNKNOWN,
+}
+
+impl DigestType {
+    pub fn from_raw(val: u8) -> Self {
+        DigestType::try_from(val).unwrap_or(DigestType::UNKNOWN)
+    }
+}
+
+/// Hash algorithm of NSEC3 and NSEC3PARAM records
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum Nsec3HashAlgorithm {
+    RESERVED = 0,
+    SHA1 = 1,
+    UNKNOWN,
+}
+
+impl Nsec3HashAlgorithm {
+    pub fn from_raw(val: u8) -> Self {
+        Nsec3HashAlgorithm::try_from(val).unwrap_or(Nsec3HashAlgorithm::UNKNOWN)
+    }
+}
@@ -9,6 +9,11 @@ use crate::enums::{OpCode, QueryResponse, ResponseCode};
 use crate::ErrorFlags;
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
+
+/// Length of the header in bytes
+pub const HEADER_LENGTH: usize = 12;
 
 /// Masks for extracting DNS header flags
 #[allow(non_camel_case_types)]
@@ -30,6 +35,7 @@ pub enum header_masks {
 /// A parsed DNS header
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Header {
     /// Transaction ID
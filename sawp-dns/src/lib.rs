@@ -1,7 +1,9 @@
 //! A DNS protocol parser. Given bytes and a [`sawp::parser::Direction`], it will
 //! attempt to parse the bytes and return a [`Message`]. The parser will
 //! inform the caller about what went wrong if no message is returned (see [`sawp::parser::Parse`]
-//! for details on possible return types).
+//! for details on possible return types). Messages are parsed as datagrams by default, set
+//! [`Dns::transport`] to [`Transport::Tcp`] for the length-prefixed messages of DNS over
+//! TCP and TLS.
 //!
 //! The following protocol references were used to create this module:
 //!
@@ -12,26 +14,32 @@
 //! [RFC2535](https://tools.ietf.org/html/rfc2535)
 //! [RFC2845](https://tools.ietf.org/html/rfc2845)
 //! [RFC2930](https://tools.ietf.org/html/rfc2930)
+//! [RFC3492](https://tools.ietf.org/html/rfc3492)
 //! [RFC3655](https://tools.ietf.org/html/rfc3655)
+//! [RFC4034](https://tools.ietf.org/html/rfc4034)
 //! [RFC4255](https://tools.ietf.org/html/rfc4255)
 //! [RFC4408](https://tools.ietf.org/html/rfc4408)
 //! [RFC4635](https://tools.ietf.org/html/rfc4635)
 //! [RFC5001](https://tools.ietf.org/html/rfc5001)
+//! [RFC5155](https://tools.ietf.org/html/rfc5155)
 //! [RFC6742](https://tools.ietf.org/html/rfc6742)
 //! [RFC6891](https://tools.ietf.org/html/rfc6891)
 //! [RFC6975](https://tools.ietf.org/html/rfc6975)
 //! [RFC7314](https://tools.ietf.org/html/rfc7314)
+//! [RFC7344](https://tools.ietf.org/html/rfc7344)
+//! [RFC7766](https://tools.ietf.org/html/rfc7766)
 //! [RFC7828](https://tools.ietf.org/html/rfc7828)
 //! [RFC7830](https://tools.ietf.org/html/rfc7830)
+//! [RFC7858](https://tools.ietf.org/html/rfc7858)
 //! [RFC7871](https://tools.ietf.org/html/rfc7871)
 //! [RFC7873](https://tools.ietf.org/html/rfc7873)
 //! [RFC7901](https://tools.ietf.org/html/rfc7901)
 //! [RFC8145](https://tools.ietf.org/html/rfc8145)
 //! [RFC8764](https://tools.ietf.org/html/rfc8764)
 //! [RFC8914](https://tools.ietf.org/html/rfc8914)
+//! [RFC9460](https://tools.ietf.org/html/rfc9460)
 //! [Cisco - Identifying DNS Traffic](https://docs.umbrella.com/umbrella-api/docs/identifying-dns-traffic2)
 //! [Draft DNSOP Zone Digest](https://tools.ietf.org/html/draft-ietf-dnsop-dns-zone-digest-14)
-//! [Draft DNSOP SVCB](https://datatracker.ietf.org/doc/draft-ietf-dnsop-svcb-https/00)
 //! [Draft EDNS Tags](https://datatracker.ietf.org/doc/draft-bellis-dnsop-edns-tags/)
 //! [Eastlake Kitchen Sink](https://tools.ietf.org/html/draft-eastlake-kitchen-sink)
 //! [NIMROD DNS](https://tools.ietf.org/html/draft-ietf-nimrod-dns-00)
@@ -46,7 +54,7 @@
 //! use sawp_dns::{Dns, Message};
 //!
 //! fn parse_bytes(input: &[u8]) -> std::result::Result<&[u8], Error> {
-//!     let dns = Dns {};
+//!     let dns = Dns::default();
 //!     let mut bytes = input;
 //!     while bytes.len() > 0 {
 //!         // If we know that this is a request or response, change the Direction
@@ -72,7 +80,15 @@
 //! }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+
 use sawp::error::{NomError, Result};
+use sawp::limits::Limits;
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::Probe;
 use sawp::protocol::Protocol;
@@ -84,6 +100,8 @@ mod ffi;
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 pub mod answer;
 use answer::*;
@@ -104,7 +122,11 @@ use question::*;
 
 pub mod rdata;
 
+pub mod svcb;
+
+use nom::bytes::streaming::take;
 use nom::error::ErrorKind;
+use nom::number::streaming::be_u16;
 
 // This is a helper type for the module since the input will always be
 // &'a [u8] and the error will always be (&'a [i8], nom::error::Errorkind)
@@ -150,7 +172,8 @@ where
 {
     move |i, reference| {
         let mut input = i;
-        let mut res = Vec::with_capacity(count);
+        // count is read from the input: don't trust it for preallocation
+        let mut res = Vec::with_capacity(count.min(i.len()));
 
         for _ in 0..count {
             let input_ = input;
@@ -196,11 +219,31 @@ pub enum ErrorFlags {
     DnsNameExceedsMaxLen = 0b0000_0000_1000_0000,
     /// a ptr either points to an invalid location or is self-referential
     DnsNameInvalidCompression = 0b0000_0001_0000_0000,
+    /// a resource limit was reached - the message is truncated
+    LimitExceeded = 0b0000_0010_0000_0000,
+    /// the length prefix of a TCP message is shorter or longer than its content, or the
+    /// message is too short or malformed - the rest of its length is skipped
+    LengthMismatch = 0b0000_0100_0000_0000,
+    /// an NSEC or NSEC3 type bitmap has an invalid window length, windows out of order or a
+    /// window running past the end of the rdata - the rest of the bitmap is dropped
+    InvalidTypeBitmap = 0b0000_1000_0000_0000,
+    /// the params of an SVCB or HTTPS record are not in increasing order of keys
+    SvcParamKeyOrder = 0b0001_0000_0000_0000,
+    /// a key appears more than once in the params of an SVCB or HTTPS record
+    SvcParamDuplicateKey = 0b0010_0000_0000_0000,
+    /// an SVCB or HTTPS param value is malformed or runs past the end of the rdata, a key
+    /// it requires is missing, the reserved key 65535 is used or an AliasMode record has params
+    SvcParamInvalid = 0b0100_0000_0000_0000,
+    /// a field of the rdata runs past the end of the rdata - it holds the bytes left and
+    /// the fields after it are empty. Rdata too short for its fixed size fields is kept
+    /// as UNKNOWN
+    MalformedRdata = 0b1000_0000_0000_0000,
 }
 
 /// Breakdown of the parsed dns bytes
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub header: Header,
@@ -214,8 +257,34 @@ pub struct Message {
 
 impl Message {}
 
-#[derive(Debug)]
-pub struct Dns {}
+/// Transport of the messages to parse
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Transport {
+    /// A single message per datagram
+    #[default]
+    Udp,
+    /// DNS over TCP or decrypted DNS over TLS: each message is prefixed with
+    /// its 2-byte length, with any number of messages per segment such as in
+    /// zone transfers
+    Tcp,
+}
+
+#[derive(Debug, Default)]
+pub struct Dns {
+    /// Resource limits applied to every message. `max_elements` bounds the
+    /// number of records, TXT strings, NSEC types and SVCB params. The labels
+    /// and compression pointers followed for a single name are bounded by
+    /// [`MAX_LABEL_PARSES`] instead.
+    pub limits: Limits,
+    /// Transport of the messages to parse
+    pub transport: Transport,
+    /// Also return the names of questions and records as [`DomainName`]s,
+    /// keeping their labels and compression pointers. Names within rdata, such
+    /// as the target of a CNAME or the signer of an RRSIG, are only returned
+    /// joined by dots.
+    pub domain_names: bool,
+}
 
 impl<'a> Protocol<'a> for Dns {
     type Message = Message;
@@ -225,8 +294,118 @@ impl<'a> Protocol<'a> for Dns {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
 impl<'a> Probe<'a> for Dns {}
 
+impl Dns {
+    /// Parses the header and sections of a message into `message`, which
+    /// holds what was parsed so far on error. The rest of the message is
+    /// consumed once a limit is reached since it can't be resynchronized.
+    fn parse_message<'a>(&self, input: &'a [u8], message: &mut Message) -> Result<&'a [u8]> {
+        let reference_bytes = input; // An internal copy of the full input used to dereference pointers during parsing.
+        let tracker = self.limits.tracker();
+
+        let (input, (header, error_flags)) = Header::parse(input)?;
+        message.header = header;
+        message.error_flags |= error_flags;
+
+        let (input, (questions, error_flags)) = Question::parse_questions(
+            input,
+            reference_bytes,
+            message.header.qdcount.into(),
+            &tracker,
+            self.domain_names,
+        )?;
+        message.queries = questions;
+        message.error_flags |= error_flags;
+        if tracker.exceeded() {
+            message.error_flags |= ErrorFlags::LimitExceeded;
+            return Ok(&[]);
+        }
+
+        let (input, (answers, error_flags)) = Answer::parse_answers(
+            input,
+            reference_bytes,
+            message.header.ancount.into(),
+            &tracker,
+            self.domain_names,
+        )?;
+        message.answers = answers;
+        message.error_flags |= error_flags;
+        if tracker.exceeded() {
+            message.error_flags |= ErrorFlags::LimitExceeded;
+            return Ok(&[]);
+        }
+
+        let (input, (nameservers, error_flags)) = Answer::parse_answers(
+            input,
+            reference_bytes,
+            message.header.nscount.into(),
+            &tracker,
+            self.domain_names,
+        )?;
+        message.nameservers = nameservers;
+        message.error_flags |= error_flags;
+        if tracker.exceeded() {
+            message.error_flags |= ErrorFlags::LimitExceeded;
+            return Ok(&[]);
+        }
+
+        let (input, (additionals, error_flags)) = Answer::parse_additionals(
+            input,
+            reference_bytes,
+            message.header.arcount.into(),
+            &tracker,
+            self.domain_names,
+        )?;
+        message.additional = additionals;
+        message.error_flags |= error_flags;
+        if tracker.exceeded() {
+            message.error_flags |= ErrorFlags::LimitExceeded;
+            return Ok(&[]);
+        }
+
+        Ok(input)
+    }
+
+    //                     Num Bytes       Byte Placement
+    // Length:             2               (0,1)
+    // Message:            Length          (2..)
+    fn parse_tcp<'a>(
+        &self,
+        input: &'a [u8],
+        mut message: Message,
+    ) -> Result<(&'a [u8], Option<Message>)> {
+        let (input, length) = be_u16(input)?;
+        let (input, data) = take(length)(input)?;
+
+        // The whole frame is consumed whatever its content so parsing stays
+        // in sync with the stream
+        if data.len() < HEADER_LENGTH {
+            message.error_flags |= ErrorFlags::LengthMismatch;
+            return Ok((input, Some(message)));
+        }
+
+        match self.parse_message(data, &mut message) {
+            Ok(rest) => {
+                if !rest.is_empty() {
+                    message.error_flags |= ErrorFlags::LengthMismatch;
+                }
+            }
+            // The records run past the end of the message or are malformed,
+            // return the ones parsed
+            Err(_) => message.error_flags |= ErrorFlags::LengthMismatch,
+        }
+
+        Ok((input, Some(message)))
+    }
+}
+
 /// Returns ErrorKind::Incomplete if more data is needed.
 /// If part of the message was parsed successfully will attempt to return a partial message
 /// with an appropriate error_flags field indicating what went wrong.
@@ -236,7 +415,6 @@ impl<'a> Parse<'a> for Dns {
         input: &'a [u8],
         _direction: Direction,
     ) -> Result<(&'a [u8], Option<Self::Message>)> {
-        let reference_bytes = input; // An internal copy of the full input used to dereference pointers during parsing.
         let mut message = Message {
             header: Header {
                 transaction_id: 0,
@@ -263,31 +441,13 @@ impl<'a> Parse<'a> for Dns {
             error_flags: ErrorFlags::none(),
         };
 
-        let (input, (header, error_flags)) = Header::parse(input)?;
-        message.header = header;
-        message.error_flags |= error_flags;
-
-        let (input, (questions, error_flags)) =
-            Question::parse_questions(input, reference_bytes, message.header.qdcount.into())?;
-        message.queries = questions;
-        message.error_flags |= error_flags;
-
-        let (input, (answers, error_flags)) =
-            Answer::parse_answers(input, reference_bytes, message.header.ancount.into())?;
-        message.answers = answers;
-        message.error_flags |= error_flags;
-
-        let (input, (nameservers, error_flags)) =
-            Answer::parse_answers(input, reference_bytes, message.header.nscount.into())?;
-        message.nameservers = nameservers;
-        message.error_flags |= error_flags;
-
-        let (input, (additionals, error_flags)) =
-            Answer::parse_additionals(input, reference_bytes, message.header.arcount.into())?;
-        message.additional = additionals;
-        message.error_flags |= error_flags;
-
-        Ok((input, Some(message)))
+        match self.transport {
+            Transport::Udp => {
+                let input = self.parse_message(input, &mut message)?;
+                Ok((input, Some(message)))
+            }
+            Transport::Tcp => self.parse_tcp(input, message),
+        }
     }
 }
 
@@ -296,14 +456,17 @@ mod test {
     #![allow(clippy::type_complexity)]
     use crate::enums::*;
     use crate::rdata::*;
+    use crate::svcb::*;
     use crate::{
         Answer, Dns, ErrorFlags, Header, Message, OpCode, Parse, QueryResponse, Question,
-        RecordClass, RecordType, ResponseCode,
+        RecordClass, RecordType, ResponseCode, Transport,
     };
     use rstest::rstest;
     use sawp::error::{Error, Result};
+    use sawp::limits::Limits;
     use sawp::parser::Direction;
-    use sawp_flags::Flag;
+    use sawp::testing::{assert_datagram, assert_incremental};
+    use sawp_flags::{Flag, Flags};
 
     #[rstest(
     input,
@@ -392,6 +555,7 @@ mod test {
                             record_type_raw: 1,
                             record_class: RecordClass::IN,
                             record_class_raw: 1,
+                            domain_name: None,
                         }
                     ],
                     nameservers: vec ! [],
@@ -416,6 +580,7 @@ mod test {
                             rclass_raw: 1,
                             ttl: 10000,
                             data: RDataType::A(vec![212, 204, 214, 114]),
+                            domain_name: None,
                         }
                     ],
                     additional: vec ! [],
@@ -492,6 +657,7 @@ mod test {
                     record_type_raw: 1,
                     record_class: RecordClass::IN,
                     record_class_raw: 1,
+                    domain_name: None,
                 }
             ],
             answers: vec![
@@ -514,6 +680,7 @@ mod test {
                     rclass_raw: 1,
                     ttl: 10000,
                     data: RDataType::A(vec![212, 204, 214, 114]),
+                    domain_name: None,
                 }
             ],
             nameservers: vec![],
@@ -570,6 +737,7 @@ mod test {
                     record_type_raw: 1,
                     record_class: RecordClass::IN,
                     record_class_raw: 1,
+                    domain_name: None,
                 }
             ],
             nameservers: vec![],
@@ -582,6 +750,7 @@ mod test {
                     rclass_raw: 1,
                     ttl: 10000,
                     data: RDataType::A(vec![212, 204, 214, 114]),
+                    domain_name: None,
                 }
             ],
             additional: vec![],
@@ -638,6 +807,7 @@ mod test {
                     record_type_raw: 261,
                     record_class: RecordClass::IN,
                     record_class_raw: 1,
+                    domain_name: None,
                 }
             ],
             nameservers: vec![],
@@ -650,6 +820,7 @@ mod test {
                     rclass_raw: 1,
                     ttl: 10000,
                     data: (RDataType::A(vec![212, 204, 214, 114])),
+                    domain_name: None,
                 }
             ],
             additional: vec![],
@@ -706,6 +877,7 @@ mod test {
                     record_type_raw: 1,
                     record_class: RecordClass::UNKNOWN,
                     record_class_raw: 32,
+                    domain_name: None,
                 }
             ],
             nameservers: vec![],
@@ -718,6 +890,7 @@ mod test {
                     rclass_raw: 96,
                     ttl: 10000,
                     data: (RDataType::A(vec![212, 204, 214, 114])),
+                    domain_name: None,
                 }
             ],
             additional: vec![],
@@ -774,6 +947,7 @@ mod test {
                         record_type_raw: 1,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 nameservers: vec![],
@@ -786,6 +960,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 10000,
                         data: (RDataType::A(vec![212, 204, 214, 114])),
+                        domain_name: None,
                     }
                 ],
                 additional: vec![],
@@ -842,6 +1017,7 @@ mod test {
                         record_type_raw: 16,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -853,6 +1029,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 270,
                         data: (RDataType::TXT(b"v=spf1 ptr ?all".to_vec())),
+                        domain_name: None,
                     }
                 ],
                 nameservers: vec![],
@@ -981,6 +1158,7 @@ mod test {
                         record_type_raw: 15,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -992,6 +1170,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 552,
                         data: (RDataType::MX(b"smtp4.google.com".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"google.com".to_vec(),
@@ -1001,6 +1180,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 552,
                         data: (RDataType::MX(b"smtp5.google.com".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"google.com".to_vec(),
@@ -1010,6 +1190,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 552,
                         data: (RDataType::MX(b"smtp6.google.com".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"google.com".to_vec(),
@@ -1019,6 +1200,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 552,
                         data: (RDataType::MX(b"smtp1.google.com".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"google.com".to_vec(),
@@ -1028,6 +1210,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 552,
                         data: (RDataType::MX(b"smtp2.google.com".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"google.com".to_vec(),
@@ -1037,6 +1220,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 552,
                         data: (RDataType::MX(b"smtp3.google.com".to_vec())),
+                        domain_name: None,
                     },
                 ],
                 nameservers: vec![],
@@ -1049,6 +1233,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![216, 239, 37, 26])),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"smtp5.google.com".to_vec(),
@@ -1058,6 +1243,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![64, 233, 167, 25])),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"smtp6.google.com".to_vec(),
@@ -1067,6 +1253,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![66, 102, 9, 25])),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"smtp1.google.com".to_vec(),
@@ -1076,6 +1263,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![216, 239, 57, 25])),
+                        domain_name: None,
                     },
                         Answer {
                         name: b"smtp2.google.com".to_vec(),
@@ -1085,6 +1273,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![216, 239, 37, 25])),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"smtp3.google.com".to_vec(),
@@ -1094,6 +1283,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![216, 239, 57, 26])),
+                        domain_name: None,
                     },
                 ],
                 error_flags: ErrorFlags::none(),
@@ -1142,6 +1332,7 @@ mod test {
                             record_type_raw: 29,
                             record_class: RecordClass::IN,
                             record_class_raw: 1,
+                            domain_name: None,
                         }
                     ],
                     answers: vec![],
@@ -1202,6 +1393,7 @@ mod test {
                         record_type_raw: 12,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -1213,6 +1405,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 86309,
                         data: (RDataType::PTR(b"66-192-9-104.gen.twtelecom.net".to_vec())),
+                        domain_name: None,
                     }
                 ],
                 nameservers: vec![],
@@ -1270,6 +1463,7 @@ mod test {
                         record_type_raw: 28,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                 }],
                 answers: vec![
                     Answer {
@@ -1283,6 +1477,7 @@ mod test {
                         0x20, 0x01, 0x04, 0xf8, 0x00, 0x04, 0x00, 0x07, 0x02, 0xe0, 0x81, 0xff, 0xfe,
                         0x52, 0x9a, 0x6b,
                     ])),
+                        domain_name: None,
                 }],
                 nameservers: vec![],
                 additional: vec![],
@@ -1337,6 +1532,7 @@ mod test {
                         record_type_raw: 28,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -1348,6 +1544,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 633,
                         data: (RDataType::CNAME(b"www.l.google.com".to_vec())),
+                        domain_name: None,
                     }
                 ],
                 nameservers: vec![],
@@ -1399,6 +1596,7 @@ mod test {
                         record_type_raw: 28,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![],
@@ -1463,6 +1661,7 @@ mod test {
                         record_type_raw: 255,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -1477,6 +1676,7 @@ mod test {
                         0x20, 0x01, 0x04, 0xf8, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
                         0x00, 0x00, 0x00, 0x0d,
                         ])),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"www.isc.org".to_vec(),
@@ -1486,6 +1686,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 600,
                         data: (RDataType::A(vec![0xcc, 0x98, 0xb8, 0x58])),
+                        domain_name: None,
                     },
                 ],
                 nameservers: vec![],
@@ -1562,6 +1763,7 @@ mod test {
                         record_type_raw: 2,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -1573,6 +1775,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 3600,
                         data: (RDataType::NS(b"ns-ext.nrt1.isc.org".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"isc.org".to_vec(),
@@ -1582,6 +1785,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 3600,
                         data: (RDataType::NS(b"ns-ext.sth1.isc.org".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"isc.org".to_vec(),
@@ -1591,6 +1795,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 3600,
                         data: (RDataType::NS(b"ns-ext.isc.org".to_vec())),
+                        domain_name: None,
                     },
                     Answer {
                         name: b"isc.org".to_vec(),
@@ -1600,6 +1805,7 @@ mod test {
                         rclass_raw: 1,
                         ttl: 3600,
                         data: (RDataType::NS(b"ns-ext.lga1.isc.org".to_vec())),
+                        domain_name: None,
                     },
                 ],
                 nameservers: vec![],
@@ -1739,6 +1945,7 @@ mod test {
                         record_type_raw: 16,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -1772,6 +1979,7 @@ mod test {
                             .as_bytes()
                             .to_vec(),
                         )),
+                        domain_name: None,
                     }
                 ],
                 nameservers: vec![],
@@ -1844,6 +2052,7 @@ mod test {
                     record_type_raw: 1,
                     record_class: RecordClass::IN,
                     record_class_raw: 1,
+                    domain_name: None,
                 }
             ],
             answers: vec![],
@@ -1864,6 +2073,7 @@ mod test {
                         expire: 1_209_600,
                         minimum: 86400,
                     })),
+                    domain_name: None,
                 }
             ],
             additional: vec![
@@ -1881,6 +2091,7 @@ mod test {
                         flags: 0,
                         data: vec![],
                     })),
+                    domain_name: None,
                 }
             ],
             error_flags: ErrorFlags::none(),
@@ -1943,6 +2154,7 @@ mod test {
                         record_type_raw: 1,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![],
@@ -1962,6 +2174,7 @@ mod test {
                             flags: 0,
                             data: vec![],
                         })),
+                        domain_name: None,
                     },
                     Answer {
                         name: vec![0x00],
@@ -1977,6 +2190,7 @@ mod test {
                             flags: 0,
                             data: vec![],
                         })),
+                        domain_name: None,
                     },
                 ],
                 error_flags: ErrorFlags::ExtraOptRr.into(),
@@ -2074,6 +2288,7 @@ mod test {
                         record_type_raw: 33,
                         record_class: RecordClass::IN,
                         record_class_raw: 1,
+                        domain_name: None,
                     }
                 ],
                 answers: vec![
@@ -2089,7 +2304,8 @@ mod test {
                             weight: 100,
                             port: 88,
                             target: b"localdc.samba.example.com".to_vec()
-                        })
+                        }),
+                        domain_name: None,
                     }
                 ],
                 nameservers: vec![],
@@ -2232,6 +2448,7 @@ mod test {
                     record_type_raw: 44,
                     record_class: RecordClass::IN,
                     record_class_raw: 1,
+                    domain_name: None,
                 }
             ],
             answers: vec![
@@ -2250,7 +2467,8 @@ mod test {
                         0x7d, 0x41, 0x0e, 0xea, 0x8d, 0xfa, 0xe6, 0x01, 0x7d, 0xba, 0x82,
                         0x51, 0xc2, 0x63, 0x36, 0x8a, 0x57, 0x94, 0xb5, 0xd7, 0x43,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2266,7 +2484,8 @@ mod test {
                         0xe9, 0xd7, 0x62, 0xca, 0x6b, 0x63, 0x93, 0x1a, 0x92, 0xde, 0x1c,
                         0x69, 0x7d, 0xe3, 0x25, 0x76, 0xb8, 0xf6, 0x88, 0xf7,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2283,7 +2502,8 @@ mod test {
                         0x0e, 0x69, 0xbd, 0xff, 0xcd, 0xd1, 0x65, 0x00, 0x10, 0x0a, 0x3c,
                         0xd5, 0xb9, 0xce, 0xd7, 0xc6, 0x89, 0xde, 0xbb, 0x09, 0xe7,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2300,7 +2520,8 @@ mod test {
                         0x1e, 0xd0, 0xb1, 0xad, 0xd1, 0x85, 0xb8, 0x40, 0xa5, 0xe5, 0x35,
                         0x93, 0xc7, 0xe3, 0xb6, 0x1f, 0x05, 0xb6, 0x38, 0x09, 0x57,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2316,7 +2537,8 @@ mod test {
                         0x3e, 0xef, 0xea, 0x71, 0xca, 0x65, 0xfb, 0x5d, 0x8f, 0x45, 0xf4,
                         0x33, 0x0b, 0x72, 0x68, 0xb4, 0xa4, 0x1d, 0x14, 0xa5,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2332,7 +2554,8 @@ mod test {
                         0x1f, 0x2d, 0x63, 0x72, 0x57, 0x10, 0xd1, 0x5c, 0x14, 0xfd, 0x88,
                         0xab, 0xf4, 0xec, 0x9c, 0xab, 0x05, 0x54, 0xc6, 0x33,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2349,7 +2572,8 @@ mod test {
                         0x92, 0xaf, 0x3d, 0x8a, 0x51, 0x0b, 0x1a, 0x64, 0x6c, 0x14, 0xa4,
                         0x96, 0x32, 0x43, 0x9b, 0x58, 0x7b, 0x16, 0xf8, 0xf7, 0xbe,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
                 Answer {
                     name: b"many-rrs.weberdns.de".to_vec(),
@@ -2365,7 +2589,8 @@ mod test {
                         0x5d, 0xed, 0xb2, 0x7f, 0x44, 0x8e, 0x1f, 0x5c, 0xbc, 0x67, 0x73,
                         0xbb, 0xc6, 0xc5, 0x0e, 0x51, 0xdf, 0xc1, 0xe1, 0x8d,
                         ]
-                    }))
+                    })),
+                    domain_name: None,
                 },
             ],
             nameservers: vec![],
@@ -2382,7 +2607,8 @@ mod test {
             version: 0,
             flags: 0,
             data: vec![]
-            })
+            }),
+            domain_name: None,
             }],
             error_flags: ErrorFlags::none()
             }
@@ -2491,6 +2717,7 @@ mod test {
                 record_type_raw: 249,
                 record_class: RecordClass::ANY,
                 record_class_raw: 255,
+                domain_name: None,
             }
         ],
         answers: vec![
@@ -2528,7 +2755,8 @@ mod test {
                     0xea, 0x42, 0xde, 0xc8, 0x57, 0x8a,
                     ],
                     other_data: vec![]
-                })
+                }),
+                domain_name: None,
             }
         ],
         nameservers: vec![],
@@ -2554,7 +2782,8 @@ mod test {
                     original_id: 564,
                     error: TSigResponseCode::NOERROR,
                     other_data: vec![]
-                })
+                }),
+                domain_name: None,
             }
         ],
         error_flags: ErrorFlags::none()
@@ -2614,6 +2843,7 @@ mod test {
                     record_type_raw: 257,
                     record_class: RecordClass::IN,
                     record_class_raw: 1,
+                    domain_name: None,
                 }],
                 answers: vec![Answer {
                     name: b"google.com".to_vec(),
@@ -2626,7 +2856,8 @@ mod test {
                         flags: 0,
                         tag: b"issue".to_vec(),
                         value: b"symantec.com".to_vec()
-                })
+                }),
+                    domain_name: None,
                 }],
                 nameservers: vec![],
                 additional: vec![],
@@ -2636,11 +2867,980 @@ mod test {
     ),
     )]
     fn dns(input: &[u8], expected: Result<(usize, Option<Message>)>) {
-        let dns = Dns {};
+        let dns = Dns::default();
         assert_eq!(
             dns.parse(input, Direction::Unknown)
                 .map(|(rem, msg)| (rem.len(), msg)),
             expected
         );
+        // Messages are parsed as datagrams, truncated ones are returned with error flags
+        assert_datagram(&dns, input, Direction::Unknown);
+    }
+
+    #[rstest(
+        limits,
+        expected_queries,
+        expected_flags,
+        case::default(Limits::default(), 2, ErrorFlags::none()),
+        case::max_elements(
+            Limits {
+                max_elements: 1,
+                ..Limits::default()
+            },
+            1,
+            ErrorFlags::LimitExceeded.into()
+        ),
+        case::max_alloc(
+            Limits {
+                max_alloc: 1,
+                ..Limits::default()
+            },
+            1,
+            ErrorFlags::LimitExceeded.into()
+        )
+    )]
+    fn dns_limits(limits: Limits, expected_queries: usize, expected_flags: Flags<ErrorFlags>) {
+        let input = [
+            0x31, 0x21, // Transaction ID: 0x3121
+            0x01, 0x00, // Flags: query, recursion desired
+            0x00, 0x02, // QDCOUNT: 2
+            0x00, 0x00, // ANCOUNT: 0
+            0x00, 0x00, // NSCOUNT: 0
+            0x00, 0x00, // ARCOUNT: 0
+            0x01, b'a', 0x00, // Name: a
+            0x00, 0x01, // Type: A
+            0x00, 0x01, // Class: IN
+            0x01, b'b', 0x01, b'c', 0x00, // Name: b.c
+            0x00, 0x01, // Type: A
+            0x00, 0x01, // Class: IN
+        ];
+        let dns = Dns {
+            limits,
+            ..Dns::default()
+        };
+        assert_datagram(&dns, &input, Direction::Unknown);
+        let (rem, message) = dns.parse(&input, Direction::Unknown).unwrap();
+        let message = message.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(message.queries.len(), expected_queries);
+        assert_eq!(message.error_flags, expected_flags);
+    }
+
+    #[test]
+    fn dns_label_limit() {
+        let input = [
+            0x31, 0x21, // Transaction ID: 0x3121
+            0x01, 0x00, // Flags: query, recursion desired
+            0x00, 0x02, // QDCOUNT: 2
+            0x00, 0x00, // ANCOUNT: 0
+            0x00, 0x00, // NSCOUNT: 0
+            0x00, 0x00, // ARCOUNT: 0
+            0x01, b'a', 0x00, // Name: a
+            0x00, 0x01, // Type: A
+            0x00, 0x01, // Class: IN
+            0xc0, 0x15, // Name: pointer to the next pointer
+            0xc0, 0x13, // pointer back to the previous one
+            0x00, 0x01, // Type: A
+            0x00, 0x01, // Class: IN
+        ];
+        let dns = Dns::default();
+        let (rem, message) = dns.parse(&input, Direction::Unknown).unwrap();
+        let message = message.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(message.queries.len(), 1);
+        assert_eq!(message.error_flags, ErrorFlags::LimitExceeded);
+    }
+
+    const QUERY: [u8; 19] = [
+        0x31, 0x21, // Transaction ID: 0x3121
+        0x01, 0x00, // Flags: query, recursion desired
+        0x00, 0x01, // QDCOUNT: 1
+        0x00, 0x00, // ANCOUNT: 0
+        0x00, 0x00, // NSCOUNT: 0
+        0x00, 0x00, // ARCOUNT: 0
+        0x01, b'a', 0x00, // Name: a
+        0x00, 0x01, // Type: A
+        0x00, 0x01, // Class: IN
+    ];
+
+    /// Prefixes the first `length` bytes of `message` with `prefix`
+    fn tcp(prefix: u16, message: &[u8], length: usize) -> Vec<u8> {
+        let mut out = prefix.to_be_bytes().to_vec();
+        out.extend_from_slice(&message[..length]);
+        out
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::message(
+            tcp(19, &QUERY, 19),
+            Ok((0, 1, ErrorFlags::none()))
+        ),
+        case::pipelined(
+            [tcp(19, &QUERY, 19), tcp(19, &QUERY, 19)].concat(),
+            Ok((21, 1, ErrorFlags::none()))
+        ),
+        case::partial_length(vec![0x00], Err(Error::incomplete_needed(1))),
+        case::partial_message(tcp(19, &QUERY, 15), Err(Error::incomplete_needed(4))),
+        case::trailing_bytes(
+            [tcp(21, &QUERY, 19), vec![0x00, 0x00]].concat(),
+            Ok((0, 1, ErrorFlags::LengthMismatch.into()))
+        ),
+        case::truncated_question(
+            tcp(15, &QUERY, 15),
+            Ok((0, 0, ErrorFlags::LengthMismatch.into()))
+        ),
+        case::shorter_than_header(
+            tcp(4, &QUERY, 4),
+            Ok((0, 0, ErrorFlags::LengthMismatch.into()))
+        ),
+        case::shorter_than_header_then_message(
+            [tcp(4, &QUERY, 4), tcp(19, &QUERY, 19)].concat(),
+            Ok((21, 0, ErrorFlags::LengthMismatch.into()))
+        )
+    )]
+    fn dns_tcp(input: Vec<u8>, expected: Result<(usize, usize, Flags<ErrorFlags>)>) {
+        let dns = Dns {
+            transport: Transport::Tcp,
+            ..Dns::default()
+        };
+        assert_eq!(
+            dns.parse(&input, Direction::ToServer)
+                .map(|(rem, message)| {
+                    let message = message.unwrap();
+                    (rem.len(), message.queries.len(), message.error_flags)
+                }),
+            expected
+        );
+    }
+
+    #[test]
+    fn dns_tcp_stream() {
+        let new = || Dns {
+            transport: Transport::Tcp,
+            ..Dns::default()
+        };
+        let input = [tcp(19, &QUERY, 19), tcp(19, &QUERY, 19)].concat();
+        let stream = assert_incremental(new, &input, Direction::ToServer);
+        assert_eq!(stream.messages.len(), 2);
+        assert_eq!(stream.consumed, input.len());
+    }
+
+    #[test]
+    fn dns_domain_names() {
+        let input = [
+            0x31, 0x21, // Transaction ID: 0x3121
+            0x81, 0x00, // Flags: response, recursion desired
+            0x00, 0x01, // QDCOUNT: 1
+            0x00, 0x01, // ANCOUNT: 1
+            0x00, 0x00, // NSCOUNT: 0
+            0x00, 0x00, // ARCOUNT: 0
+            0x03, b'a', b'.', b'b', 0x03, b'c', b'o', b'm', 0x00, // question: a\.b.com
+            0x00, 0x01, // RType: A
+            0x00, 0x01, // RClass: IN
+            0xc0, 0x0c, // answer: a\.b.com
+            0x00, 0x01, // RType: A
+            0x00, 0x01, // RClass: IN
+            0x00, 0x00, 0x27, 0x10, // TTL: 10000
+            0x00, 0x04, // Data length: 4
+            0xd4, 0xcc, 0xd6, 0x72, // 212.204.214.114
+        ];
+        let dns = Dns {
+            domain_names: true,
+            ..Dns::default()
+        };
+        let (_, message) = dns.parse(&input, Direction::ToClient).unwrap();
+        let message = message.unwrap();
+
+        // The dotted names are ambiguous
+        assert_eq!(message.queries[0].name, b"a.b.com");
+        let question = message.queries[0].domain_name.as_ref().unwrap();
+        assert_eq!(question.labels, vec![b"a.b".to_vec(), b"com".to_vec()]);
+        assert_eq!(question.offset, Some(12));
+        assert!(!question.is_compressed());
+
+        let answer = message.answers[0].domain_name.as_ref().unwrap();
+        assert_eq!(answer.to_string(), "a\\.b.com");
+        assert_eq!(answer.offset, Some(25));
+        assert_eq!(answer.pointers, vec![12]);
+        assert!(answer.eq_ignore_case(question));
+
+        let (_, message) = Dns::default().parse(&input, Direction::ToClient).unwrap();
+        assert_eq!(message.unwrap().answers[0].domain_name, None);
+    }
+
+    /// A response with a single record of the root name
+    fn response(rtype: RecordType, rdata: &[u8]) -> Vec<u8> {
+        let mut out = vec![
+            0x31, 0x21, // Transaction ID: 0x3121
+            0x81, 0x00, // Flags: response, recursion desired
+            0x00, 0x00, // QDCOUNT: 0
+            0x00, 0x01, // ANCOUNT: 1
+            0x00, 0x00, // NSCOUNT: 0
+            0x00, 0x00, // ARCOUNT: 0
+            0x00, // answer: <Root>
+        ];
+        out.extend_from_slice(&(rtype as u16).to_be_bytes());
+        out.extend_from_slice(&[
+            0x00, 0x01, // RClass: IN
+            0x00, 0x00, 0x0e, 0x10, // TTL: 3600
+        ]);
+        out.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        out.extend_from_slice(rdata);
+        out
+    }
+
+    #[rstest(
+        rtype,
+        rdata,
+        expected,
+        expected_flags,
+        case::ds(
+            RecordType::DS,
+            &[
+                0x4f, 0x66, // key tag: 20326
+                0x08, // algorithm: RSASHA256
+                0x02, // digest type: SHA256
+                0xe0, 0x6d, 0x44, 0xb8, // digest
+            ],
+            RDataType::DS(RDataDS {
+                key_tag: 20326,
+                algorithm: DnssecAlgorithm::RSASHA256,
+                digest_type: DigestType::SHA256,
+                digest: vec![0xe0, 0x6d, 0x44, 0xb8],
+            }),
+            ErrorFlags::none()
+        ),
+        case::cds(
+            RecordType::CDS,
+            &[
+                0x00, 0x00, // key tag: 0
+                0x00, // algorithm: delete
+                0x00, // digest type: reserved
+                0x00, // digest
+            ],
+            RDataType::CDS(RDataDS {
+                key_tag: 0,
+                algorithm: DnssecAlgorithm::RESERVED,
+                digest_type: DigestType::RESERVED,
+                digest: vec![0x00],
+            }),
+            ErrorFlags::none()
+        ),
+        case::dnskey(
+            RecordType::DNSKEY,
+            &[
+                0x01, 0x01, // flags: zone key, secure entry point
+                0x03, // protocol: 3
+                0x0d, // algorithm: ECDSAP256SHA256
+                0x99, 0xdb, 0x2c, 0xc1, // public key
+            ],
+            RDataType::DNSKEY(RDataDNSKEY {
+                flags: 0x0101,
+                protocol: 3,
+                algorithm: DnssecAlgorithm::ECDSAP256SHA256,
+                public_key: vec![0x99, 0xdb, 0x2c, 0xc1],
+            }),
+            ErrorFlags::none()
+        ),
+        case::cdnskey(
+            RecordType::CDNSKEY,
+            &[
+                0x01, 0x00, // flags: zone key
+                0x03, // protocol: 3
+                0x63, // algorithm: 99
+                0x01, // public key
+            ],
+            RDataType::CDNSKEY(RDataDNSKEY {
+                flags: 0x0100,
+                protocol: 3,
+                algorithm: DnssecAlgorithm::UNKNOWN,
+                public_key: vec![0x01],
+            }),
+            ErrorFlags::none()
+        ),
+        case::rrsig(
+            RecordType::RRSIG,
+            &[
+                0x00, 0x06, // type covered: SOA
+                0x05, // algorithm: RSASHA1
+                0x01, // labels: 1
+                0x00, 0x00, 0x0e, 0x10, // original TTL: 3600
+                0x65, 0x53, 0xf1, 0x00, // expiration: 1700000000
+                0x65, 0x2b, 0x64, 0x00, // inception: 1697342464
+                0x9d, 0x6f, // key tag: 40303
+                0x03, b'c', b'o', b'm', 0x00, // signer name: com
+                0x12, 0x34, // signature
+            ],
+            RDataType::RRSIG(RDataRRSIG {
+                type_covered: RecordType::SOA,
+                algorithm: DnssecAlgorithm::RSASHA1,
+                labels: 1,
+                original_ttl: 3600,
+                expiration: 1_700_000_000,
+                inception: 1_697_342_464,
+                key_tag: 40303,
+                signer_name: b"com".to_vec(),
+                signature: vec![0x12, 0x34],
+            }),
+            ErrorFlags::none()
+        ),
+        case::nsec(
+            RecordType::NSEC,
+            &[
+                0x01, b'b', 0x00, // next domain name: b
+                0x00, 0x06, 0x40, 0x01, 0x00, 0x00, 0x00, 0x03, // window 0: A MX RRSIG NSEC
+                0x01, 0x01, 0x40, // window 1: CAA
+            ],
+            RDataType::NSEC(RDataNSEC {
+                next_domain_name: b"b".to_vec(),
+                types: vec![
+                    RecordType::A,
+                    RecordType::MX,
+                    RecordType::RRSIG,
+                    RecordType::NSEC,
+                    RecordType::CAA,
+                ],
+            }),
+            ErrorFlags::none()
+        ),
+        case::nsec_windows_out_of_order(
+            RecordType::NSEC,
+            &[
+                0x00, // next domain name: <Root>
+                0x01, 0x01, 0x40, // window 1: CAA
+                0x00, 0x01, 0x40, // window 0: A
+            ],
+            RDataType::NSEC(RDataNSEC {
+                next_domain_name: vec![],
+                types: vec![RecordType::CAA, RecordType::A],
+            }),
+            ErrorFlags::InvalidTypeBitmap.into()
+        ),
+        case::nsec_empty_window(
+            RecordType::NSEC,
+            &[
+                0x00, // next domain name: <Root>
+                0x00, 0x00, // window 0: empty
+            ],
+            RDataType::NSEC(RDataNSEC {
+                next_domain_name: vec![],
+                types: vec![],
+            }),
+            ErrorFlags::InvalidTypeBitmap.into()
+        ),
+        case::nsec_window_past_rdata(
+            RecordType::NSEC,
+            &[
+                0x00, // next domain name: <Root>
+                0x00, 0x01, 0x40, // window 0: A
+                0x01, 0x05, 0x40, // window 1: 5 bytes long, 1 left
+            ],
+            RDataType::NSEC(RDataNSEC {
+                next_domain_name: vec![],
+                types: vec![RecordType::A],
+            }),
+            ErrorFlags::InvalidTypeBitmap.into()
+        ),
+        case::nsec_dangling_window(
+            RecordType::NSEC,
+            &[
+                0x00, // next domain name: <Root>
+                0x00, 0x01, 0x40, // window 0: A
+                0x01, // window 1 without a length
+            ],
+            RDataType::NSEC(RDataNSEC {
+                next_domain_name: vec![],
+                types: vec![RecordType::A],
+            }),
+            ErrorFlags::InvalidTypeBitmap.into()
+        ),
+        case::nsec3(
+            RecordType::NSEC3,
+            &[
+                0x01, // hash algorithm: SHA1
+                0x01, // flags: opt-out
+                0x00, 0x0a, // iterations: 10
+                0x02, 0xaa, 0xbb, // salt
+                0x03, 0x01, 0x02, 0x03, // next hashed owner name
+                0x00, 0x01, 0x40, // window 0: A
+            ],
+            RDataType::NSEC3(RDataNSEC3 {
+                hash_algorithm: Nsec3HashAlgorithm::SHA1,
+                flags: 1,
+                iterations: 10,
+                salt: vec![0xaa, 0xbb],
+                next_hashed_owner_name: vec![0x01, 0x02, 0x03],
+                types: vec![RecordType::A],
+            }),
+            ErrorFlags::none()
+        ),
+        case::nsec3_salt_past_rdata(
+            RecordType::NSEC3,
+            &[
+                0x01, // hash algorithm: SHA1
+                0x00, // flags: 0
+                0x00, 0x0a, // iterations: 10
+                0x05, 0xaa, 0xbb, // salt: 5 bytes long, 2 left
+            ],
+            RDataType::NSEC3(RDataNSEC3 {
+                hash_algorithm: Nsec3HashAlgorithm::SHA1,
+                flags: 0,
+                iterations: 10,
+                salt: vec![0xaa, 0xbb],
+                next_hashed_owner_name: vec![],
+                types: vec![],
+            }),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::nsec3_hash_past_rdata(
+            RecordType::NSEC3,
+            &[
+                0x01, // hash algorithm: SHA1
+                0x00, // flags: 0
+                0x00, 0x0a, // iterations: 10
+                0x00, // salt: empty
+                0x14, 0x01, 0x02, // next hashed owner name: 20 bytes long, 2 left
+            ],
+            RDataType::NSEC3(RDataNSEC3 {
+                hash_algorithm: Nsec3HashAlgorithm::SHA1,
+                flags: 0,
+                iterations: 10,
+                salt: vec![],
+                next_hashed_owner_name: vec![0x01, 0x02],
+                types: vec![],
+            }),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::nsec3param(
+            RecordType::NSEC3PARAM,
+            &[
+                0x01, // hash algorithm: SHA1
+                0x00, // flags: 0
+                0x00, 0x00, // iterations: 0
+                0x00, // salt: empty
+            ],
+            RDataType::NSEC3PARAM(RDataNSEC3PARAM {
+                hash_algorithm: Nsec3HashAlgorithm::SHA1,
+                flags: 0,
+                iterations: 0,
+                salt: vec![],
+            }),
+            ErrorFlags::none()
+        ),
+        case::nsec3param_salt_past_rdata(
+            RecordType::NSEC3PARAM,
+            &[
+                0x01, // hash algorithm: SHA1
+                0x00, // flags: 0
+                0x00, 0x00, // iterations: 0
+                0x04, 0xaa, // salt: 4 bytes long, 1 left
+            ],
+            RDataType::NSEC3PARAM(RDataNSEC3PARAM {
+                hash_algorithm: Nsec3HashAlgorithm::SHA1,
+                flags: 0,
+                iterations: 0,
+                salt: vec![0xaa],
+            }),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::ds_truncated(
+            RecordType::DS,
+            &[
+                0x30, 0x39, // key tag: 12345
+                0x08, // algorithm, no digest type
+            ],
+            RDataType::UNKNOWN(vec![0x30, 0x39, 0x08]),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::dnskey_truncated(
+            RecordType::DNSKEY,
+            &[
+                0x01, 0x01, // flags: 257
+            ],
+            RDataType::UNKNOWN(vec![0x01, 0x01]),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::rrsig_truncated(
+            RecordType::RRSIG,
+            &[
+                0x00, 0x01, // type covered: A
+                0x08, // algorithm: RSASHA256
+                0x02, // labels: 2
+                0x00, 0x00, 0x0e, 0x10, // original ttl: 3600, no expiration
+            ],
+            RDataType::UNKNOWN(vec![0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x0e, 0x10]),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::nsec3_truncated(
+            RecordType::NSEC3,
+            &[
+                0x01, // hash algorithm: SHA1
+                0x00, // flags: 0
+                0x00, // iterations, 1 byte of 2
+            ],
+            RDataType::UNKNOWN(vec![0x01, 0x00, 0x00]),
+            ErrorFlags::MalformedRdata.into()
+        ),
+        case::nsec3param_empty(
+            RecordType::NSEC3PARAM,
+            &[],
+            RDataType::UNKNOWN(vec![]),
+            ErrorFlags::MalformedRdata.into()
+        )
+    )]
+    fn dns_dnssec(
+        rtype: RecordType,
+        rdata: &[u8],
+        expected: RDataType,
+        expected_flags: Flags<ErrorFlags>,
+    ) {
+        let input = response(rtype, rdata);
+        let dns = Dns::default();
+        assert_datagram(&dns, &input, Direction::ToClient);
+        let (rem, message) = dns.parse(&input, Direction::ToClient).unwrap();
+        let message = message.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(message.answers[0].data, expected);
+        assert_eq!(message.error_flags, expected_flags);
+    }
+
+    #[test]
+    fn dns_type_bitmap_limit() {
+        let input = response(
+            RecordType::NSEC,
+            &[
+                0x00, // next domain name: <Root>
+                0x00, 0x01, 0x40, // window 0: A
+                0x01, 0x01, 0x40, // window 1: CAA
+            ],
+        );
+        let dns = Dns {
+            limits: Limits {
+                max_elements: 2,
+                ..Limits::default()
+            },
+            ..Dns::default()
+        };
+        let (rem, message) = dns.parse(&input, Direction::ToClient).unwrap();
+        let message = message.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(
+            message.answers[0].data,
+            RDataType::NSEC(RDataNSEC {
+                next_domain_name: vec![],
+                types: vec![RecordType::A],
+            })
+        );
+        assert_eq!(message.error_flags, ErrorFlags::LimitExceeded);
+    }
+
+    #[rstest(
+        rtype,
+        rdata,
+        expected,
+        expected_flags,
+        case::https(
+            RecordType::HTTPS,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x00, 0x00, 0x02, 0x00, 0x01, // mandatory: alpn
+                0x00, 0x01, 0x00, 0x06, 0x02, b'h', b'2', 0x02, b'h', b'3', // alpn: h2, h3
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+                0x00, 0x04, 0x00, 0x08, 0x68, 0x10, 0x84, 0xe5, 0x68, 0x10, 0x85, 0xe5, // ipv4hint
+                0x00, 0x05, 0x00, 0x03, 0x00, 0x01, 0xfe, // ech
+                0x00, 0x06, 0x00, 0x10, 0x26, 0x06, 0x47, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x68, 0x10, 0x84, 0xe5, // ipv6hint
+            ],
+            RDataType::HTTPS(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::MANDATORY,
+                        key_raw: 0,
+                        value: SvcParamValue::Mandatory(vec![1]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::ALPN,
+                        key_raw: 1,
+                        value: SvcParamValue::Alpn(vec![b"h2".to_vec(), b"h3".to_vec()]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(443),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::IPV4HINT,
+                        key_raw: 4,
+                        value: SvcParamValue::Ipv4Hint(vec![
+                            vec![0x68, 0x10, 0x84, 0xe5],
+                            vec![0x68, 0x10, 0x85, 0xe5],
+                        ]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::ECH,
+                        key_raw: 5,
+                        value: SvcParamValue::Ech(vec![0x00, 0x01, 0xfe]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::IPV6HINT,
+                        key_raw: 6,
+                        value: SvcParamValue::Ipv6Hint(vec![vec![
+                            0x26, 0x06, 0x47, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                            0x00, 0x68, 0x10, 0x84, 0xe5,
+                        ]]),
+                    },
+                ],
+            }),
+            ErrorFlags::none()
+        ),
+        case::alias(
+            RecordType::SVCB,
+            &[
+                0x00, 0x00, // priority: 0
+                0x03, b's', b'v', b'c', 0x00, // target name: svc
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 0,
+                target_name: b"svc".to_vec(),
+                params: vec![],
+            }),
+            ErrorFlags::none()
+        ),
+        case::unknown_key(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x00, 0x00, 0x02, 0xff, 0x00, // mandatory: key65280
+                0xff, 0x00, 0x00, 0x01, 0x2a, // key65280
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::MANDATORY,
+                        key_raw: 0,
+                        value: SvcParamValue::Mandatory(vec![65280]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::UNKNOWN,
+                        key_raw: 65280,
+                        value: SvcParamValue::Raw(vec![0x2a]),
+                    },
+                ],
+            }),
+            ErrorFlags::none()
+        ),
+        case::out_of_order(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+                0x00, 0x01, 0x00, 0x03, 0x02, b'h', b'2', // alpn: h2
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(443),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::ALPN,
+                        key_raw: 1,
+                        value: SvcParamValue::Alpn(vec![b"h2".to_vec()]),
+                    },
+                ],
+            }),
+            ErrorFlags::SvcParamKeyOrder.into()
+        ),
+        case::duplicate_key(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+                0x00, 0x03, 0x00, 0x02, 0x00, 0x50, // port: 80
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(443),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(80),
+                    },
+                ],
+            }),
+            ErrorFlags::SvcParamDuplicateKey.into()
+        ),
+        case::duplicate_key_out_of_order(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+                0x00, 0x01, 0x00, 0x03, 0x02, b'h', b'2', // alpn: h2
+                0x00, 0x03, 0x00, 0x02, 0x00, 0x50, // port: 80
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(443),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::ALPN,
+                        key_raw: 1,
+                        value: SvcParamValue::Alpn(vec![b"h2".to_vec()]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(80),
+                    },
+                ],
+            }),
+            ErrorFlags::SvcParamKeyOrder | ErrorFlags::SvcParamDuplicateKey
+        ),
+        case::mandatory_out_of_order(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x00, 0x00, 0x04, 0x00, 0x03, 0x00, 0x01, // mandatory: port, alpn
+                0x00, 0x01, 0x00, 0x03, 0x02, b'h', b'2', // alpn: h2
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::MANDATORY,
+                        key_raw: 0,
+                        value: SvcParamValue::Mandatory(vec![3, 1]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::ALPN,
+                        key_raw: 1,
+                        value: SvcParamValue::Alpn(vec![b"h2".to_vec()]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::PORT,
+                        key_raw: 3,
+                        value: SvcParamValue::Port(443),
+                    },
+                ],
+            }),
+            ErrorFlags::SvcParamInvalid.into()
+        ),
+        case::malformed_value(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x03, 0x00, 0x01, 0x01, // port: 1 byte
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![SvcParam {
+                    key: SvcParamKey::PORT,
+                    key_raw: 3,
+                    value: SvcParamValue::Raw(vec![0x01]),
+                }],
+            }),
+            ErrorFlags::SvcParamInvalid.into()
+        ),
+        case::missing_mandatory_key(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x00, 0x00, 0x02, 0x00, 0x03, // mandatory: port
+                0x00, 0x02, 0x00, 0x00, // no-default-alpn
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![
+                    SvcParam {
+                        key: SvcParamKey::MANDATORY,
+                        key_raw: 0,
+                        value: SvcParamValue::Mandatory(vec![3]),
+                    },
+                    SvcParam {
+                        key: SvcParamKey::NODEFAULTALPN,
+                        key_raw: 2,
+                        value: SvcParamValue::NoDefaultAlpn,
+                    },
+                ],
+            }),
+            ErrorFlags::SvcParamInvalid.into()
+        ),
+        case::value_past_rdata(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+                0x00, 0x04, 0x00, 0x08, 0x68, 0x10, // ipv4hint: 8 bytes long, 2 left
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![SvcParam {
+                    key: SvcParamKey::PORT,
+                    key_raw: 3,
+                    value: SvcParamValue::Port(443),
+                }],
+            }),
+            ErrorFlags::SvcParamInvalid.into()
+        ),
+        case::alias_with_params(
+            RecordType::SVCB,
+            &[
+                0x00, 0x00, // priority: 0
+                0x00, // target name: <Root>
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 0,
+                target_name: vec![],
+                params: vec![SvcParam {
+                    key: SvcParamKey::PORT,
+                    key_raw: 3,
+                    value: SvcParamValue::Port(443),
+                }],
+            }),
+            ErrorFlags::SvcParamInvalid.into()
+        ),
+        case::reserved_key(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0xff, 0xff, 0x00, 0x00, // key65535
+            ],
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![SvcParam {
+                    key: SvcParamKey::UNKNOWN,
+                    key_raw: 65535,
+                    value: SvcParamValue::Raw(vec![]),
+                }],
+            }),
+            ErrorFlags::SvcParamInvalid.into()
+        )
+    )]
+    fn dns_svcb(
+        rtype: RecordType,
+        rdata: &[u8],
+        expected: RDataType,
+        expected_flags: Flags<ErrorFlags>,
+    ) {
+        let input = response(rtype, rdata);
+        let dns = Dns::default();
+        assert_datagram(&dns, &input, Direction::ToClient);
+        let (rem, message) = dns.parse(&input, Direction::ToClient).unwrap();
+        let message = message.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(message.answers[0].data, expected);
+        assert_eq!(message.error_flags, expected_flags);
+    }
+
+    #[test]
+    fn dns_svcb_limit() {
+        let input = response(
+            RecordType::SVCB,
+            &[
+                0x00, 0x01, // priority: 1
+                0x00, // target name: <Root>
+                0x00, 0x01, 0x00, 0x03, 0x02, b'h', b'2', // alpn: h2
+                0x00, 0x03, 0x00, 0x02, 0x01, 0xbb, // port: 443
+            ],
+        );
+        let dns = Dns {
+            limits: Limits {
+                max_elements: 2,
+                ..Limits::default()
+            },
+            ..Dns::default()
+        };
+        let (rem, message) = dns.parse(&input, Direction::ToClient).unwrap();
+        let message = message.unwrap();
+        assert_eq!(rem.len(), 0);
+        assert_eq!(
+            message.answers[0].data,
+            RDataType::SVCB(RDataSVCB {
+                priority: 1,
+                target_name: vec![],
+                params: vec![SvcParam {
+                    key: SvcParamKey::ALPN,
+                    key_raw: 1,
+                    value: SvcParamValue::Alpn(vec![b"h2".to_vec()]),
+                }],
+            })
+        );
+        assert_eq!(message.error_flags, ErrorFlags::LimitExceeded);
+    }
+
+    #[cfg(feature = "visit")]
+    #[test]
+    fn dns_visit() {
+        use sawp::protocol::Protocol;
+        use sawp_visit::{Path, Value};
+
+        let input = [
+            0x31, 0x21, // Transaction ID: 0x3121
+            0x01, 0x00, // Flags: query, recursion desired
+            0x00, 0x02, // QDCOUNT: 2
+            0x00, 0x00, // ANCOUNT: 0
+            0x00, 0x00, // NSCOUNT: 0
+            0x00, 0x00, // ARCOUNT: 0
+            0x01, b'a', 0x00, // Name: a
+            0x00, 0x01, // Type: A
+            0x00, 0x01, // Class: IN
+            0x01, b'b', 0x01, b'c', 0x00, // Name: b.c
+            0x00, 0x1c, // Type: AAAA
+            0x00, 0x01, // Class: IN
+        ];
+        let (_, message) = Dns::default().parse(&input, Direction::Unknown).unwrap();
+        let message = message.unwrap();
+
+        let path: Path = "dns.queries[*].name".parse().unwrap();
+        let path = path.strip_prefix(Dns::name()).unwrap();
+        assert_eq!(
+            path.query(&message),
+            vec![Value::Bytes(b"a"), Value::Bytes(b"b.c")]
+        );
+        assert_eq!(
+            sawp_visit::query(&message, "queries[1].record_type").unwrap(),
+            vec![Value::Variant("AAAA")]
+        );
+        assert_eq!(
+            sawp_visit::query(&message, "header.transaction_id").unwrap(),
+            vec![Value::Uint(0x3121)]
+        );
     }
 }
@@ -1,25 +1,231 @@
+use alloc::{string::String, vec::Vec};
+
 use crate::ErrorFlags::DnsNameInvalidCompression;
 use crate::{ErrorFlags, IResult};
 use nom::error::ErrorKind;
 use nom::multi::length_data;
 use nom::number::streaming::{be_u16, be_u8};
 use sawp::error::NomError;
+use sawp::limits::Tracker;
 use sawp_flags::{Flag, Flags};
 
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
+
 // From RFC 1035: DNS labels cannot exceed 63 characters in length
 const DNS_MAX_LABEL_LEN: usize = 63;
 // Similar: DNS names cannot exceed length 255.
 const DNS_MAX_DOMAIN_LEN: usize = 255;
-// Since min label size is 2 bytes (length byte + minimum txt of 1), the maximum number of label parses needed to parse a domain would be 255/2
-const MAX_LABEL_PARSES: usize = 128;
+/// Since min label size is 2 bytes (length byte + minimum txt of 1), the maximum number of label
+/// parses needed to parse a domain would be 255/2. Bounds the number of labels and compression
+/// pointers followed for a single name, past it the message is flagged as exceeding a limit.
+pub const MAX_LABEL_PARSES: usize = 128;
 const MSG_COMPRESSION_FLAG: u8 = 0b1100_0000;
 const MSG_COMPRESSION_OFFSET_MASK: u16 = 0b0011_1111_1111_1111;
 
+/// A domain name as a sequence of labels, which may hold any byte including
+/// dots, along with where it was found in the message
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DomainName {
+    /// Labels from the leftmost, without their length byte. The root name has
+    /// no labels.
+    pub labels: Vec<Vec<u8>>,
+    /// Offset of the name in the message, `None` if it isn't part of the
+    /// message
+    pub offset: Option<usize>,
+    /// Offsets in the message which the compression pointers of the name
+    /// pointed to, in the order they were followed
+    pub pointers: Vec<usize>,
+}
+
+impl DomainName {
+    /// Parses a name, following its compression pointers into `reference_bytes`
+    pub fn parse<'b: 'i + 'r, 'i: 'r, 'r>(
+        reference_bytes: &'b [u8],
+        tracker: &'r Tracker,
+    ) -> impl FnMut(&'i [u8]) -> IResult<'i, (DomainName, Flags<ErrorFlags>)> + 'r {
+        move |input| {
+            let offset = DomainName::offset(input, reference_bytes);
+            let (rem, (labels, pointers, error_flags)) =
+                Name::parse_labels(input, reference_bytes, tracker)?;
+            Ok((
+                rem,
+                (DomainName::new(&labels, offset, pointers), error_flags),
+            ))
+        }
+    }
+
+    fn new(labels: &[&[u8]], offset: Option<usize>, pointers: Vec<usize>) -> Self {
+        DomainName {
+            labels: labels.iter().map(|label| label.to_vec()).collect(),
+            offset,
+            pointers,
+        }
+    }
+
+    /// Offset of `input` in the message, `None` if it isn't part of it
+    fn offset(input: &[u8], reference_bytes: &[u8]) -> Option<usize> {
+        (input.as_ptr() as usize)
+            .checked_sub(reference_bytes.as_ptr() as usize)
+            .filter(|offset| *offset < reference_bytes.len())
+    }
+
+    /// Number of bytes of the labels
+    pub fn len(&self) -> usize {
+        self.labels.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Whether the name was compressed
+    pub fn is_compressed(&self) -> bool {
+        !self.pointers.is_empty()
+    }
+
+    /// Compares names ignoring the case of ASCII letters, as DNS does
+    pub fn eq_ignore_case(&self, other: &DomainName) -> bool {
+        self.labels.len() == other.labels.len()
+            && self
+                .labels
+                .iter()
+                .zip(&other.labels)
+                .all(|(label, other)| label.eq_ignore_ascii_case(other))
+    }
+
+    /// The name with its internationalized labels, starting with `xn--`,
+    /// decoded from punycode. Labels which aren't valid are kept as is, with
+    /// bytes which aren't UTF-8 replaced by U+FFFD.
+    pub fn to_unicode(&self) -> String {
+        let labels: Vec<String> = self
+            .labels
+            .iter()
+            .map(|label| {
+                let decoded = match label.get(..4) {
+                    Some(prefix) if prefix.eq_ignore_ascii_case(b"xn--") => {
+                        punycode_decode(&label[4..])
+                    }
+                    _ => None,
+                };
+                decoded.unwrap_or_else(|| String::from_utf8_lossy(label).into_owned())
+            })
+            .collect();
+        labels.join(".")
+    }
+}
+
+/// Presentation format of RFC 1035 section 5.1: dots and backslashes within
+/// labels are escaped with a backslash and other bytes which aren't printable
+/// as `\DDD`. The root name is `.`.
+impl core::fmt::Display for DomainName {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.labels.is_empty() {
+            return write!(fmt, ".");
+        }
+
+        for (index, label) in self.labels.iter().enumerate() {
+            if index > 0 {
+                write!(fmt, ".")?;
+            }
+            for byte in label {
+                match byte {
+                    b'.' | b'\\' => write!(fmt, "\\{}", char::from(*byte))?,
+                    0x21..=0x7e => write!(fmt, "{}", char::from(*byte))?,
+                    _ => write!(fmt, "\\{:03}", byte)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+const PUNYCODE_BASE: u32 = 36;
+const PUNYCODE_TMIN: u32 = 1;
+const PUNYCODE_TMAX: u32 = 26;
+const PUNYCODE_SKEW: u32 = 38;
+const PUNYCODE_DAMP: u32 = 700;
+const PUNYCODE_INITIAL_BIAS: u32 = 72;
+const PUNYCODE_INITIAL_N: u32 = 128;
+
+/// Bias adaptation of RFC 3492 section 6.1
+fn punycode_adapt(delta: u32, points: u32, first: bool) -> u32 {
+    let mut delta = if first {
+        delta / PUNYCODE_DAMP
+    } else {
+        delta / 2
+    };
+    delta += delta / points;
+
+    let mut k = 0;
+    while delta > ((PUNYCODE_BASE - PUNYCODE_TMIN) * PUNYCODE_TMAX) / 2 {
+        delta /= PUNYCODE_BASE - PUNYCODE_TMIN;
+        k += PUNYCODE_BASE;
+    }
+    k + (PUNYCODE_BASE - PUNYCODE_TMIN + 1) * delta / (delta + PUNYCODE_SKEW)
+}
+
+/// Decodes a punycode label without its `xn--` prefix, as in RFC 3492
+/// section 6.2. Returns `None` if it isn't valid.
+fn punycode_decode(input: &[u8]) -> Option<String> {
+    let (basic, extended) = match input.iter().rposition(|byte| *byte == b'-') {
+        Some(delimiter) => (&input[..delimiter], &input[delimiter + 1..]),
+        None => (&input[..0], input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+
+    let mut output: Vec<char> = basic.iter().map(|byte| char::from(*byte)).collect();
+    let mut n = PUNYCODE_INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = PUNYCODE_INITIAL_BIAS;
+    let mut digits = extended.iter();
+
+    while digits.len() > 0 {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = PUNYCODE_BASE;
+        loop {
+            let digit = match digits.next()? {
+                byte @ b'a'..=b'z' => byte - b'a',
+                byte @ b'A'..=b'Z' => byte - b'A',
+                byte @ b'0'..=b'9' => byte - b'0' + 26,
+                _ => return None,
+            };
+            let digit = u32::from(digit);
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = if k <= bias {
+                PUNYCODE_TMIN
+            } else if k >= bias + PUNYCODE_TMAX {
+                PUNYCODE_TMAX
+            } else {
+                k - bias
+            };
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(PUNYCODE_BASE - threshold)?;
+            k += PUNYCODE_BASE;
+        }
+
+        let points = output.len() as u32 + 1;
+        bias = punycode_adapt(i - old_i, points, old_i == 0);
+        n = n.checked_add(i / points)?;
+        i %= points;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+
+    Some(output.into_iter().collect())
+}
+
 #[derive(Debug)]
 pub struct Name {}
 
 impl Name {
-    fn parse_label(input: &[u8]) -> IResult<(&[u8], Flags<ErrorFlags>)> {
+    fn parse_label(input: &[u8]) -> IResult<'_, (&[u8], Flags<ErrorFlags>)> {
         let (rem, label) = length_data::<_, _, sawp::error::NomError<&[u8]>, _>(be_u8)(input)?;
 
         let error_flags = if label.len() > DNS_MAX_LABEL_LEN {
@@ -38,12 +244,11 @@ impl Name {
     fn follow_compressed_segment<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
-    ) -> IResult<'a, &'a [u8]> {
+    ) -> IResult<'a, (&'a [u8], usize)> {
         let (rem, referenced_name_loc) = be_u16::<_, sawp::error::NomError<&[u8]>>(input)?;
-        if let Some(offset) = reference_bytes
-            .get(usize::from(referenced_name_loc) & MSG_COMPRESSION_OFFSET_MASK as usize..)
-        {
-            Ok((rem, offset))
+        let location = usize::from(referenced_name_loc & MSG_COMPRESSION_OFFSET_MASK);
+        if let Some(offset) = reference_bytes.get(location..) {
+            Ok((rem, (offset, location)))
         } else {
             Err(nom::Err::Error(NomError::new(
                 rem,
@@ -52,94 +257,147 @@ impl Name {
         }
     }
 
-    pub fn parse<'b: 'i + 'r, 'i: 'r, 'r>(
-        reference_bytes: &'b [u8],
-    ) -> impl FnMut(&'i [u8]) -> IResult<(Vec<u8>, Flags<ErrorFlags>)> + 'r {
-        move |mut input| {
-            let mut current_position = input;
-            let mut current_position_is_base = true;
-            let mut error_flags = ErrorFlags::none();
-            let mut name: Vec<u8> = Vec::new();
-
-            // Special case for zero-label string
-            if !input.is_empty() && input[0] == b'\0' {
-                return Ok((&input[1..], (vec![], ErrorFlags::none())));
+    /// Parses the labels of a name and the offsets its compression pointers
+    /// point to
+    #[allow(clippy::type_complexity)]
+    fn parse_labels<'i>(
+        mut input: &'i [u8],
+        reference_bytes: &'i [u8],
+        tracker: &Tracker,
+    ) -> IResult<'i, (Vec<&'i [u8]>, Vec<usize>, Flags<ErrorFlags>)> {
+        let mut current_position = input;
+        let mut current_position_is_base = true;
+        let mut error_flags = ErrorFlags::none();
+        let mut labels = Vec::new();
+        let mut pointers = Vec::new();
+
+        // Special case for zero-label string
+        if !input.is_empty() && input[0] == b'\0' {
+            return Ok((&input[1..], (labels, pointers, ErrorFlags::none())));
+        }
+
+        let mut label_parses = 0;
+        loop {
+            if current_position.is_empty() || current_position[0] == b'\0' {
+                break;
             }
 
-            for _ in 0..MAX_LABEL_PARSES {
-                if current_position.is_empty() || current_position[0] == b'\0' {
-                    break;
-                }
+            label_parses += 1;
+            if label_parses > MAX_LABEL_PARSES {
+                tracker.exceed();
+                return Err(nom::Err::Error(NomError::new(input, ErrorKind::Count)));
+            }
 
-                if Name::name_is_compressed(current_position[0]) {
-                    match Name::follow_compressed_segment(current_position, reference_bytes) {
-                        Ok((rem, offset)) => {
-                            if offset == current_position {
-                                // If the pointer points to itself, bail out and flag to avoid using MAX_LABEL_PARSES cycles
-                                error_flags |= DnsNameInvalidCompression;
-                                return Ok((rem, (name, error_flags)));
-                            }
-
-                            if current_position_is_base {
-                                input = rem;
-                                current_position_is_base = false;
-                            }
-                            current_position = offset;
+            if Name::name_is_compressed(current_position[0]) {
+                match Name::follow_compressed_segment(current_position, reference_bytes) {
+                    Ok((rem, (offset, location))) => {
+                        if offset == current_position {
+                            // If the pointer points to itself, bail out and flag to avoid using MAX_LABEL_PARSES cycles
+                            error_flags |= DnsNameInvalidCompression;
+                            return Ok((rem, (labels, pointers, error_flags)));
                         }
-                        Err(nom::Err::Error(NomError {
-                            input: pos,
-                            code: ErrorKind::Verify,
-                        })) => {
-                            error_flags |= ErrorFlags::DnsNameInvalidCompression;
-                            return Ok((pos, (name, error_flags)));
-                        }
-                        Err(e) => {
-                            return Err(e);
+
+                        if current_position_is_base {
+                            input = rem;
+                            current_position_is_base = false;
                         }
+                        pointers.push(location);
+                        current_position = offset;
                     }
-                } else {
-                    let (mut rem, (label, inner_error_flags)) =
-                        Name::parse_label(current_position)?;
-                    error_flags |= inner_error_flags;
-
-                    if name.len() < DNS_MAX_DOMAIN_LEN {
-                        // Truncate the label so the name won't exceed the max length
-                        let length =
-                            std::cmp::min(label.len(), (DNS_MAX_DOMAIN_LEN - 1) - name.len());
-                        // Check if we truncated
-                        if name.len() + label.len() + 1 > DNS_MAX_DOMAIN_LEN {
-                            error_flags |= ErrorFlags::DnsNameExceedsMaxLen;
-                        };
-                        // always extend
-                        if !name.is_empty() {
-                            name.push(b'.');
-                        }
-                        name.extend_from_slice(&label[..length]);
+                    Err(nom::Err::Error(NomError {
+                        input: pos,
+                        code: ErrorKind::Verify,
+                    })) => {
+                        error_flags |= ErrorFlags::DnsNameInvalidCompression;
+                        return Ok((pos, (labels, pointers, error_flags)));
                     }
+                    Err(e) => {
+                        return Err(e);
+                    }
+                }
+            } else {
+                let (mut rem, (label, inner_error_flags)) = Name::parse_label(current_position)?;
+                error_flags |= inner_error_flags;
+                labels.push(label);
 
-                    current_position = rem;
+                current_position = rem;
 
-                    if rem.first() == Some(&b'\0') {
-                        rem = &rem[1..];
-                    }
+                if rem.first() == Some(&b'\0') {
+                    rem = &rem[1..];
+                }
 
-                    if current_position_is_base {
-                        input = rem;
-                    }
+                if current_position_is_base {
+                    input = rem;
                 }
             }
+        }
+        Ok((input, (labels, pointers, error_flags)))
+    }
+
+    /// Parses a name as its labels joined by dots, truncated to the maximum
+    /// length of a name
+    pub fn parse<'b: 'i + 'r, 'i: 'r, 'r>(
+        reference_bytes: &'b [u8],
+        tracker: &'r Tracker,
+    ) -> impl FnMut(&'i [u8]) -> IResult<'i, (Vec<u8>, Flags<ErrorFlags>)> + 'r {
+        move |input| {
+            let (input, (labels, _, mut error_flags)) =
+                Name::parse_labels(input, reference_bytes, tracker)?;
+            let name = Name::join(&labels, &mut error_flags);
             Ok((input, (name, error_flags)))
         }
     }
+
+    /// Parses a name as in [`Name::parse`] and, if `domain_name` is set, as a
+    /// [`DomainName`] as well, walking its labels once
+    #[allow(clippy::type_complexity)]
+    pub fn parse_with_domain_name<'b: 'i + 'r, 'i: 'r, 'r>(
+        reference_bytes: &'b [u8],
+        tracker: &'r Tracker,
+        domain_name: bool,
+    ) -> impl FnMut(&'i [u8]) -> IResult<'i, (Vec<u8>, Option<DomainName>, Flags<ErrorFlags>)> + 'r
+    {
+        move |input| {
+            let offset = DomainName::offset(input, reference_bytes);
+            let (input, (labels, pointers, mut error_flags)) =
+                Name::parse_labels(input, reference_bytes, tracker)?;
+            let name = Name::join(&labels, &mut error_flags);
+            let domain_name = domain_name.then(|| DomainName::new(&labels, offset, pointers));
+            Ok((input, (name, domain_name, error_flags)))
+        }
+    }
+
+    /// Joins `labels` with dots, truncated to the maximum length of a name
+    fn join(labels: &[&[u8]], error_flags: &mut Flags<ErrorFlags>) -> Vec<u8> {
+        let mut name: Vec<u8> = Vec::new();
+
+        for label in labels {
+            if name.len() < DNS_MAX_DOMAIN_LEN {
+                // Truncate the label so the name won't exceed the max length
+                let length = core::cmp::min(label.len(), (DNS_MAX_DOMAIN_LEN - 1) - name.len());
+                // Check if we truncated
+                if name.len() + label.len() + 1 > DNS_MAX_DOMAIN_LEN {
+                    *error_flags |= ErrorFlags::DnsNameExceedsMaxLen;
+                };
+                // always extend
+                if !name.is_empty() {
+                    name.push(b'.');
+                }
+                name.extend_from_slice(&label[..length]);
+            }
+        }
+        name
+    }
 }
 
 #[cfg(test)]
 mod test {
     #![allow(clippy::type_complexity)]
 
-    use crate::name::Name;
+    use crate::name::{DomainName, Name};
     use crate::{ErrorFlags, IResult};
     use rstest::rstest;
+    use sawp::limits::Limits;
     use sawp_flags::{Flag, Flags};
 
     #[rstest(
@@ -492,6 +750,92 @@ mod test {
     ),
     )]
     fn name(input: &[u8], reference_bytes: &[u8], expected: IResult<(Vec<u8>, Flags<ErrorFlags>)>) {
-        assert_eq!(Name::parse(reference_bytes)(input), expected);
+        assert_eq!(
+            Name::parse(reference_bytes, &Limits::default().tracker())(input),
+            expected
+        );
+    }
+
+    fn domain_name(labels: &[&[u8]]) -> DomainName {
+        DomainName {
+            labels: labels.iter().map(|label| label.to_vec()).collect(),
+            ..DomainName::default()
+        }
+    }
+
+    #[test]
+    fn domain_name_compressed() {
+        let reference_bytes = [
+            0x31, 0x21, // Transaction ID: 0x3121
+            0x81, 0x00, // Flags: response, recursion desired
+            0x00, 0x01, // QDCOUNT: 1
+            0x00, 0x01, // ANCOUNT: 1
+            0x00, 0x00, // NSCOUNT: 0
+            0x00, 0x00, // ARCOUNT: 0
+            0x03, b'w', b'w', b'w', 0x07, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 0x03, b'c',
+            b'o', b'm', 0x00, // question: www.example.com
+            0x00, 0x01, // RType: A
+            0x00, 0x01, // RClass: IN
+            0x04, b'm', b'a', b'i', b'l', 0xc0, 0x10, // answer: mail.example.com
+            0x00, 0x01, // RType: A
+        ];
+        let limits = Limits::default();
+        let tracker = limits.tracker();
+
+        let (rem, (name, error_flags)) =
+            DomainName::parse(&reference_bytes, &tracker)(&reference_bytes[33..]).unwrap();
+        assert_eq!(rem, &[0x00, 0x01]);
+        assert_eq!(error_flags, ErrorFlags::none());
+        assert_eq!(
+            name,
+            DomainName {
+                labels: vec![b"mail".to_vec(), b"example".to_vec(), b"com".to_vec()],
+                offset: Some(33),
+                pointers: vec![16],
+            }
+        );
+        assert!(name.is_compressed());
+        assert_eq!(name.len(), 14);
+
+        let (_, (question, _)) =
+            DomainName::parse(&reference_bytes, &tracker)(&reference_bytes[12..]).unwrap();
+        assert_eq!(question.offset, Some(12));
+        assert!(!question.is_compressed());
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case::root(domain_name(&[]), "."),
+        case::simple(domain_name(&[b"www", b"example", b"com"]), "www.example.com"),
+        case::dotted_label(domain_name(&[b"a.b", b"com"]), "a\\.b.com"),
+        case::binary_label(domain_name(&[b"a\x00 \\", b"com"]), "a\\000\\032\\\\.com")
+    )]
+    fn domain_name_display(name: DomainName, expected: &str) {
+        assert_eq!(name.to_string(), expected);
+    }
+
+    #[test]
+    fn domain_name_eq_ignore_case() {
+        let name = domain_name(&[b"WWW", b"Example", b"com"]);
+        assert!(name.eq_ignore_case(&domain_name(&[b"www", b"example", b"COM"])));
+        assert!(!name.eq_ignore_case(&domain_name(&[b"www", b"example"])));
+        assert!(!name.eq_ignore_case(&domain_name(&[b"www.example", b"com"])));
+        assert_ne!(name, domain_name(&[b"www", b"example", b"com"]));
+    }
+
+    #[rstest(
+        name,
+        expected,
+        case::ascii(domain_name(&[b"example", b"com"]), "example.com"),
+        case::bucher(domain_name(&[b"xn--bcher-kva", b"example"]), "b\u{fc}cher.example"),
+        case::upper_case_prefix(domain_name(&[b"XN--mnchen-3ya", b"de"]), "m\u{fc}nchen.de"),
+        case::no_basic(domain_name(&[b"xn--fiqs8s"]), "\u{4e2d}\u{56fd}"),
+        case::invalid_punycode(domain_name(&[b"xn--a!b", b"com"]), "xn--a!b.com"),
+        case::truncated_punycode(domain_name(&[b"xn--bcher-kv"]), "xn--bcher-kv"),
+        case::invalid_utf8(domain_name(&[b"\xff", b"com"]), "\u{fffd}.com")
+    )]
+    fn domain_name_to_unicode(name: DomainName, expected: &str) {
+        assert_eq!(name.to_unicode(), expected);
     }
 }
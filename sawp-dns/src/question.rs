@@ -1,16 +1,24 @@
 use nom::number::streaming::be_u16;
 
+use nom::error::ErrorKind;
+use sawp::error::NomError;
+use sawp::limits::Tracker;
 use sawp_flags::{Flag, Flags};
 
+use alloc::vec::Vec;
+
 use crate::enums::{RecordClass, RecordType};
-use crate::{custom_count, ErrorFlags, IResult, Name};
+use crate::{custom_count, DomainName, ErrorFlags, IResult, Name};
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 /// A parsed DNS question
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Question {
     pub name: Vec<u8>,
@@ -20,14 +28,23 @@ pub struct Question {
     #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
     pub record_class: RecordClass,
     pub record_class_raw: u16,
+    /// The name with its labels, if enabled by [`crate::Dns::domain_names`]
+    #[cfg_attr(feature = "ffi", sawp_ffi(skip))]
+    pub domain_name: Option<DomainName>,
 }
 
 impl Question {
     fn parse<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
+        domain_names: bool,
     ) -> IResult<'a, (Question, Flags<ErrorFlags>)> {
-        let (input, (name, mut error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (name, domain_name, mut error_flags)) =
+            Name::parse_with_domain_name(reference_bytes, tracker, domain_names)(input)?;
+        if !tracker.alloc(name.len() + domain_name.as_ref().map_or(0, DomainName::len)) {
+            return Err(nom::Err::Error(NomError::new(input, ErrorKind::Count)));
+        }
         let (input, working_qtype) = be_u16(input)?;
         let qtype: RecordType = RecordType::from_raw(working_qtype);
         if qtype == RecordType::UNKNOWN {
@@ -49,6 +66,7 @@ impl Question {
                     record_class_raw: working_qclass,
                     record_type: qtype,
                     record_type_raw: working_qtype,
+                    domain_name,
                 },
                 error_flags,
             ),
@@ -59,12 +77,18 @@ impl Question {
         input: &'a [u8],
         reference_bytes: &'a [u8],
         qdcnt: usize,
+        tracker: &Tracker,
+        domain_names: bool,
     ) -> IResult<'a, (Vec<Question>, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
 
         let (input, questions) = custom_count(
             |input, reference_bytes| {
-                let (input, (answer, inner_error_flags)) = Question::parse(input, reference_bytes)?;
+                if !tracker.element() {
+                    return Err(nom::Err::Error(NomError::new(input, ErrorKind::Count)));
+                }
+                let (input, (answer, inner_error_flags)) =
+                    Question::parse(input, reference_bytes, tracker, domain_names)?;
                 error_flags |= inner_error_flags;
                 Ok((input, answer))
             },
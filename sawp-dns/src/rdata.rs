@@ -5,16 +5,26 @@ use sawp_flags::{Flag, Flags};
 
 use byteorder::{BigEndian, ByteOrder};
 
+use alloc::vec::Vec;
+
 use crate::edns::EdnsOption;
-use crate::enums::{RecordType, SshfpAlgorithm, SshfpFingerprint, TSigResponseCode, TkeyMode};
+use crate::enums::{
+    DigestType, DnssecAlgorithm, Nsec3HashAlgorithm, RecordType, SshfpAlgorithm, SshfpFingerprint,
+    TSigResponseCode, TkeyMode,
+};
 
+use crate::svcb::RDataSVCB;
 use crate::{ErrorFlags, IResult, Name};
 use nom::combinator::rest;
+use sawp::limits::Tracker;
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataCAA {
     pub flags: u8,
@@ -22,8 +32,79 @@ pub struct RDataCAA {
     pub value: Vec<u8>,
 }
 
+/// DS and CDS records
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataDS {
+    /// Key tag of the DNSKEY referred to
+    pub key_tag: u16,
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub algorithm: DnssecAlgorithm,
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub digest_type: DigestType,
+    pub digest: Vec<u8>,
+}
+
+/// DNSKEY and CDNSKEY records
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataDNSKEY {
+    pub flags: u16, // bit [7] = Zone Key. bit [8] = Revoked. bit [15] = Secure Entry Point.
+    /// Always 3
+    pub protocol: u8,
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub algorithm: DnssecAlgorithm,
+    pub public_key: Vec<u8>,
+}
+
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataNSEC {
+    pub next_domain_name: Vec<u8>,
+    /// Types present at the owner name
+    pub types: Vec<RecordType>,
+}
+
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataNSEC3 {
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub hash_algorithm: Nsec3HashAlgorithm,
+    pub flags: u8, // bit [7] = Opt-Out. bit[0..6] are reserved.
+    /// Additional hash iterations
+    pub iterations: u16,
+    pub salt: Vec<u8>,
+    /// Hash of the next owner name, not base32 encoded
+    pub next_hashed_owner_name: Vec<u8>,
+    /// Types present at the original owner name
+    pub types: Vec<RecordType>,
+}
+
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataNSEC3PARAM {
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub hash_algorithm: Nsec3HashAlgorithm,
+    /// Always 0 in NSEC3PARAM records
+    pub flags: u8,
+    /// Additional hash iterations
+    pub iterations: u16,
+    pub salt: Vec<u8>,
+}
+
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataOPT {
     /// Requestor's UDP payload size
@@ -37,6 +118,30 @@ pub struct RDataOPT {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataRRSIG {
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    /// Type of the RRset covered by the signature
+    pub type_covered: RecordType,
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub algorithm: DnssecAlgorithm,
+    /// Number of labels in the original owner name
+    pub labels: u8,
+    pub original_ttl: u32,
+    /// Time signature expires - seconds since epoch, modulo 2^32
+    pub expiration: u32,
+    /// Time signature incepted - seconds since epoch, modulo 2^32
+    pub inception: u32,
+    /// Key tag of the DNSKEY which made the signature
+    pub key_tag: u16,
+    pub signer_name: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataSoa {
     /// Primary NS for this zone
@@ -57,6 +162,7 @@ pub struct RDataSoa {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataSSHFP {
     #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
@@ -69,6 +175,7 @@ pub struct RDataSSHFP {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataSRV {
     pub priority: u16,
@@ -79,6 +186,7 @@ pub struct RDataSRV {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataTKEY {
     pub algorithm: Vec<u8>,
@@ -96,6 +204,7 @@ pub struct RDataTKEY {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct RDataTSIG {
     pub algorithm_name: Vec<u8>,
@@ -115,6 +224,7 @@ pub struct RDataTSIG {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum RDataType {
     /// Addresses
@@ -131,9 +241,21 @@ pub enum RDataType {
     /// Multiple field records
     CAA(RDataCAA),
     OPT(RDataOPT),
+    /// DNSSEC
+    DS(RDataDS),
+    CDS(RDataDS),
+    DNSKEY(RDataDNSKEY),
+    CDNSKEY(RDataDNSKEY),
+    RRSIG(RDataRRSIG),
+    NSEC(RDataNSEC),
+    NSEC3(RDataNSEC3),
+    NSEC3PARAM(RDataNSEC3PARAM),
     SOA(RDataSoa),
     SRV(RDataSRV),
     SSHFP(RDataSSHFP),
+    /// Service binding
+    SVCB(RDataSVCB),
+    HTTPS(RDataSVCB),
     TKEY(RDataTKEY),
     TSIG(RDataTSIG),
     UNKNOWN(Vec<u8>),
@@ -144,6 +266,7 @@ impl RDataType {
         input: &'a [u8],
         reference_bytes: &'a [u8],
         rtype: RecordType,
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
         match rtype {
             RecordType::A => RDataType::parse_rdata_a(input)
@@ -152,19 +275,33 @@ impl RDataType {
                 .map(|(input, rdata)| (input, (rdata, ErrorFlags::none()))),
             RecordType::CAA => RDataType::parse_rdata_caa(input)
                 .map(|(input, rdata)| (input, (rdata, ErrorFlags::none()))),
-            RecordType::CNAME => RDataType::parse_rdata_cname(input, reference_bytes),
-            RecordType::MX => RDataType::parse_rdata_mx(input, reference_bytes),
-            RecordType::NS => RDataType::parse_rdata_ns(input, reference_bytes),
+            RecordType::CDNSKEY => RDataType::parse_rdata_dnskey(input, RDataType::CDNSKEY),
+            RecordType::CDS => RDataType::parse_rdata_ds(input, RDataType::CDS),
+            RecordType::CNAME => RDataType::parse_rdata_cname(input, reference_bytes, tracker),
+            RecordType::DNSKEY => RDataType::parse_rdata_dnskey(input, RDataType::DNSKEY),
+            RecordType::DS => RDataType::parse_rdata_ds(input, RDataType::DS),
+            RecordType::HTTPS => RDataSVCB::parse(input, reference_bytes, tracker).map(
+                |(input, (rdata, error_flags))| (input, (RDataType::HTTPS(rdata), error_flags)),
+            ),
+            RecordType::MX => RDataType::parse_rdata_mx(input, reference_bytes, tracker),
+            RecordType::NS => RDataType::parse_rdata_ns(input, reference_bytes, tracker),
+            RecordType::NSEC => RDataType::parse_rdata_nsec(input, reference_bytes, tracker),
+            RecordType::NSEC3 => RDataType::parse_rdata_nsec3(input, tracker),
+            RecordType::NSEC3PARAM => RDataType::parse_rdata_nsec3param(input),
             RecordType::NUL => RDataType::parse_rdata_null(input)
                 .map(|(input, rdata)| (input, (rdata, ErrorFlags::none()))),
             RecordType::OPT => RDataType::parse_rdata_opt(input),
-            RecordType::PTR => RDataType::parse_rdata_ptr(input, reference_bytes),
-            RecordType::SOA => RDataType::parse_rdata_soa(input, reference_bytes),
-            RecordType::SRV => RDataType::parse_rdata_srv(input, reference_bytes),
+            RecordType::PTR => RDataType::parse_rdata_ptr(input, reference_bytes, tracker),
+            RecordType::RRSIG => RDataType::parse_rdata_rrsig(input, reference_bytes, tracker),
+            RecordType::SOA => RDataType::parse_rdata_soa(input, reference_bytes, tracker),
+            RecordType::SRV => RDataType::parse_rdata_srv(input, reference_bytes, tracker),
             RecordType::SSHFP => RDataType::parse_rdata_sshfp(input)
                 .map(|(input, rdata)| (input, (rdata, ErrorFlags::none()))),
-            RecordType::TKEY => RDataType::parse_rdata_tkey(input, reference_bytes),
-            RecordType::TSIG => RDataType::parse_rdata_tsig(input, reference_bytes),
+            RecordType::SVCB => RDataSVCB::parse(input, reference_bytes, tracker).map(
+                |(input, (rdata, error_flags))| (input, (RDataType::SVCB(rdata), error_flags)),
+            ),
+            RecordType::TKEY => RDataType::parse_rdata_tkey(input, reference_bytes, tracker),
+            RecordType::TSIG => RDataType::parse_rdata_tsig(input, reference_bytes, tracker),
             RecordType::TXT => RDataType::parse_rdata_txt(input)
                 .map(|(input, rdata)| (input, (rdata, ErrorFlags::none()))),
             _ => RDataType::parse_rdata_unknown(input)
@@ -172,17 +309,17 @@ impl RDataType {
         }
     }
 
-    fn parse_rdata_a(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_a(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, data) = rest(input)?;
         Ok((input, RDataType::A(data.to_vec())))
     }
 
-    fn parse_rdata_aaaa(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_aaaa(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, data) = rest(input)?;
         Ok((input, RDataType::AAAA(data.to_vec())))
     }
 
-    fn parse_rdata_caa(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_caa(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, flags) = be_u8(input)?;
         let (input, tag_length) = be_u8(input)?;
         let (input, tag) = take(tag_length)(input)?;
@@ -201,28 +338,31 @@ impl RDataType {
     fn parse_rdata_cname<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
-        let (input, (name, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (name, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
         Ok((input, (RDataType::CNAME(name), error_flags)))
     }
 
     fn parse_rdata_ns<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
-        let (input, (name, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (name, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
         Ok((input, (RDataType::NS(name), error_flags)))
     }
 
     fn parse_rdata_ptr<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
-        let (input, (name, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (name, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
         Ok((input, (RDataType::PTR(name), error_flags)))
     }
 
-    pub fn parse_rdata_opt(input: &[u8]) -> IResult<(RDataType, Flags<ErrorFlags>)> {
+    pub fn parse_rdata_opt(input: &[u8]) -> IResult<'_, (RDataType, Flags<ErrorFlags>)> {
         let (input, udp_payload_size) = be_u16(input)?;
         let (input, extended_rcode) = be_u8(input)?;
         let (input, version) = be_u8(input)?;
@@ -248,9 +388,10 @@ impl RDataType {
     fn parse_rdata_soa<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
-        let (input, (mname, mut error_flags)) = Name::parse(reference_bytes)(input)?;
-        let (input, (rname, inner_error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (mname, mut error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
+        let (input, (rname, inner_error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
 
         error_flags |= inner_error_flags;
 
@@ -280,8 +421,9 @@ impl RDataType {
     fn parse_rdata_tkey<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
-        let (input, (algorithm, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (algorithm, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
         let (input, inception) = be_u32(input)?;
         let (input, expiration) = be_u32(input)?;
         let (input, mode) = be_u16(input)?;
@@ -311,8 +453,9 @@ impl RDataType {
     fn parse_rdata_tsig<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
-        let (input, (algorithm_name, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (algorithm_name, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
         let (input, time_signed_raw) = take(6_usize)(input)?;
         let (input, fudge) = be_u16(input)?;
         let (input, mac_size) = be_u16(input)?;
@@ -342,21 +485,23 @@ impl RDataType {
     fn parse_rdata_mx<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
         // Skip the preference field
         let (input, _) = be_u16(input)?;
-        let (input, (name, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (name, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
         Ok((input, (RDataType::MX(name), error_flags)))
     }
 
     fn parse_rdata_srv<'a>(
         input: &'a [u8],
         reference_bytes: &'a [u8],
+        tracker: &Tracker,
     ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
         let (input, priority) = be_u16(input)?;
         let (input, weight) = be_u16(input)?;
         let (input, port) = be_u16(input)?;
-        let (input, (target, error_flags)) = Name::parse(reference_bytes)(input)?;
+        let (input, (target, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
 
         Ok((
             input,
@@ -372,18 +517,18 @@ impl RDataType {
         ))
     }
 
-    fn parse_rdata_txt(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_txt(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, len) = be_u8(input)?;
         let (input, txt) = take(len)(input)?;
         Ok((input, RDataType::TXT(txt.to_vec())))
     }
 
-    fn parse_rdata_null(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_null(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, data) = rest(input)?;
         Ok((input, RDataType::NUL(data.to_vec())))
     }
 
-    fn parse_rdata_sshfp(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_sshfp(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, algorithm) = be_u8(input)?;
         let (input, fingerprint_type) = be_u8(input)?;
         let (input, fingerprint) = rest(input)?;
@@ -398,8 +543,270 @@ impl RDataType {
         ))
     }
 
-    fn parse_rdata_unknown(input: &[u8]) -> IResult<RDataType> {
+    fn parse_rdata_ds(
+        input: &[u8],
+        rdata: fn(RDataDS) -> RDataType,
+    ) -> IResult<'_, (RDataType, Flags<ErrorFlags>)> {
+        if input.len() < 4 {
+            return Ok(malformed_rdata(input));
+        }
+        let (input, key_tag) = be_u16(input)?;
+        let (input, algorithm) = be_u8(input)?;
+        let (input, digest_type) = be_u8(input)?;
+        let (input, digest) = rest(input)?;
+
+        Ok((
+            input,
+            (
+                rdata(RDataDS {
+                    key_tag,
+                    algorithm: DnssecAlgorithm::from_raw(algorithm),
+                    digest_type: DigestType::from_raw(digest_type),
+                    digest: digest.to_vec(),
+                }),
+                ErrorFlags::none(),
+            ),
+        ))
+    }
+
+    fn parse_rdata_dnskey(
+        input: &[u8],
+        rdata: fn(RDataDNSKEY) -> RDataType,
+    ) -> IResult<'_, (RDataType, Flags<ErrorFlags>)> {
+        if input.len() < 4 {
+            return Ok(malformed_rdata(input));
+        }
+        let (input, flags) = be_u16(input)?;
+        let (input, protocol) = be_u8(input)?;
+        let (input, algorithm) = be_u8(input)?;
+        let (input, public_key) = rest(input)?;
+
+        Ok((
+            input,
+            (
+                rdata(RDataDNSKEY {
+                    flags,
+                    protocol,
+                    algorithm: DnssecAlgorithm::from_raw(algorithm),
+                    public_key: public_key.to_vec(),
+                }),
+                ErrorFlags::none(),
+            ),
+        ))
+    }
+
+    fn parse_rdata_rrsig<'a>(
+        input: &'a [u8],
+        reference_bytes: &'a [u8],
+        tracker: &Tracker,
+    ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
+        if input.len() < 18 {
+            return Ok(malformed_rdata(input));
+        }
+        let (input, type_covered) = be_u16(input)?;
+        let (input, algorithm) = be_u8(input)?;
+        let (input, labels) = be_u8(input)?;
+        let (input, original_ttl) = be_u32(input)?;
+        let (input, expiration) = be_u32(input)?;
+        let (input, inception) = be_u32(input)?;
+        let (input, key_tag) = be_u16(input)?;
+        let (input, (signer_name, error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
+        let (input, signature) = rest(input)?;
+
+        Ok((
+            input,
+            (
+                RDataType::RRSIG(RDataRRSIG {
+                    type_covered: RecordType::from_raw(type_covered),
+                    algorithm: DnssecAlgorithm::from_raw(algorithm),
+                    labels,
+                    original_ttl,
+                    expiration,
+                    inception,
+                    key_tag,
+                    signer_name,
+                    signature: signature.to_vec(),
+                }),
+                error_flags,
+            ),
+        ))
+    }
+
+    fn parse_rdata_nsec<'a>(
+        input: &'a [u8],
+        reference_bytes: &'a [u8],
+        tracker: &Tracker,
+    ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
+        let (input, (next_domain_name, mut error_flags)) =
+            Name::parse(reference_bytes, tracker)(input)?;
+        let (input, (types, bitmap_error_flags)) = parse_type_bitmap(input, tracker);
+        error_flags |= bitmap_error_flags;
+
+        Ok((
+            input,
+            (
+                RDataType::NSEC(RDataNSEC {
+                    next_domain_name,
+                    types,
+                }),
+                error_flags,
+            ),
+        ))
+    }
+
+    fn parse_rdata_nsec3<'a>(
+        input: &'a [u8],
+        tracker: &Tracker,
+    ) -> IResult<'a, (RDataType, Flags<ErrorFlags>)> {
+        if input.len() < 5 {
+            return Ok(malformed_rdata(input));
+        }
+        let mut error_flags = ErrorFlags::none();
+        let (input, hash_algorithm) = be_u8(input)?;
+        let (input, flags) = be_u8(input)?;
+        let (input, iterations) = be_u16(input)?;
+        let (input, salt_length) = be_u8(input)?;
+        let (input, salt) = take_rdata(input, salt_length, &mut error_flags);
+        let (input, next_hashed_owner_name) = match input.split_first() {
+            Some((&hash_length, input)) => take_rdata(input, hash_length, &mut error_flags),
+            None => {
+                error_flags |= ErrorFlags::MalformedRdata;
+                (input, input)
+            }
+        };
+        let (input, (types, bitmap_error_flags)) = parse_type_bitmap(input, tracker);
+        error_flags |= bitmap_error_flags;
+
+        Ok((
+            input,
+            (
+                RDataType::NSEC3(RDataNSEC3 {
+                    hash_algorithm: Nsec3HashAlgorithm::from_raw(hash_algorithm),
+                    flags,
+                    iterations,
+                    salt: salt.to_vec(),
+                    next_hashed_owner_name: next_hashed_owner_name.to_vec(),
+                    types,
+                }),
+                error_flags,
+            ),
+        ))
+    }
+
+    fn parse_rdata_nsec3param(input: &[u8]) -> IResult<'_, (RDataType, Flags<ErrorFlags>)> {
+        if input.len() < 5 {
+            return Ok(malformed_rdata(input));
+        }
+        let mut error_flags = ErrorFlags::none();
+        let (input, hash_algorithm) = be_u8(input)?;
+        let (input, flags) = be_u8(input)?;
+        let (input, iterations) = be_u16(input)?;
+        let (input, salt_length) = be_u8(input)?;
+        let (input, salt) = take_rdata(input, salt_length, &mut error_flags);
+
+        Ok((
+            input,
+            (
+                RDataType::NSEC3PARAM(RDataNSEC3PARAM {
+                    hash_algorithm: Nsec3HashAlgorithm::from_raw(hash_algorithm),
+                    flags,
+                    iterations,
+                    salt: salt.to_vec(),
+                }),
+                error_flags,
+            ),
+        ))
+    }
+
+    fn parse_rdata_unknown(input: &[u8]) -> IResult<'_, RDataType> {
         let (input, data) = rest(input)?;
         Ok((input, RDataType::UNKNOWN(data.to_vec())))
     }
 }
+
+/// Maximum length of a window of a type bitmap
+const MAX_WINDOW_LENGTH: u8 = 32;
+
+/// Parses the type bitmap ending the rdata of NSEC and NSEC3 records into the
+/// list of types it contains. Each window holds a bit per type of a block of
+/// 256, blocks must be in increasing order and be 1 to 32 bytes long.
+///
+/// `input` is the rest of the rdata. A window running past it is flagged and
+/// dropped along with the rest of the bitmap, as are the types past the
+/// element limit.
+fn parse_type_bitmap<'a>(
+    mut input: &'a [u8],
+    tracker: &Tracker,
+) -> (&'a [u8], (Vec<RecordType>, Flags<ErrorFlags>)) {
+    let mut types = Vec::new();
+    let mut error_flags = ErrorFlags::none();
+    let mut last_window: Option<u8> = None;
+
+    while let [window, length, rem @ ..] = input {
+        let (window, length) = (*window, *length);
+        if usize::from(length) > rem.len() {
+            error_flags |= ErrorFlags::InvalidTypeBitmap;
+            return (&[], (types, error_flags));
+        }
+        let (bitmap, rem) = rem.split_at(usize::from(length));
+        input = rem;
+
+        if length == 0
+            || length > MAX_WINDOW_LENGTH
+            || matches!(last_window, Some(last) if last >= window)
+        {
+            error_flags |= ErrorFlags::InvalidTypeBitmap;
+        }
+        last_window = Some(window);
+
+        let bitmap = &bitmap[..bitmap.len().min(usize::from(MAX_WINDOW_LENGTH))];
+        let count = bitmap.iter().map(|byte| byte.count_ones() as usize).sum();
+        if !tracker.elements(count) {
+            error_flags |= ErrorFlags::LimitExceeded;
+            return (&[], (types, error_flags));
+        }
+
+        for (index, byte) in bitmap.iter().enumerate() {
+            for bit in 0..8 {
+                if byte & (0x80 >> bit) != 0 {
+                    let rtype = u16::from(window) << 8 | (index as u16) << 3 | bit;
+                    types.push(RecordType::from_raw(rtype));
+                }
+            }
+        }
+    }
+
+    // A window number without its length
+    if !input.is_empty() {
+        error_flags |= ErrorFlags::InvalidTypeBitmap;
+    }
+
+    (&[], (types, error_flags))
+}
+
+/// Rdata too short for the fixed size fields of its type, kept as `UNKNOWN`
+/// and flagged as `MalformedRdata`.
+fn malformed_rdata(input: &[u8]) -> (&[u8], (RDataType, Flags<ErrorFlags>)) {
+    (
+        &[],
+        (
+            RDataType::UNKNOWN(input.to_vec()),
+            ErrorFlags::MalformedRdata.into(),
+        ),
+    )
+}
+
+/// Takes `length` bytes of the rdata in `input`, or what is left of it if it
+/// is shorter, which is flagged as `MalformedRdata`.
+fn take_rdata<'a>(
+    input: &'a [u8],
+    length: u8,
+    error_flags: &mut Flags<ErrorFlags>,
+) -> (&'a [u8], &'a [u8]) {
+    if usize::from(length) > input.len() {
+        *error_flags |= ErrorFlags::MalformedRdata;
+        return (&[], input);
+    }
+    let (data, rem) = input.split_at(usize::from(length));
+    (rem, data)
+}
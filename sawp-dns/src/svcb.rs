@@ -0,0 +1,267 @@
+//! Service binding
+//! [RFC9460](https://tools.ietf.org/html/rfc9460)
+//!
+//! SVCB and HTTPS records point clients to the alternative endpoints of a service
+//! along with the parameters needed to connect to them, such as the supported
+//! ALPN protocol ids, a port, IP address hints and the ECH configuration used to
+//! encrypt the client hello. A priority of 0 makes the record an alias to the target
+//! name instead, which should have no parameters.
+
+use nom::bytes::streaming::take;
+use nom::number::streaming::be_u16;
+
+use num_enum::TryFromPrimitive;
+
+use sawp::limits::Tracker;
+use sawp_flags::{Flag, Flags};
+
+use core::convert::TryFrom;
+
+use alloc::vec::Vec;
+
+use crate::{ErrorFlags, IResult, Name};
+#[cfg(feature = "ffi")]
+use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
+
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u16)]
+pub enum SvcParamKey {
+    /// Keys the client must support to use the record
+    MANDATORY = 0,
+    /// Additional supported protocols
+    ALPN = 1,
+    /// No support for the default protocol
+    NODEFAULTALPN = 2,
+    /// Port for alternative endpoint
+    PORT = 3,
+    /// IPv4 address hints
+    IPV4HINT = 4,
+    /// Encrypted ClientHello configuration
+    ECH = 5,
+    /// IPv6 address hints
+    IPV6HINT = 6,
+    UNKNOWN,
+}
+
+impl SvcParamKey {
+    /// Invalid key reserved by RFC9460
+    pub const RESERVED: u16 = 65535;
+
+    pub fn from_raw(val: u16) -> Self {
+        SvcParamKey::try_from(val).unwrap_or(SvcParamKey::UNKNOWN)
+    }
+}
+
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum SvcParamValue {
+    /// Raw keys the client must support
+    Mandatory(Vec<u16>),
+    /// Protocol ids such as `h2` or `h3`
+    Alpn(Vec<Vec<u8>>),
+    NoDefaultAlpn,
+    Port(u16),
+    /// Addresses of 4 bytes each
+    Ipv4Hint(Vec<Vec<u8>>),
+    /// ECHConfigList as is
+    Ech(Vec<u8>),
+    /// Addresses of 16 bytes each
+    Ipv6Hint(Vec<Vec<u8>>),
+    /// Value of an unknown key or a malformed value
+    Raw(Vec<u8>),
+}
+
+impl SvcParamValue {
+    /// Decodes the value of `key`, `None` if it is malformed.
+    fn new(key: SvcParamKey, data: &[u8]) -> Option<Self> {
+        match key {
+            SvcParamKey::MANDATORY => split_exact(data, 2).map(|keys| {
+                SvcParamValue::Mandatory(
+                    keys.iter()
+                        .map(|key| u16::from_be_bytes([key[0], key[1]]))
+                        .collect(),
+                )
+            }),
+            SvcParamKey::ALPN if !data.is_empty() => {
+                let mut ids = Vec::new();
+                let mut data = data;
+                while let Some((&len, rem)) = data.split_first() {
+                    let len = usize::from(len);
+                    if len == 0 || len > rem.len() {
+                        return None;
+                    }
+                    let (id, rem) = rem.split_at(len);
+                    ids.push(id.to_vec());
+                    data = rem;
+                }
+                Some(SvcParamValue::Alpn(ids))
+            }
+            SvcParamKey::NODEFAULTALPN if data.is_empty() => Some(SvcParamValue::NoDefaultAlpn),
+            SvcParamKey::PORT if data.len() == 2 => {
+                Some(SvcParamValue::Port(u16::from_be_bytes([data[0], data[1]])))
+            }
+            SvcParamKey::IPV4HINT => split_exact(data, 4).map(SvcParamValue::Ipv4Hint),
+            SvcParamKey::ECH if !data.is_empty() => Some(SvcParamValue::Ech(data.to_vec())),
+            SvcParamKey::IPV6HINT => split_exact(data, 16).map(SvcParamValue::Ipv6Hint),
+            SvcParamKey::UNKNOWN => Some(SvcParamValue::Raw(data.to_vec())),
+            _ => None,
+        }
+    }
+}
+
+/// Splits `data` in items of `size` bytes, `None` if it is empty or doesn't divide evenly.
+fn split_exact(data: &[u8], size: usize) -> Option<Vec<Vec<u8>>> {
+    let items = data.chunks_exact(size);
+    if data.is_empty() || !items.remainder().is_empty() {
+        return None;
+    }
+    Some(items.map(|item| item.to_vec()).collect())
+}
+
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct SvcParam {
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub key: SvcParamKey,
+    pub key_raw: u16,
+    pub value: SvcParamValue,
+}
+
+impl SvcParam {
+    pub fn parse(input: &[u8]) -> IResult<'_, (SvcParam, Flags<ErrorFlags>)> {
+        let mut error_flags = ErrorFlags::none();
+
+        let (input, key_raw) = be_u16(input)?;
+        let (input, value_length) = be_u16(input)?;
+        let (input, data) = take(value_length)(input)?;
+
+        if key_raw == SvcParamKey::RESERVED {
+            error_flags |= ErrorFlags::SvcParamInvalid;
+        }
+
+        let key = SvcParamKey::from_raw(key_raw);
+        let value = SvcParamValue::new(key, data).unwrap_or_else(|| {
+            error_flags |= ErrorFlags::SvcParamInvalid;
+            SvcParamValue::Raw(data.to_vec())
+        });
+
+        Ok((
+            input,
+            (
+                SvcParam {
+                    key,
+                    key_raw,
+                    value,
+                },
+                error_flags,
+            ),
+        ))
+    }
+}
+
+/// SVCB and HTTPS records
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_dns"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct RDataSVCB {
+    /// 0 for AliasMode, ServiceMode otherwise with lower values preferred
+    pub priority: u16,
+    /// Empty if the target is the owner name of the record
+    pub target_name: Vec<u8>,
+    pub params: Vec<SvcParam>,
+}
+
+impl RDataSVCB {
+    pub fn parse<'a>(
+        input: &'a [u8],
+        reference_bytes: &'a [u8],
+        tracker: &Tracker,
+    ) -> IResult<'a, (RDataSVCB, Flags<ErrorFlags>)> {
+        let (mut input, priority) = be_u16(input)?;
+        let (rem, (target_name, mut error_flags)) = Name::parse(reference_bytes, tracker)(input)?;
+        input = rem;
+
+        let mut params: Vec<SvcParam> = Vec::new();
+        while !input.is_empty() {
+            // Each param counts as an element
+            if !tracker.element() {
+                error_flags |= ErrorFlags::LimitExceeded;
+                input = &[];
+                break;
+            }
+            let (rem, (param, inner_error_flags)) = match SvcParam::parse(input) {
+                Ok(parsed) => parsed,
+                // `input` is the rest of the rdata, the param runs past its end
+                Err(nom::Err::Incomplete(_)) => {
+                    error_flags |= ErrorFlags::SvcParamInvalid;
+                    input = &[];
+                    break;
+                }
+                Err(err) => return Err(err),
+            };
+            error_flags |= inner_error_flags;
+            input = rem;
+
+            match params.last() {
+                Some(last) if last.key_raw == param.key_raw => {
+                    error_flags |= ErrorFlags::SvcParamDuplicateKey;
+                }
+                Some(last) if last.key_raw > param.key_raw => {
+                    error_flags |= ErrorFlags::SvcParamKeyOrder;
+                }
+                _ => {}
+            }
+            params.push(param);
+        }
+
+        let mut keys: Vec<u16> = params.iter().map(|param| param.key_raw).collect();
+        // Keys in order are only duplicated next to each other
+        if error_flags.contains(ErrorFlags::SvcParamKeyOrder) {
+            keys.sort_unstable();
+            if keys.windows(2).any(|pair| pair[0] == pair[1]) {
+                error_flags |= ErrorFlags::SvcParamDuplicateKey;
+            }
+        }
+
+        // AliasMode records should have no params
+        if !RDataSVCB::is_consistent(&params, &keys) || (priority == 0 && !params.is_empty()) {
+            error_flags |= ErrorFlags::SvcParamInvalid;
+        }
+
+        Ok((
+            input,
+            (
+                RDataSVCB {
+                    priority,
+                    target_name,
+                    params,
+                },
+                error_flags,
+            ),
+        ))
+    }
+
+    /// Whether the keys listed as mandatory are in increasing order and present, and alpn
+    /// along with no-default-alpn. `keys` are the keys of `params`, sorted.
+    fn is_consistent(params: &[SvcParam], keys: &[u16]) -> bool {
+        let has_key = |key: u16| keys.binary_search(&key).is_ok();
+        params.iter().all(|param| match &param.value {
+            SvcParamValue::Mandatory(mandatory) => {
+                mandatory.windows(2).all(|pair| pair[0] < pair[1])
+                    && mandatory
+                        .iter()
+                        .all(|key| *key != SvcParamKey::MANDATORY as u16 && has_key(*key))
+            }
+            SvcParamValue::NoDefaultAlpn => has_key(SvcParamKey::ALPN as u16),
+            _ => true,
+        })
+    }
+}
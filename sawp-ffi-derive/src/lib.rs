@@ -4,16 +4,16 @@
 //!
 //! Attributes: `#[sawp_ffi(...)]`
 //! - `copy`: Return `Type` instead of `*const Type`.
-//!           Useful for enums with `repr(Integer)`
+//!   Useful for enums with `repr(Integer)`
 //! - `skip`: Don't generate accessor for member.
-//!           Note: only public members will have accessors.
+//!   Note: only public members will have accessors.
 //! - `flag` = `flag_repr`: Return `flag_repr` instead of `*const Type`
-//!           `flag_repr` should be the `repr` type of `Flag`.
-//!           Requires member type to be sawp_flags::Flags
+//!   `flag_repr` should be the `repr` type of `Flag`.
+//!   Requires member type to be sawp_flags::Flags
 //! - `type_only`: Only generate enum `Type` and `<enum>_get_type`.
-//!             Won't generate accessors for variant fields
+//!   Won't generate accessors for variant fields
 //! - `prefix` = `prefix`: Prefix for all functions.
-//!             eg: `<prefix>_<struct_name>_get_<field>`
+//!   eg: `<prefix>_<struct_name>_get_<field>`
 //!
 //! Note: accessors are functions so they will be in snake_case.
 //! Struct and Enum names will be converted to snake_case in function names.
@@ -824,7 +824,7 @@ fn gen_enum_named_accessor(
                 #[no_mangle]
                 pub unsafe extern "C" fn #idx_name(#enum_variable: *const Vec<#inner>, n: usize) -> *const #inner {
                     if !#enum_variable.is_null() {
-                        &(*#enum_variable)[n]
+                        &(&*#enum_variable)[n]
                     }
                     else {
                         panic!("{} is NULL ", stringify!(#enum_variable));
@@ -969,7 +969,7 @@ fn gen_enum_unnamed_accessor(
                 #[no_mangle]
                 pub unsafe extern "C" fn #idx_name(#enum_variable: *const Vec<#inner>, n: usize) -> *const #inner {
                     if !#enum_variable.is_null() {
-                        &(*#enum_variable)[n]
+                        &(&*#enum_variable)[n]
                     }
                     else {
                         panic!("{} is NULL ", stringify!(#enum_variable));
@@ -84,17 +84,37 @@ mod tests {
         nullcheck!(ptr);
     }
 
+    /// Runs `test` in a child test process and checks that it panicked.
+    ///
+    /// Since rust 1.81 a panic can no longer unwind out of an `extern "C"`
+    /// function and aborts the process instead, which `#[should_panic]`
+    /// can't catch.
+    fn assert_panics_in_child(name: &str, test: impl FnOnce()) {
+        if std::env::var_os("SAWP_FFI_TEST_CHILD").is_some() {
+            test();
+            return;
+        }
+
+        let status = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", name, "--test-threads=1"])
+            .env("SAWP_FFI_TEST_CHILD", "1")
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()
+            .unwrap();
+        assert!(!status.success());
+    }
+
     #[test]
-    #[should_panic]
     fn test_derive_nullcheck() {
         #[derive(GenerateFFI)]
         pub struct MyStruct {
             pub field: u8,
         }
 
-        unsafe {
+        assert_panics_in_child("tests::test_derive_nullcheck", || unsafe {
             my_struct_get_field(std::ptr::null());
-        }
+        });
     }
 
     #[test]
@@ -329,7 +349,6 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn test_get_vec_at_index_panics_called_with_null() {
         #[derive(GenerateFFI)]
         #[sawp_ffi(prefix = "sawp")]
@@ -338,8 +357,11 @@ mod tests {
             B(usize),
         }
 
-        unsafe {
-            sawp_my_enum_three_get_a(null());
-        }
+        assert_panics_in_child(
+            "tests::test_get_vec_at_index_panics_called_with_null",
+            || unsafe {
+                sawp_my_enum_three_get_a(null());
+            },
+        );
     }
 }
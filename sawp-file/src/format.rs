@@ -33,15 +33,15 @@ pub enum Direction {
 /// A chunk of input data to parse.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Data {
-    direction: Direction,
-    data: Vec<u8>,
+    pub direction: Direction,
+    pub data: Vec<u8>,
 }
 
 /// Identifies a missing chunk of input data.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct Gap {
-    direction: Direction,
-    gap: usize,
+    pub direction: Direction,
+    pub gap: usize,
 }
 
 /// A list of all API calls we want to expose.
@@ -123,6 +123,12 @@ impl<W: Write> Writer<W> {
         self.inner.write_all(&bytes)?;
         Ok(())
     }
+
+    /// Flushes the calls written so far to the sink.
+    pub fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
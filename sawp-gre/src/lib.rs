@@ -17,7 +17,7 @@
 //! use sawp_gre::{Gre, Message};
 //!
 //! fn parse_bytes(input: &[u8]) -> std::result::Result<&[u8], Error> {
-//!     let gre = Gre {};
+//!     let gre = Gre::default();
 //!     let mut bytes = input;
 //!     while bytes.len() > 0 {
 //!         match gre.parse(bytes, Direction::Unknown) {
@@ -41,7 +41,13 @@
 //! }
 //! ````
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 use sawp::error::{Error, ErrorKind, Result};
+use sawp::limits::{Limits, Tracker};
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::{Probe, Status};
 use sawp::protocol::Protocol;
@@ -52,11 +58,15 @@ pub use sawp_flags::{Flag, Flags};
 use nom::bytes::streaming::take;
 use nom::number::streaming::{be_u16, be_u32, be_u8};
 
-use std::ops::BitAnd;
+use alloc::{vec, vec::Vec};
+use core::ops::BitAnd;
+
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 /// Upper limit on number of Source Route Entries to be handled when routing bit is set in deprecated
-/// GRE to avoid an infinite loop.
-const MAX_SRE_ENTRIES: u32 = 10;
+/// GRE to avoid an infinite loop. Used as the default `max_elements` limit.
+pub const MAX_SRE_ENTRIES: usize = 10;
 /// Required protocol type for PPP. Beyond checking for this protocol type, the GRE parser is not
 /// concerned about the protocol type.
 const ETHERTYPE_PPP: u16 = 0x880b;
@@ -80,6 +90,9 @@ pub enum ErrorFlags {
     /// Indicate that the maximum number of source route entries has been processed. This means that
     /// there is more data remaining than what was processed into the message.
     MAX_SRE_REACHED = 0b0000_1000,
+    /// Indicate that a resource limit was reached. This is set along with MAX_SRE_REACHED or when
+    /// a PPTP payload is larger than the allocation limit, in which case it is not copied.
+    LIMIT_EXCEEDED = 0b0001_0000,
 }
 
 /// Flags for handling the first 2 octets of data containing GRE flags (and PPTP specific flags)
@@ -112,6 +125,7 @@ pub enum GreFlags {
 /// Source Route Entries are present in deprecated GRE and need to be handled.
 /// See https://tools.ietf.org/html/rfc1701 for implementation in GRE headers
 /// and https://tools.ietf.org/html/rfc1702 for further details.
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct SourceRouteEntry {
     address_family: u16,
@@ -125,6 +139,7 @@ pub struct SourceRouteEntry {
 ///  Current GRE: https://tools.ietf.org/html/rfc2784
 ///  Deprecated GRE: https://tools.ietf.org/html/rfc1701
 ///  Point-to-Point Tunneling Protocol (Enhanced GRE Header): https://tools.ietf.org/html/rfc2637
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Data {
     Gre {
@@ -149,9 +164,25 @@ pub enum Data {
 }
 
 #[derive(Debug)]
-pub struct Gre {}
+pub struct Gre {
+    /// Resource limits applied to every message. `max_elements` bounds the number of source route
+    /// entries and defaults to [`MAX_SRE_ENTRIES`].
+    pub limits: Limits,
+}
+
+impl Default for Gre {
+    fn default() -> Self {
+        Gre {
+            limits: Limits {
+                max_elements: MAX_SRE_ENTRIES,
+                ..Limits::default()
+            },
+        }
+    }
+}
 
 /// Breakdown of the parsed GRE bytes
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub header: Flags<GreFlags>,
@@ -161,8 +192,8 @@ pub struct Message {
 }
 
 impl Message {
-    /// Convenience functions for handling the various components of the flag/version bits in a
-    /// GRE header.
+    // Convenience functions for handling the various components of the flag/version bits in a
+    // GRE header.
 
     fn is_checksum_set(&self) -> bool {
         self.header.intersects(GreFlags::CHECKSUM)
@@ -277,11 +308,12 @@ impl Message {
     fn parse_source_route_entries<'a>(
         &mut self,
         input: &'a [u8],
+        tracker: &Tracker,
     ) -> Result<(&'a [u8], Vec<SourceRouteEntry>)> {
         if self.is_routing_set() {
             let mut source_route_entries: Vec<SourceRouteEntry> = Vec::new();
             let mut input_copy = input;
-            for _ in 0..MAX_SRE_ENTRIES {
+            while tracker.element() {
                 let (input, address_family) = be_u16(input_copy)?;
                 let (input, sre_offset) = be_u8(input)?;
                 let (input, sre_length) = be_u8(input)?;
@@ -298,7 +330,7 @@ impl Message {
                 }
                 input_copy = input;
             }
-            self.error_flags |= ErrorFlags::MAX_SRE_REACHED;
+            self.error_flags |= ErrorFlags::MAX_SRE_REACHED | ErrorFlags::LIMIT_EXCEEDED;
             Ok((input_copy, source_route_entries))
         } else {
             Ok((input, vec![]))
@@ -311,9 +343,15 @@ impl Message {
         &mut self,
         input: &'a [u8],
         length: u16,
+        tracker: &Tracker,
     ) -> Result<(&'a [u8], Vec<u8>)> {
         if self.is_sequence_number_set() {
             let (input, payload) = take(length)(input)?;
+            if !tracker.alloc(length.into()) {
+                // The payload is consumed but not copied
+                self.error_flags |= ErrorFlags::LIMIT_EXCEEDED;
+                return Ok((input, vec![]));
+            }
             Ok((input, payload.to_vec()))
         } else {
             Ok((input, vec![]))
@@ -336,11 +374,11 @@ impl Message {
 
     /// Main parsing function for deprecated GRE. There are no validity checks that need to be done
     /// on the content of these fields.
-    fn parse_deprecated<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+    fn parse_deprecated<'a>(&mut self, input: &'a [u8], tracker: &Tracker) -> Result<&'a [u8]> {
         let (input, checksum, offset) = self.parse_checksum_and_routing(input)?;
         let (input, key) = self.parse_key(input)?;
         let (input, sequence_number) = self.parse_sequence(input)?;
-        let (input, source_route_entries) = self.parse_source_route_entries(input)?;
+        let (input, source_route_entries) = self.parse_source_route_entries(input, tracker)?;
 
         self.data = Data::GreDeprecated {
             checksum,
@@ -355,12 +393,12 @@ impl Message {
 
     /// Main parsing function for PPTP. There are no validity checks that need to be done on the
     /// content of these fields.
-    fn parse_pptp<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+    fn parse_pptp<'a>(&mut self, input: &'a [u8], tracker: &Tracker) -> Result<&'a [u8]> {
         let (input, payload_length) = be_u16(input)?;
         let (input, call_id) = be_u16(input)?;
         let (input, sequence_number) = self.parse_sequence(input)?;
         let (input, acknowledgement_number) = self.parse_acknowledgement(input)?;
-        let (input, payload) = self.parse_pptp_payload(input, payload_length)?;
+        let (input, payload) = self.parse_pptp_payload(input, payload_length, tracker)?;
         self.data = Data::Pptp {
             payload_length,
             call_id,
@@ -399,6 +437,12 @@ impl Protocol<'_> for Gre {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
 impl<'a> Probe<'a> for Gre {
     fn probe(&self, input: &'a [u8], direction: Direction) -> Status {
         match self.parse(input, direction) {
@@ -427,6 +471,7 @@ impl<'a> Parse<'a> for Gre {
         let (input, gre_flags_raw) = be_u16(input)?;
         let (input, protocol_type) = be_u16(input)?;
 
+        let tracker = self.limits.tracker();
         let mut message = Message {
             header: Flags::from_bits(gre_flags_raw),
             protocol_type,
@@ -438,10 +483,10 @@ impl<'a> Parse<'a> for Gre {
             let input = message.parse_gre(input)?;
             Ok((input, Some(message)))
         } else if message.is_valid_deprecated_gre() {
-            let input = message.parse_deprecated(input)?;
+            let input = message.parse_deprecated(input, &tracker)?;
             Ok((input, Some(message)))
         } else if message.is_valid_pptp() {
-            let input = message.parse_pptp(input)?;
+            let input = message.parse_pptp(input, &tracker)?;
             Ok((input, Some(message)))
         } else {
             message.check_error_gre_flags();
@@ -456,6 +501,7 @@ mod tests {
     use super::*;
     use rstest::rstest;
     use sawp::probe::Status;
+    use sawp::testing::assert_datagram;
 
     #[test]
     fn test_name() {
@@ -745,7 +791,7 @@ mod tests {
                     routing_info: vec![0xff, 0xff, 0xff, 0xff],
                 }],
                 },
-                error_flags: ErrorFlags::MAX_SRE_REACHED.into(),
+                error_flags: ErrorFlags::MAX_SRE_REACHED | ErrorFlags::LIMIT_EXCEEDED,
             })))),
         case::deprecated_routing_sre_missing(
             &[
@@ -1043,8 +1089,32 @@ mod tests {
             })))),
     )]
     fn test_parse(input: &[u8], expected: Result<(&[u8], Option<Message>)>) {
-        let gre = Gre {};
+        let gre = Gre::default();
         assert_eq!(gre.parse(input, Direction::Unknown), expected);
+        // The payload ends with the packet
+        assert_datagram(&gre, input, Direction::Unknown);
+    }
+
+    #[test]
+    fn test_parse_pptp_payload_limit() {
+        let gre = Gre {
+            limits: Limits {
+                max_alloc: 2,
+                ..Gre::default().limits
+            },
+        };
+        let input = [
+            0x30, 0x01, // header: key and sequence flag set. Version 1.
+            0x88, 0x0b, // protocol type: must be 0x880b for PPTP
+            0x00, 0x04, 0x00, 0xff, // key (payload length (4) and call id
+            0x01, 0x02, 0x03, 0x04, // sequence number
+            0x09, 0x0a, 0x0b, 0x0c, // payload
+        ];
+        let (rem, message) = gre.parse(&input, Direction::Unknown).unwrap();
+        assert!(rem.is_empty());
+        let message = message.unwrap();
+        assert_eq!(message.error_flags, ErrorFlags::LIMIT_EXCEEDED);
+        assert!(matches!(message.data, Data::Pptp { payload, .. } if payload.is_empty()));
     }
 
     #[rstest(
@@ -1079,7 +1149,7 @@ mod tests {
         )
     )]
     fn test_probe(input: &[u8], expected: Status) {
-        let gre = Gre {};
+        let gre = Gre::default();
 
         assert_eq!(gre.probe(input, Direction::Unknown), expected)
     }
@@ -2,7 +2,13 @@
 
 use super::{payloads::Attribute, Direction, Ike, Message};
 
-use sawp::{error::Error, parser::Parse};
+use sawp::{
+    error::Error,
+    limits::Limits,
+    metrics::{self, Recorder, Snapshot},
+    parser::Parse,
+    protocol::Protocol,
+};
 
 use sawp_ffi::IntoFFIPtr;
 
@@ -19,6 +25,35 @@ pub unsafe extern "C" fn sawp_ike_create() -> *mut Ike {
     parser.into_ffi_ptr()
 }
 
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_ike_set_limits(parser: *mut Ike, limits: Limits) {
+    let parser = sawp_ffi::deref_mut!(parser);
+    parser.limits = limits;
+}
+
+/// Metrics of the parsers called through the FFI.
+static RECORDER: Recorder = Recorder::new();
+
+/// Copies the metrics collected by the ike parsers into `snapshot`.
+///
+/// Returns false if nothing was recorded, for instance when the library was
+/// built without the `metrics` feature.
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_ike_metrics(snapshot: *mut Snapshot) -> bool {
+    sawp_ffi::nullcheck!(snapshot);
+    match metrics::get(Ike::name()) {
+        Some(metrics) => {
+            *snapshot = metrics;
+            true
+        }
+        None => false,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn sawp_ike_destroy(d: *mut Ike) {
     if !d.is_null() {
@@ -36,7 +71,9 @@ pub unsafe extern "C" fn sawp_ike_parse(
     length: usize,
 ) -> *mut ParseResult {
     let input = std::slice::from_raw_parts(data, length);
-    match (*parser).parse(input, direction) {
+    let result = (*parser).parse(input, direction);
+    RECORDER.record::<Ike>(input, &result);
+    match result {
         Ok((sl, message)) => ParseResult {
             message: message.into_ffi_ptr(),
             // Should never actually underflow as parse cannot grow the input slice
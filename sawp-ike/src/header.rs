@@ -5,6 +5,8 @@ use sawp_flags::{BitFlags, Flag, Flags};
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 use nom::combinator::{map, verify};
 use nom::number::streaming::{be_u32, be_u64, be_u8};
@@ -17,6 +19,7 @@ pub const HEADER_LEN: u32 = 28;
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, FromPrimitive, PartialEq, Eq, Copy, Clone)]
 #[repr(u8)]
 pub enum ExchangeType {
@@ -63,6 +66,7 @@ pub enum IkeFlags {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Header {
     pub initiator_spi: u64,
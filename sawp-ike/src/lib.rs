@@ -54,15 +54,24 @@
 //! }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+// Newer clippy renamed integer_arithmetic to arithmetic_side_effects, which the MSRV doesn't know
+#![allow(renamed_and_removed_lints)]
 #![deny(clippy::integer_arithmetic)]
 
+extern crate alloc;
+
 pub mod header;
 pub mod payloads;
 
 use header::{Header, IkeFlags, HEADER_LEN};
 use payloads::{Payload, PayloadType};
 
+use alloc::vec::Vec;
+
 use sawp::error::Result;
+use sawp::limits::Limits;
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::Probe;
 use sawp::protocol::Protocol;
@@ -74,6 +83,8 @@ mod ffi;
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 use nom::bytes::streaming::{tag, take};
 use nom::combinator::opt;
@@ -112,6 +123,11 @@ pub enum ErrorFlags {
     /// Either a nonexistant flag bit was set or both IKEv1 and IKEv2 flags were set at the same
     /// time.
     InvalidFlags = 0b0000_0010_0000_0000,
+    /// A resource limit was reached.
+    ///
+    /// Payloads past the element limit are dropped. Messages longer than the allocation limit
+    /// have no payloads parsed.
+    LimitExceeded = 0b0000_0100_0000_0000,
 }
 
 impl ErrorFlags {
@@ -130,6 +146,7 @@ impl ErrorFlags {
 /// transport layer (i.e. layer 3 Ethernet header followed by encrypted payload).
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Message {
     /// An IKE payload
@@ -141,6 +158,7 @@ pub enum Message {
 /// The parsed IKEv1 or v2 message
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct IkeMessage {
     /// The header
@@ -159,6 +177,7 @@ pub struct IkeMessage {
 /// The full encrypted payload, tail padding, and integrity check is not parsed.
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct EspMessage {
     pub spi: u32,
@@ -187,7 +206,9 @@ pub struct Ike {
     // it as ESP payloads. When IKE was not prefixed with 0x00 then all packets should
     // be IKE. As such we have 3 states - ESP encapsulation present (Some(true)), ESP
     // encapsulation not present (Some(false)), and not yet determined (None).
-    saw_udp_encapsulation: std::cell::Cell<Option<bool>>,
+    saw_udp_encapsulation: core::cell::Cell<Option<bool>>,
+    /// Resource limits applied to every message. `max_elements` bounds the number of payloads.
+    pub limits: Limits,
 }
 
 impl Probe<'_> for Ike {}
@@ -200,6 +221,15 @@ impl Protocol<'_> for Ike {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        match self {
+            Message::Ike(message) => message.error_flags.bits().into(),
+            Message::Esp(_) => 0,
+        }
+    }
+}
+
 impl<'a> Parse<'a> for Ike {
     fn parse(
         &self,
@@ -241,6 +271,17 @@ impl<'a> Parse<'a> for Ike {
         let mut payloads = Vec::new();
         let mut payload_error_flags = ErrorFlags::none();
 
+        let tracker = self.limits.tracker();
+        if !tracker.alloc(payload_input.len()) {
+            let message = Message::Ike(IkeMessage {
+                header,
+                payloads,
+                encrypted_data: Vec::with_capacity(0),
+                error_flags: header_error_flags | ErrorFlags::LimitExceeded,
+            });
+            return Ok((input, Some(message)));
+        }
+
         if header.major_version == 1 && header.flags.contains(IkeFlags::ENCRYPTED) {
             let message = Message::Ike(IkeMessage {
                 header,
@@ -261,6 +302,10 @@ impl<'a> Parse<'a> for Ike {
         // In the case of encryption, all the payloads are encrypted and
         // are inside the encrypted data block.
         while next_payload != PayloadType::NoNextPayload {
+            if !tracker.element() {
+                payload_error_flags |= ErrorFlags::LimitExceeded;
+                break;
+            }
             let should_early_break = next_payload == PayloadType::EncryptedAndAuthenticated
                 || next_payload == PayloadType::EncryptedAndAuthenticatedFragment;
             let (tmp_payload_input, (payload, errors)) = parse(payload_input, next_payload)?;
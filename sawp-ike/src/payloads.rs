@@ -5,6 +5,8 @@ use sawp_flags::{Flag, Flags};
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 use nom::bits::streaming::take as bit_take;
 use nom::bytes::streaming::take;
@@ -15,8 +17,15 @@ use nom::sequence::tuple;
 
 use num_enum::FromPrimitive;
 
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::net::{Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "std")]
+use std::net::{Ipv4Addr, Ipv6Addr};
+
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, FromPrimitive, PartialEq, Eq, Clone, Copy)]
 #[repr(u8)]
 pub enum PayloadType {
@@ -87,12 +96,14 @@ impl PayloadType {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Payload {
     pub next_payload: PayloadType,
     pub critical_bit: Option<u8>,
     pub reserved: u8,
     pub payload_length: u16,
+    #[cfg_attr(feature = "visit", visit(flatten))]
     pub data: PayloadData,
 }
 
@@ -110,7 +121,10 @@ impl Payload {
      * each type specific parser. In these cases the generic payload is omitted from their
      * diagrams.
      */
-    pub fn parse_v1(input: &[u8], payload_type: PayloadType) -> IResult<(Self, Flags<ErrorFlags>)> {
+    pub fn parse_v1(
+        input: &[u8],
+        payload_type: PayloadType,
+    ) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
 
         let (input, (raw_next_payload, reserved, payload_length)) =
@@ -158,7 +172,10 @@ impl Payload {
      *  | Next Payload  |C|  RESERVED   |         Payload Length        |
      *  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
      */
-    pub fn parse_v2(input: &[u8], payload_type: PayloadType) -> IResult<(Self, Flags<ErrorFlags>)> {
+    pub fn parse_v2(
+        input: &[u8],
+        payload_type: PayloadType,
+    ) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
 
         let (input, (raw_next_payload, (critical_bit, reserved), payload_length)) =
@@ -215,6 +232,7 @@ impl Payload {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum PayloadData {
     V1SecurityAssociation {
@@ -309,7 +327,10 @@ pub enum PayloadData {
 }
 
 impl PayloadData {
-    pub fn parse(input: &[u8], payload_type: PayloadType) -> IResult<(Self, Flags<ErrorFlags>)> {
+    pub fn parse(
+        input: &[u8],
+        payload_type: PayloadType,
+    ) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         match payload_type {
             // V1
             PayloadType::V1SecurityAssociation => Self::parse_v1_sa(input),
@@ -401,7 +422,7 @@ impl PayloadData {
     // ~                           Situation                           ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_sa(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_sa(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u32,
@@ -436,7 +457,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
     // !                        SPI (variable)                         !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_proposal(input: &[u8]) -> IResult<(V1Proposal, Flags<ErrorFlags>)> {
+    fn parse_v1_proposal(input: &[u8]) -> IResult<'_, (V1Proposal, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -503,7 +524,7 @@ impl PayloadData {
     // ~                        SA Attributes                          ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_transform(input: &[u8]) -> IResult<(V1Transform, Flags<ErrorFlags>)> {
+    fn parse_v1_transform(input: &[u8]) -> IResult<'_, (V1Transform, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -560,7 +581,7 @@ impl PayloadData {
     // ~                   Identification Data                         ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_identification(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_identification(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, be_u24, rest)),
             |(id_type, doi_specific_data, id_data): (_, _, &[u8])| {
@@ -585,7 +606,7 @@ impl PayloadData {
     // ~                       Certificate Data                        ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_certificate(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_certificate(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, rest)),
             |(cert_encoding, certificate_data): (_, &[u8])| {
@@ -609,7 +630,7 @@ impl PayloadData {
     // ~                    Certificate Authority                      ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_certificate_request(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_certificate_request(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, rest)),
             |(cert_encoding, certification_authority): (_, &[u8])| {
@@ -640,7 +661,7 @@ impl PayloadData {
     // ~                       Notification Data                       ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_notification(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_notification(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u32,
@@ -683,7 +704,7 @@ impl PayloadData {
     // ~               Security Parameter Index(es) (SPI)              ~
     // !                                                               !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_delete(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_delete(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u32,
@@ -728,7 +749,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
     // ~                        KEK Attributes                         ~
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
-    fn parse_v1_sa_kek(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_sa_kek(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -790,7 +811,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+                                               ~
     // ~                                                               ~
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
-    fn parse_v1_sa_tek(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_sa_tek(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, rest)),
             |(protocol_id, payload): (_, &[u8])| {
@@ -813,7 +834,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
     // ~                    Key Packets                                ~
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
-    fn parse_v1_key_download(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_key_download(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 peek(tuple((be_u16, be_u16))),
@@ -856,7 +877,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
     // ~                    Key Packet Attributes                      ~
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-!
-    fn parse_v1_key_packet(input: &[u8]) -> IResult<(KeyPacket, Flags<ErrorFlags>)> {
+    fn parse_v1_key_packet(input: &[u8]) -> IResult<'_, (KeyPacket, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -895,7 +916,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
     // !                      Sequence Number                          !
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_v1_sequence_number(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_v1_sequence_number(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(be_u32, |sequence| {
             (Self::V1SequenceNumber(sequence), ErrorFlags::none())
         })(input)
@@ -909,7 +930,7 @@ impl PayloadData {
     // ~                          <Proposals>                          ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_sa(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_sa(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             map(many1(complete(Self::parse_proposal)), |proposals| {
                 let (proposals, errs): (Vec<_>, Vec<_>) = proposals.into_iter().unzip();
@@ -934,7 +955,7 @@ impl PayloadData {
     // ~                        <Transforms>                           ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_proposal(input: &[u8]) -> IResult<(Proposal, Flags<ErrorFlags>)> {
+    fn parse_proposal(input: &[u8]) -> IResult<'_, (Proposal, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -1001,7 +1022,7 @@ impl PayloadData {
     // ~                      Transform Attributes                     ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_transform(input: &[u8]) -> IResult<(Transform, Flags<ErrorFlags>)> {
+    fn parse_transform(input: &[u8]) -> IResult<'_, (Transform, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
         let (
             input,
@@ -1058,7 +1079,7 @@ impl PayloadData {
     // AF, or Attribute Format, dictates whether it is TLV (AF = 0) or TV (AF = 1) format.
     // In TLV mode, Attribute Length is present, nonzero, and measures Attribute Value.
     // In TV mode, Attribute Length is no present and Attribute Value is 2 octets.
-    pub fn parse_attribute(input: &[u8]) -> IResult<(Attribute, Flags<ErrorFlags>)> {
+    pub fn parse_attribute(input: &[u8]) -> IResult<'_, (Attribute, Flags<ErrorFlags>)> {
         let (input, (att_format, att_type)) = map(
             nom::bits::bits::<_, _, nom::error::Error<(&[u8], usize)>, _, _>(tuple((
                 bit_take(1usize),
@@ -1102,7 +1123,7 @@ impl PayloadData {
     // ~                       Key Exchange Data                       ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_ke(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_ke(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u16, be_u16, rest)),
             |(diffie_hellman_group_num, reserved, key_exchange_data): (_, _, &[u8])| {
@@ -1123,14 +1144,14 @@ impl PayloadData {
     }
 
     // See parse_traffic_selector
-    fn parse_traffic_selector_init(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_traffic_selector_init(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(Self::parse_traffic_selector, |(ts, err)| {
             (Self::TrafficSelectorInitiator(ts), err)
         })(input)
     }
 
     // See parse_traffic_selector
-    fn parse_traffic_selector_resp(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_traffic_selector_resp(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(Self::parse_traffic_selector, |(ts, err)| {
             (Self::TrafficSelectorResponder(ts), err)
         })(input)
@@ -1146,7 +1167,7 @@ impl PayloadData {
     // ~                       <Traffic Selectors>                     ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_traffic_selector(input: &[u8]) -> IResult<(TrafficSelector, Flags<ErrorFlags>)> {
+    fn parse_traffic_selector(input: &[u8]) -> IResult<'_, (TrafficSelector, Flags<ErrorFlags>)> {
         map(
             tuple((
                 peek(tuple((be_u8, be_u24))),
@@ -1196,7 +1217,7 @@ impl PayloadData {
     // handled ones are 7 (IPv4 addresses), 8 (IPv6 addresses), and 9 (Fibre).
     pub fn parse_traffic_selector_body(
         input: &[u8],
-    ) -> IResult<(TrafficSelectorBody, Flags<ErrorFlags>)> {
+    ) -> IResult<'_, (TrafficSelectorBody, Flags<ErrorFlags>)> {
         let mut error_flags = ErrorFlags::none();
         let (input, (ts_type, ip_protocol_id, selector_length)) =
             tuple((be_u8, be_u8, be_u16))(input)?;
@@ -1213,8 +1234,8 @@ impl PayloadData {
                         Address::Ipv4(AddressV4 {
                             start_port,
                             end_port,
-                            starting_address: std::net::Ipv4Addr::from(starting_address),
-                            ending_address: std::net::Ipv4Addr::from(ending_address),
+                            starting_address: Ipv4Addr::from(starting_address),
+                            ending_address: Ipv4Addr::from(ending_address),
                         }),
                         ErrorFlags::none(),
                     )
@@ -1231,8 +1252,8 @@ impl PayloadData {
                         Address::Ipv6(AddressV6 {
                             start_port,
                             end_port,
-                            starting_address: std::net::Ipv6Addr::from(starting_address),
-                            ending_address: std::net::Ipv6Addr::from(ending_address),
+                            starting_address: Ipv6Addr::from(starting_address),
+                            ending_address: Ipv6Addr::from(ending_address),
                         }),
                         ErrorFlags::none(),
                     )
@@ -1298,7 +1319,7 @@ impl PayloadData {
     // ~                       Certificate Data                        ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_certificate(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_certificate(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, rest)),
             |(cert_encoding, certificate_data): (_, &[u8])| {
@@ -1322,7 +1343,7 @@ impl PayloadData {
     // ~                    Certification Authority                    ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_certificate_request(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_certificate_request(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, rest)),
             |(cert_encoding, certification_authority): (_, &[u8])| {
@@ -1347,7 +1368,7 @@ impl PayloadData {
     // ~                      Authentication Data                      ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_authentication(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_authentication(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, be_u24, rest)),
             |(auth_method, reserved, authentication_data): (_, _, &[u8])| {
@@ -1369,7 +1390,7 @@ impl PayloadData {
 
     // Simply takes the rest of the buffer, clones it into a vec and returns it.
     // Used for several types which are simple data buffers with no other data.
-    fn parse_raw(input: &[u8]) -> IResult<(Vec<u8>, Flags<ErrorFlags>)> {
+    fn parse_raw(input: &[u8]) -> IResult<'_, (Vec<u8>, Flags<ErrorFlags>)> {
         rest(input).map(|(i, data)| (i, (data.to_vec(), ErrorFlags::none())))
     }
 
@@ -1387,7 +1408,7 @@ impl PayloadData {
     // ~                       Notification Data                       ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_notify(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_notify(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -1416,13 +1437,13 @@ impl PayloadData {
     }
 
     // See parse_identification
-    fn parse_identification_init(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_identification_init(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(Self::parse_identification, |(identification, err)| {
             (Self::IdentificationInit(identification), err)
         })(input)
     }
 
-    fn parse_identification_resp(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_identification_resp(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(Self::parse_identification, |(identification, err)| {
             (Self::IdentificationResp(identification), err)
         })(input)
@@ -1438,7 +1459,7 @@ impl PayloadData {
     // ~                   Identification Data                         ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_identification(input: &[u8]) -> IResult<(Identification, Flags<ErrorFlags>)> {
+    fn parse_identification(input: &[u8]) -> IResult<'_, (Identification, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, be_u24, rest)),
             |(id_type, reserved, identification_data): (_, _, &[u8])| {
@@ -1468,7 +1489,7 @@ impl PayloadData {
     // ~               Security Parameter Index(es) (SPI)              ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_delete(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_delete(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -1501,7 +1522,7 @@ impl PayloadData {
     // ~               Security Parameter Index(es) (SPI)              ~
     // |                                                               |
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_config(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_config(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((
                 be_u8,
@@ -1537,7 +1558,7 @@ impl PayloadData {
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
     // |     Type      | Type_Data...                                  ~
     // +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-    fn parse_eap(input: &[u8]) -> IResult<(Self, Flags<ErrorFlags>)> {
+    fn parse_eap(input: &[u8]) -> IResult<'_, (Self, Flags<ErrorFlags>)> {
         map(
             tuple((be_u8, be_u8, be_u16, be_u8, rest)),
             |(code, identifier, length, r#type, type_data): (_, _, _, _, &[u8])| {
@@ -1558,6 +1579,7 @@ impl PayloadData {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct V1Proposal {
     pub next_payload: u8,
@@ -1573,6 +1595,7 @@ pub struct V1Proposal {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct V1Transform {
     pub next_payload: u8,
@@ -1584,6 +1607,7 @@ pub struct V1Transform {
     pub attributes: Vec<Attribute>,
 }
 
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct SaKek {
     pub protocol: u8,
@@ -1603,6 +1627,7 @@ pub struct SaKek {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct KeyPacket {
     pub kd_type: u8,
@@ -1615,6 +1640,7 @@ pub struct KeyPacket {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Proposal {
     pub last_substruc: u8,
@@ -1630,6 +1656,7 @@ pub struct Proposal {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Transform {
     pub last_substruc: u8,
@@ -1643,6 +1670,7 @@ pub struct Transform {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, FromPrimitive, PartialEq, Eq, Clone, Copy)]
 #[repr(u8)]
 pub enum TransformType {
@@ -1667,6 +1695,7 @@ pub enum TransformType {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Attribute {
     pub att_format: AttributeFormat,
@@ -1677,6 +1706,7 @@ pub struct Attribute {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq, FromPrimitive)]
 #[repr(u8)]
 pub enum AttributeFormat {
@@ -1687,6 +1717,7 @@ pub enum AttributeFormat {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct TrafficSelector {
     pub number_ts: u8,
@@ -1696,6 +1727,7 @@ pub struct TrafficSelector {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct TrafficSelectorBody {
     pub ts_type: u8,
@@ -1706,6 +1738,7 @@ pub struct TrafficSelectorBody {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Address {
     Ipv4(AddressV4),
@@ -1716,26 +1749,29 @@ pub enum Address {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct AddressV4 {
     pub start_port: u16,
     pub end_port: u16,
-    pub starting_address: std::net::Ipv4Addr,
-    pub ending_address: std::net::Ipv4Addr,
+    pub starting_address: Ipv4Addr,
+    pub ending_address: Ipv4Addr,
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct AddressV6 {
     pub start_port: u16,
     pub end_port: u16,
-    pub starting_address: std::net::Ipv6Addr,
-    pub ending_address: std::net::Ipv6Addr,
+    pub starting_address: Ipv6Addr,
+    pub ending_address: Ipv6Addr,
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Fibre {
     pub reserved: u8,
@@ -1750,6 +1786,7 @@ pub struct Fibre {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Certificate {
     pub cert_encoding: u8,
@@ -1758,6 +1795,7 @@ pub struct Certificate {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct CertificateRequest {
     pub cert_encoding: u8,
@@ -1766,6 +1804,7 @@ pub struct CertificateRequest {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_ike"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Identification {
     pub id_type: u8,
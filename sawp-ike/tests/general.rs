@@ -1,3 +1,5 @@
+use sawp::limits::Limits;
+use sawp::parser::{Direction, Parse};
 use sawp::protocol::Protocol;
 use sawp_ike::*;
 
@@ -5,3 +7,83 @@ use sawp_ike::*;
 fn test_name() {
     assert_eq!(Ike::name(), "ike");
 }
+
+#[rstest::rstest(
+    limits,
+    expected_payloads,
+    case::max_elements(Limits { max_elements: 1, ..Limits::default() }, 1),
+    case::max_alloc(Limits { max_alloc: 16, ..Limits::default() }, 0)
+)]
+fn test_limits(limits: Limits, expected_payloads: usize) {
+    // Retrieved from https://github.com/vathpela/wireshark/blob/master/test/captures/ikev1-certs.pcap
+    let input = [
+        0xfa, 0xfa, 0xeb, 0x49, 0x38, 0x2a, 0x76, 0x3c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x01, 0x10, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x74, 0x0d, 0x01,
+        0x00, 0x34, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x28, 0x00,
+        0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x20, 0x00, 0x01, 0x00, 0x00, 0x80, 0x0b, 0x00, 0x01,
+        0x80, 0x0c, 0x70, 0x80, 0x80, 0x01, 0x00, 0x05, 0x80, 0x02, 0x00, 0x01, 0x80, 0x03, 0x00,
+        0x03, 0x80, 0x04, 0x00, 0x02, 0x0d, 0x00, 0x00, 0x10, 0x4f, 0x45, 0x68, 0x79, 0x4c, 0x64,
+        0x41, 0x43, 0x65, 0x63, 0x66, 0x61, 0x00, 0x00, 0x00, 0x14, 0xaf, 0xca, 0xd7, 0x13, 0x68,
+        0xa1, 0xf1, 0xc9, 0x6b, 0x86, 0x96, 0xfc, 0x77, 0x57, 0x01, 0x00,
+    ];
+    let mut ike = Ike::default();
+    ike.limits = limits;
+    match ike.parse(&input, Direction::Unknown) {
+        Ok((rest, Some(Message::Ike(message)))) => {
+            assert!(rest.is_empty());
+            assert_eq!(message.payloads.len(), expected_payloads);
+            assert!(message.error_flags.contains(ErrorFlags::LimitExceeded));
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[cfg(feature = "visit")]
+#[test]
+fn test_visit() {
+    use sawp_flags::Flag;
+    use sawp_ike::header::{ExchangeType, Header, IkeFlags};
+    use sawp_ike::payloads::{Payload, PayloadData, PayloadType};
+    use sawp_visit::{Path, Value};
+
+    let notify = |notify_message_type| Payload {
+        next_payload: PayloadType::Notify,
+        critical_bit: Some(0),
+        reserved: 0,
+        payload_length: 8,
+        data: PayloadData::Notify {
+            protocol_id: 0,
+            spi_size: 0,
+            spi: Vec::new(),
+            notify_message_type,
+            notification_data: Vec::new(),
+        },
+    };
+    let message = Message::Ike(IkeMessage {
+        header: Header {
+            initiator_spi: 1,
+            responder_spi: 0,
+            next_payload: PayloadType::Notify,
+            version: 0x20,
+            major_version: 2,
+            minor_version: 0,
+            exchange_type: ExchangeType::IkeSaInit,
+            flags: IkeFlags::INITIATOR.into(),
+            message_id: 0,
+            length: 44,
+        },
+        payloads: vec![notify(16430), notify(16406)],
+        encrypted_data: Vec::new(),
+        error_flags: ErrorFlags::none(),
+    });
+
+    let path: Path = "ike.payloads[*].notify_message_type".parse().unwrap();
+    assert_eq!(
+        path.strip_prefix(Ike::name()).unwrap().query(&message),
+        vec![Value::Uint(16430), Value::Uint(16406)]
+    );
+    assert_eq!(
+        sawp_visit::query(&message, "header.exchange_type").unwrap(),
+        vec![Value::Variant("IkeSaInit")]
+    );
+}
@@ -2,6 +2,7 @@ use rstest::rstest;
 use sawp::error::Result;
 use sawp::parser::{Direction, Parse};
 use sawp::protocol::Protocol;
+use sawp::testing::assert_incremental;
 use sawp_flags::Flag;
 use sawp_ike::{header::*, payloads::*, *};
 
@@ -166,6 +167,7 @@ fn ikev1_full_parse(input: &[u8], expected: Result<(usize, Option<<Ike as Protoc
             .map(|(left, msg)| (left.len(), msg)),
         expected
     );
+    assert_incremental(Ike::default, input, Direction::Unknown);
 }
 
 #[rstest(input, ptype, expected,
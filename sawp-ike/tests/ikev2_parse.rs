@@ -2,6 +2,7 @@ use rstest::rstest;
 use sawp::error::{Error, Result};
 use sawp::parser::{Direction, Parse};
 use sawp::protocol::Protocol;
+use sawp::testing::assert_incremental;
 use sawp_flags::Flag;
 use sawp_ike::{header::*, payloads::*, *};
 
@@ -281,6 +282,7 @@ fn ikev2_full_parse(input: &[u8], expected: Result<(usize, Option<<Ike as Protoc
             .map(|(left, msg)| (left.len(), msg)),
         expected
     );
+    assert_incremental(Ike::default, input, Direction::Unknown);
 }
 
 // Payload only tests. Only parses the unique part of a given payload (without Generic Payload
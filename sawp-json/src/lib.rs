@@ -3,22 +3,174 @@
 #![allow(clippy::unneeded_field_pattern)]
 
 use sawp::error::{Error, ErrorKind, Result};
+use sawp::limits::{Limits, Tracker};
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::Probe;
 use sawp::protocol::Protocol;
-use serde_json::{Deserializer, Value};
+use sawp_flags::{BitFlags, Flag, Flags};
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{Deserializer, Map, Value};
 
-#[derive(Debug)]
-pub struct Json {}
+use std::fmt;
+
+#[derive(Debug, Default)]
+pub struct Json {
+    /// Resource limits applied to every message. Strings and object keys are
+    /// charged to `max_alloc` and array elements and object members to
+    /// `max_elements`. Values past a limit or nested deeper than `max_depth`
+    /// are skipped without being kept.
+    pub limits: Limits,
+}
+
+/// Error flags raised while parsing JSON - to be used in the returned Message
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BitFlags)]
+pub enum ErrorFlags {
+    /// A resource limit was reached. Strings past the allocation limit are
+    /// empty, arrays and objects nested too deep are empty and elements past
+    /// the limit are dropped.
+    LimitExceeded = 0b0000_0001,
+}
+
+/// Builds a [`Value`] while charging its strings, elements and depth to a
+/// tracker. Input past a limit is skipped without being kept.
+#[derive(Clone, Copy)]
+struct Limited<'t> {
+    tracker: &'t Tracker,
+    depth: usize,
+}
+
+impl<'t> Limited<'t> {
+    fn nested(self) -> Self {
+        Limited {
+            tracker: self.tracker,
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Limited<'_> {
+    type Value = Value;
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Limited<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> core::result::Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> core::result::Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> core::result::Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> core::result::Result<Value, E> {
+        Ok(Value::from(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> core::result::Result<Value, E> {
+        if self.tracker.alloc(value.len()) {
+            Ok(Value::String(value.to_owned()))
+        } else {
+            Ok(Value::String(String::new()))
+        }
+    }
+
+    fn visit_unit<E>(self) -> core::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> core::result::Result<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let element = Element {
+            seed: self.nested(),
+            keep: self.tracker.depth(self.depth + 1),
+        };
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element_seed(element)? {
+            values.extend(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> core::result::Result<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let member = Element {
+            seed: self.nested(),
+            keep: self.tracker.depth(self.depth + 1),
+        };
+        let mut object = Map::new();
+        while let Some(key) = map.next_key_seed(member)? {
+            match key {
+                Some(Value::String(key)) => {
+                    object.insert(key, map.next_value_seed(self.nested())?);
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+/// An array element or object member, charged before it is parsed. It is
+/// skipped if it can't be kept.
+#[derive(Clone, Copy)]
+struct Element<'t> {
+    seed: Limited<'t>,
+    keep: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for Element<'_> {
+    type Value = Option<Value>;
+
+    fn deserialize<D>(self, deserializer: D) -> core::result::Result<Option<Value>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        if self.keep && self.seed.tracker.element() {
+            self.seed.deserialize(deserializer).map(Some)
+        } else {
+            deserializer
+                .deserialize_ignored_any(IgnoredAny)
+                .map(|_| None)
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub value: Value,
+    pub error_flags: Flags<ErrorFlags>,
 }
 
 impl Message {
     pub fn new(value: Value) -> Self {
-        Self { value }
+        Self {
+            value,
+            error_flags: ErrorFlags::none(),
+        }
     }
 }
 
@@ -30,21 +182,109 @@ impl Protocol<'_> for Json {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
+/// Exposes a JSON value as a field tree: objects members are fields, arrays are
+/// sequences and `null` is absent.
+#[cfg(feature = "visit")]
+#[repr(transparent)]
+struct Node(Value);
+
+#[cfg(feature = "visit")]
+impl Node {
+    fn new(value: &Value) -> &Node {
+        // Safety: Node is a transparent wrapper around Value
+        unsafe { &*(value as *const Value as *const Node) }
+    }
+}
+
+#[cfg(feature = "visit")]
+impl sawp_visit::Visit for Node {
+    fn visit<'a>(&'a self, visitor: &mut dyn sawp_visit::Visitor<'a>) {
+        use sawp_visit::Value as Leaf;
+
+        match &self.0 {
+            Value::Null => (),
+            Value::Bool(value) => visitor.value(Leaf::Bool(*value)),
+            Value::Number(number) => {
+                if let Some(value) = number.as_u64() {
+                    visitor.value(Leaf::Uint(value));
+                } else if let Some(value) = number.as_i64() {
+                    visitor.value(Leaf::Int(value));
+                } else if let Some(value) = number.as_f64() {
+                    visitor.value(Leaf::Float(value));
+                }
+            }
+            Value::String(value) => visitor.value(Leaf::Str(value)),
+            Value::Array(values) => {
+                visitor.sequence(values.len());
+                for (index, value) in values.iter().enumerate() {
+                    visitor.element(index, Node::new(value));
+                }
+            }
+            Value::Object(map) => {
+                for (name, value) in map {
+                    visitor.field(name, Node::new(value));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "visit")]
+impl sawp_visit::Visit for Message {
+    fn visit<'a>(&'a self, visitor: &mut dyn sawp_visit::Visitor<'a>) {
+        Node::new(&self.value).visit(visitor);
+    }
+}
+
 impl<'a> Parse<'a> for Json {
     fn parse(
         &self,
         input: &'a [u8],
         _direction: Direction,
     ) -> Result<(&'a [u8], Option<Self::Message>)> {
-        let mut stream = Deserializer::from_slice(input).into_iter::<Value>();
+        // Find the end of the value without keeping it
+        let mut stream = Deserializer::from_slice(input).into_iter::<IgnoredAny>();
+        let length = match stream.next() {
+            // A number may continue in the next bytes
+            Some(Ok(_)) if stream.byte_offset() == input.len() && is_number(input) => {
+                return Err(Error::incomplete())
+            }
+            Some(Ok(_)) => stream.byte_offset(),
+            Some(Err(err)) if err.is_eof() => return Err(Error::incomplete()),
+            None => return Err(Error::incomplete()),
+            Some(Err(_)) => return Err(Error::new(ErrorKind::InvalidData)),
+        };
 
-        match stream.next() {
-            Some(Ok(value)) => Ok((&input[stream.byte_offset()..], Some(Message::new(value)))),
-            _ => Err(Error::new(ErrorKind::InvalidData)),
+        let tracker = self.limits.tracker();
+        let seed = Limited {
+            tracker: &tracker,
+            depth: 0,
+        };
+        let value = seed
+            .deserialize(&mut Deserializer::from_slice(&input[..length]))
+            .map_err(|_| Error::new(ErrorKind::InvalidData))?;
+        let mut message = Message::new(value);
+        if tracker.exceeded() {
+            message.error_flags |= ErrorFlags::LimitExceeded;
         }
+        Ok((&input[length..], Some(message)))
     }
 }
 
+/// Whether the value at the start of `input` is a number.
+fn is_number(input: &[u8]) -> bool {
+    matches!(
+        input.iter().find(|c| !c.is_ascii_whitespace()),
+        Some(b'-' | b'0'..=b'9')
+    )
+}
+
 impl<'a> Probe<'a> for Json {}
 
 #[cfg(test)]
@@ -53,17 +293,19 @@ mod tests {
     use rstest::rstest;
     use sawp::error::{Error, ErrorKind, Result};
     use sawp::probe::Status;
+    use sawp::testing::assert_incremental;
     use serde_json::json;
 
     #[rstest(
         input,
         expected,
-        case::empty(b"", Err(Error::new(ErrorKind::InvalidData))),
+        case::empty(b"", Err(Error::incomplete())),
         case::singlequote(b"''", Err(Error::new(ErrorKind::InvalidData))),
-        case::incomplete(b"{\"a\":", Err(Error::new(ErrorKind::InvalidData))),
+        case::incomplete(b"{\"a\":", Err(Error::incomplete())),
 
         // Smoke tests
-        case::number(b"1234", Ok((0, Some(Message::new(json!(1234)))))),
+        case::number(b"1234", Err(Error::incomplete())),
+        case::number_end(b"1234 ", Ok((1, Some(Message::new(json!(1234)))))),
         case::null(b"null", Ok((0, Some(Message::new(json!(null)))))),
         case::bool_true(b"true", Ok((0, Some(Message::new(json!(true)))))),
         case::bool_false(b"false", Ok((0, Some(Message::new(json!(false)))))),
@@ -76,23 +318,90 @@ mod tests {
         case::multi(b"{}[1]", Ok((3, Some(Message::new(json!({})))))),
     )]
     fn test_parse(input: &[u8], expected: Result<(usize, Option<<Json as Protocol>::Message>)>) {
-        let json = Json {};
+        let json = Json::default();
         assert_eq!(
             expected,
             json.parse(input, Direction::Unknown)
                 .map(|(left, msg)| (left.len(), msg)),
         );
+        assert_incremental(Json::default, input, Direction::Unknown);
     }
 
     #[rstest(
         input,
         expected,
-        case::empty(b"", Status::Unrecognized),
-        case::incomplete(b"{\"a\":", Status::Unrecognized),
-        case::number(b"1234", Status::Recognized)
+        case::empty(b"", Status::Incomplete),
+        case::incomplete(b"{\"a\":", Status::Incomplete),
+        case::number(b"1234 ", Status::Recognized)
     )]
     fn test_probe(input: &[u8], expected: Status) {
-        let json = Json {};
+        let json = Json::default();
         assert_eq!(expected, json.probe(input, Direction::Unknown));
     }
+
+    #[rstest(
+        input,
+        limits,
+        expected,
+        case::max_alloc(
+            b"[\"ab\", \"cd\", \"ef\"]",
+            Limits { max_alloc: 4, ..Limits::default() },
+            Ok((0, Some(Message {
+                value: json!(["ab", "cd", ""]),
+                error_flags: ErrorFlags::LimitExceeded.into(),
+            })))
+        ),
+        case::max_depth(
+            b"[[1], 2]",
+            Limits { max_depth: 1, ..Limits::default() },
+            Ok((0, Some(Message {
+                value: json!([[], 2]),
+                error_flags: ErrorFlags::LimitExceeded.into(),
+            })))
+        ),
+        case::max_elements(
+            b"{\"a\": [1, 2], \"b\": 3} []",
+            Limits { max_elements: 2, ..Limits::default() },
+            Ok((3, Some(Message {
+                value: json!({"a": [1]}),
+                error_flags: ErrorFlags::LimitExceeded.into(),
+            })))
+        ),
+        case::within_limits(
+            b"{\"a\": [1]}",
+            Limits { max_depth: 2, max_elements: 2, ..Limits::default() },
+            Ok((0, Some(Message::new(json!({"a": [1]})))))
+        ),
+    )]
+    fn test_parse_limits(
+        input: &[u8],
+        limits: Limits,
+        expected: Result<(usize, Option<<Json as Protocol>::Message>)>,
+    ) {
+        let json = Json { limits };
+        assert_eq!(
+            expected,
+            json.parse(input, Direction::Unknown)
+                .map(|(left, msg)| (left.len(), msg)),
+        );
+        assert_incremental(|| Json { limits }, input, Direction::Unknown);
+    }
+
+    #[cfg(feature = "visit")]
+    #[test]
+    fn test_visit() {
+        use sawp_visit::Value as Leaf;
+
+        let message = Message::new(json!({
+            "a": [{"b": 1}, {"b": -2}, {"b": 0.5}, {"b": null}],
+            "c": "d",
+            "e": true,
+        }));
+        assert_eq!(
+            sawp_visit::query(&message, "a[*].b"),
+            Ok(vec![Leaf::Uint(1), Leaf::Int(-2), Leaf::Float(0.5)])
+        );
+        assert_eq!(sawp_visit::query(&message, "c"), Ok(vec![Leaf::Str("d")]));
+        assert_eq!(sawp_visit::query(&message, "e"), Ok(vec![Leaf::Bool(true)]));
+    }
 }
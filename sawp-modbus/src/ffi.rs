@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 use super::*;
 use sawp::error::Error;
+use sawp::metrics::{self, Recorder, Snapshot};
 use sawp::parser::Parse;
 use sawp_ffi::*;
 
@@ -13,10 +14,58 @@ pub struct ParseResult {
 
 #[no_mangle]
 pub unsafe extern "C" fn sawp_modbus_create(probe_strict: bool) -> *mut Modbus {
-    let parser = Modbus { probe_strict };
+    let parser = Modbus {
+        probe_strict,
+        ..Default::default()
+    };
     parser.into_ffi_ptr()
 }
 
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_modbus_set_limits(parser: *mut Modbus, limits: Limits) {
+    let parser = deref_mut!(parser);
+    parser.limits = limits;
+}
+
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_modbus_set_framing(parser: *mut Modbus, framing: Framing) {
+    let parser = deref_mut!(parser);
+    parser.framing = framing;
+}
+
+/// See `Message::is_disruptive`
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_modbus_message_is_disruptive(message: *const Message) -> bool {
+    sawp_ffi::deref!(message).is_disruptive()
+}
+
+/// Metrics of the parsers called through the FFI.
+static RECORDER: Recorder = Recorder::new();
+
+/// Copies the metrics collected by the modbus parsers into `snapshot`.
+///
+/// Returns false if nothing was recorded, for instance when the library was
+/// built without the `metrics` feature.
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_modbus_metrics(snapshot: *mut Snapshot) -> bool {
+    nullcheck!(snapshot);
+    match metrics::get(Modbus::name()) {
+        Some(metrics) => {
+            *snapshot = metrics;
+            true
+        }
+        None => false,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn sawp_modbus_destroy(d: *mut Modbus) {
     if !d.is_null() {
@@ -34,7 +83,9 @@ pub unsafe extern "C" fn sawp_modbus_parse(
     length: usize,
 ) -> *mut ParseResult {
     let input = std::slice::from_raw_parts(data, length);
-    match (*parser).parse(input, direction) {
+    let result = (*parser).parse(input, direction);
+    RECORDER.record::<Modbus>(input, &result);
+    match result {
         Ok((sl, message)) => ParseResult {
             message: message.into_ffi_ptr(),
             size_read: length - sl.len(),
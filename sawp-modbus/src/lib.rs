@@ -9,6 +9,11 @@
 //!
 //! [PI_MBUS_300](https://modbus.org/docs/PI_MBUS_300.pdf)
 //!
+//! [Modbus_over_serial_line_V1_02](https://modbus.org/docs/Modbus_over_serial_line_V1_02.pdf)
+//!
+//! Modbus/TCP is parsed by default, set [`Modbus::framing`] to [`Framing::Rtu`]
+//! or [`Framing::Ascii`] for serial line frames tunnelled over TCP or UDP.
+//!
 //! # Example
 //! ```
 //! use sawp::parser::{Direction, Parse};
@@ -43,13 +48,18 @@
 //! }
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::unneeded_field_pattern)]
 
+extern crate alloc;
+
 /// Re-export of the `Flags` struct that is used to represent bit flags
 /// in this crate.
 pub use sawp_flags::{Flag, Flags};
 
 use sawp::error::{Error, ErrorKind, Result};
+use sawp::limits::Limits;
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::{Probe, Status};
 use sawp::protocol::Protocol;
@@ -57,18 +67,31 @@ use sawp::protocol::Protocol;
 use sawp_flags::BitFlags;
 
 use nom::bytes::streaming::take;
-use nom::number::streaming::{be_u16, be_u8};
+use nom::number::streaming::{be_u16, be_u8, le_u16};
 
 use num_enum::TryFromPrimitive;
-use std::convert::TryFrom;
-use std::ops::RangeInclusive;
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops::RangeInclusive;
 
 /// FFI structs and Accessors
 #[cfg(feature = "ffi")]
 mod ffi;
 
+#[cfg(feature = "policy")]
+pub mod policy;
+pub mod security;
+pub mod state;
+pub mod transactions;
+pub mod values;
+
+pub use transactions::{Event, Transactions};
+
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 // Used for exception handling -- any function above this is an exception
 const ERROR_MASK: u8 = 0x80;
@@ -78,9 +101,24 @@ const MAX_QUANTITY_WORD_ACCESS: u16 = 125;
 // Valid count range for reading
 const MIN_RD_COUNT: u8 = 1;
 const MAX_RD_COUNT: u8 = 250;
+// Valid byte count ranges of file record requests and responses
+const MIN_RD_FILE_COUNT: u8 = 0x07;
+const MAX_RD_FILE_COUNT: u8 = 0xf5;
+const MIN_WR_FILE_COUNT: u8 = 0x09;
+const MAX_WR_FILE_COUNT: u8 = 0xfb;
+// The only reference type of file records
+const FILE_REFERENCE_TYPE: u8 = 6;
+const MAX_RECORD_NUMBER: u16 = 0x270f;
+const MAX_FIFO_COUNT: u16 = 31;
+// Byte count of the status, event count and message count of an event log
+const EVENT_LOG_HEADER_COUNT: u8 = 6;
+const MAX_EVENT_LOG_EVENTS: usize = 64;
 
 const MIN_LENGTH: u16 = 2;
 const MAX_LENGTH: u16 = 254;
+// Maximum number of hex digits between the start and end of an ASCII frame,
+// the unit id and PDU followed by the LRC
+const MAX_ASCII_LENGTH: usize = 2 * (MAX_LENGTH as usize + 1);
 
 /// Function code groups based on general use. Allows for easier
 /// parsing of certain functions, since generally most functions in a group
@@ -180,6 +218,9 @@ impl From<&Message> for Flags<CodeCategory> {
                         CodeCategory::PUBLIC_ASSIGNED.into()
                     }
                 }
+                Data::DeviceIdentification(_) | Data::CANopen(_) => {
+                    CodeCategory::PUBLIC_ASSIGNED.into()
+                }
                 _ => CodeCategory::none(),
             },
             FunctionCode::Unknown => CodeCategory::from_raw(msg.function.raw),
@@ -193,7 +234,7 @@ impl From<&Message> for Flags<CodeCategory> {
 /// error flags to see if and what errors were in the
 /// pack of bytes and take action using this information.
 #[allow(non_camel_case_types)]
-#[repr(u8)]
+#[repr(u16)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq, BitFlags)]
 pub enum ErrorFlags {
     DATA_VALUE = 0b0000_0001,
@@ -201,11 +242,21 @@ pub enum ErrorFlags {
     EXC_CODE = 0b0000_0100,
     FUNC_CODE = 0b0000_1000,
     PROTO_ID = 0b0001_0000,
+    /// A resource limit was reached, the message data was not parsed
+    LIMIT_EXCEEDED = 0b0010_0000,
+    /// The CRC of an RTU frame doesn't match its contents
+    CRC = 0b0100_0000,
+    /// The LRC of an ASCII frame doesn't match its contents
+    LRC = 0b1000_0000,
+    /// An ASCII frame holds characters which aren't hex digits or an odd
+    /// number of them
+    HEX_DIGIT = 0b0001_0000_0000,
 }
 
 /// Information on the function code parsed
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Function {
     /// Value of the function byte
@@ -231,6 +282,7 @@ impl Function {
 }
 
 /// Function code names as stated in the [protocol reference](https://modbus.org/docs/Modbus_Application_Protocol_V1_1b.pdf)
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum FunctionCode {
@@ -262,8 +314,8 @@ pub enum FunctionCode {
     Unknown,
 }
 
-impl std::fmt::Display for FunctionCode {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for FunctionCode {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "{:?}", self)
     }
 }
@@ -277,6 +329,7 @@ impl FunctionCode {
 /// Information on the diagnostic subfunction code parsed
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Diagnostic {
     /// Value of the subfunction bytes
@@ -296,6 +349,7 @@ impl Diagnostic {
 }
 
 /// Subfunction code names as stated in the [protocol reference](https://modbus.org/docs/Modbus_Application_Protocol_V1_1b.pdf)
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum DiagnosticSubfunction {
@@ -321,15 +375,99 @@ pub enum DiagnosticSubfunction {
     Reserved,
 }
 
-impl std::fmt::Display for DiagnosticSubfunction {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl DiagnosticSubfunction {
+    /// Whether responses return the value of a counter
+    pub fn is_counter(self) -> bool {
+        matches!(
+            self,
+            DiagnosticSubfunction::RetBusMsgCount
+                | DiagnosticSubfunction::RetBusCommErrCount
+                | DiagnosticSubfunction::RetBusExcErrCount
+                | DiagnosticSubfunction::RetServerMsgCount
+                | DiagnosticSubfunction::RetServerNoRespCount
+                | DiagnosticSubfunction::RetServerNAKCount
+                | DiagnosticSubfunction::RetServerBusyCount
+                | DiagnosticSubfunction::RetBusCharOverrunCount
+                | DiagnosticSubfunction::RetOverrunErrCount
+        )
+    }
+
+    /// Whether responses echo the data of the request
+    pub fn is_echo(self) -> bool {
+        matches!(
+            self,
+            DiagnosticSubfunction::RetQueryData
+                | DiagnosticSubfunction::RestartCommOpt
+                | DiagnosticSubfunction::ChangeInputDelimiter
+                | DiagnosticSubfunction::ClearCtrDiagReg
+                | DiagnosticSubfunction::ClearOverrunCounterFlag
+        )
+    }
+}
+
+impl core::fmt::Display for DiagnosticSubfunction {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "{:?}", self)
     }
 }
 
+/// [`FunctionCode::Diagnostic`] data decoded per subfunction
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DiagnosticValue {
+    /// Data without a meaning of its own, such as the query data of
+    /// [`DiagnosticSubfunction::RetQueryData`] or the zeros of requests for a
+    /// value, of reserved subfunctions or of an unexpected length. The bytes
+    /// are only kept in the `data` of [`Data::Diagnostic`]
+    Raw,
+    /// [`DiagnosticSubfunction::RestartCommOpt`]
+    Restart {
+        /// Whether the communications event log is cleared
+        clear_log: bool,
+    },
+    /// [`DiagnosticSubfunction::ChangeInputDelimiter`] new end of message
+    /// character of ASCII frames
+    Delimiter(u8),
+    /// [`DiagnosticSubfunction::RetDiagReg`] responses, the meaning of the
+    /// bits being device specific
+    Register(u16),
+    /// Responses of the subfunctions returning a counter
+    Counter(u16),
+}
+
+impl DiagnosticValue {
+    fn new(code: DiagnosticSubfunction, data: &[u8], direction: &Direction) -> Self {
+        let word = match data {
+            [high, low] => Some((*high, *low)),
+            _ => None,
+        };
+
+        match (code, word) {
+            (DiagnosticSubfunction::RestartCommOpt, Some((high, _))) => DiagnosticValue::Restart {
+                clear_log: high == 0xff,
+            },
+            (DiagnosticSubfunction::ChangeInputDelimiter, Some((high, _))) => {
+                DiagnosticValue::Delimiter(high)
+            }
+            (DiagnosticSubfunction::RetDiagReg, Some((high, low)))
+                if *direction == Direction::ToClient =>
+            {
+                DiagnosticValue::Register(u16::from_be_bytes([high, low]))
+            }
+            (code, Some((high, low))) if code.is_counter() && *direction == Direction::ToClient => {
+                DiagnosticValue::Counter(u16::from_be_bytes([high, low]))
+            }
+            _ => DiagnosticValue::Raw,
+        }
+    }
+}
+
 /// Information on the mei code parsed
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct MEI {
     /// Value of the mei function byte
@@ -349,6 +487,7 @@ impl MEI {
 }
 
 /// MEI function code names as stated in the [protocol reference](https://modbus.org/docs/Modbus_Application_Protocol_V1_1b.pdf)
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum MEIType {
@@ -357,15 +496,154 @@ pub enum MEIType {
     RdDevId = 0x0e,
 }
 
-impl std::fmt::Display for MEIType {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for MEIType {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "{:?}", self)
+    }
+}
+
+/// Read device ID code names of [`MEIType::RdDevId`] requests/responses
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum ReadDevIdCode {
+    /// Stream access to the basic objects
+    Basic = 0x01,
+    /// Stream access to the basic and regular objects
+    Regular,
+    /// Stream access to all objects
+    Extended,
+    /// Access to a single object
+    Specific,
+    Unknown,
+}
+
+impl core::fmt::Display for ReadDevIdCode {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "{:?}", self)
+    }
+}
+
+impl ReadDevIdCode {
+    pub fn from_raw(val: u8) -> Self {
+        match ReadDevIdCode::try_from(val) {
+            Ok(ReadDevIdCode::Unknown) | Err(_) => ReadDevIdCode::Unknown,
+            Ok(code) => code,
+        }
+    }
+}
+
+/// Device identification object names as stated in the [protocol reference](https://modbus.org/docs/Modbus_Application_Protocol_V1_1b.pdf)
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum DeviceObjectId {
+    VendorName = 0x00,
+    ProductCode,
+    MajorMinorRevision,
+    VendorUrl,
+    ProductName,
+    ModelName,
+    UserApplicationName,
+    // 0x07 - 0x7f: RESERVED
+    Reserved,
+    // 0x80 - 0xff: device dependent
+    Private = 0x80,
+}
+
+impl core::fmt::Display for DeviceObjectId {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "{:?}", self)
     }
 }
 
+impl DeviceObjectId {
+    pub fn from_raw(val: u8) -> Self {
+        match val {
+            x if x >= DeviceObjectId::Private as u8 => DeviceObjectId::Private,
+            x => DeviceObjectId::try_from(x).unwrap_or(DeviceObjectId::Reserved),
+        }
+    }
+}
+
+/// An object of a [`MEIType::RdDevId`] response
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct DeviceObject {
+    /// Value of the object id byte
+    pub id: u8,
+    /// Object name associated with the id
+    #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
+    pub code: DeviceObjectId,
+    /// Object value, an ASCII string for the objects of the basic and regular
+    /// categories
+    pub value: Vec<u8>,
+}
+
+/// [`MEIType::RdDevId`] information on parsed in function data
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum DeviceIdentification {
+    Request {
+        read_code: ReadDevIdCode,
+        /// First object to read, or the object to read with
+        /// [`ReadDevIdCode::Specific`]
+        object_id: u8,
+    },
+    Response {
+        read_code: ReadDevIdCode,
+        /// Highest category of objects supported by the device, with 0x80
+        /// set if it supports access to single objects
+        conformity_level: u8,
+        /// Whether the objects of the category don't fit in this response
+        more_follows: bool,
+        /// Object to request next when more follows
+        next_object_id: u8,
+        objects: Vec<DeviceObject>,
+    },
+}
+
+impl DeviceIdentification {
+    /// Gets the value of the object named `code` in a response.
+    pub fn object(&self, code: DeviceObjectId) -> Option<&[u8]> {
+        match self {
+            DeviceIdentification::Response { objects, .. } => objects
+                .iter()
+                .find(|object| object.code == code)
+                .map(|object| object.value.as_slice()),
+            DeviceIdentification::Request { .. } => None,
+        }
+    }
+}
+
+/// [`MEIType::CANOpenGenRefReqResp`] requests/responses as mapped by
+/// [CiA 309-2](https://www.can-cia.org/can-knowledge/canopen/cia309/)
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct CANopenReference {
+    pub protocol_control: u8,
+    pub gateway_id: u8,
+    pub node_id: u8,
+    /// Object dictionary index
+    pub index: u16,
+    /// Object dictionary subindex
+    pub subindex: u8,
+    pub starting_address: u16,
+    /// Number of data bytes to read or write
+    pub count: u16,
+    pub data: Vec<u8>,
+}
+
 /// Information on the exception code parsed
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Exception {
     /// Value of the exception code byte
@@ -385,6 +663,7 @@ impl Exception {
 }
 
 /// Exception code names as stated in the [protocol reference](https://modbus.org/docs/Modbus_Application_Protocol_V1_1b.pdf)
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum ExceptionCode {
@@ -401,8 +680,8 @@ pub enum ExceptionCode {
     Unknown,
 }
 
-impl std::fmt::Display for ExceptionCode {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for ExceptionCode {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "{:?}", self)
     }
 }
@@ -410,6 +689,7 @@ impl std::fmt::Display for ExceptionCode {
 /// Read information on parsed in function data
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Read {
     Request { address: u16, quantity: u16 },
@@ -419,6 +699,7 @@ pub enum Read {
 /// Write information on parsed in function data
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Write {
     /// [`AccessType::MULTIPLE`] requests, responses fall in [`Write::Other`]
@@ -439,15 +720,58 @@ pub enum Write {
     Other { address: u16, data: u16 },
 }
 
+/// A file record of [`FunctionCode::RdFileRec`] requests and
+/// [`FunctionCode::WrFileRec`] requests/responses
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct FileRecord {
+    pub reference_type: u8,
+    pub file_number: u16,
+    pub record_number: u16,
+    /// Number of registers in the record
+    pub record_length: u16,
+    /// Register values, empty for read requests
+    pub data: Vec<u8>,
+}
+
+/// A file record of [`FunctionCode::RdFileRec`] responses
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub struct FileRecordData {
+    pub reference_type: u8,
+    /// Register values
+    pub data: Vec<u8>,
+}
+
+/// [`FunctionCode::RdFIFOQueue`] information on parsed in function data
+#[cfg_attr(feature = "ffi", derive(GenerateFFI))]
+#[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
+#[derive(Debug, PartialEq, Eq)]
+pub enum FIFO {
+    Request {
+        address: u16,
+    },
+    /// Register values in the queue
+    Response(Vec<u16>),
+}
+
 /// Represents the various fields found in the PDU
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Data {
     Exception(Exception),
     Diagnostic {
         func: Diagnostic,
         data: Vec<u8>,
+        /// `data` decoded per subfunction
+        value: DiagnosticValue,
     },
     MEI {
         mei_type: MEI,
@@ -459,21 +783,70 @@ pub enum Data {
         read: Read,
         write: Write,
     },
+    /// [`FunctionCode::RdFileRec`] requests and [`FunctionCode::WrFileRec`] requests/responses
+    FileRecords(Vec<FileRecord>),
+    /// [`FunctionCode::RdFileRec`] responses
+    FileRecordData(Vec<FileRecordData>),
+    FIFO(FIFO),
+    /// [`MEIType::RdDevId`] requests/responses
+    DeviceIdentification(DeviceIdentification),
+    /// [`MEIType::CANOpenGenRefReqResp`] requests/responses
+    CANopen(CANopenReference),
+    /// [`FunctionCode::GetCommEventCtr`] responses
+    CommEventCounter {
+        status: u16,
+        event_count: u16,
+    },
+    /// [`FunctionCode::GetCommEventLog`] responses
+    CommEventLog {
+        status: u16,
+        event_count: u16,
+        message_count: u16,
+        /// Event bytes, the most recent first
+        events: Vec<u8>,
+    },
+    /// [`FunctionCode::ReportServerID`] responses
+    ServerID {
+        server_id: u8,
+        /// 0x00 if the device is off, 0xff if it is on
+        run_indicator: u8,
+        /// Device specific data
+        data: Vec<u8>,
+    },
     /// Used for data that doesn't fit elsewhere
     ByteVec(Vec<u8>),
     Empty,
 }
 
+/// Framing of the application data units around the PDU
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Framing {
+    /// Modbus/TCP: MBAP header followed by the PDU
+    #[default]
+    Tcp,
+    /// Modbus RTU: unit id, PDU and CRC-16, as sent on serial lines and
+    /// tunnelled over TCP or UDP by serial gateways
+    Rtu,
+    /// Modbus ASCII: `:`, unit id, PDU and LRC as hex digits, then CRLF
+    Ascii,
+}
+
 #[derive(Debug, Default)]
 pub struct Modbus {
     /// Enable strict probing, such as only recognizing
     /// public assigned function codes
     pub probe_strict: bool,
+    /// Resource limits applied to every message
+    pub limits: Limits,
+    /// Framing of the messages to parse and probe
+    pub framing: Framing,
 }
 
 /// Breakdown of the parsed modbus bytes
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_modbus"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub transaction_id: u16,
@@ -486,11 +859,24 @@ pub struct Message {
     #[cfg_attr(feature = "ffi", sawp_ffi(flag = "u8"))]
     pub category: Flags<CodeCategory>,
     pub data: Data,
-    #[cfg_attr(feature = "ffi", sawp_ffi(flag = "u8"))]
+    #[cfg_attr(feature = "ffi", sawp_ffi(flag = "u16"))]
     pub error_flags: Flags<ErrorFlags>,
 }
 
 impl Message {
+    /// Whether the message is a diagnostic taking the device off the network,
+    /// Force Listen Only Mode or Restart Communications Option. These are
+    /// valid Modbus and aren't flagged as errors.
+    pub fn is_disruptive(&self) -> bool {
+        matches!(
+            &self.data,
+            Data::Diagnostic { func, .. } if matches!(
+                func.code,
+                DiagnosticSubfunction::ForceListenOnlyMode | DiagnosticSubfunction::RestartCommOpt
+            )
+        )
+    }
+
     /// Subtracts 2 from the length (the unit id and function bytes)
     /// so that length checks do not need to account for the 2 bytes
     fn data_length(&self) -> u16 {
@@ -537,7 +923,7 @@ impl Message {
     // Response:
     //     Diagnostic Code:        2           (0,1)
     //     Data:                   x           (2..)
-    fn parse_diagnostic<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+    fn parse_diagnostic<'a>(&mut self, input: &'a [u8], direction: Direction) -> Result<&'a [u8]> {
         if self.data_length() < 2 {
             self.error_flags |= ErrorFlags::DATA_LENGTH;
             return Ok(input);
@@ -546,8 +932,10 @@ impl Message {
         let (input, diag_func) = be_u16(input)?;
         let (input, rest) = take(self.data_length() - 2)(input)?;
 
+        let func = Diagnostic::new(diag_func);
         self.data = Data::Diagnostic {
-            func: Diagnostic::new(diag_func),
+            value: DiagnosticValue::new(func.code, rest, &direction),
+            func,
             data: rest.to_vec(),
         };
         Ok(input)
@@ -574,6 +962,351 @@ impl Message {
         Ok(input)
     }
 
+    /// Parses MEI data and decodes the known MEI types, keeping the raw data
+    /// if they are truncated.
+    fn parse_mei_data<'a>(&mut self, input: &'a [u8], request: bool) -> Result<&'a [u8]> {
+        let rest = self.parse_mei(input)?;
+        let code = match &self.data {
+            Data::MEI { mei_type, .. } => mei_type.code,
+            _ => return Ok(rest),
+        };
+
+        // The MEI data following its type
+        let data = &input[1..input.len() - rest.len()];
+        let decoded = match code {
+            MEIType::RdDevId if request => self.parse_device_id_request(data),
+            MEIType::RdDevId => self.parse_device_id_response(data),
+            MEIType::CANOpenGenRefReqResp => self.parse_canopen(data),
+            MEIType::Unknown => return Ok(rest),
+        };
+        match decoded {
+            Ok(data) => self.data = data,
+            Err(_) => self.error_flags |= ErrorFlags::DATA_LENGTH,
+        }
+
+        Ok(rest)
+    }
+
+    //                         Num Bytes   Byte Placement
+    // Read Device ID Code:    1           (0)
+    // Object ID:              1           (1)
+    fn parse_device_id_request(&mut self, input: &[u8]) -> Result<Data> {
+        let (input, read_code) = be_u8(input)?;
+        let (input, object_id) = be_u8(input)?;
+        let read_code = ReadDevIdCode::from_raw(read_code);
+
+        if read_code == ReadDevIdCode::Unknown {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        if !input.is_empty() {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        Ok(Data::DeviceIdentification(DeviceIdentification::Request {
+            read_code,
+            object_id,
+        }))
+    }
+
+    //                         Num Bytes   Byte Placement
+    // Read Device ID Code:    1           (0)
+    // Conformity Level:       1           (1)
+    // More Follows:           1           (2)
+    // Next Object ID:         1           (3)
+    // Number of Objects:      1           (4)
+    // Objects:                x           (5..)
+    //     Object ID:          1           (0)
+    //     Object Length:      1           (1)
+    //     Object Value:       Length      (2..Length + 2)
+    fn parse_device_id_response(&mut self, input: &[u8]) -> Result<Data> {
+        let (input, read_code) = be_u8(input)?;
+        let (input, conformity_level) = be_u8(input)?;
+        let (input, more_follows) = be_u8(input)?;
+        let (input, next_object_id) = be_u8(input)?;
+        let (mut input, count) = be_u8(input)?;
+        let read_code = ReadDevIdCode::from_raw(read_code);
+
+        if read_code == ReadDevIdCode::Unknown
+            || !matches!(conformity_level & !0x80, 0x01..=0x03)
+            || (more_follows != 0x00 && more_follows != 0xff)
+            || (more_follows == 0x00 && next_object_id != 0x00)
+        {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        let mut objects = Vec::new();
+        for _ in 0..count {
+            let (rest, id) = be_u8(input)?;
+            let (rest, length) = be_u8(rest)?;
+            let (rest, value) = take(length)(rest)?;
+            objects.push(DeviceObject {
+                id,
+                code: DeviceObjectId::from_raw(id),
+                value: value.to_vec(),
+            });
+            input = rest;
+        }
+
+        if !input.is_empty() {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        Ok(Data::DeviceIdentification(DeviceIdentification::Response {
+            read_code,
+            conformity_level,
+            more_follows: more_follows == 0xff,
+            next_object_id,
+            objects,
+        }))
+    }
+
+    //                         Num Bytes   Byte Placement
+    // Protocol Control:       1           (0)
+    // Gateway ID:             1           (1)
+    // Node ID:                1           (2)
+    // Index:                  2           (3,4)
+    // Subindex:               1           (5)
+    // Starting Address:       2           (6,7)
+    // Count:                  2           (8,9)
+    // Data:                   x           (10..)
+    fn parse_canopen(&mut self, input: &[u8]) -> Result<Data> {
+        let (input, protocol_control) = be_u8(input)?;
+        let (input, gateway_id) = be_u8(input)?;
+        let (input, node_id) = be_u8(input)?;
+        let (input, index) = be_u16(input)?;
+        let (input, subindex) = be_u8(input)?;
+        let (input, starting_address) = be_u16(input)?;
+        let (input, count) = be_u16(input)?;
+
+        Ok(Data::CANopen(CANopenReference {
+            protocol_control,
+            gateway_id,
+            node_id,
+            index,
+            subindex,
+            starting_address,
+            count,
+            data: input.to_vec(),
+        }))
+    }
+
+    //                         Num Bytes       Byte Placement
+    // Byte Count:             1               (0)
+    // Records:                Count           (1..Count + 1)
+    //     Reference Type:     1               (0)
+    //     File Number:        2               (1,2)
+    //     Record Number:      2               (3,4)
+    //     Record Length:      2               (5,6)
+    //     Record Data:        2 * Length      (7..) (FunctionCode::WrFileRec only)
+    fn parse_file_records<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, count) = be_u8(input)?;
+        let with_data = self.function.code == FunctionCode::WrFileRec;
+        let counts = if with_data {
+            MIN_WR_FILE_COUNT..=MAX_WR_FILE_COUNT
+        } else {
+            MIN_RD_FILE_COUNT..=MAX_RD_FILE_COUNT
+        };
+
+        if !counts.contains(&count) {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        if self.data_length() - 1 != u16::from(count) {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        let (input, mut records) = take(count)(input)?;
+        let mut files = Vec::new();
+        while !records.is_empty() {
+            let (rest, reference_type) = be_u8(records)?;
+            let (rest, file_number) = be_u16(rest)?;
+            let (rest, record_number) = be_u16(rest)?;
+            let (rest, record_length) = be_u16(rest)?;
+            let (rest, data) = if with_data {
+                take(2 * usize::from(record_length))(rest)?
+            } else {
+                (rest, &rest[..0])
+            };
+
+            if reference_type != FILE_REFERENCE_TYPE
+                || file_number == 0
+                || record_number > MAX_RECORD_NUMBER
+            {
+                self.error_flags |= ErrorFlags::DATA_VALUE;
+            }
+
+            files.push(FileRecord {
+                reference_type,
+                file_number,
+                record_number,
+                record_length,
+                data: data.to_vec(),
+            });
+            records = rest;
+        }
+
+        self.data = Data::FileRecords(files);
+        Ok(input)
+    }
+
+    //                         Num Bytes       Byte Placement
+    // Byte Count:             1               (0)
+    // Records:                Count           (1..Count + 1)
+    //     Record Length:      1               (0)
+    //     Reference Type:     1               (1)
+    //     Record Data:        Length - 1      (2..Length + 1)
+    fn parse_file_record_data<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, count) = be_u8(input)?;
+
+        if count > MAX_RD_FILE_COUNT {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        if self.data_length() - 1 != u16::from(count) {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        let (input, mut records) = take(count)(input)?;
+        let mut files = Vec::new();
+        while !records.is_empty() {
+            let (rest, length) = be_u8(records)?;
+            let (rest, record) = take(length)(rest)?;
+            let (data, reference_type) = be_u8(record)?;
+
+            if data.len() % 2 != 0 {
+                self.error_flags |= ErrorFlags::DATA_LENGTH;
+            }
+
+            if reference_type != FILE_REFERENCE_TYPE {
+                self.error_flags |= ErrorFlags::DATA_VALUE;
+            }
+
+            files.push(FileRecordData {
+                reference_type,
+                data: data.to_vec(),
+            });
+            records = rest;
+        }
+
+        self.data = Data::FileRecordData(files);
+        Ok(input)
+    }
+
+    //                         Num Bytes       Byte Placement
+    // Request:
+    //     FIFO Address:       2               (0,1)
+    // Response:
+    //     Byte Count:         2               (0,1)
+    //     FIFO Count:         2               (2,3)
+    //     FIFO Values:        2 * Count       (4..)
+    fn parse_fifo_request<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, address) = be_u16(input)?;
+        self.data = Data::FIFO(FIFO::Request { address });
+        Ok(input)
+    }
+
+    fn parse_fifo_response<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, count) = be_u16(input)?;
+        let (input, fifo_count) = be_u16(input)?;
+
+        if fifo_count > MAX_FIFO_COUNT {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        if u32::from(count) != 2 + 2 * u32::from(fifo_count) || self.data_length() - 2 != count {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        let (input, values) = take(2 * usize::from(fifo_count))(input)?;
+        self.data = Data::FIFO(FIFO::Response(
+            values
+                .chunks_exact(2)
+                .map(|value| u16::from_be_bytes([value[0], value[1]]))
+                .collect(),
+        ));
+        Ok(input)
+    }
+
+    //                         Num Bytes       Byte Placement
+    // Status:                 2               (0,1)
+    // Event Count:            2               (2,3)
+    fn parse_comm_event_counter<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, status) = be_u16(input)?;
+        let (input, event_count) = be_u16(input)?;
+
+        if status != 0x0000 && status != 0xffff {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        self.data = Data::CommEventCounter {
+            status,
+            event_count,
+        };
+        Ok(input)
+    }
+
+    //                         Num Bytes       Byte Placement
+    // Byte Count:             1               (0)
+    // Status:                 2               (1,2)
+    // Event Count:            2               (3,4)
+    // Message Count:          2               (5,6)
+    // Events:                 Count - 6       (7..Count + 1)
+    fn parse_comm_event_log<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, count) = be_u8(input)?;
+        let (input, status) = be_u16(input)?;
+        let (input, event_count) = be_u16(input)?;
+        let (input, message_count) = be_u16(input)?;
+
+        if count < EVENT_LOG_HEADER_COUNT || self.data_length() - 1 != u16::from(count) {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        if status != 0x0000 && status != 0xffff {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        let (input, events) = take(count.saturating_sub(EVENT_LOG_HEADER_COUNT))(input)?;
+        if events.len() > MAX_EVENT_LOG_EVENTS {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        self.data = Data::CommEventLog {
+            status,
+            event_count,
+            message_count,
+            events: events.to_vec(),
+        };
+        Ok(input)
+    }
+
+    //                         Num Bytes       Byte Placement
+    // Byte Count:             1               (0)
+    // Server ID:              1               (1)
+    // Run Indicator:          1               (2)
+    // Data:                   Count - 2       (3..Count + 1)
+    fn parse_server_id<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        let (input, count) = be_u8(input)?;
+        let (input, server_id) = be_u8(input)?;
+        let (input, run_indicator) = be_u8(input)?;
+
+        if count < 2 || self.data_length() - 1 != u16::from(count) {
+            self.error_flags |= ErrorFlags::DATA_LENGTH;
+        }
+
+        if run_indicator != 0x00 && run_indicator != 0xff {
+            self.error_flags |= ErrorFlags::DATA_VALUE;
+        }
+
+        let (input, data) = take(count.saturating_sub(2))(input)?;
+        self.data = Data::ServerID {
+            server_id,
+            run_indicator,
+            data: data.to_vec(),
+        };
+        Ok(input)
+    }
+
     fn parse_bytevec<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
         let (input, data) = take(self.data_length())(input)?;
         self.data = Data::ByteVec(data.to_vec());
@@ -622,7 +1355,7 @@ impl Message {
             self.error_flags |= ErrorFlags::DATA_VALUE;
         }
 
-        if self.data_length() - 1 != count.into() {
+        if self.data_length() - 1 != u16::from(count) {
             self.error_flags |= ErrorFlags::DATA_VALUE;
         }
 
@@ -672,7 +1405,7 @@ impl Message {
                 offset += 4; // Add 4 bytes for the read section of the request
             }
 
-            if quantity == 0 || self.length - offset != count.into() {
+            if quantity == 0 || self.length - offset != u16::from(count) {
                 self.error_flags |= ErrorFlags::DATA_LENGTH;
             }
 
@@ -779,8 +1512,8 @@ impl Message {
                     self.error_flags |= ErrorFlags::DATA_LENGTH;
                 }
 
-                let input = self.parse_diagnostic(input)?;
-                if let Data::Diagnostic { func, data } = &self.data {
+                let input = self.parse_diagnostic(input, Direction::ToServer)?;
+                if let Data::Diagnostic { func, data, .. } = &self.data {
                     if data.len() == 2 {
                         match func.code {
                             DiagnosticSubfunction::RetQueryData
@@ -807,9 +1540,9 @@ impl Message {
 
                 return Ok(input);
             }
-            FunctionCode::MEI => return self.parse_mei(input),
-            FunctionCode::RdFileRec | FunctionCode::WrFileRec if self.data_length() == 0 => {
-                self.error_flags |= ErrorFlags::DATA_LENGTH
+            FunctionCode::MEI => return self.parse_mei_data(input, true),
+            FunctionCode::RdFileRec | FunctionCode::WrFileRec => {
+                return self.parse_file_records(input)
             }
             FunctionCode::RdExcStatus
             | FunctionCode::GetCommEventCtr
@@ -819,8 +1552,12 @@ impl Message {
             {
                 self.error_flags |= ErrorFlags::DATA_LENGTH
             }
-            FunctionCode::RdFIFOQueue if self.data_length() != 2 => {
-                self.error_flags |= ErrorFlags::DATA_LENGTH
+            FunctionCode::RdFIFOQueue => {
+                if self.data_length() != 2 {
+                    self.error_flags |= ErrorFlags::DATA_LENGTH;
+                }
+
+                return self.parse_fifo_request(input);
             }
             _ => {
                 if self.function.raw == 0 || self.function.raw >= ERROR_MASK {
@@ -849,14 +1586,23 @@ impl Message {
     fn parse_response<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
         match self.function.code {
             _ if self.function.raw >= ERROR_MASK => return self.parse_exception(input),
-            FunctionCode::Diagnostic => return self.parse_diagnostic(input),
-            FunctionCode::MEI => return self.parse_mei(input),
+            FunctionCode::Diagnostic => return self.parse_diagnostic(input, Direction::ToClient),
+            FunctionCode::MEI => return self.parse_mei_data(input, false),
             FunctionCode::RdExcStatus if self.data_length() != 1 => {
                 self.error_flags |= ErrorFlags::DATA_LENGTH
             }
-            FunctionCode::GetCommEventCtr if self.data_length() != 4 => {
-                self.error_flags |= ErrorFlags::DATA_LENGTH
+            FunctionCode::GetCommEventCtr => {
+                if self.data_length() != 4 {
+                    self.error_flags |= ErrorFlags::DATA_LENGTH;
+                }
+
+                return self.parse_comm_event_counter(input);
             }
+            FunctionCode::GetCommEventLog => return self.parse_comm_event_log(input),
+            FunctionCode::ReportServerID => return self.parse_server_id(input),
+            FunctionCode::RdFileRec => return self.parse_file_record_data(input),
+            FunctionCode::WrFileRec => return self.parse_file_records(input),
+            FunctionCode::RdFIFOQueue => return self.parse_fifo_response(input),
             _ => {
                 if self.access_type.intersects(AccessType::READ) {
                     return self.parse_read_response(input);
@@ -871,17 +1617,57 @@ impl Message {
         self.parse_bytevec(input)
     }
 
-    fn parse_unknown<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
-        match self.function.code {
-            _ if self.function.raw >= ERROR_MASK => self.parse_exception(input),
-            FunctionCode::Diagnostic => self.parse_diagnostic(input),
-            FunctionCode::MEI => self.parse_mei(input),
-            _ => self.parse_bytevec(input),
-        }
-    }
-
-    /// Matches this message with another. Used to validate requests with responses.
-    pub fn matches(&mut self, other: &Message) -> bool {
+    /// Parses the data following the function code and sets the category.
+    /// Data missing or left over is flagged.
+    fn parse_pdu(&mut self, data: &[u8], direction: Direction) -> Result<()> {
+        let result = match direction {
+            Direction::ToServer => self.parse_request(data),
+            Direction::ToClient => self.parse_response(data),
+            Direction::Unknown => self.parse_unknown(data),
+        };
+        match result {
+            Ok(rest) => {
+                if !rest.is_empty() {
+                    self.error_flags |= ErrorFlags::DATA_LENGTH;
+                }
+            }
+            Err(Error {
+                kind: ErrorKind::Incomplete(_),
+            }) => {
+                self.error_flags |= ErrorFlags::DATA_LENGTH;
+                if self.data == Data::Empty {
+                    self.data = Data::ByteVec(data.to_vec());
+                }
+            }
+            Err(err) => return Err(err),
+        }
+
+        self.category = Flags::from(&*self);
+        Ok(())
+    }
+
+    fn parse_unknown<'a>(&mut self, input: &'a [u8]) -> Result<&'a [u8]> {
+        match self.function.code {
+            _ if self.function.raw >= ERROR_MASK => self.parse_exception(input),
+            FunctionCode::Diagnostic => self.parse_diagnostic(input, Direction::Unknown),
+            FunctionCode::MEI => self.parse_mei(input),
+            _ => self.parse_bytevec(input),
+        }
+    }
+
+    /// Number of bytes read for a quantity of coils/inputs or registers
+    fn read_count(&self, quantity: u16) -> usize {
+        let quantity = usize::from(quantity);
+
+        if self.access_type.intersects(AccessType::BIT_ACCESS_MASK) {
+            (quantity / 8) + usize::from((quantity % 8) != 0)
+        } else {
+            2 * quantity
+        }
+    }
+
+    /// Matches this message with another. Used to validate requests with responses.
+    pub fn matches(&mut self, other: &Message) -> bool {
         if self.transaction_id != other.transaction_id
             || self.unit_id != other.unit_id
             || self.function.code != other.function.code
@@ -904,6 +1690,58 @@ impl Message {
 
         match (&self.data, &other.data) {
             (Data::Exception(_), _) => true,
+            // Requests without data
+            (
+                Data::ByteVec(_),
+                Data::CommEventCounter { .. } | Data::CommEventLog { .. } | Data::ServerID { .. },
+            )
+            | (
+                Data::CommEventCounter { .. } | Data::CommEventLog { .. } | Data::ServerID { .. },
+                Data::ByteVec(_),
+            ) => true,
+            (Data::FileRecords(records), Data::FileRecordData(data))
+            | (Data::FileRecordData(data), Data::FileRecords(records)) => {
+                if records.len() != data.len()
+                    || records.iter().zip(data).any(|(record, data)| {
+                        record.reference_type != data.reference_type
+                            || 2 * usize::from(record.record_length) != data.data.len()
+                    })
+                {
+                    self.error_flags |= ErrorFlags::DATA_VALUE;
+                }
+
+                true
+            }
+            // Write file record responses echo the request
+            (Data::FileRecords(records), Data::FileRecords(other_records)) => {
+                if records != other_records {
+                    self.error_flags |= ErrorFlags::DATA_VALUE;
+                }
+
+                true
+            }
+            (Data::FIFO(_), Data::FIFO(_)) => true,
+            (
+                Data::DeviceIdentification(DeviceIdentification::Request { read_code, .. }),
+                Data::DeviceIdentification(DeviceIdentification::Response {
+                    read_code: other_code,
+                    ..
+                }),
+            )
+            | (
+                Data::DeviceIdentification(DeviceIdentification::Response { read_code, .. }),
+                Data::DeviceIdentification(DeviceIdentification::Request {
+                    read_code: other_code,
+                    ..
+                }),
+            ) => {
+                if read_code != other_code {
+                    self.error_flags |= ErrorFlags::DATA_VALUE;
+                }
+
+                true
+            }
+            (Data::CANopen(_), Data::CANopen(_)) => true,
             (Data::ByteVec(_), Data::ByteVec(_)) => true,
             (Data::ByteVec(_), _) => self.error_flags.intersects(ErrorFlags::DATA_LENGTH),
             (_, Data::ByteVec(_)) => other.error_flags.intersects(ErrorFlags::DATA_LENGTH),
@@ -914,9 +1752,7 @@ impl Message {
                     quantity,
                 }),
             ) => {
-                let other_count = usize::from(*quantity);
-
-                if data.len() != (other_count / 8) + usize::from((other_count % 8) != 0) {
+                if data.len() != self.read_count(*quantity) {
                     self.error_flags |= ErrorFlags::DATA_VALUE;
                 }
 
@@ -946,9 +1782,7 @@ impl Message {
                 }),
                 Data::Read(Read::Response(data)),
             ) => {
-                let count = usize::from(*quantity);
-
-                if data.len() != (count / 8) + usize::from((count % 8) != 0) {
+                if data.len() != self.read_count(*quantity) {
                     self.error_flags |= ErrorFlags::DATA_VALUE;
                 }
 
@@ -1037,12 +1871,23 @@ impl Message {
                 true
             }
             (
-                Data::Diagnostic { func, data: _ },
+                Data::Diagnostic { func, data, .. },
                 Data::Diagnostic {
                     func: other_func,
-                    data: _,
+                    data: other_data,
+                    ..
                 },
-            ) => func == other_func,
+            ) => {
+                if func != other_func {
+                    return false;
+                }
+
+                if func.code.is_echo() && data != other_data {
+                    self.error_flags |= ErrorFlags::DATA_VALUE;
+                }
+
+                true
+            }
             (
                 Data::MEI { mei_type, data: _ },
                 Data::MEI {
@@ -1105,26 +1950,23 @@ impl Message {
                 return None;
             }
 
-            // Multiply by two because each register value is 2 bytes
-            let mut offset = (address - (start + 1)) as usize * 2;
+            let index = (address - (start + 1)) as usize;
 
-            // In case of Coils, offset is in bit (convert to byte)
+            // Coils are packed 8 per byte, least significant bit first
             if self.access_type.contains(AccessType::COILS) {
-                offset >>= 3;
+                return data
+                    .get(index / 8)
+                    .map(|byte| u16::from((byte >> (index % 8)) & 0x1));
             }
 
-            let mut value =
-                if let (Some(val1), Some(val2)) = (data.get(offset), data.get(offset + 1)) {
-                    ((*val1 as u16) << 8) | *val2 as u16
-                } else {
-                    return None;
-                };
+            // Multiply by two because each register value is 2 bytes
+            let offset = index * 2;
 
-            if self.access_type.contains(AccessType::COILS) {
-                value = (value >> ((address - (start + 1)) & 0x7)) & 0x1;
+            if let (Some(val1), Some(val2)) = (data.get(offset), data.get(offset + 1)) {
+                Some(((*val1 as u16) << 8) | *val2 as u16)
+            } else {
+                None
             }
-
-            Some(value)
         } else {
             None
         }
@@ -1142,7 +1984,7 @@ impl Message {
                 address,
                 and_mask: _,
                 or_mask: _,
-            }) => Some((address + 1)..=(address + 1)),
+            }) => address.checked_add(1).map(|address| address..=address),
             Data::Read(Read::Request { address, quantity })
             | Data::Write(Write::MultReq {
                 address,
@@ -1177,6 +2019,12 @@ impl Protocol<'_> for Modbus {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
 impl<'a> Probe<'a> for Modbus {
     fn probe(&self, input: &'a [u8], direction: Direction) -> Status {
         match self.parse(input, direction) {
@@ -1198,12 +2046,192 @@ impl<'a> Probe<'a> for Modbus {
     }
 }
 
-impl<'a> Parse<'a> for Modbus {
-    fn parse(
+/// CRC-16 of an RTU frame, with the polynomial 0xa001 (0x8005 reflected)
+/// and an initial value of 0xffff
+fn crc16(input: &[u8]) -> u16 {
+    input.iter().fold(0xffff, |crc, byte| {
+        (0..8).fold(crc ^ u16::from(*byte), |crc, _| {
+            if crc & 1 != 0 {
+                (crc >> 1) ^ 0xa001
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// Reads the byte at `offset` of an RTU frame, which holds a count used to
+/// find the end of the frame
+fn rtu_count(input: &[u8], offset: usize) -> Result<usize> {
+    match input.get(offset) {
+        Some(count) => Ok(usize::from(*count)),
+        None => Err(Error::incomplete_needed(offset + 1 - input.len())),
+    }
+}
+
+/// Length of the data following the function code of an RTU request, `None`
+/// if it can't be known from the function code
+fn rtu_request_length(function: &Function, input: &[u8]) -> Result<Option<usize>> {
+    let length = match function.code {
+        _ if function.raw >= ERROR_MASK => return Ok(None),
+        FunctionCode::RdCoils
+        | FunctionCode::RdDiscreteInputs
+        | FunctionCode::RdHoldRegs
+        | FunctionCode::RdInputRegs
+        | FunctionCode::WrSingleCoil
+        | FunctionCode::WrSingleReg
+        | FunctionCode::Diagnostic => 4,
+        FunctionCode::RdExcStatus
+        | FunctionCode::GetCommEventCtr
+        | FunctionCode::GetCommEventLog
+        | FunctionCode::ReportServerID => 0,
+        FunctionCode::WrMultCoils | FunctionCode::WrMultRegs => 5 + rtu_count(input, 4)?,
+        FunctionCode::RdWrMultRegs => 9 + rtu_count(input, 8)?,
+        FunctionCode::MaskWrReg => 6,
+        FunctionCode::RdFIFOQueue => 2,
+        FunctionCode::RdFileRec | FunctionCode::WrFileRec => 1 + rtu_count(input, 0)?,
+        // MEI type, read device id code and object id
+        FunctionCode::MEI if input.first() == Some(&(MEIType::RdDevId as u8)) => 3,
+        _ => return Ok(None),
+    };
+    Ok(Some(length))
+}
+
+/// Length of the data following the function code of an RTU response,
+/// `None` if it can't be known from the function code
+fn rtu_response_length(function: &Function, input: &[u8]) -> Result<Option<usize>> {
+    let length = match function.code {
+        _ if function.raw >= ERROR_MASK => 1,
+        FunctionCode::RdCoils
+        | FunctionCode::RdDiscreteInputs
+        | FunctionCode::RdHoldRegs
+        | FunctionCode::RdInputRegs
+        | FunctionCode::GetCommEventLog
+        | FunctionCode::ReportServerID
+        | FunctionCode::RdFileRec
+        | FunctionCode::WrFileRec
+        | FunctionCode::RdWrMultRegs => 1 + rtu_count(input, 0)?,
+        FunctionCode::WrSingleCoil
+        | FunctionCode::WrSingleReg
+        | FunctionCode::WrMultCoils
+        | FunctionCode::WrMultRegs
+        | FunctionCode::Diagnostic
+        | FunctionCode::GetCommEventCtr => 4,
+        FunctionCode::RdExcStatus => 1,
+        FunctionCode::MaskWrReg => 6,
+        FunctionCode::RdFIFOQueue => 2 + (rtu_count(input, 0)? << 8 | rtu_count(input, 1)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(length))
+}
+
+/// Finds the length of the data following the function code of the RTU
+/// frame at the start of `input`, given the data after the unit id and
+/// function code.
+///
+/// RTU frames have no length field so it is derived from the function code
+/// and counts in the data. With an unknown direction, the shortest of the
+/// request and response lengths with a matching CRC is used. Frames whose
+/// length can't be derived are assumed to end with the input, as gateways
+/// send a frame per packet.
+fn rtu_data_length(
+    function: &Function,
+    direction: &Direction,
+    input: &[u8],
+    data: &[u8],
+) -> Result<usize> {
+    let lengths = match direction {
+        Direction::ToServer => [Some(rtu_request_length(function, data)), None],
+        Direction::ToClient => [Some(rtu_response_length(function, data)), None],
+        Direction::Unknown => [
+            Some(rtu_request_length(function, data)),
+            Some(rtu_response_length(function, data)),
+        ],
+    };
+
+    let mut matched: Option<usize> = None;
+    let mut mismatched = None;
+    let mut incomplete = None;
+    let mut unknown = false;
+    for length in lengths.into_iter().flatten() {
+        match length {
+            Ok(Some(length)) if data.len() >= length + 2 => {
+                let crc = u16::from_le_bytes([data[length], data[length + 1]]);
+                if crc == crc16(&input[..length + 2]) {
+                    matched = Some(matched.map_or(length, |other| other.min(length)));
+                } else if mismatched.is_none() {
+                    mismatched = Some(length);
+                }
+            }
+            Ok(Some(length)) => {
+                incomplete.get_or_insert(Error::incomplete_needed(length + 2 - data.len()));
+            }
+            Ok(None) => unknown = true,
+            Err(err) => {
+                incomplete.get_or_insert(err);
+            }
+        }
+    }
+
+    if let Some(length) = matched {
+        return Ok(length);
+    }
+    if let Some(err) = incomplete {
+        return Err(err);
+    }
+    match mismatched {
+        Some(length) if !unknown => Ok(length),
+        _ if data.len() >= 2 => Ok(data.len() - 2),
+        _ => Err(Error::incomplete_needed(2 - data.len())),
+    }
+}
+
+/// LRC of an ASCII frame, the two's complement of the sum of its bytes
+fn lrc(input: &[u8]) -> u8 {
+    input
+        .iter()
+        .fold(0u8, |lrc, byte| lrc.wrapping_add(*byte))
+        .wrapping_neg()
+}
+
+fn hex_digit(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes the hex digits of an ASCII frame. Returns false if any of them
+/// is invalid, these decode to 0, or if a digit is left over.
+fn decode_hex(input: &[u8], output: &mut Vec<u8>) -> bool {
+    let pairs = input.chunks_exact(2);
+    let mut valid = pairs.remainder().is_empty();
+    for pair in pairs {
+        match (hex_digit(pair[0]), hex_digit(pair[1])) {
+            (Some(high), Some(low)) => output.push(high << 4 | low),
+            _ => {
+                valid = false;
+                output.push(0);
+            }
+        }
+    }
+    valid
+}
+
+impl Modbus {
+    //                     Num Bytes   Byte Placement
+    // Transaction ID:     2           (0,1)
+    // Protocol ID:        2           (2,3)
+    // Length:             2           (4,5)
+    // Unit ID:            1           (6)
+    // PDU:                Length - 1  (7..)
+    fn parse_tcp<'a>(
         &self,
         input: &'a [u8],
         direction: Direction,
-    ) -> Result<(&'a [u8], Option<Self::Message>)> {
+    ) -> Result<(&'a [u8], Option<Message>)> {
         let (input, transaction_id) = be_u16(input)?;
         let (input, protocol_id) = be_u16(input)?;
         let mut err_flags = ErrorFlags::none();
@@ -1225,47 +2253,166 @@ impl<'a> Parse<'a> for Modbus {
             error_flags: err_flags,
         };
 
+        // The length can't be trusted to skip the rest of the message, only
+        // the header is consumed
         if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
             message.error_flags |= ErrorFlags::DATA_LENGTH;
-            if input.len() > usize::from(length) {
-                return Ok((&input[usize::from(length)..input.len()], Some(message)));
-            } else {
-                return Ok((&[], Some(message)));
-            }
+            return Ok((input, Some(message)));
         }
 
         let (input, data) = take(length)(input)?;
+        let tracker = self.limits.tracker();
+        if !tracker.alloc(length.into()) {
+            message.error_flags |= ErrorFlags::LIMIT_EXCEEDED;
+            return Ok((input, Some(message)));
+        }
+
         let (data, unit_id) = be_u8(data)?;
         let (data, raw_func) = be_u8(data)?;
         message.unit_id = unit_id;
         message.function = Function::new(raw_func);
         message.access_type = message.function.code.into();
+        message.parse_pdu(data, direction)?;
 
-        let result = match direction {
-            Direction::ToServer => message.parse_request(data),
-            Direction::ToClient => message.parse_response(data),
-            Direction::Unknown => message.parse_unknown(data),
+        Ok((input, Some(message)))
+    }
+
+    //                     Num Bytes   Byte Placement
+    // Unit ID:            1           (0)
+    // PDU:                x           (1..x + 1)
+    // CRC:                2           (x + 1, x + 2)
+    //
+    // The message has no transaction or protocol id, its length is that of the
+    // unit id and PDU as in an MBAP header.
+    fn parse_rtu<'a>(
+        &self,
+        input: &'a [u8],
+        direction: Direction,
+    ) -> Result<(&'a [u8], Option<Message>)> {
+        let (rest, unit_id) = be_u8(input)?;
+        let (rest, raw_func) = be_u8(rest)?;
+        let function = Function::new(raw_func);
+        let data_length = rtu_data_length(&function, &direction, input, rest)?;
+
+        let (rest, frame) = take(data_length + 2)(input)?;
+        let (rest, crc) = le_u16(rest)?;
+
+        let mut message = Message {
+            transaction_id: 0,
+            protocol_id: 0,
+            length: u16::try_from(frame.len()).unwrap_or(u16::MAX),
+            unit_id,
+            access_type: function.code.into(),
+            function,
+            category: CodeCategory::none(),
+            data: Data::Empty,
+            error_flags: ErrorFlags::none(),
         };
-        match result {
-            Ok(rest) => {
-                if !rest.is_empty() {
-                    message.error_flags |= ErrorFlags::DATA_LENGTH;
-                }
-            }
-            Err(Error {
-                kind: ErrorKind::Incomplete(_),
-            }) => {
+
+        if crc != crc16(frame) {
+            message.error_flags |= ErrorFlags::CRC;
+        }
+
+        if message.length > MAX_LENGTH {
+            message.error_flags |= ErrorFlags::DATA_LENGTH;
+            return Ok((rest, Some(message)));
+        }
+
+        let tracker = self.limits.tracker();
+        if !tracker.alloc(frame.len()) {
+            message.error_flags |= ErrorFlags::LIMIT_EXCEEDED;
+            return Ok((rest, Some(message)));
+        }
+
+        message.parse_pdu(&frame[2..], direction)?;
+
+        Ok((rest, Some(message)))
+    }
+
+    //                     Num Chars   Char Placement
+    // Start:              1           (0)
+    // Unit ID:            2           (1,2)
+    // PDU:                2x          (3..2x + 3)
+    // LRC:                2           (2x + 3, 2x + 4)
+    // End:                2           (2x + 5, 2x + 6)
+    //
+    // The message has no transaction or protocol id, its length is that of the
+    // decoded unit id and PDU as in an MBAP header.
+    fn parse_ascii<'a>(
+        &self,
+        input: &'a [u8],
+        direction: Direction,
+    ) -> Result<(&'a [u8], Option<Message>)> {
+        // Not through `tag` so the error doesn't depend on the verbose feature
+        let input = match input.split_first() {
+            Some((b':', input)) => input,
+            Some(_) => return Err(Error::parse(None)),
+            None => return Err(Error::incomplete_needed(1)),
+        };
+        let search = &input[..input.len().min(MAX_ASCII_LENGTH + 2)];
+        let end = match search.windows(2).position(|end| end == b"\r\n") {
+            Some(end) => end,
+            None if search.len() < MAX_ASCII_LENGTH + 2 => return Err(Error::incomplete()),
+            None => return Err(Error::parse(None)),
+        };
+        let (frame, rest) = (&input[..end], &input[end + 2..]);
+
+        let mut message = Message {
+            transaction_id: 0,
+            protocol_id: 0,
+            length: 0,
+            unit_id: 0,
+            function: Function::new(0),
+            access_type: AccessType::none(),
+            category: CodeCategory::none(),
+            data: Data::Empty,
+            error_flags: ErrorFlags::none(),
+        };
+
+        let tracker = self.limits.tracker();
+        if !tracker.alloc(frame.len() / 2) {
+            message.error_flags |= ErrorFlags::LIMIT_EXCEEDED;
+            return Ok((rest, Some(message)));
+        }
+
+        let mut decoded = Vec::with_capacity(frame.len() / 2);
+        if !decode_hex(frame, &mut decoded) {
+            message.error_flags |= ErrorFlags::HEX_DIGIT;
+        }
+
+        let (frame, checksum) = match decoded.split_last() {
+            Some((checksum, frame)) if frame.len() >= usize::from(MIN_LENGTH) => (frame, checksum),
+            _ => {
                 message.error_flags |= ErrorFlags::DATA_LENGTH;
-                if message.data == Data::Empty {
-                    message.data = Data::ByteVec(data.to_vec());
-                }
+                return Ok((rest, Some(message)));
             }
-            Err(err) => return Err(err),
+        };
+        if *checksum != lrc(frame) {
+            message.error_flags |= ErrorFlags::LRC;
         }
 
-        message.category = Flags::from(&message);
+        // The frame is at most MAX_LENGTH bytes, bounded by the search above
+        message.length = frame.len() as u16;
+        message.unit_id = frame[0];
+        message.function = Function::new(frame[1]);
+        message.access_type = message.function.code.into();
+        message.parse_pdu(&frame[2..], direction)?;
+
+        Ok((rest, Some(message)))
+    }
+}
 
-        Ok((input, Some(message)))
+impl<'a> Parse<'a> for Modbus {
+    fn parse(
+        &self,
+        input: &'a [u8],
+        direction: Direction,
+    ) -> Result<(&'a [u8], Option<Self::Message>)> {
+        match self.framing {
+            Framing::Tcp => self.parse_tcp(input, direction),
+            Framing::Rtu => self.parse_rtu(input, direction),
+            Framing::Ascii => self.parse_ascii(input, direction),
+        }
     }
 }
 
@@ -1275,6 +2422,7 @@ mod tests {
     use rstest::rstest;
     use sawp::error::{Error, Result};
     use sawp::probe::Status;
+    use sawp::testing::{assert_datagram, assert_incremental};
     use std::str::FromStr;
 
     #[test]
@@ -1288,7 +2436,7 @@ mod tests {
         case::empty(b"", Err(Error::incomplete_needed(2))),
         case::hello_world(
             b"hello world",
-            Ok((0, Some(Message{
+            Ok((5, Some(Message{
                 transaction_id: 26725,
                 protocol_id: 27756,
                 length: 28448,
@@ -1325,7 +2473,7 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 4, code: DiagnosticSubfunction::ForceListenOnlyMode }, data: vec![0x00, 0x00] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 4, code: DiagnosticSubfunction::ForceListenOnlyMode }, data: vec![0x00, 0x00], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
@@ -1377,7 +2525,7 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::RESERVED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 22, code: DiagnosticSubfunction::Reserved }, data: vec![] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 22, code: DiagnosticSubfunction::Reserved }, data: vec![], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
@@ -1404,7 +2552,7 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::RESERVED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 5, code: DiagnosticSubfunction::Reserved }, data: vec![] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 5, code: DiagnosticSubfunction::Reserved }, data: vec![], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
@@ -1431,7 +2579,7 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::RESERVED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 9, code: DiagnosticSubfunction::Reserved }, data: vec![] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 9, code: DiagnosticSubfunction::Reserved }, data: vec![], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
@@ -1662,7 +2810,7 @@ mod tests {
                 // Function Code: Report Server ID (17)
                 0x11
             ],
-            Ok((1, Some(Message{
+            Ok((2, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
                 length: 1,
@@ -1975,6 +3123,7 @@ mod tests {
                 .map(|(left, msg)| (left.len(), msg)),
             expected
         );
+        assert_incremental(Modbus::default, input, Direction::Unknown);
     }
 
     #[rstest(
@@ -2458,7 +3607,7 @@ mod tests {
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
                 data: Data::MEI{ mei_type: MEI { raw: 13, code: MEIType::CANOpenGenRefReqResp }, data: vec![] },
-                error_flags: ErrorFlags::none(),
+                error_flags: ErrorFlags::DATA_LENGTH.into(),
             })))
         ),
         case::diagnostic(
@@ -2486,7 +3635,7 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 4, code: DiagnosticSubfunction::ForceListenOnlyMode }, data: vec![0x00, 0x00] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 4, code: DiagnosticSubfunction::ForceListenOnlyMode }, data: vec![0x00, 0x00], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
@@ -2515,7 +3664,7 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 1, code: DiagnosticSubfunction::RestartCommOpt }, data: vec![0x01, 0x00] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 1, code: DiagnosticSubfunction::RestartCommOpt }, data: vec![0x01, 0x00], value: DiagnosticValue::Restart { clear_log: false } },
                 error_flags: ErrorFlags::DATA_VALUE.into(),
             })))
         ),
@@ -2568,102 +3717,348 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::RESERVED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 22, code: DiagnosticSubfunction::Reserved }, data: vec![0x00, 0x00] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 22, code: DiagnosticSubfunction::Reserved }, data: vec![0x00, 0x00], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
-    )]
-    fn test_request(
-        input: &[u8],
-        expected: Result<(usize, Option<<Modbus as Protocol>::Message>)>,
-    ) {
-        let modbus = Modbus::default();
-        assert_eq!(
-            modbus
-                .parse(input, sawp::parser::Direction::ToServer)
-                .map(|(left, msg)| (left.len(), msg)),
-            expected
-        );
-    }
-
-    #[rstest(
-        input,
-        expected,
-        case::read_coils(
+        case::read_file_record(
             &[
                 // Transaction ID: 1
                 0x00, 0x01,
                 // Protocol ID: 0
                 0x00, 0x00,
-                // Length: 4
-                0x00, 0x04,
+                // Length: 17
+                0x00, 0x11,
                 // Unit ID: 1
                 0x01,
-                // Function Code: Read Coils (1)
-                0x01,
-                // Byte Count: 1
-                0x01,
-                // Data
-                0x00
+                // Function Code: Read File Record (20)
+                0x14,
+                // Byte Count: 14
+                0x0e,
+                // Reference Type: 6, File Number: 4, Record Number: 1, Record Length: 2
+                0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02,
+                // Reference Type: 6, File Number: 3, Record Number: 9, Record Length: 2
+                0x06, 0x00, 0x03, 0x00, 0x09, 0x00, 0x02
             ],
             Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 4,
+                length: 17,
                 unit_id: 1,
-                function: Function { raw: 1, code: FunctionCode::RdCoils },
-                access_type: AccessType::READ | AccessType::COILS,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Read(Read::Response(vec![0x00])),
+                data: Data::FileRecords(vec![
+                    FileRecord { reference_type: 6, file_number: 4, record_number: 1, record_length: 2, data: vec![] },
+                    FileRecord { reference_type: 6, file_number: 3, record_number: 9, record_length: 2, data: vec![] },
+                ]),
                 error_flags: ErrorFlags::none(),
             })))
         ),
-        case::read_holding_regs(
+        case::read_file_record_invalid_reference(
             &[
                 // Transaction ID: 1
                 0x00, 0x01,
                 // Protocol ID: 0
                 0x00, 0x00,
-                // Length: 7
-                0x00, 0x07,
+                // Length: 10
+                0x00, 0x0a,
                 // Unit ID: 1
                 0x01,
-                // Function Code: Read Holding Registers (3)
-                0x03,
-                // Byte Count: 4
-                0x04,
-                // Data
-                0x00, 0x09,
-                0x00, 0x18
+                // Function Code: Read File Record (20)
+                0x14,
+                // Byte Count: 7
+                0x07,
+                // Reference Type: 5, File Number: 4, Record Number: 1, Record Length: 2
+                0x05, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02
             ],
             Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 7,
+                length: 10,
                 unit_id: 1,
-                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
-                access_type: AccessType::READ | AccessType::HOLDING,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Read(Read::Response(vec![0x00, 0x09, 0x00, 0x18])),
-                error_flags: ErrorFlags::none(),
+                data: Data::FileRecords(vec![
+                    FileRecord { reference_type: 5, file_number: 4, record_number: 1, record_length: 2, data: vec![] },
+                ]),
+                error_flags: ErrorFlags::DATA_VALUE.into(),
             })))
         ),
-        case::read_write_mult_regs(
+        case::write_file_record(
             &[
                 // Transaction ID: 1
                 0x00, 0x01,
                 // Protocol ID: 0
                 0x00, 0x00,
-                // Length: 5
-                0x00, 0x05,
+                // Length: 16
+                0x00, 0x10,
                 // Unit ID: 1
                 0x01,
-                // Function Code: Read/Write Multiple Registers (23)
-                0x17,
-                // Byte Count: 2
-                0x02,
-                // Data
-                0x0e, 0x0f
+                // Function Code: Write File Record (21)
+                0x15,
+                // Byte Count: 13
+                0x0d,
+                // Reference Type: 6, File Number: 4, Record Number: 7, Record Length: 3
+                0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03,
+                // Record Data
+                0x06, 0xaf, 0x04, 0xbe, 0x10, 0x0d
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 16,
+                unit_id: 1,
+                function: Function { raw: 21, code: FunctionCode::WrFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::FileRecords(vec![
+                    FileRecord { reference_type: 6, file_number: 4, record_number: 7, record_length: 3, data: vec![0x06, 0xaf, 0x04, 0xbe, 0x10, 0x0d] },
+                ]),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::read_fifo_queue(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 4
+                0x00, 0x04,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read FIFO Queue (24)
+                0x18,
+                // FIFO Address: 1246
+                0x04, 0xde
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 4,
+                unit_id: 1,
+                function: Function { raw: 24, code: FunctionCode::RdFIFOQueue },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::FIFO(FIFO::Request { address: 1246 }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::mei_dev_id(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 5
+                0x00, 0x05,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Encapsulated Interface Transport (43)
+                0x2b,
+                // MEI type: Read Device ID (14)
+                0x0e,
+                // Read Device ID Code: Basic (1)
+                0x01,
+                // Object ID: VendorName (0)
+                0x00
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 5,
+                unit_id: 1,
+                function: Function { raw: 43, code: FunctionCode::MEI },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::DeviceIdentification(DeviceIdentification::Request {
+                    read_code: ReadDevIdCode::Basic,
+                    object_id: 0,
+                }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::mei_dev_id_invalid_code(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 5
+                0x00, 0x05,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Encapsulated Interface Transport (43)
+                0x2b,
+                // MEI type: Read Device ID (14)
+                0x0e,
+                // Read Device ID Code: 5 (invalid)
+                0x05,
+                // Object ID: VendorName (0)
+                0x00
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 5,
+                unit_id: 1,
+                function: Function { raw: 43, code: FunctionCode::MEI },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::DeviceIdentification(DeviceIdentification::Request {
+                    read_code: ReadDevIdCode::Unknown,
+                    object_id: 0,
+                }),
+                error_flags: ErrorFlags::DATA_VALUE.into(),
+            })))
+        ),
+        case::mei_gen_ref_header(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 15
+                0x00, 0x0f,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Encapsulated Interface Transport (43)
+                0x2b,
+                // MEI type: CAN Open General Reference Request and Response (13)
+                0x0d,
+                // Protocol Control: 0
+                0x00,
+                // Gateway ID: 1
+                0x01,
+                // Node ID: 2
+                0x02,
+                // Index: 0x1018
+                0x10, 0x18,
+                // Subindex: 1
+                0x01,
+                // Starting Address: 0
+                0x00, 0x00,
+                // Count: 4
+                0x00, 0x04,
+                // Data: 0102
+                0x01, 0x02
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 15,
+                unit_id: 1,
+                function: Function { raw: 43, code: FunctionCode::MEI },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::CANopen(CANopenReference {
+                    protocol_control: 0,
+                    gateway_id: 1,
+                    node_id: 2,
+                    index: 0x1018,
+                    subindex: 1,
+                    starting_address: 0,
+                    count: 4,
+                    data: vec![0x01, 0x02],
+                }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+    )]
+    fn test_request(
+        input: &[u8],
+        expected: Result<(usize, Option<<Modbus as Protocol>::Message>)>,
+    ) {
+        let modbus = Modbus::default();
+        assert_eq!(
+            modbus
+                .parse(input, sawp::parser::Direction::ToServer)
+                .map(|(left, msg)| (left.len(), msg)),
+            expected
+        );
+        assert_incremental(Modbus::default, input, sawp::parser::Direction::ToServer);
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::read_coils(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 4
+                0x00, 0x04,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read Coils (1)
+                0x01,
+                // Byte Count: 1
+                0x01,
+                // Data
+                0x00
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 4,
+                unit_id: 1,
+                function: Function { raw: 1, code: FunctionCode::RdCoils },
+                access_type: AccessType::READ | AccessType::COILS,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Response(vec![0x00])),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::read_holding_regs(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 7
+                0x00, 0x07,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read Holding Registers (3)
+                0x03,
+                // Byte Count: 4
+                0x04,
+                // Data
+                0x00, 0x09,
+                0x00, 0x18
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 7,
+                unit_id: 1,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::READ | AccessType::HOLDING,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Response(vec![0x00, 0x09, 0x00, 0x18])),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::read_write_mult_regs(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 5
+                0x00, 0x05,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read/Write Multiple Registers (23)
+                0x17,
+                // Byte Count: 2
+                0x02,
+                // Data
+                0x0e, 0x0f
             ],
             Ok((0, Some(Message{
                 transaction_id: 1,
@@ -2832,7 +4227,7 @@ mod tests {
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
                 data: Data::MEI{ mei_type: MEI { raw: 13, code: MEIType::CANOpenGenRefReqResp }, data: vec![] },
-                error_flags: ErrorFlags::none(),
+                error_flags: ErrorFlags::DATA_LENGTH.into(),
             })))
         ),
         case::diagnostic(
@@ -2860,291 +4255,541 @@ mod tests {
                 function: Function { raw: 8, code: FunctionCode::Diagnostic },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Diagnostic { func: Diagnostic { raw: 4, code: DiagnosticSubfunction::ForceListenOnlyMode }, data: vec![0x00, 0x00] },
+                data: Data::Diagnostic { func: Diagnostic { raw: 4, code: DiagnosticSubfunction::ForceListenOnlyMode }, data: vec![0x00, 0x00], value: DiagnosticValue::Raw },
                 error_flags: ErrorFlags::none(),
             })))
         ),
-    )]
-    fn test_response(
-        input: &[u8],
-        expected: Result<(usize, Option<<Modbus as Protocol>::Message>)>,
-    ) {
-        let modbus = Modbus::default();
-        assert_eq!(
-            modbus
-                .parse(input, sawp::parser::Direction::ToClient)
-                .map(|(left, msg)| (left.len(), msg)),
-            expected
-        );
-    }
-
-    #[rstest(
-        req,
-        resp,
-        expected,
-        case::read_coils(
-            Message{
+        case::read_file_record(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 15
+                0x00, 0x0f,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read File Record (20)
+                0x14,
+                // Byte Count: 12
+                0x0c,
+                // Record Length: 5, Reference Type: 6
+                0x05, 0x06,
+                // Record Data
+                0x0d, 0xfe, 0x00, 0x20,
+                // Record Length: 5, Reference Type: 6
+                0x05, 0x06,
+                // Record Data
+                0x33, 0xcd, 0x00, 0x40
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 6,
+                length: 15,
                 unit_id: 1,
-                function: Function { raw: 1, code: FunctionCode::RdCoils },
-                access_type: AccessType::READ | AccessType::COILS,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Read (
-                    Read::Request {
-                        address: 0x0000,
-                        quantity: 0x0001
-                    }
-                ),
+                data: Data::FileRecordData(vec![
+                    FileRecordData { reference_type: 6, data: vec![0x0d, 0xfe, 0x00, 0x20] },
+                    FileRecordData { reference_type: 6, data: vec![0x33, 0xcd, 0x00, 0x40] },
+                ]),
                 error_flags: ErrorFlags::none(),
-            },
-            Message{
-                transaction_id: 1,
-                protocol_id: 0,
-                length: 4,
-                unit_id: 1,
-                function: Function { raw: 1, code: FunctionCode::RdCoils },
-                access_type: AccessType::READ | AccessType::COILS,
-                category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Read(Read::Response(vec![0x00])),
-                error_flags: ErrorFlags::none(),
-            },
-            true
+            })))
         ),
-        case::write_single_coil(
-            Message{
-                transaction_id: 1,
-                protocol_id: 0,
-                length: 6,
-                unit_id: 1,
-                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
-                access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
-                category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::Other {
-                        address: 0x0002,
-                        data: 0x0000
-                    }
-                ),
-                error_flags: ErrorFlags::none(),
-            },
-            Message{
+        case::read_file_record_odd_length(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 8
+                0x00, 0x08,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read File Record (20)
+                0x14,
+                // Byte Count: 5
+                0x05,
+                // Record Length: 4, Reference Type: 6
+                0x04, 0x06,
+                // Record Data
+                0x0d, 0xfe, 0x00
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 6,
+                length: 8,
                 unit_id: 1,
-                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
-                access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write(
-                    Write::Other {
-                        address: 0x0002,
-                        data: 0x0000
-                    }
-                ),
-                error_flags: ErrorFlags::none(),
-            },
-            true
+                data: Data::FileRecordData(vec![
+                    FileRecordData { reference_type: 6, data: vec![0x0d, 0xfe, 0x00] },
+                ]),
+                error_flags: ErrorFlags::DATA_LENGTH.into(),
+            })))
         ),
-        case::write_mult_regs(
-            Message{
-                transaction_id: 1,
-                protocol_id: 0,
-                length: 11,
-                unit_id: 1,
-                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
-                access_type: AccessType::HOLDING | AccessType::WRITE_MULTIPLE,
-                category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::MultReq {
-                        address: 0x0003,
-                        quantity: 0x0002,
-                        data: vec![0x0a, 0x0b, 0x00, 0x00]
-                    }
-                ),
-                error_flags: ErrorFlags::none(),
-            },
-            Message{
+        case::write_file_record(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 16
+                0x00, 0x10,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Write File Record (21)
+                0x15,
+                // Byte Count: 13
+                0x0d,
+                // Reference Type: 6, File Number: 4, Record Number: 7, Record Length: 3
+                0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03,
+                // Record Data
+                0x06, 0xaf, 0x04, 0xbe, 0x10, 0x0d
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 6,
+                length: 16,
                 unit_id: 1,
-                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
-                access_type: AccessType::WRITE_MULTIPLE | AccessType::HOLDING,
+                function: Function { raw: 21, code: FunctionCode::WrFileRec },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write(
-                    Write::Other {
-                        address: 0x0003,
-                        data: 0x0004
-                    }
-                ),
+                data: Data::FileRecords(vec![
+                    FileRecord { reference_type: 6, file_number: 4, record_number: 7, record_length: 3, data: vec![0x06, 0xaf, 0x04, 0xbe, 0x10, 0x0d] },
+                ]),
                 error_flags: ErrorFlags::none(),
-            },
-            true
+            })))
         ),
-        case::read_file_record(
-            Message{
+        case::read_fifo_queue(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 10
+                0x00, 0x0a,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read FIFO Queue (24)
+                0x18,
+                // Byte Count: 6
+                0x00, 0x06,
+                // FIFO Count: 2
+                0x00, 0x02,
+                // FIFO Values
+                0x01, 0xb8, 0x12, 0x84
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
                 length: 10,
                 unit_id: 1,
-                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                function: Function { raw: 24, code: FunctionCode::RdFIFOQueue },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::ByteVec(vec![0x07, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]),
+                data: Data::FIFO(FIFO::Response(vec![0x01b8, 0x1284])),
                 error_flags: ErrorFlags::none(),
-            },
-            Message{
+            })))
+        ),
+        case::read_fifo_queue_invalid_count(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 10
+                0x00, 0x0a,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Read FIFO Queue (24)
+                0x18,
+                // Byte Count: 6
+                0x00, 0x06,
+                // FIFO Count: 1
+                0x00, 0x01,
+                // FIFO Values
+                0x01, 0xb8, 0x12, 0x84
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
                 length: 10,
                 unit_id: 1,
-                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                function: Function { raw: 24, code: FunctionCode::RdFIFOQueue },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::ByteVec(vec![0x07, 0x07, 0x06, 0x01, 0x00, 0x00, 0x00, 0x00]),
-                error_flags: ErrorFlags::none(),
-            },
-            true
+                data: Data::FIFO(FIFO::Response(vec![0x01b8])),
+                error_flags: ErrorFlags::DATA_LENGTH.into(),
+            })))
         ),
-        case::mask_write_reg(
-            Message {
+        case::get_comm_event_counter(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 6
+                0x00, 0x06,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Get Comm Event Counter (11)
+                0x0b,
+                // Status: Busy
+                0xff, 0xff,
+                // Event Count: 264
+                0x01, 0x08
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 8,
+                length: 6,
                 unit_id: 1,
-                function: Function { raw: 22, code: FunctionCode::MaskWrReg },
-                access_type: AccessType::WRITE | AccessType::HOLDING,
+                function: Function { raw: 11, code: FunctionCode::GetCommEventCtr },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::Mask {
-                        address: 0x0001,
-                        and_mask: 0x0002,
-                        or_mask: 0x0003
-                    }
-                ),
+                data: Data::CommEventCounter { status: 0xffff, event_count: 264 },
                 error_flags: ErrorFlags::none(),
-            },
-            Message {
+            })))
+        ),
+        case::get_comm_event_log(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 11
+                0x00, 0x0b,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Get Comm Event Log (12)
+                0x0c,
+                // Byte Count: 8
+                0x08,
+                // Status: Ready
+                0x00, 0x00,
+                // Event Count: 264
+                0x01, 0x08,
+                // Message Count: 289
+                0x01, 0x21,
+                // Events
+                0x20, 0x00
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 8,
+                length: 11,
                 unit_id: 1,
-                function: Function { raw: 22, code: FunctionCode::MaskWrReg },
-                access_type: AccessType::WRITE | AccessType::HOLDING,
+                function: Function { raw: 12, code: FunctionCode::GetCommEventLog },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::Mask {
-                        address: 0x0002,
-                        and_mask: 0x0002,
-                        or_mask: 0x0003
-                    }
-                ),
+                data: Data::CommEventLog { status: 0, event_count: 264, message_count: 289, events: vec![0x20, 0x00] },
                 error_flags: ErrorFlags::none(),
-            },
-            true
+            })))
         ),
-        case::unit_mismatch(
-            Message{
+        case::get_comm_event_log_invalid_status(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 9
+                0x00, 0x09,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Get Comm Event Log (12)
+                0x0c,
+                // Byte Count: 6
+                0x06,
+                // Status: 1
+                0x00, 0x01,
+                // Event Count: 264
+                0x01, 0x08,
+                // Message Count: 289
+                0x01, 0x21
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 10,
-                unit_id: 2,
-                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                length: 9,
+                unit_id: 1,
+                function: Function { raw: 12, code: FunctionCode::GetCommEventLog },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::ByteVec(vec![]),
-                error_flags: ErrorFlags::none(),
-            },
-            Message{
+                data: Data::CommEventLog { status: 1, event_count: 264, message_count: 289, events: vec![] },
+                error_flags: ErrorFlags::DATA_VALUE.into(),
+            })))
+        ),
+        case::report_server_id(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 6
+                0x00, 0x06,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Report Server ID (17)
+                0x11,
+                // Byte Count: 3
+                0x03,
+                // Server ID: 17
+                0x11,
+                // Run Indicator: On
+                0xff,
+                // Data
+                0x2a
+            ],
+            Ok((0, Some(Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 10,
+                length: 6,
                 unit_id: 1,
-                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                function: Function { raw: 17, code: FunctionCode::ReportServerID },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::ByteVec(vec![]),
+                data: Data::ServerID { server_id: 17, run_indicator: 0xff, data: vec![0x2a] },
                 error_flags: ErrorFlags::none(),
-            },
-            false
+            })))
         ),
-    )]
-    fn test_matching(mut req: Message, mut resp: Message, expected: bool) {
-        assert_eq!(req.matches(&resp), expected);
-        assert_eq!(resp.matches(&req), expected);
-    }
-
-    #[rstest(
-        msg,
-        addr,
-        expected,
-        case::write_single_coil(
+        case::report_server_id_invalid_run_indicator(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 5
+                0x00, 0x05,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Report Server ID (17)
+                0x11,
+                // Byte Count: 2
+                0x02,
+                // Server ID: 17
+                0x11,
+                // Run Indicator: 1
+                0x01
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 5,
+                unit_id: 1,
+                function: Function { raw: 17, code: FunctionCode::ReportServerID },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ServerID { server_id: 17, run_indicator: 0x01, data: vec![] },
+                error_flags: ErrorFlags::DATA_VALUE.into(),
+            })))
+        ),
+        case::mei_dev_id(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 23
+                0x00, 0x17,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Encapsulated Interface Transport (43)
+                0x2b,
+                // MEI type: Read Device ID (14)
+                0x0e,
+                // Read Device ID Code: Basic (1)
+                0x01,
+                // Conformity Level: Basic, individual access (0x81)
+                0x81,
+                // More Follows: No
+                0x00,
+                // Next Object ID: 0
+                0x00,
+                // Number of Objects: 3
+                0x03,
+                // Object ID: VendorName (0), Length: 4, Value: "sawp"
+                0x00, 0x04, 0x73, 0x61, 0x77, 0x70,
+                // Object ID: ProductCode (1), Length: 2, Value: "M1"
+                0x01, 0x02, 0x4d, 0x31,
+                // Object ID: MajorMinorRevision (2), Length: 3, Value: "1.0"
+                0x02, 0x03, 0x31, 0x2e, 0x30
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 23,
+                unit_id: 1,
+                function: Function { raw: 43, code: FunctionCode::MEI },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::DeviceIdentification(DeviceIdentification::Response {
+                    read_code: ReadDevIdCode::Basic,
+                    conformity_level: 0x81,
+                    more_follows: false,
+                    next_object_id: 0,
+                    objects: vec![
+                        DeviceObject { id: 0, code: DeviceObjectId::VendorName, value: b"sawp".to_vec() },
+                        DeviceObject { id: 1, code: DeviceObjectId::ProductCode, value: b"M1".to_vec() },
+                        DeviceObject { id: 2, code: DeviceObjectId::MajorMinorRevision, value: b"1.0".to_vec() },
+                    ],
+                }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::mei_dev_id_invalid_more_follows(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 8
+                0x00, 0x08,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Encapsulated Interface Transport (43)
+                0x2b,
+                // MEI type: Read Device ID (14)
+                0x0e,
+                // Read Device ID Code: Basic (1)
+                0x01,
+                // Conformity Level: Basic (1)
+                0x01,
+                // More Follows: 1 (invalid)
+                0x01,
+                // Next Object ID: 0
+                0x00,
+                // Number of Objects: 0
+                0x00
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 8,
+                unit_id: 1,
+                function: Function { raw: 43, code: FunctionCode::MEI },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::DeviceIdentification(DeviceIdentification::Response {
+                    read_code: ReadDevIdCode::Basic,
+                    conformity_level: 0x01,
+                    more_follows: false,
+                    next_object_id: 0,
+                    objects: vec![],
+                }),
+                error_flags: ErrorFlags::DATA_VALUE.into(),
+            })))
+        ),
+        case::mei_dev_id_truncated_object(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 10
+                0x00, 0x0a,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Encapsulated Interface Transport (43)
+                0x2b,
+                // MEI type: Read Device ID (14)
+                0x0e,
+                // Read Device ID Code: Basic (1)
+                0x01,
+                // Conformity Level: Basic (1)
+                0x01,
+                // More Follows: No
+                0x00,
+                // Next Object ID: 0
+                0x00,
+                // Number of Objects: 1
+                0x01,
+                // Object ID: VendorName (0), Length: 4, Value: missing
+                0x00, 0x04
+            ],
+            Ok((0, Some(Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 1,
+                function: Function { raw: 43, code: FunctionCode::MEI },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::MEI {
+                    mei_type: MEI { raw: 14, code: MEIType::RdDevId },
+                    data: vec![0x01, 0x01, 0x00, 0x00, 0x01, 0x00, 0x04],
+                },
+                error_flags: ErrorFlags::DATA_LENGTH.into(),
+            })))
+        ),
+    )]
+    fn test_response(
+        input: &[u8],
+        expected: Result<(usize, Option<<Modbus as Protocol>::Message>)>,
+    ) {
+        let modbus = Modbus::default();
+        assert_eq!(
+            modbus
+                .parse(input, sawp::parser::Direction::ToClient)
+                .map(|(left, msg)| (left.len(), msg)),
+            expected
+        );
+        assert_incremental(Modbus::default, input, sawp::parser::Direction::ToClient);
+    }
+
+    #[rstest(
+        req,
+        resp,
+        expected,
+        case::read_coils(
             Message{
                 transaction_id: 1,
                 protocol_id: 0,
                 length: 6,
                 unit_id: 1,
-                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
-                access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
+                function: Function { raw: 1, code: FunctionCode::RdCoils },
+                access_type: AccessType::READ | AccessType::COILS,
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::Other {
-                        address: 0x0002,
-                        data: 0x0000
+                data: Data::Read (
+                    Read::Request {
+                        address: 0x0000,
+                        quantity: 0x0001
                     }
                 ),
                 error_flags: ErrorFlags::none(),
             },
-            3,
-            Some(0)
-        ),
-        case::write_mult_regs(
             Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 11,
+                length: 4,
                 unit_id: 1,
-                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
-                access_type: AccessType::HOLDING | AccessType::WRITE_MULTIPLE,
+                function: Function { raw: 1, code: FunctionCode::RdCoils },
+                access_type: AccessType::READ | AccessType::COILS,
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::MultReq {
-                        address: 0x0003,
-                        quantity: 0x0002,
-                        data: vec![0x0a, 0x0b, 0x00, 0x00]
-                    }
-                ),
+                data: Data::Read(Read::Response(vec![0x00])),
                 error_flags: ErrorFlags::none(),
             },
-            4,
-            Some(0x0a0b)
+            true
         ),
-        case::read_file_record(
+        case::write_single_coil(
             Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 10,
+                length: 6,
                 unit_id: 1,
-                function: Function { raw: 20, code: FunctionCode::RdFileRec },
-                access_type: AccessType::none(),
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::ByteVec(vec![0x07, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]),
+                data: Data::Write (
+                    Write::Other {
+                        address: 0x0002,
+                        data: 0x0000
+                    }
+                ),
                 error_flags: ErrorFlags::none(),
             },
-            0,
-            None
-        )
-    )]
-    fn test_write_value_at_address(msg: Message, addr: u16, expected: Option<u16>) {
-        assert_eq!(msg.get_write_value_at_address(addr), expected);
-    }
-
-    #[rstest(
-        msg,
-        expected,
-        case::write_single_coil(
             Message{
                 transaction_id: 1,
                 protocol_id: 0,
@@ -3153,7 +4798,7 @@ mod tests {
                 function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
                 access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
+                data: Data::Write(
                     Write::Other {
                         address: 0x0002,
                         data: 0x0000
@@ -3161,7 +4806,7 @@ mod tests {
                 ),
                 error_flags: ErrorFlags::none(),
             },
-            Some(3..=3)
+            true
         ),
         case::write_mult_regs(
             Message{
@@ -3181,27 +4826,23 @@ mod tests {
                 ),
                 error_flags: ErrorFlags::none(),
             },
-            Some(4..=5)
-        ),
-        case::mask_write_reg(
-            Message {
+            Message{
                 transaction_id: 1,
                 protocol_id: 0,
-                length: 8,
+                length: 6,
                 unit_id: 1,
-                function: Function { raw: 22, code: FunctionCode::MaskWrReg },
-                access_type: AccessType::WRITE | AccessType::HOLDING,
+                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
+                access_type: AccessType::WRITE_MULTIPLE | AccessType::HOLDING,
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Write (
-                    Write::Mask {
-                        address: 0x0001,
-                        and_mask: 0x0002,
-                        or_mask: 0x0003
+                data: Data::Write(
+                    Write::Other {
+                        address: 0x0003,
+                        data: 0x0004
                     }
                 ),
                 error_flags: ErrorFlags::none(),
             },
-            Some(2..=2)
+            true
         ),
         case::read_file_record(
             Message{
@@ -3212,42 +4853,563 @@ mod tests {
                 function: Function { raw: 20, code: FunctionCode::RdFileRec },
                 access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::ByteVec(vec![0x07, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]),
+                data: Data::FileRecords(vec![FileRecord { reference_type: 6, file_number: 4, record_number: 1, record_length: 2, data: vec![] }]),
                 error_flags: ErrorFlags::none(),
             },
-            None
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 9,
+                unit_id: 1,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::FileRecordData(vec![FileRecordData { reference_type: 6, data: vec![0x0d, 0xfe, 0x00, 0x20] }]),
+                error_flags: ErrorFlags::none(),
+            },
+            true
         ),
-        // test with overflow of address + quantity
-        case::read_file_record(
+        case::read_file_record_mismatch(
             Message{
                 transaction_id: 1,
                 protocol_id: 0,
                 length: 10,
                 unit_id: 1,
-                function: Function { raw: 1, code: FunctionCode::RdCoils },
-                access_type: AccessType::COILS | AccessType::READ,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
                 category: CodeCategory::PUBLIC_ASSIGNED.into(),
-                data: Data::Read (
-                    Read::Request {
-                        address: 0xA000,
-                        quantity: 0xC000,
-                    }
-                ),
+                data: Data::FileRecords(vec![FileRecord { reference_type: 6, file_number: 4, record_number: 1, record_length: 1, data: vec![] }]),
                 error_flags: ErrorFlags::none(),
             },
-            None
-        )
-    )]
-    fn test_address_range(msg: Message, expected: Option<RangeInclusive<u16>>) {
-        assert_eq!(msg.get_address_range(), expected);
-    }
-
-    #[rstest(
-        input,
-        probe_strict,
-        expected,
-        case::empty(b"", false, Status::Incomplete),
-        case::hello_world(b"hello world", false, Status::Unrecognized),
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 9,
+                unit_id: 1,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::FileRecordData(vec![FileRecordData { reference_type: 6, data: vec![0x0d, 0xfe, 0x00, 0x20] }]),
+                error_flags: ErrorFlags::none(),
+            },
+            true
+        ),
+        case::read_fifo_queue(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 4,
+                unit_id: 1,
+                function: Function { raw: 24, code: FunctionCode::RdFIFOQueue },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::FIFO(FIFO::Request { address: 1246 }),
+                error_flags: ErrorFlags::none(),
+            },
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 1,
+                function: Function { raw: 24, code: FunctionCode::RdFIFOQueue },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::FIFO(FIFO::Response(vec![0x01b8, 0x1284])),
+                error_flags: ErrorFlags::none(),
+            },
+            true
+        ),
+        case::get_comm_event_counter(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 2,
+                unit_id: 1,
+                function: Function { raw: 11, code: FunctionCode::GetCommEventCtr },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ByteVec(vec![]),
+                error_flags: ErrorFlags::none(),
+            },
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 1,
+                function: Function { raw: 11, code: FunctionCode::GetCommEventCtr },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::CommEventCounter { status: 0xffff, event_count: 264 },
+                error_flags: ErrorFlags::none(),
+            },
+            true
+        ),
+        case::mask_write_reg(
+            Message {
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 8,
+                unit_id: 1,
+                function: Function { raw: 22, code: FunctionCode::MaskWrReg },
+                access_type: AccessType::WRITE | AccessType::HOLDING,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::Mask {
+                        address: 0x0001,
+                        and_mask: 0x0002,
+                        or_mask: 0x0003
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            Message {
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 8,
+                unit_id: 1,
+                function: Function { raw: 22, code: FunctionCode::MaskWrReg },
+                access_type: AccessType::WRITE | AccessType::HOLDING,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::Mask {
+                        address: 0x0002,
+                        and_mask: 0x0002,
+                        or_mask: 0x0003
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            true
+        ),
+        case::unit_mismatch(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 2,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ByteVec(vec![]),
+                error_flags: ErrorFlags::none(),
+            },
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 1,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ByteVec(vec![]),
+                error_flags: ErrorFlags::none(),
+            },
+            false
+        ),
+    )]
+    fn test_matching(mut req: Message, mut resp: Message, expected: bool) {
+        assert_eq!(req.matches(&resp), expected);
+        assert_eq!(resp.matches(&req), expected);
+    }
+
+    #[test]
+    fn test_matching_file_records() {
+        let modbus = Modbus::default();
+        let request = [
+            0x00, 0x01, // Transaction ID: 1
+            0x00, 0x00, // Protocol ID: 0
+            0x00, 0x0a, // Length: 10
+            0x01, // Unit ID: 1
+            0x14, // Function Code: Read File Record (20)
+            0x07, // Byte Count: 7
+            0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x01, // File 4, Record 1, Length 1
+        ];
+        let response = [
+            0x00, 0x01, // Transaction ID: 1
+            0x00, 0x00, // Protocol ID: 0
+            0x00, 0x09, // Length: 9
+            0x01, // Unit ID: 1
+            0x14, // Function Code: Read File Record (20)
+            0x06, // Byte Count: 6
+            0x05, 0x06, // Record Length: 5, Reference Type: 6
+            0x0d, 0xfe, 0x00, 0x20, // Record Data: 2 registers
+        ];
+        let (_, request) = modbus.parse(&request, Direction::ToServer).unwrap();
+        let (_, response) = modbus.parse(&response, Direction::ToClient).unwrap();
+        let (mut request, response) = (request.unwrap(), response.unwrap());
+        assert_eq!(request.error_flags, ErrorFlags::none());
+        assert_eq!(response.error_flags, ErrorFlags::none());
+        assert!(request.matches(&response));
+        assert_eq!(request.error_flags, ErrorFlags::DATA_VALUE);
+    }
+
+    #[test]
+    fn test_device_identification() {
+        let modbus = Modbus::default();
+        let request = [
+            0x00, 0x01, // Transaction ID: 1
+            0x00, 0x00, // Protocol ID: 0
+            0x00, 0x05, // Length: 5
+            0x01, // Unit ID: 1
+            0x2b, // Function Code: Encapsulated Interface Transport (43)
+            0x0e, // MEI type: Read Device ID (14)
+            0x02, // Read Device ID Code: Regular (2)
+            0x00, // Object ID: VendorName (0)
+        ];
+        let response = [
+            0x00, 0x01, // Transaction ID: 1
+            0x00, 0x00, // Protocol ID: 0
+            0x00, 0x0e, // Length: 14
+            0x01, // Unit ID: 1
+            0x2b, // Function Code: Encapsulated Interface Transport (43)
+            0x0e, // MEI type: Read Device ID (14)
+            0x01, // Read Device ID Code: Basic (1)
+            0x01, // Conformity Level: Basic (1)
+            0x00, // More Follows: No
+            0x00, // Next Object ID: 0
+            0x01, // Number of Objects: 1
+            0x00, 0x04, 0x73, 0x61, 0x77, 0x70, // VendorName: "sawp"
+        ];
+        let (_, request) = modbus.parse(&request, Direction::ToServer).unwrap();
+        let (_, response) = modbus.parse(&response, Direction::ToClient).unwrap();
+        let (mut request, response) = (request.unwrap(), response.unwrap());
+        assert_eq!(response.error_flags, ErrorFlags::none());
+        match &response.data {
+            Data::DeviceIdentification(id) => {
+                assert_eq!(id.object(DeviceObjectId::VendorName), Some(&b"sawp"[..]));
+                assert_eq!(id.object(DeviceObjectId::ProductCode), None);
+            }
+            data => panic!("unexpected data {:?}", data),
+        }
+        assert!(request.matches(&response));
+        assert_eq!(request.error_flags, ErrorFlags::DATA_VALUE);
+    }
+
+    #[rstest(
+        input,
+        direction,
+        expected,
+        case::query_data(
+            &[0x00, 0x00, 0xa5, 0x37],
+            Direction::ToClient,
+            DiagnosticValue::Raw
+        ),
+        case::restart_clear_log(
+            &[0x00, 0x01, 0xff, 0x00],
+            Direction::ToServer,
+            DiagnosticValue::Restart { clear_log: true }
+        ),
+        case::restart_unknown_direction(
+            &[0x00, 0x01, 0x00, 0x00],
+            Direction::Unknown,
+            DiagnosticValue::Restart { clear_log: false }
+        ),
+        case::register(
+            &[0x00, 0x02, 0x80, 0x01],
+            Direction::ToClient,
+            DiagnosticValue::Register(0x8001)
+        ),
+        case::register_request(&[0x00, 0x02, 0x00, 0x00], Direction::ToServer, DiagnosticValue::Raw),
+        case::delimiter(
+            &[0x00, 0x03, 0x0d, 0x00],
+            Direction::ToServer,
+            DiagnosticValue::Delimiter(b'\r')
+        ),
+        case::counter(
+            &[0x00, 0x0e, 0x01, 0x2c],
+            Direction::ToClient,
+            DiagnosticValue::Counter(300)
+        ),
+        case::counter_request(&[0x00, 0x0e, 0x00, 0x00], Direction::ToServer, DiagnosticValue::Raw),
+        case::counter_unknown_direction(&[0x00, 0x0e, 0x01, 0x2c], Direction::Unknown, DiagnosticValue::Raw),
+        case::counter_invalid_length(&[0x00, 0x0e, 0x01], Direction::ToClient, DiagnosticValue::Raw),
+        case::clear_counters(&[0x00, 0x0a, 0x00, 0x00], Direction::ToClient, DiagnosticValue::Raw)
+    )]
+    fn test_diagnostic_value(input: &[u8], direction: Direction, expected: DiagnosticValue) {
+        let mut frame = vec![
+            0x00,
+            0x01, // Transaction ID: 1
+            0x00,
+            0x00, // Protocol ID: 0
+            0x00,
+            input.len() as u8 + 2, // Length
+            0x01,                  // Unit ID: 1
+            0x08,                  // Function Code: Diagnostics (8)
+        ];
+        frame.extend_from_slice(input);
+        let (_, message) = Modbus::default().parse(&frame, direction).unwrap();
+        match message.unwrap().data {
+            Data::Diagnostic { value, .. } => assert_eq!(value, expected),
+            data => panic!("unexpected data {:?}", data),
+        }
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::force_listen_only(&[0x00, 0x04, 0x00, 0x00], true),
+        case::restart(&[0x00, 0x01, 0xff, 0x00], true),
+        case::return_query_data(&[0x00, 0x00, 0x12, 0x34], false)
+    )]
+    fn test_is_disruptive(input: &[u8], expected: bool) {
+        let mut frame = vec![
+            0x00,
+            0x01, // Transaction ID: 1
+            0x00,
+            0x00, // Protocol ID: 0
+            0x00,
+            input.len() as u8 + 2, // Length
+            0x01,                  // Unit ID: 1
+            0x08,                  // Function Code: Diagnostics (8)
+        ];
+        frame.extend_from_slice(input);
+        let (_, message) = Modbus::default()
+            .parse(&frame, Direction::ToServer)
+            .unwrap();
+        let message = message.unwrap();
+        assert_eq!(message.is_disruptive(), expected);
+        assert_eq!(message.error_flags, ErrorFlags::none());
+    }
+
+    #[test]
+    fn test_matching_diagnostic_echo() {
+        let modbus = Modbus::default();
+        let request = [
+            0x00, 0x01, // Transaction ID: 1
+            0x00, 0x00, // Protocol ID: 0
+            0x00, 0x06, // Length: 6
+            0x01, // Unit ID: 1
+            0x08, // Function Code: Diagnostics (8)
+            0x00, 0x00, // Diagnostic Code: Return Query Data (0)
+            0xa5, 0x37, // Data
+        ];
+        let mut response = request;
+        let (_, request) = modbus.parse(&request, Direction::ToServer).unwrap();
+        let mut request = request.unwrap();
+
+        let (_, echo) = modbus.parse(&response, Direction::ToClient).unwrap();
+        assert!(request.matches(&echo.unwrap()));
+        assert_eq!(request.error_flags, ErrorFlags::none());
+
+        response[11] = 0x38;
+        let (_, other) = modbus.parse(&response, Direction::ToClient).unwrap();
+        assert!(request.matches(&other.unwrap()));
+        assert_eq!(request.error_flags, ErrorFlags::DATA_VALUE);
+    }
+
+    #[rstest(
+        msg,
+        addr,
+        expected,
+        case::write_single_coil(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 1,
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::Other {
+                        address: 0x0002,
+                        data: 0x0000
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            3,
+            Some(0)
+        ),
+        case::write_mult_regs(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 11,
+                unit_id: 1,
+                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
+                access_type: AccessType::HOLDING | AccessType::WRITE_MULTIPLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::MultReq {
+                        address: 0x0003,
+                        quantity: 0x0002,
+                        data: vec![0x0a, 0x0b, 0x00, 0x00]
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            4,
+            Some(0x0a0b)
+        ),
+        case::read_file_record(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 1,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ByteVec(vec![0x07, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]),
+                error_flags: ErrorFlags::none(),
+            },
+            0,
+            None
+        ),
+        case::write_mult_coils(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 9,
+                unit_id: 1,
+                function: Function { raw: 15, code: FunctionCode::WrMultCoils },
+                access_type: AccessType::COILS | AccessType::WRITE_MULTIPLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::MultReq {
+                        address: 0x0013,
+                        quantity: 0x000a,
+                        data: vec![0xcd, 0x01]
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            // Coil 28, the first bit of the second byte
+            28,
+            Some(1)
+        )
+    )]
+    fn test_write_value_at_address(msg: Message, addr: u16, expected: Option<u16>) {
+        assert_eq!(msg.get_write_value_at_address(addr), expected);
+    }
+
+    #[rstest(
+        msg,
+        expected,
+        case::write_single_coil(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 1,
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::WRITE_SINGLE | AccessType::COILS,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::Other {
+                        address: 0x0002,
+                        data: 0x0000
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            Some(3..=3)
+        ),
+        case::write_mult_regs(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 11,
+                unit_id: 1,
+                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
+                access_type: AccessType::HOLDING | AccessType::WRITE_MULTIPLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::MultReq {
+                        address: 0x0003,
+                        quantity: 0x0002,
+                        data: vec![0x0a, 0x0b, 0x00, 0x00]
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            Some(4..=5)
+        ),
+        case::mask_write_reg(
+            Message {
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 8,
+                unit_id: 1,
+                function: Function { raw: 22, code: FunctionCode::MaskWrReg },
+                access_type: AccessType::WRITE | AccessType::HOLDING,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::Mask {
+                        address: 0x0001,
+                        and_mask: 0x0002,
+                        or_mask: 0x0003
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            Some(2..=2)
+        ),
+        case::read_file_record(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 1,
+                function: Function { raw: 20, code: FunctionCode::RdFileRec },
+                access_type: AccessType::none(),
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ByteVec(vec![0x07, 0x06, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]),
+                error_flags: ErrorFlags::none(),
+            },
+            None
+        ),
+        // test with overflow of address + quantity
+        case::read_file_record(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 10,
+                unit_id: 1,
+                function: Function { raw: 1, code: FunctionCode::RdCoils },
+                access_type: AccessType::COILS | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read (
+                    Read::Request {
+                        address: 0xA000,
+                        quantity: 0xC000,
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            None
+        ),
+        case::write_single_reg_last_address(
+            Message{
+                transaction_id: 1,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 1,
+                function: Function { raw: 6, code: FunctionCode::WrSingleReg },
+                access_type: AccessType::HOLDING | AccessType::WRITE_SINGLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write (
+                    Write::Other {
+                        address: 0xffff,
+                        data: 0x0000
+                    }
+                ),
+                error_flags: ErrorFlags::none(),
+            },
+            None
+        )
+    )]
+    fn test_address_range(msg: Message, expected: Option<RangeInclusive<u16>>) {
+        assert_eq!(msg.get_address_range(), expected);
+    }
+
+    #[rstest(
+        input,
+        probe_strict,
+        expected,
+        case::empty(b"", false, Status::Incomplete),
+        case::hello_world(b"hello world", false, Status::Unrecognized),
         case::diagnostic(
             &[
                 // Transaction ID: 1
@@ -3268,78 +5430,562 @@ mod tests {
             false,
             Status::Recognized
         ),
-        case::invalid_diagnostic(
+        case::invalid_diagnostic(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 2
+                0x00, 0x02,
+                // Unit ID: 3
+                0x03,
+                // Function Code: Diagnostics (8)
+                0x08
+            ],
+            false,
+            Status::Unrecognized
+        ),
+        case::unknown_func(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 2
+                0x00, 0x02,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Unknown (100)
+                0x64
+            ],
+            false,
+            Status::Recognized
+        ),
+        case::strict_diagnostic(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 6
+                0x00, 0x06,
+                // Unit ID: 3
+                0x03,
+                // Function Code: Diagnostics (8)
+                0x08,
+                // Diagnostic Code: Force Listen Only Mode (4)
+                0x00, 0x04,
+                // Data: 0000
+                0x00, 0x00
+            ],
+            true,
+            Status::Recognized
+        ),
+        case::strict_unknown_func(
+            &[
+                // Transaction ID: 1
+                0x00, 0x01,
+                // Protocol ID: 0
+                0x00, 0x00,
+                // Length: 2
+                0x00, 0x02,
+                // Unit ID: 1
+                0x01,
+                // Function Code: Unknown (100)
+                0x64
+            ],
+            true,
+            Status::Unrecognized
+        ),
+    )]
+    fn test_probe(input: &[u8], probe_strict: bool, expected: Status) {
+        let modbus = Modbus {
+            probe_strict,
+            ..Default::default()
+        };
+        assert_eq!(modbus.probe(input, Direction::Unknown), expected);
+    }
+
+    #[test]
+    fn test_limits() {
+        let modbus = Modbus {
+            limits: Limits {
+                max_alloc: 4,
+                ..Limits::default()
+            },
+            ..Default::default()
+        };
+        let input = [
+            0x00, 0x01, // Transaction ID: 1
+            0x00, 0x00, // Protocol ID: 0
+            0x00, 0x06, // Length: 6
+            0x01, // Unit ID: 1
+            0x01, // Function Code: Read Coils (1)
+            0x00, 0x00, // Start Address: 0
+            0x00, 0x01, // Quantity: 1
+        ];
+        let (rest, message) = modbus.parse(&input, Direction::ToServer).unwrap();
+        let message = message.unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(message.error_flags, ErrorFlags::LIMIT_EXCEEDED);
+        assert_eq!(message.data, Data::Empty);
+    }
+
+    #[rstest(
+        input,
+        direction,
+        expected,
+        case::empty(b"", Direction::ToServer, Err(Error::incomplete_needed(1))),
+        case::read_holding_regs_request(
+            &[
+                // Unit ID: 17
+                0x11,
+                // Function Code: Read Holding Registers (3)
+                0x03,
+                // Start Address: 107
+                0x00, 0x6b,
+                // Quantity: 3
+                0x00, 0x03,
+                // CRC
+                0x76, 0x87,
+            ],
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::HOLDING | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Request { address: 107, quantity: 3 }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::read_holding_regs_response(
+            &[
+                // Unit ID: 17
+                0x11,
+                // Function Code: Read Holding Registers (3)
+                0x03,
+                // Byte Count: 6
+                0x06,
+                // Data
+                0xae, 0x41, 0x56, 0x52, 0x43, 0x40,
+                // CRC
+                0x49, 0xad,
+            ],
+            Direction::ToClient,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 9,
+                unit_id: 17,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::HOLDING | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Response(vec![0xae, 0x41, 0x56, 0x52, 0x43, 0x40])),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::read_holding_regs_response_unknown_direction(
+            &[
+                // Unit ID: 17
+                0x11,
+                // Function Code: Read Holding Registers (3)
+                0x03,
+                // Byte Count: 6
+                0x06,
+                // Data
+                0xae, 0x41, 0x56, 0x52, 0x43, 0x40,
+                // CRC
+                0x49, 0xad,
+            ],
+            Direction::Unknown,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 9,
+                unit_id: 17,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::HOLDING | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::ByteVec(vec![0x06, 0xae, 0x41, 0x56, 0x52, 0x43, 0x40]),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::write_mult_regs_request(
             &[
-                // Transaction ID: 1
+                // Unit ID: 1
+                0x01,
+                // Function Code: Write Multiple Registers (16)
+                0x10,
+                // Start Address: 1
                 0x00, 0x01,
-                // Protocol ID: 0
-                0x00, 0x00,
-                // Length: 2
+                // Quantity: 2
                 0x00, 0x02,
-                // Unit ID: 3
-                0x03,
-                // Function Code: Diagnostics (8)
-                0x08
+                // Byte Count: 4
+                0x04,
+                // Data
+                0x00, 0x0a, 0x01, 0x02,
+                // CRC
+                0x92, 0x30,
             ],
-            false,
-            Status::Unrecognized
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 11,
+                unit_id: 1,
+                function: Function { raw: 16, code: FunctionCode::WrMultRegs },
+                access_type: AccessType::HOLDING | AccessType::WRITE_MULTIPLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write(Write::MultReq {
+                    address: 1,
+                    quantity: 2,
+                    data: vec![0x00, 0x0a, 0x01, 0x02],
+                }),
+                error_flags: ErrorFlags::none(),
+            })))
         ),
-        case::unknown_func(
+        case::exception(
             &[
-                // Transaction ID: 1
-                0x00, 0x01,
-                // Protocol ID: 0
-                0x00, 0x00,
-                // Length: 2
-                0x00, 0x02,
-                // Unit ID: 1
-                0x01,
-                // Function Code: Unknown (100)
-                0x64
+                // Unit ID: 10
+                0x0a,
+                // Function Code: Read Coils (1) exception
+                0x81,
+                // Exception Code: Illegal Data Address (2)
+                0x02,
+                // CRC
+                0xb0, 0x53,
             ],
-            false,
-            Status::Recognized
+            Direction::ToClient,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 3,
+                unit_id: 10,
+                function: Function { raw: 129, code: FunctionCode::RdCoils },
+                access_type: AccessType::COILS | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Exception(Exception { raw: 2, code: ExceptionCode::IllegalDataAddr }),
+                error_flags: ErrorFlags::none(),
+            })))
         ),
-        case::strict_diagnostic(
+        case::bad_crc(
             &[
-                // Transaction ID: 1
-                0x00, 0x01,
-                // Protocol ID: 0
-                0x00, 0x00,
-                // Length: 6
-                0x00, 0x06,
-                // Unit ID: 3
-                0x03,
-                // Function Code: Diagnostics (8)
-                0x08,
-                // Diagnostic Code: Force Listen Only Mode (4)
-                0x00, 0x04,
-                // Data: 0000
-                0x00, 0x00
+                // Unit ID: 17
+                0x11,
+                // Function Code: Write Single Coil (5)
+                0x05,
+                // Address: 172
+                0x00, 0xac,
+                // Data: On
+                0xff, 0x00,
+                // CRC
+                0x8b, 0x4e,
             ],
-            true,
-            Status::Recognized
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::COILS | AccessType::WRITE_SINGLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write(Write::Other { address: 172, data: 0xff00 }),
+                error_flags: ErrorFlags::CRC.into(),
+            })))
         ),
-        case::strict_unknown_func(
+        case::next_frame(
+            &[
+                // Unit ID: 10
+                0x0a,
+                // Function Code: Read Coils (1) exception
+                0x81,
+                // Exception Code: Illegal Data Address (2)
+                0x02,
+                // CRC
+                0xb0, 0x53,
+                // Next frame
+                0x0a, 0x81,
+            ],
+            Direction::ToClient,
+            Ok((2, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 3,
+                unit_id: 10,
+                function: Function { raw: 129, code: FunctionCode::RdCoils },
+                access_type: AccessType::COILS | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Exception(Exception { raw: 2, code: ExceptionCode::IllegalDataAddr }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::missing_bytes(
             &[
-                // Transaction ID: 1
-                0x00, 0x01,
-                // Protocol ID: 0
-                0x00, 0x00,
-                // Length: 2
-                0x00, 0x02,
                 // Unit ID: 1
                 0x01,
-                // Function Code: Unknown (100)
-                0x64
+                // Function Code: Write Multiple Registers (16)
+                0x10,
+                // Start Address: 1
+                0x00, 0x01,
+                // Quantity: 2
+                0x00, 0x02,
             ],
-            true,
+            Direction::ToServer,
+            Err(Error::incomplete_needed(1))
+        ),
+    )]
+    fn test_rtu(
+        input: &[u8],
+        direction: Direction,
+        expected: Result<(usize, Option<<Modbus as Protocol>::Message>)>,
+    ) {
+        let rtu = || Modbus {
+            framing: Framing::Rtu,
+            ..Default::default()
+        };
+        assert_eq!(
+            rtu()
+                .parse(input, direction.clone())
+                .map(|(left, msg)| (left.len(), msg)),
+            expected
+        );
+        assert_incremental(rtu, input, direction);
+    }
+
+    #[test]
+    fn test_rtu_unknown_length() {
+        let modbus = Modbus {
+            framing: Framing::Rtu,
+            ..Default::default()
+        };
+        let input = [
+            0x01, // Unit ID: 1
+            0x64, // Function Code: Unknown (100)
+            0x00, 0xaa, 0xbb, // Data
+            0x38, 0x23, // CRC
+        ];
+        let (rest, message) = modbus.parse(&input, Direction::ToServer).unwrap();
+        let message = message.unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(message.error_flags, ErrorFlags::none());
+        assert_eq!(message.data, Data::ByteVec(vec![0x00, 0xaa, 0xbb]));
+        assert_datagram(&modbus, &input, Direction::ToServer);
+    }
+
+    #[rstest(
+        input,
+        direction,
+        expected,
+        case::empty(b"", Direction::ToServer, Err(Error::incomplete_needed(1))),
+        case::read_holding_regs_request(
+            b":1103006B00037E\r\n",
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::HOLDING | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Request { address: 107, quantity: 3 }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::read_holding_regs_response(
+            b":110306AE4156524340CC\r\n",
+            Direction::ToClient,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 9,
+                unit_id: 17,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::HOLDING | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Response(vec![0xae, 0x41, 0x56, 0x52, 0x43, 0x40])),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::lowercase(
+            b":1103006b00037e\r\n",
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 3, code: FunctionCode::RdHoldRegs },
+                access_type: AccessType::HOLDING | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Read(Read::Request { address: 107, quantity: 3 }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::exception(
+            b":0A810273\r\n",
+            Direction::ToClient,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 3,
+                unit_id: 10,
+                function: Function { raw: 129, code: FunctionCode::RdCoils },
+                access_type: AccessType::COILS | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Exception(Exception { raw: 2, code: ExceptionCode::IllegalDataAddr }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::bad_lrc(
+            b":110500ACFF00F3\r\n",
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::COILS | AccessType::WRITE_SINGLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write(Write::Other { address: 172, data: 0xff00 }),
+                error_flags: ErrorFlags::LRC.into(),
+            })))
+        ),
+        case::bad_hex_digit(
+            b":1105G0ACFF003F\r\n",
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::COILS | AccessType::WRITE_SINGLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write(Write::Other { address: 172, data: 0xff00 }),
+                error_flags: ErrorFlags::HEX_DIGIT.into(),
+            })))
+        ),
+        case::odd_hex_digits(
+            b":110500ACFF003F0\r\n",
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 6,
+                unit_id: 17,
+                function: Function { raw: 5, code: FunctionCode::WrSingleCoil },
+                access_type: AccessType::COILS | AccessType::WRITE_SINGLE,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Write(Write::Other { address: 172, data: 0xff00 }),
+                error_flags: ErrorFlags::HEX_DIGIT.into(),
+            })))
+        ),
+        case::too_short(
+            b":11\r\n",
+            Direction::ToServer,
+            Ok((0, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 0,
+                unit_id: 0,
+                function: Function { raw: 0, code: FunctionCode::Unknown },
+                access_type: AccessType::none(),
+                category: CodeCategory::none(),
+                data: Data::Empty,
+                error_flags: ErrorFlags::DATA_LENGTH.into(),
+            })))
+        ),
+        case::next_frame(
+            b":0A810273\r\n:0A",
+            Direction::ToClient,
+            Ok((3, Some(Message{
+                transaction_id: 0,
+                protocol_id: 0,
+                length: 3,
+                unit_id: 10,
+                function: Function { raw: 129, code: FunctionCode::RdCoils },
+                access_type: AccessType::COILS | AccessType::READ,
+                category: CodeCategory::PUBLIC_ASSIGNED.into(),
+                data: Data::Exception(Exception { raw: 2, code: ExceptionCode::IllegalDataAddr }),
+                error_flags: ErrorFlags::none(),
+            })))
+        ),
+        case::missing_end(b":1103006B00037E\r", Direction::ToServer, Err(Error::incomplete())),
+        case::missing_start(b"1103006B00037E\r\n", Direction::ToServer, Err(Error::parse(None))),
+    )]
+    fn test_ascii(
+        input: &[u8],
+        direction: Direction,
+        expected: Result<(usize, Option<<Modbus as Protocol>::Message>)>,
+    ) {
+        let ascii = || Modbus {
+            framing: Framing::Ascii,
+            ..Default::default()
+        };
+        assert_eq!(
+            ascii()
+                .parse(input, direction.clone())
+                .map(|(left, msg)| (left.len(), msg)),
+            expected
+        );
+        assert_incremental(ascii, input, direction);
+    }
+
+    #[test]
+    fn test_ascii_missing_end() {
+        let modbus = Modbus {
+            framing: Framing::Ascii,
+            ..Default::default()
+        };
+        let mut input = vec![b':'];
+        input.resize(MAX_ASCII_LENGTH + 2, b'0');
+        assert_eq!(
+            modbus.parse(&input, Direction::ToServer),
+            Err(Error::incomplete())
+        );
+        input.push(b'0');
+        assert_eq!(
+            modbus.parse(&input, Direction::ToServer),
+            Err(Error::parse(None))
+        );
+    }
+
+    #[rstest(
+        input,
+        framing,
+        expected,
+        case::rtu(&[0x11, 0x03, 0x00, 0x6b, 0x00, 0x03, 0x76, 0x87], Framing::Rtu, Status::Recognized),
+        case::rtu_bad_crc(&[0x11, 0x03, 0x00, 0x6b, 0x00, 0x03, 0x87, 0x76], Framing::Rtu, Status::Unrecognized),
+        case::rtu_incomplete(&[0x11, 0x03, 0x00, 0x6b], Framing::Rtu, Status::Incomplete),
+        case::rtu_as_tcp(&[0x11, 0x05, 0x00, 0xac, 0xff, 0x00, 0x4e, 0x8b], Framing::Tcp, Status::Unrecognized),
+        case::ascii(b":1103006B00037E\r\n", Framing::Ascii, Status::Recognized),
+        case::ascii_bad_lrc(b":1103006B00037F\r\n", Framing::Ascii, Status::Unrecognized),
+        case::ascii_as_rtu(b":1103006B00037E\r\n", Framing::Rtu, Status::Unrecognized),
+        case::tcp_as_ascii(
+            &[0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x03, 0x08, 0x00, 0x04, 0x00, 0x00],
+            Framing::Ascii,
+            Status::Unrecognized
+        ),
+        case::tcp_as_rtu(
+            &[0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x03, 0x08, 0x00, 0x04, 0x00, 0x00],
+            Framing::Rtu,
             Status::Unrecognized
         ),
     )]
-    fn test_probe(input: &[u8], probe_strict: bool, expected: Status) {
-        let modbus = Modbus { probe_strict };
-        assert_eq!(modbus.probe(input, Direction::Unknown), expected);
+    fn test_probe_framing(input: &[u8], framing: Framing, expected: Status) {
+        let modbus = Modbus {
+            framing,
+            ..Default::default()
+        };
+        assert_eq!(modbus.probe(input, Direction::ToServer), expected);
     }
 
     #[test]
@@ -0,0 +1,562 @@
+//! Allow-list policies of the functions, addresses and values permitted per
+//! unit.
+//!
+//! A [`Policy`] lists, for each unit id, rules allowing function codes,
+//! optionally restricted to address ranges and bounds of the written values.
+//! Requests are evaluated against it with [`Policy::evaluate`], returning a
+//! [`Violation`] for everything that no rule allows, consecutive addresses
+//! with the same violation being reported as a single range.
+//!
+//! Policies are loaded from JSON or TOML. Functions are given by their
+//! [`FunctionCode`] name or their number. Addresses are inclusive ranges of
+//! the coil/register numbers starting at 1 returned by
+//! [`Message::get_address_range`], so "read holding registers 40001-40100" of
+//! unit 3 is written as:
+//!
+//! ```toml
+//! [[unit]]
+//! unit_id = 3
+//!
+//! [[unit.allow]]
+//! functions = ["RdHoldRegs"]
+//! addresses = [[1, 100]]
+//! ```
+//!
+//! # Example
+//! ```
+//! use sawp::parser::{Direction, Parse};
+//! use sawp_modbus::policy::{Policy, ViolationKind};
+//! use sawp_modbus::Modbus;
+//!
+//! let policy = Policy::from_toml(
+//!     r#"
+//!     [[unit]]
+//!     unit_id = 1
+//!
+//!     [[unit.allow]]
+//!     functions = ["RdHoldRegs", "WrSingleReg"]
+//!     addresses = [[1, 10]]
+//!     values = [0, 100]
+//!     "#,
+//! )
+//! .unwrap();
+//!
+//! // Write Single Register 0x0001 to 0x0203 on unit 1
+//! let frame = [0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x06, 0x00, 0x01, 0x02, 0x03];
+//! let (_, message) = Modbus::default().parse(&frame, Direction::ToServer).unwrap();
+//!
+//! let violations = policy.evaluate(&message.unwrap());
+//! assert_eq!(
+//!     violations[0].kind,
+//!     ViolationKind::Value { start: 2, end: 2, value: 0x0203 }
+//! );
+//! ```
+
+use crate::{Data, FunctionCode, Message, Read, Write, ERROR_MASK};
+
+use serde::{de, Deserialize, Deserializer};
+
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Errors encountered while loading a policy
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Json(error) => write!(f, "invalid JSON policy: {}", error),
+            Error::Toml(error) => write!(f, "invalid TOML policy: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Functions, addresses and values allowed per unit id
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Units allowed to be accessed, requests to other units are violations
+    #[serde(default, rename = "unit")]
+    pub units: Vec<Unit>,
+}
+
+/// Rules of a single unit
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Unit {
+    pub unit_id: u8,
+    #[serde(default)]
+    pub allow: Vec<Rule>,
+}
+
+/// Functions allowed on a unit, optionally restricted to some addresses and
+/// written values
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Raw function codes, given by name or number in the policy
+    #[serde(deserialize_with = "function_codes")]
+    pub functions: Vec<u8>,
+    /// Inclusive coil/register ranges, all addresses if empty
+    #[serde(default)]
+    pub addresses: Vec<(u16, u16)>,
+    /// Inclusive bounds of the written values, all values if unset
+    #[serde(default)]
+    pub values: Option<(u16, u16)>,
+}
+
+impl Rule {
+    fn allows_address(&self, address: u16) -> bool {
+        self.addresses.is_empty()
+            || self
+                .addresses
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&address))
+    }
+
+    fn allows_value(&self, value: u16) -> bool {
+        match self.values {
+            Some((min, max)) => (min..=max).contains(&value),
+            None => true,
+        }
+    }
+}
+
+/// What a request did that the policy doesn't allow
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// The unit isn't in the policy
+    Unit,
+    /// No rule of the unit allows the function
+    Function,
+    /// No rule allowing the function allows the inclusive address range
+    Address { start: u16, end: u16 },
+    /// No rule allowing the function at the addresses allows the written
+    /// values, `value` being the first of them
+    Value { start: u16, end: u16, value: u16 },
+}
+
+/// A request which isn't allowed by the policy
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub unit_id: u8,
+    pub transaction_id: u16,
+    /// Raw function code
+    pub function: u8,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unit {} function {} ({})",
+            self.unit_id,
+            FunctionCode::from_raw(self.function),
+            self.function
+        )?;
+        match self.kind {
+            ViolationKind::Unit => write!(f, ": unit not allowed"),
+            ViolationKind::Function => write!(f, ": function not allowed"),
+            ViolationKind::Address { start, end } if start == end => {
+                write!(f, ": address {} not allowed", start)
+            }
+            ViolationKind::Address { start, end } => {
+                write!(f, ": addresses {}-{} not allowed", start, end)
+            }
+            ViolationKind::Value { start, end, value } if start == end => {
+                write!(f, ": value {} not allowed at address {}", value, start)
+            }
+            ViolationKind::Value { start, end, value } => write!(
+                f,
+                ": values from {} not allowed at addresses {}-{}",
+                value, start, end
+            ),
+        }
+    }
+}
+
+impl Policy {
+    pub fn from_json(policy: &str) -> Result<Self, Error> {
+        serde_json::from_str(policy).map_err(Error::Json)
+    }
+
+    pub fn from_toml(policy: &str) -> Result<Self, Error> {
+        toml::from_str(policy).map_err(Error::Toml)
+    }
+
+    /// Evaluates a request, returning everything it does that no rule allows.
+    ///
+    /// Every address read or written is checked, along with the values
+    /// written by single, multiple and mask write functions. As the result of
+    /// a mask write depends on the current value of the register, it is only
+    /// allowed if every value it can give is.
+    pub fn evaluate(&self, message: &Message) -> Vec<Violation> {
+        let violation = |kind| Violation {
+            unit_id: message.unit_id,
+            transaction_id: message.transaction_id,
+            function: message.function.raw,
+            kind,
+        };
+
+        let unit = match self
+            .units
+            .iter()
+            .find(|unit| unit.unit_id == message.unit_id)
+        {
+            Some(unit) => unit,
+            None => return vec![violation(ViolationKind::Unit)],
+        };
+        let rules: Vec<&Rule> = unit
+            .allow
+            .iter()
+            .filter(|rule| rule.functions.contains(&message.function.raw))
+            .collect();
+        if rules.is_empty() {
+            return vec![violation(ViolationKind::Function)];
+        }
+
+        let mut violations: Vec<Violation> = Vec::new();
+        for address in addresses(message).flatten() {
+            let rules: Vec<&&Rule> = rules
+                .iter()
+                .filter(|rule| rule.allows_address(address))
+                .collect();
+            let kind = if rules.is_empty() {
+                ViolationKind::Address {
+                    start: address,
+                    end: address,
+                }
+            } else {
+                match written_values(message, address) {
+                    Some((min, max))
+                        if !rules
+                            .iter()
+                            .any(|rule| rule.allows_value(min) && rule.allows_value(max)) =>
+                    {
+                        let allows_min = rules.iter().any(|rule| rule.allows_value(min));
+                        ViolationKind::Value {
+                            start: address,
+                            end: address,
+                            value: if allows_min { max } else { min },
+                        }
+                    }
+                    _ => continue,
+                }
+            };
+
+            let extended = match violations.last_mut() {
+                Some(last) => extend(&mut last.kind, kind),
+                None => false,
+            };
+            if !extended {
+                violations.push(violation(kind));
+            }
+        }
+
+        violations
+    }
+}
+
+/// Extends the range of `kind` with the following address of `next` if they
+/// are the same violation
+fn extend(kind: &mut ViolationKind, next: ViolationKind) -> bool {
+    match (kind, next) {
+        (
+            ViolationKind::Address { end, .. },
+            ViolationKind::Address {
+                start,
+                end: next_end,
+            },
+        )
+        | (
+            ViolationKind::Value { end, .. },
+            ViolationKind::Value {
+                start,
+                end: next_end,
+                ..
+            },
+        ) if end.checked_add(1) == Some(start) => {
+            *end = next_end;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Smallest and largest values written at `address`, a mask write giving
+/// anything between the bits it sets and those it sets or keeps
+fn written_values(message: &Message, address: u16) -> Option<(u16, u16)> {
+    match &message.data {
+        Data::Write(Write::Mask {
+            address: _,
+            and_mask,
+            or_mask,
+        }) => {
+            let set = or_mask & !and_mask;
+            Some((set, set | and_mask))
+        }
+        _ => message
+            .get_write_value_at_address(address)
+            .map(|value| (value, value)),
+    }
+}
+
+/// Addresses accessed by a request, including the read addresses of
+/// [`FunctionCode::RdWrMultRegs`]
+fn addresses(message: &Message) -> impl Iterator<Item = RangeInclusive<u16>> {
+    let read = match &message.data {
+        Data::ReadWrite {
+            read: Read::Request { address, quantity },
+            ..
+        } if *quantity > 0 && *quantity <= u16::MAX - address => {
+            Some((address + 1)..=(address + quantity))
+        }
+        _ => None,
+    };
+
+    message.get_address_range().into_iter().chain(read)
+}
+
+/// Function code by name or number
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FunctionRef {
+    Raw(u8),
+    Name(String),
+}
+
+fn function_codes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    Vec::<FunctionRef>::deserialize(deserializer)?
+        .into_iter()
+        .map(|function| match function {
+            FunctionRef::Raw(raw) => Ok(raw),
+            FunctionRef::Name(name) => (0..ERROR_MASK)
+                .find(|raw| {
+                    let code = FunctionCode::from_raw(*raw);
+                    code != FunctionCode::Unknown && code.to_string() == name
+                })
+                .ok_or_else(|| de::Error::custom(format!("unknown function {}", name))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Modbus;
+    use rstest::rstest;
+    use sawp::parser::{Direction, Parse};
+
+    const POLICY: &str = r#"
+        [[unit]]
+        unit_id = 3
+
+        [[unit.allow]]
+        functions = ["RdHoldRegs", 4]
+        addresses = [[1, 100]]
+
+        [[unit.allow]]
+        functions = ["WrSingleReg", "WrMultRegs"]
+        addresses = [[10, 20]]
+        values = [0, 1000]
+
+        [[unit.allow]]
+        functions = ["WrSingleReg"]
+        addresses = [[15, 15]]
+
+        [[unit.allow]]
+        functions = ["MaskWrReg"]
+        addresses = [[30, 30]]
+        values = [0, 255]
+    "#;
+
+    fn request(input: &[u8]) -> Message {
+        Modbus::default()
+            .parse(input, Direction::ToServer)
+            .unwrap()
+            .1
+            .unwrap()
+    }
+
+    #[test]
+    fn test_load() {
+        let policy = Policy::from_toml(POLICY).unwrap();
+        let json = Policy::from_json(
+            r#"{"unit": [{"unit_id": 3, "allow": [
+                {"functions": ["RdHoldRegs", 4], "addresses": [[1, 100]]},
+                {"functions": [6, 16], "addresses": [[10, 20]], "values": [0, 1000]},
+                {"functions": ["WrSingleReg"], "addresses": [[15, 15]]},
+                {"functions": ["MaskWrReg"], "addresses": [[30, 30]], "values": [0, 255]}
+            ]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(policy, json);
+        assert_eq!(policy.units[0].allow[0].functions, vec![3, 4]);
+
+        assert!(matches!(
+            Policy::from_toml("[[unit]]\nunit_id = 1\n[[unit.allow]]\nfunctions = [\"Unknown\"]"),
+            Err(Error::Toml(_))
+        ));
+        assert!(matches!(
+            Policy::from_json(r#"{"units": []}"#),
+            Err(Error::Json(_))
+        ));
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::read_allowed(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x64, // Quantity: 100
+            ],
+            vec![]
+        ),
+        case::read_out_of_range(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x03, // Unit ID: 3
+                0x04, // Function Code: Read Input Registers (4)
+                0x00, 0x62, // Starting Address: 98
+                0x00, 0x04, // Quantity: 4
+            ],
+            vec![ViolationKind::Address { start: 101, end: 102 }]
+        ),
+        case::write_out_of_ranges(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x13, // MBAP: transaction 1, length 19
+                0x03, // Unit ID: 3
+                0x10, // Function Code: Write Multiple Registers (16)
+                0x00, 0x07, // Starting Address: 7
+                0x00, 0x06, // Quantity: 6
+                0x0c, // Byte Count: 12
+                0x00, 0x01, 0x00, 0x02, 0x00, 0x03, // Registers: 1, 2, 3
+                0x00, 0x04, 0x07, 0xd0, 0x07, 0xd1, // Registers: 4, 2000, 2001
+            ],
+            vec![
+                ViolationKind::Address { start: 8, end: 9 },
+                ViolationKind::Value { start: 12, end: 13, value: 2000 }
+            ]
+        ),
+        case::unknown_unit(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x04, // Unit ID: 4
+                0x03, // Function Code: Read Holding Registers (3)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x01, // Quantity: 1
+            ],
+            vec![ViolationKind::Unit]
+        ),
+        case::function_not_allowed(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x03, // Unit ID: 3
+                0x05, // Function Code: Write Single Coil (5)
+                0x00, 0x00, // Output Address: 0
+                0xff, 0x00, // Output Value: On
+            ],
+            vec![ViolationKind::Function]
+        ),
+        case::write_value_out_of_bounds(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x0b, // MBAP: transaction 1, length 11
+                0x03, // Unit ID: 3
+                0x10, // Function Code: Write Multiple Registers (16)
+                0x00, 0x0a, // Starting Address: 10
+                0x00, 0x02, // Quantity: 2
+                0x04, // Byte Count: 4
+                0x03, 0xe8, 0x03, 0xe9, // Registers: 1000, 1001
+            ],
+            vec![ViolationKind::Value { start: 12, end: 12, value: 1001 }]
+        ),
+        case::mask_write_allowed(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x08, // MBAP: transaction 1, length 8
+                0x03, // Unit ID: 3
+                0x16, // Function Code: Mask Write Register (22)
+                0x00, 0x1d, // Reference Address: 29
+                0x00, 0xf0, // And Mask: 0x00f0
+                0x00, 0x05, // Or Mask: 0x0005
+            ],
+            vec![]
+        ),
+        case::mask_write_value_out_of_bounds(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x08, // MBAP: transaction 1, length 8
+                0x03, // Unit ID: 3
+                0x16, // Function Code: Mask Write Register (22)
+                0x00, 0x1d, // Reference Address: 29
+                0x0f, 0x00, // And Mask: 0x0f00
+                0x00, 0x05, // Or Mask: 0x0005
+            ],
+            vec![ViolationKind::Value { start: 30, end: 30, value: 0x0f05 }]
+        ),
+        case::write_value_unbounded(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x03, // Unit ID: 3
+                0x06, // Function Code: Write Single Register (6)
+                0x00, 0x0e, // Register Address: 14
+                0xff, 0xff, // Register Value: 65535
+            ],
+            vec![]
+        ),
+    )]
+    fn test_evaluate(input: &[u8], expected: Vec<ViolationKind>) {
+        let policy = Policy::from_toml(POLICY).unwrap();
+        let message = request(input);
+        let violations = policy.evaluate(&message);
+        assert_eq!(
+            violations
+                .iter()
+                .map(|violation| violation.kind)
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert!(violations
+            .iter()
+            .all(|violation| violation.function == message.function.raw));
+    }
+
+    #[rstest(
+        kind,
+        expected,
+        case::address(
+            ViolationKind::Address { start: 8, end: 8 },
+            "unit 3 function WrMultRegs (16): address 8 not allowed"
+        ),
+        case::addresses(
+            ViolationKind::Address { start: 8, end: 9 },
+            "unit 3 function WrMultRegs (16): addresses 8-9 not allowed"
+        ),
+        case::value(
+            ViolationKind::Value { start: 12, end: 12, value: 1001 },
+            "unit 3 function WrMultRegs (16): value 1001 not allowed at address 12"
+        ),
+        case::values(
+            ViolationKind::Value { start: 12, end: 13, value: 1001 },
+            "unit 3 function WrMultRegs (16): values from 1001 not allowed at addresses 12-13"
+        )
+    )]
+    fn test_display(kind: ViolationKind, expected: &str) {
+        let violation = Violation {
+            unit_id: 3,
+            transaction_id: 1,
+            function: 16,
+            kind,
+        };
+        assert_eq!(violation.to_string(), expected);
+    }
+}
@@ -0,0 +1,314 @@
+//! Role based authorization of Modbus/TCP Security.
+//!
+//! Modbus/TCP Security carries Modbus/TCP over TLS, usually on port 802,
+//! and conveys the role of a client in an extension of its X.509
+//! certificate. [`role`] extracts that role from the DER certificate seen in
+//! the TLS handshake, and an [`Authorization`] table of the functions allowed
+//! per role flags the requests a client isn't authorized to send.
+//!
+//! The following protocol reference was used to create this module:
+//!
+//! [MB-TCP-Security-v21](https://modbus.org/docs/MB-TCP-Security-v21_2018-07-24.pdf)
+//!
+//! # Example
+//! ```
+//! use sawp::parser::{Direction, Parse};
+//! use sawp_modbus::security::Authorization;
+//! use sawp_modbus::{FunctionCode, Modbus};
+//!
+//! let mut authorization = Authorization::default();
+//! authorization.allow("Operator", &[FunctionCode::RdHoldRegs as u8]);
+//!
+//! // Write Single Register 0x0001 to 0x0203 on unit 1
+//! let frame = [0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x06, 0x00, 0x01, 0x02, 0x03];
+//! let (_, message) = Modbus::default().parse(&frame, Direction::ToServer).unwrap();
+//!
+//! let unauthorized = authorization.check("Operator", &message.unwrap()).unwrap();
+//! assert_eq!(unauthorized.function, 6);
+//! assert!(unauthorized.write);
+//! ```
+
+use crate::{AccessType, FunctionCode, Message};
+
+use sawp::error::{Error, ErrorKind, Result};
+
+use nom::bytes::complete::take;
+use nom::number::complete::be_u8;
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// DER contents of the RoleOID extension id, 1.3.6.1.4.1.50316.802.1
+pub const ROLE_OID: &[u8] = &[
+    0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0x89, 0x0c, 0x86, 0x22, 0x01,
+];
+
+const BOOLEAN: u8 = 0x01;
+const OCTET_STRING: u8 = 0x04;
+const OBJECT_IDENTIFIER: u8 = 0x06;
+const UTF8_STRING: u8 = 0x0c;
+const SEQUENCE: u8 = 0x30;
+// Context specific, constructed [3] of the TBS certificate
+const EXTENSIONS: u8 = 0xa3;
+// Maximum number of length bytes of the long form
+const MAX_LENGTH_BYTES: u8 = 4;
+
+/// A DER encoded element
+struct Tlv<'a> {
+    tag: u8,
+    value: &'a [u8],
+}
+
+//                     Num Bytes       Byte Placement
+// Tag:                1               (0)
+// Length:             1               (1)
+//     Long Form:      Length & 0x7f   (2..)
+// Value:              Length          (..)
+fn tlv(input: &[u8]) -> Result<(&[u8], Tlv<'_>)> {
+    let (input, tag) = be_u8(input)?;
+    let (input, length) = be_u8(input)?;
+    let (input, length) = if length & 0x80 == 0 {
+        (input, usize::from(length))
+    } else {
+        let count = length & 0x7f;
+        // The indefinite form isn't valid DER
+        if count == 0 || count > MAX_LENGTH_BYTES {
+            return Err(Error::new(ErrorKind::InvalidData));
+        }
+        let (input, length) = take(count)(input)?;
+        let length = length
+            .iter()
+            .fold(0, |length, byte| (length << 8) | usize::from(*byte));
+        (input, length)
+    };
+    let (input, value) = take(length)(input)?;
+
+    Ok((input, Tlv { tag, value }))
+}
+
+fn expect(input: &[u8], tag: u8) -> Result<(&[u8], &[u8])> {
+    match tlv(input)? {
+        (input, found) if found.tag == tag => Ok((input, found.value)),
+        _ => Err(Error::new(ErrorKind::InvalidData)),
+    }
+}
+
+/// Extracts the role of a DER encoded X.509 certificate, `None` if it
+/// doesn't have a RoleOID extension
+pub fn role(certificate: &[u8]) -> Result<Option<String>> {
+    let (_, certificate) = expect(certificate, SEQUENCE)?;
+    let (_, mut tbs) = expect(certificate, SEQUENCE)?;
+
+    while !tbs.is_empty() {
+        let (rest, element) = tlv(tbs)?;
+        if element.tag == EXTENSIONS {
+            return role_extension(element.value);
+        }
+        tbs = rest;
+    }
+
+    Ok(None)
+}
+
+//                     Num Bytes       Byte Placement
+// Extensions:         x               (0..)
+//     Extension ID:   x               (0..)
+//     Critical:       3               (optional)
+//     Value:          x               (..)
+//         Role:       x               UTF8String
+fn role_extension(input: &[u8]) -> Result<Option<String>> {
+    let (_, mut extensions) = expect(input, SEQUENCE)?;
+
+    while !extensions.is_empty() {
+        let (rest, extension) = expect(extensions, SEQUENCE)?;
+        let (extension, id) = expect(extension, OBJECT_IDENTIFIER)?;
+        if id == ROLE_OID {
+            let (extension, element) = tlv(extension)?;
+            let value = match element.tag {
+                BOOLEAN => expect(extension, OCTET_STRING)?.1,
+                OCTET_STRING => element.value,
+                _ => return Err(Error::new(ErrorKind::InvalidData)),
+            };
+            let (_, role) = expect(value, UTF8_STRING)?;
+            return core::str::from_utf8(role)
+                .map(|role| Some(role.into()))
+                .map_err(|_| Error::new(ErrorKind::InvalidData));
+        }
+        extensions = rest;
+    }
+
+    Ok(None)
+}
+
+/// A request the role of its client isn't authorized to send
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unauthorized {
+    pub unit_id: u8,
+    pub transaction_id: u16,
+    /// Raw function code
+    pub function: u8,
+    /// Whether the function writes coils, registers or files
+    pub write: bool,
+}
+
+/// Function codes authorized per role. Roles which aren't in the table
+/// aren't authorized to send any request.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Authorization {
+    roles: BTreeMap<String, Vec<u8>>,
+}
+
+impl Authorization {
+    /// Authorizes a role to send raw function codes, in addition to the ones
+    /// it is already authorized to
+    pub fn allow(&mut self, role: &str, functions: &[u8]) {
+        self.roles
+            .entry(role.into())
+            .or_default()
+            .extend_from_slice(functions);
+    }
+
+    pub fn is_allowed(&self, role: &str, function: u8) -> bool {
+        matches!(self.roles.get(role), Some(functions) if functions.contains(&function))
+    }
+
+    /// Checks a request sent by a client of a role
+    pub fn check(&self, role: &str, message: &Message) -> Option<Unauthorized> {
+        if self.is_allowed(role, message.function.raw) {
+            return None;
+        }
+
+        Some(Unauthorized {
+            unit_id: message.unit_id,
+            transaction_id: message.transaction_id,
+            function: message.function.raw,
+            write: message.access_type.contains(AccessType::WRITE)
+                || message.function.code == FunctionCode::WrFileRec,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Modbus;
+    use rstest::rstest;
+    use sawp::parser::{Direction, Parse};
+
+    fn der(tag: u8, value: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if value.len() < 0x80 {
+            out.push(value.len() as u8);
+        } else {
+            out.extend_from_slice(&[0x82, (value.len() >> 8) as u8, value.len() as u8]);
+        }
+        out.extend_from_slice(value);
+        out
+    }
+
+    /// A certificate with the extensions and enough of the other fields to
+    /// exercise skipping them
+    fn certificate(extensions: &[Vec<u8>]) -> Vec<u8> {
+        let mut tbs = der(0xa0, &der(0x02, &[0x02])); // Version: v3
+        tbs.extend(der(0x02, &[0x01, 0x23])); // Serial Number
+        tbs.extend(der(SEQUENCE, &der(OBJECT_IDENTIFIER, &[0x2a, 0x86, 0x48]))); // Signature
+        tbs.extend(der(SEQUENCE, &[0x42; 200])); // Issuer, long form length
+        tbs.extend(der(SEQUENCE, &[])); // Validity
+        tbs.extend(der(SEQUENCE, &[])); // Subject
+        tbs.extend(der(SEQUENCE, &[])); // Subject Public Key Info
+        if !extensions.is_empty() {
+            tbs.extend(der(EXTENSIONS, &der(SEQUENCE, &extensions.concat())));
+        }
+
+        let mut certificate = der(SEQUENCE, &tbs);
+        certificate.extend(der(SEQUENCE, &[])); // Signature Algorithm
+        certificate.extend(der(0x03, &[0x00])); // Signature
+        der(SEQUENCE, &certificate)
+    }
+
+    fn role_extension(role: &[u8], critical: bool) -> Vec<u8> {
+        let mut extension = der(OBJECT_IDENTIFIER, ROLE_OID);
+        if critical {
+            extension.extend(der(BOOLEAN, &[0xff]));
+        }
+        extension.extend(der(OCTET_STRING, &der(UTF8_STRING, role)));
+        der(SEQUENCE, &extension)
+    }
+
+    fn basic_constraints() -> Vec<u8> {
+        let mut extension = der(OBJECT_IDENTIFIER, &[0x55, 0x1d, 0x13]);
+        extension.extend(der(OCTET_STRING, &der(SEQUENCE, &[])));
+        der(SEQUENCE, &extension)
+    }
+
+    #[rstest(
+        input,
+        expected,
+        case::no_extensions(certificate(&[]), Ok(None)),
+        case::no_role(certificate(&[basic_constraints()]), Ok(None)),
+        case::role(
+            certificate(&[basic_constraints(), role_extension(b"Operator", false)]),
+            Ok(Some("Operator".into()))
+        ),
+        case::critical_role(
+            certificate(&[role_extension(b"Engineer", true)]),
+            Ok(Some("Engineer".into()))
+        ),
+        case::invalid_utf8(
+            certificate(&[role_extension(b"\xff", false)]),
+            Err(Error::new(ErrorKind::InvalidData))
+        ),
+        case::not_a_certificate(vec![0x04, 0x00], Err(Error::new(ErrorKind::InvalidData))),
+        case::indefinite_length(vec![0x30, 0x80, 0x00, 0x00], Err(Error::new(ErrorKind::InvalidData)))
+    )]
+    fn test_role(input: Vec<u8>, expected: Result<Option<String>>) {
+        assert_eq!(role(&input), expected);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let input = certificate(&[role_extension(b"Operator", false)]);
+        for length in 0..input.len() {
+            assert!(role(&input[..length]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut authorization = Authorization::default();
+        authorization.allow("Operator", &[3, 4]);
+        authorization.allow("Engineer", &[3, 4]);
+        authorization.allow("Engineer", &[16]);
+
+        let (_, message) = Modbus::default()
+            .parse(
+                &[
+                    0x00, 0x01, 0x00, 0x00, 0x00, 0x0b, // MBAP: transaction 1, length 11
+                    0x01, // Unit ID: 1
+                    0x10, // Function Code: Write Multiple Registers (16)
+                    0x00, 0x01, // Starting Address: 1
+                    0x00, 0x02, // Quantity: 2
+                    0x04, // Byte Count: 4
+                    0x00, 0x0a, 0x01, 0x02, // Registers: 10, 258
+                ],
+                Direction::ToServer,
+            )
+            .unwrap();
+        let message = message.unwrap();
+
+        assert_eq!(authorization.check("Engineer", &message), None);
+        assert_eq!(
+            authorization.check("Operator", &message),
+            Some(Unauthorized {
+                unit_id: 1,
+                transaction_id: 1,
+                function: 16,
+                write: true,
+            })
+        );
+        assert!(authorization.check("Guest", &message).is_some());
+        assert!(authorization.is_allowed("Operator", 3));
+        assert!(!authorization.is_allowed("Guest", 3));
+    }
+}
@@ -0,0 +1,730 @@
+//! Memory map of Modbus devices built from observed transactions.
+//!
+//! A [`DeviceModel`] keeps the last known value of every coil, discrete
+//! input, input register and holding register seen in paired requests and
+//! responses, along with the client that last wrote it. Every update
+//! returns the values that changed, which can be used to detect
+//! unexpected modifications such as setpoint changes.
+//!
+//! Addresses are the coil/register numbers starting at 1, as returned by
+//! [`Message::get_address_range`]. The number of values kept is capped by
+//! the `max_elements` of [`DeviceModel::limits`].
+//!
+//! # Example
+//! ```
+//! use sawp::parser::{Direction, Parse};
+//! use sawp_modbus::state::{DeviceModel, Table};
+//! use sawp_modbus::Modbus;
+//!
+//! let modbus = Modbus::default();
+//! // Write Single Register 0x0001 to 0x0203 on unit 1
+//! let frame = [0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x06, 0x00, 0x01, 0x02, 0x03];
+//! let (_, request) = modbus.parse(&frame, Direction::ToServer).unwrap();
+//! let (_, response) = modbus.parse(&frame, Direction::ToClient).unwrap();
+//!
+//! let mut model = DeviceModel::new();
+//! let changes = model.update(&mut request.unwrap(), &response.unwrap(), "10.0.0.1");
+//! assert_eq!(changes.len(), 1);
+//!
+//! let point = model.get(1, Table::HoldingRegisters, 2).unwrap();
+//! assert_eq!(point.value, 0x0203);
+//! assert_eq!(point.writer, Some("10.0.0.1"));
+//! ```
+
+use crate::values::first_address;
+use crate::{AccessType, Data, ErrorFlags, Flags, Message, Read, Write};
+
+use sawp::limits::Limits;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// Tables of the Modbus data model
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Table {
+    Coils,
+    DiscreteInputs,
+    InputRegisters,
+    HoldingRegisters,
+}
+
+impl Table {
+    /// The table accessed by a function, if it accesses one
+    pub fn from_access(access_type: Flags<AccessType>) -> Option<Self> {
+        if access_type.contains(AccessType::COILS) {
+            Some(Table::Coils)
+        } else if access_type.contains(AccessType::DISCRETES) {
+            Some(Table::DiscreteInputs)
+        } else if access_type.contains(AccessType::INPUT) {
+            Some(Table::InputRegisters)
+        } else if access_type.contains(AccessType::HOLDING) {
+            Some(Table::HoldingRegisters)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the table holds single bits instead of 16-bit registers
+    pub fn is_bit(self) -> bool {
+        matches!(self, Table::Coils | Table::DiscreteInputs)
+    }
+}
+
+/// Last known state of a coil, input or register
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Point<W> {
+    /// 0 or 1 for coils and discrete inputs
+    pub value: u16,
+    /// Client of the last write, `None` if the value was only read or was
+    /// read back different from the last write
+    pub writer: Option<W>,
+    /// Transaction which last read or wrote the value
+    pub transaction_id: u16,
+}
+
+/// A value which changed in a transaction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change<W> {
+    pub unit_id: u8,
+    pub table: Table,
+    pub address: u16,
+    /// `None` if the value was unknown before being written
+    pub old: Option<u16>,
+    pub new: u16,
+    /// Client of the write, `None` if the change was seen in a read
+    pub writer: Option<W>,
+    pub transaction_id: u16,
+}
+
+/// Known values of a single unit
+#[derive(Debug)]
+pub struct Device<W> {
+    points: BTreeMap<(Table, u16), Point<W>>,
+}
+
+impl<W> Default for Device<W> {
+    fn default() -> Self {
+        Device {
+            points: BTreeMap::new(),
+        }
+    }
+}
+
+impl<W> Device<W> {
+    /// The last known state at an address
+    pub fn get(&self, table: Table, address: u16) -> Option<&Point<W>> {
+        self.points.get(&(table, address))
+    }
+
+    /// The known addresses and states of a table, in address order
+    pub fn iter(&self, table: Table) -> impl Iterator<Item = (u16, &Point<W>)> {
+        self.points
+            .range((table, 0)..=(table, u16::MAX))
+            .map(|((_, address), point)| (*address, point))
+    }
+}
+
+/// Memory map of the devices seen in Modbus traffic, keyed by unit id.
+///
+/// `W` identifies the client sending requests, such as its address, and is
+/// recorded as the writer of the values it changes.
+#[derive(Debug)]
+pub struct DeviceModel<W> {
+    /// Values are no longer added once `max_elements` are known
+    pub limits: Limits,
+    devices: BTreeMap<u8, Device<W>>,
+    points: usize,
+}
+
+impl<W> Default for DeviceModel<W> {
+    fn default() -> Self {
+        DeviceModel {
+            limits: Limits::default(),
+            devices: BTreeMap::new(),
+            points: 0,
+        }
+    }
+}
+
+impl<W: Clone + PartialEq> DeviceModel<W> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of values known across all units
+    pub fn len(&self) -> usize {
+        self.points
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points == 0
+    }
+
+    /// The known values of a unit
+    pub fn device(&self, unit_id: u8) -> Option<&Device<W>> {
+        self.devices.get(&unit_id)
+    }
+
+    /// The last known state of a unit at an address
+    pub fn get(&self, unit_id: u8, table: Table, address: u16) -> Option<&Point<W>> {
+        self.device(unit_id)
+            .and_then(|device| device.get(table, address))
+    }
+
+    /// Updates the model with a request and its response sent by `client`,
+    /// returning the values which changed.
+    ///
+    /// The messages are paired with [`Message::matches`], which may set
+    /// error flags on the request. Nothing is updated if they don't match,
+    /// if the response is an exception or if either has a data length or
+    /// value error.
+    ///
+    /// Values which would go over the limits aren't added and
+    /// `ErrorFlags::LIMIT_EXCEEDED` is set on the request, values already
+    /// known are still updated.
+    pub fn update(
+        &mut self,
+        request: &mut Message,
+        response: &Message,
+        client: W,
+    ) -> Vec<Change<W>> {
+        let mut changes = Vec::new();
+
+        if !request.matches(response)
+            || request
+                .error_flags
+                .intersects(ErrorFlags::DATA_LENGTH | ErrorFlags::DATA_VALUE)
+            || response
+                .error_flags
+                .intersects(ErrorFlags::DATA_LENGTH | ErrorFlags::DATA_VALUE)
+            || matches!(response.data, Data::Exception(_))
+        {
+            return changes;
+        }
+
+        let table = match Table::from_access(request.access_type) {
+            Some(table) => table,
+            None => return changes,
+        };
+        let mut update = Update {
+            devices: &mut self.devices,
+            changes: &mut changes,
+            points: &mut self.points,
+            max_points: self.limits.max_elements,
+            exceeded: false,
+            unit_id: request.unit_id,
+            transaction_id: request.transaction_id,
+            table,
+        };
+
+        match (&request.data, &response.data) {
+            (Data::Write(Write::Other { address, data }), _) => {
+                let value = if table.is_bit() {
+                    u16::from(*data != 0)
+                } else {
+                    *data
+                };
+                if let Some(address) = first_address(*address, 1) {
+                    update.write(address, value, &client);
+                }
+            }
+            (
+                Data::Write(Write::MultReq {
+                    address,
+                    quantity,
+                    data,
+                }),
+                _,
+            ) => update.write_all(*address, &values(table, data, *quantity), &client),
+            (
+                Data::Write(Write::Mask {
+                    address,
+                    and_mask,
+                    or_mask,
+                }),
+                _,
+            ) => {
+                // The result depends on the current value, which must be known
+                if let Some(address) = first_address(*address, 1) {
+                    if let Some(current) = update.get(address).map(|p| p.value) {
+                        let value = (current & and_mask) | (or_mask & !and_mask);
+                        update.write(address, value, &client);
+                    }
+                }
+            }
+            (Data::Read(Read::Request { address, quantity }), Data::Read(Read::Response(data))) => {
+                update.read_all(*address, &values(table, data, *quantity))
+            }
+            (
+                Data::ReadWrite {
+                    read: Read::Request { address, quantity },
+                    write:
+                        Write::MultReq {
+                            address: write_address,
+                            quantity: write_quantity,
+                            data: write_data,
+                        },
+                },
+                Data::Read(Read::Response(data)),
+            ) => {
+                // The write is performed before the read
+                update.write_all(
+                    *write_address,
+                    &values(table, write_data, *write_quantity),
+                    &client,
+                );
+                update.read_all(*address, &values(table, data, *quantity));
+            }
+            _ => {}
+        }
+
+        if update.exceeded {
+            request.error_flags |= ErrorFlags::LIMIT_EXCEEDED;
+        }
+        changes
+    }
+}
+
+/// Updates of a single transaction to a device
+struct Update<'a, W> {
+    /// Known devices, the device is only added once a value is recorded
+    devices: &'a mut BTreeMap<u8, Device<W>>,
+    changes: &'a mut Vec<Change<W>>,
+    /// Values known across all devices
+    points: &'a mut usize,
+    max_points: usize,
+    exceeded: bool,
+    unit_id: u8,
+    transaction_id: u16,
+    table: Table,
+}
+
+impl<W: Clone + PartialEq> Update<'_, W> {
+    /// The last known state of the device at `address`
+    fn get(&self, address: u16) -> Option<&Point<W>> {
+        self.devices
+            .get(&self.unit_id)
+            .and_then(|device| device.get(self.table, address))
+    }
+
+    /// The values of the device, adding it if it is unknown
+    fn points(&mut self) -> &mut BTreeMap<(Table, u16), Point<W>> {
+        &mut self.devices.entry(self.unit_id).or_default().points
+    }
+
+    /// Whether a value can be added at `address`, flagging the update if not
+    fn has_room(&mut self, address: u16) -> bool {
+        if self.get(address).is_some() {
+            true
+        } else if *self.points < self.max_points {
+            *self.points += 1;
+            true
+        } else {
+            self.exceeded = true;
+            false
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u16, client: &W) {
+        if !self.has_room(address) {
+            return;
+        }
+        let table = self.table;
+        let transaction_id = self.transaction_id;
+        let old = self
+            .points()
+            .insert(
+                (table, address),
+                Point {
+                    value,
+                    writer: Some(client.clone()),
+                    transaction_id,
+                },
+            )
+            .map(|point| point.value);
+
+        if old != Some(value) {
+            self.changes.push(Change {
+                unit_id: self.unit_id,
+                table: self.table,
+                address,
+                old,
+                new: value,
+                writer: Some(client.clone()),
+                transaction_id: self.transaction_id,
+            });
+        }
+    }
+
+    /// Writes `values` from the PDU address `start`
+    fn write_all(&mut self, start: u16, values: &[u16], client: &W) {
+        let start = match first_address(start, values.len() as u16) {
+            Some(start) => start,
+            None => return,
+        };
+        for (address, value) in (start..=u16::MAX).zip(values) {
+            self.write(address, *value, client);
+        }
+    }
+
+    fn read(&mut self, address: u16, value: u16) {
+        if !self.has_room(address) {
+            return;
+        }
+        let (unit_id, table, transaction_id) = (self.unit_id, self.table, self.transaction_id);
+        let points = &mut self.devices.entry(unit_id).or_default().points;
+        match points.get_mut(&(table, address)) {
+            Some(point) if point.value == value => point.transaction_id = transaction_id,
+            Some(point) => {
+                self.changes.push(Change {
+                    unit_id,
+                    table,
+                    address,
+                    old: Some(point.value),
+                    new: value,
+                    writer: None,
+                    transaction_id,
+                });
+                *point = Point {
+                    value,
+                    writer: None,
+                    transaction_id,
+                };
+            }
+            // The first value read isn't a change
+            None => {
+                points.insert(
+                    (table, address),
+                    Point {
+                        value,
+                        writer: None,
+                        transaction_id,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Reads `values` from the PDU address `start`
+    fn read_all(&mut self, start: u16, values: &[u16]) {
+        let start = match first_address(start, values.len() as u16) {
+            Some(start) => start,
+            None => return,
+        };
+        for (address, value) in (start..=u16::MAX).zip(values) {
+            self.read(address, *value);
+        }
+    }
+}
+
+/// Splits read or written data in `quantity` values, coils and discrete
+/// inputs being packed least significant bit first
+fn values(table: Table, data: &[u8], quantity: u16) -> Vec<u16> {
+    if table.is_bit() {
+        data.iter()
+            .flat_map(|byte| (0..8).map(move |bit| u16::from((byte >> bit) & 1)))
+            .take(usize::from(quantity))
+            .collect()
+    } else {
+        data.chunks_exact(2)
+            .map(|word| u16::from_be_bytes([word[0], word[1]]))
+            .take(usize::from(quantity))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Flag, Modbus};
+    use sawp::parser::{Direction, Parse};
+
+    fn pair(request: &[u8], response: &[u8]) -> (Message, Message) {
+        let modbus = Modbus::default();
+        let (_, request) = modbus.parse(request, Direction::ToServer).unwrap();
+        let (_, response) = modbus.parse(response, Direction::ToClient).unwrap();
+        (request.unwrap(), response.unwrap())
+    }
+
+    #[test]
+    fn test_read_registers() {
+        let mut model = DeviceModel::new();
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x00, 0x10, // Starting Address: 16
+                0x00, 0x02, // Quantity: 2
+            ],
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x07, // MBAP: transaction 1, length 7
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x04, // Byte Count: 4
+                0x00, 0x64, 0x01, 0x2c, // Registers: 100, 300
+            ],
+        );
+        assert_eq!(model.update(&mut request, &response, "hmi"), vec![]);
+        assert_eq!(
+            model.get(3, Table::HoldingRegisters, 18),
+            Some(&Point {
+                value: 300,
+                writer: None,
+                transaction_id: 1
+            })
+        );
+        assert_eq!(
+            model
+                .device(3)
+                .unwrap()
+                .iter(Table::HoldingRegisters)
+                .map(|(address, point)| (address, point.value))
+                .collect::<Vec<_>>(),
+            vec![(17, 100), (18, 300)]
+        );
+        assert_eq!(model.get(3, Table::InputRegisters, 17), None);
+
+        // A later read of a different value is a change without a writer
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 2, length 6
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x00, 0x11, // Starting Address: 17
+                0x00, 0x01, // Quantity: 1
+            ],
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x05, // MBAP: transaction 2, length 5
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x02, // Byte Count: 2
+                0x01, 0x90, // Register: 400
+            ],
+        );
+        assert_eq!(
+            model.update(&mut request, &response, "hmi"),
+            vec![Change {
+                unit_id: 3,
+                table: Table::HoldingRegisters,
+                address: 18,
+                old: Some(300),
+                new: 400,
+                writer: None,
+                transaction_id: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_write_coils() {
+        let mut model = DeviceModel::new();
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x09, // MBAP: transaction 1, length 9
+                0x01, // Unit ID: 1
+                0x0f, // Function Code: Write Multiple Coils (15)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x0a, // Quantity: 10
+                0x02, // Byte Count: 2
+                0x05, 0x02, // Coils: 1010000001
+            ],
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x01, // Unit ID: 1
+                0x0f, // Function Code: Write Multiple Coils (15)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x0a, // Quantity: 10
+            ],
+        );
+        let changes = model.update(&mut request, &response, 7);
+        assert_eq!(changes.len(), 10);
+        assert!(changes.iter().all(|change| change.writer == Some(7)));
+        assert_eq!(
+            model
+                .device(1)
+                .unwrap()
+                .iter(Table::Coils)
+                .map(|(_, point)| point.value)
+                .collect::<Vec<_>>(),
+            vec![1, 0, 1, 0, 0, 0, 0, 0, 0, 1]
+        );
+
+        // Writing the same value again isn't a change
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 2, length 6
+                0x01, // Unit ID: 1
+                0x05, // Function Code: Write Single Coil (5)
+                0x00, 0x02, // Output Address: 2
+                0xff, 0x00, // Output Value: On
+            ],
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 2, length 6
+                0x01, // Unit ID: 1
+                0x05, // Function Code: Write Single Coil (5)
+                0x00, 0x02, // Output Address: 2
+                0xff, 0x00, // Output Value: On
+            ],
+        );
+        assert_eq!(model.update(&mut request, &response, 8), vec![]);
+        assert_eq!(model.get(1, Table::Coils, 3).unwrap().writer, Some(8));
+    }
+
+    #[test]
+    fn test_mask_write() {
+        let mut model = DeviceModel::new();
+        let request = [
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x08, // MBAP: transaction 1, length 8
+            0x01, // Unit ID: 1
+            0x16, // Function Code: Mask Write Register (22)
+            0x00, 0x04, // Reference Address: 4
+            0x00, 0xf2, // And Mask: 0x00f2
+            0x00, 0x25, // Or Mask: 0x0025
+        ];
+
+        // Unknown current value
+        let (mut req, resp) = pair(&request, &request);
+        assert_eq!(model.update(&mut req, &resp, ()), vec![]);
+        assert_eq!(model.get(1, Table::HoldingRegisters, 5), None);
+
+        let (mut req, resp) = pair(
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 2, length 6
+                0x01, // Unit ID: 1
+                0x06, // Function Code: Write Single Register (6)
+                0x00, 0x04, // Register Address: 4
+                0x00, 0x12, // Register Value: 0x0012
+            ],
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 2, length 6
+                0x01, // Unit ID: 1
+                0x06, // Function Code: Write Single Register (6)
+                0x00, 0x04, // Register Address: 4
+                0x00, 0x12, // Register Value: 0x0012
+            ],
+        );
+        model.update(&mut req, &resp, ());
+
+        let (mut req, resp) = pair(&request, &request);
+        let changes = model.update(&mut req, &resp, ());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].old, Some(0x0012));
+        assert_eq!(changes[0].new, 0x0017);
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut model = DeviceModel {
+            limits: Limits {
+                max_elements: 3,
+                ..Limits::default()
+            },
+            ..DeviceModel::new()
+        };
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                0x01, // Unit ID: 1
+                0x03, // Function Code: Read Holding Registers (3)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x02, // Quantity: 2
+            ],
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x07, // MBAP: transaction 1, length 7
+                0x01, // Unit ID: 1
+                0x03, // Function Code: Read Holding Registers (3)
+                0x04, // Byte Count: 4
+                0x00, 0x01, 0x00, 0x02, // Registers: 1, 2
+            ],
+        );
+        model.update(&mut request, &response, ());
+        assert_eq!(request.error_flags, ErrorFlags::none());
+        assert_eq!(model.len(), 2);
+
+        // Only one more value fits, known values are still updated
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x0d, // MBAP: transaction 2, length 13
+                0x02, // Unit ID: 2
+                0x10, // Function Code: Write Multiple Registers (16)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x03, // Quantity: 3
+                0x06, // Byte Count: 6
+                0x00, 0x07, 0x00, 0x08, 0x00, 0x09, // Registers: 7, 8, 9
+            ],
+            &[
+                0x00, 0x02, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 2, length 6
+                0x02, // Unit ID: 2
+                0x10, // Function Code: Write Multiple Registers (16)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x03, // Quantity: 3
+            ],
+        );
+        assert_eq!(model.update(&mut request, &response, ()).len(), 1);
+        assert_eq!(request.error_flags, ErrorFlags::LIMIT_EXCEEDED);
+        assert_eq!(model.len(), 3);
+        assert_eq!(model.get(2, Table::HoldingRegisters, 1).unwrap().value, 7);
+        assert_eq!(model.get(2, Table::HoldingRegisters, 2), None);
+
+        // A unit without room for any value isn't added
+        let (mut request, response) = pair(
+            &[
+                0x00, 0x03, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 3, length 6
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x00, 0x00, // Starting Address: 0
+                0x00, 0x01, // Quantity: 1
+            ],
+            &[
+                0x00, 0x03, 0x00, 0x00, 0x00, 0x05, // MBAP: transaction 3, length 5
+                0x03, // Unit ID: 3
+                0x03, // Function Code: Read Holding Registers (3)
+                0x02, // Byte Count: 2
+                0x00, 0x01, // Registers: 1
+            ],
+        );
+        assert!(model.update(&mut request, &response, ()).is_empty());
+        assert_eq!(request.error_flags, ErrorFlags::LIMIT_EXCEEDED);
+        assert!(model.device(3).is_none());
+    }
+
+    #[test]
+    fn test_unmatched() {
+        let mut model: DeviceModel<()> = DeviceModel::new();
+        let request = [
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+            0x01, // Unit ID: 1
+            0x06, // Function Code: Write Single Register (6)
+            0x00, 0x04, // Register Address: 4
+            0x00, 0x12, // Register Value: 0x0012
+        ];
+
+        // Different transaction
+        let mut other = request;
+        other[1] = 0x02;
+        let (mut req, resp) = pair(&request, &other);
+        assert_eq!(model.update(&mut req, &resp, ()), vec![]);
+
+        // Exception
+        let (mut req, resp) = pair(
+            &request,
+            &[
+                0x00, 0x01, 0x00, 0x00, 0x00, 0x03, // MBAP: transaction 1, length 3
+                0x01, // Unit ID: 1
+                0x86, // Function Code: Write Single Register (6) exception
+                0x02, // Exception Code: Illegal Data Address
+            ],
+        );
+        assert_eq!(model.update(&mut req, &resp, ()), vec![]);
+
+        // Response echoing a different value
+        let mut other = request;
+        other[11] = 0x13;
+        let (mut req, resp) = pair(&request, &other);
+        assert_eq!(model.update(&mut req, &resp, ()), vec![]);
+        assert_eq!(req.error_flags, ErrorFlags::DATA_VALUE);
+
+        assert!(model.device(1).is_none());
+    }
+}
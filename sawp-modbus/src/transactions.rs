@@ -0,0 +1,386 @@
+//! Pairing of requests and responses by transaction.
+//!
+//! [`Transactions`] holds the outstanding requests of a connection keyed by
+//! transaction id and unit id and pairs responses with them using
+//! [`Message::matches`]. Besides the matched transactions, it reports the
+//! anomalies which reveal scanners and man-in-the-middle tools: requests
+//! reusing an outstanding transaction id, responses without a request, after
+//! the timeout or of another function and requests which never got a
+//! response.
+//!
+//! Time is given by the caller as the [`Duration`] since any fixed point,
+//! such as the capture timestamps of the messages.
+//!
+//! # Example
+//! ```
+//! use core::time::Duration;
+//! use sawp::parser::{Direction, Parse};
+//! use sawp_modbus::{Event, Modbus, Transactions};
+//!
+//! let modbus = Modbus::default();
+//! // Write Single Register 0x0001 to 0x0203 on unit 1
+//! let frame = [0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x06, 0x00, 0x01, 0x02, 0x03];
+//! let (_, request) = modbus.parse(&frame, Direction::ToServer).unwrap();
+//! let (_, response) = modbus.parse(&frame, Direction::ToClient).unwrap();
+//!
+//! let mut transactions = Transactions::default();
+//! assert_eq!(transactions.request(request.unwrap(), Duration::from_millis(0)), None);
+//! match transactions.response(response.unwrap(), Duration::from_millis(12)) {
+//!     Event::Matched { request, response } => assert_eq!(request, response),
+//!     event => panic!("unexpected event {:?}", event),
+//! }
+//! assert!(transactions.is_empty());
+//! ```
+
+use crate::Message;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::time::Duration;
+
+/// Default time after which an outstanding request has timed out
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default maximum number of outstanding requests
+pub const DEFAULT_MAX_PENDING: usize = 1024;
+
+/// Outcome of a request or response
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A response matched an outstanding request, any mismatch of their data
+    /// being flagged on the request
+    Matched { request: Message, response: Message },
+    /// An outstanding request replaced by a request with the same transaction
+    /// id and unit id
+    Duplicate(Message),
+    /// A response without an outstanding request
+    Unsolicited(Message),
+    /// A response to an outstanding request which had timed out, the request
+    /// being removed. The request is reported here instead of as `TimedOut`
+    Late { request: Message, response: Message },
+    /// A response with the transaction id and unit id of an outstanding
+    /// request but of another function, the request being removed
+    Mismatched { request: Message, response: Message },
+    /// An outstanding request without a response within the timeout, or left
+    /// at the end of the connection
+    TimedOut(Message),
+    /// The oldest outstanding request, dropped to stay within the maximum
+    /// number of outstanding requests
+    Dropped(Message),
+}
+
+#[derive(Debug)]
+struct Pending {
+    request: Message,
+    time: Duration,
+}
+
+/// Outstanding requests of a connection, keyed by transaction id and unit id
+#[derive(Debug)]
+pub struct Transactions {
+    /// Time after which an outstanding request has timed out
+    pub timeout: Duration,
+    /// Maximum number of outstanding requests, every request being dropped
+    /// if 0
+    pub max_pending: usize,
+    pending: BTreeMap<(u16, u8), Pending>,
+}
+
+impl Default for Transactions {
+    fn default() -> Self {
+        Transactions {
+            timeout: DEFAULT_TIMEOUT,
+            max_pending: DEFAULT_MAX_PENDING,
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl Transactions {
+    /// Number of outstanding requests
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Holds a request sent at `time` until its response, returning the
+    /// request it replaces or drops if any
+    pub fn request(&mut self, request: Message, time: Duration) -> Option<Event> {
+        if self.max_pending == 0 {
+            return Some(Event::Dropped(request));
+        }
+
+        let key = (request.transaction_id, request.unit_id);
+
+        if let Some(pending) = self.pending.insert(key, Pending { request, time }) {
+            return Some(Event::Duplicate(pending.request));
+        }
+
+        if self.pending.len() > self.max_pending {
+            let oldest = self
+                .pending
+                .iter()
+                .filter(|(other, _)| **other != key)
+                .min_by_key(|(_, pending)| pending.time)
+                .map(|(key, _)| *key)?;
+            return self
+                .pending
+                .remove(&oldest)
+                .map(|pending| Event::Dropped(pending.request));
+        }
+
+        None
+    }
+
+    /// Pairs a response received at `time` with its outstanding request.
+    ///
+    /// Requests which timed out before `time` aren't paired, they are removed
+    /// and reported along with the response.
+    pub fn response(&mut self, response: Message, time: Duration) -> Event {
+        let key = (response.transaction_id, response.unit_id);
+
+        let mut pending = match self.pending.remove(&key) {
+            Some(pending) => pending,
+            None => return Event::Unsolicited(response),
+        };
+
+        if time.saturating_sub(pending.time) > self.timeout {
+            Event::Late {
+                request: pending.request,
+                response,
+            }
+        } else if pending.request.matches(&response) {
+            Event::Matched {
+                request: pending.request,
+                response,
+            }
+        } else {
+            Event::Mismatched {
+                request: pending.request,
+                response,
+            }
+        }
+    }
+
+    /// Removes the requests outstanding for longer than the timeout at `time`,
+    /// oldest first
+    pub fn expire(&mut self, time: Duration) -> Vec<Event> {
+        let timeout = self.timeout;
+        let mut expired: Vec<Pending> = Vec::new();
+        let pending = core::mem::take(&mut self.pending);
+
+        for (key, request) in pending {
+            if time.saturating_sub(request.time) > timeout {
+                expired.push(request);
+            } else {
+                self.pending.insert(key, request);
+            }
+        }
+
+        expired.sort_by_key(|pending| pending.time);
+        expired
+            .into_iter()
+            .map(|pending| Event::TimedOut(pending.request))
+            .collect()
+    }
+
+    /// Removes all outstanding requests, such as at the end of the
+    /// connection, oldest first
+    pub fn flush(&mut self) -> Vec<Event> {
+        let mut pending: Vec<Pending> = core::mem::take(&mut self.pending).into_values().collect();
+
+        pending.sort_by_key(|pending| pending.time);
+        pending
+            .into_iter()
+            .map(|pending| Event::TimedOut(pending.request))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ErrorFlags, Modbus};
+    use sawp::parser::{Direction, Parse};
+
+    // Read Holding Registers 0x0010-0x0011 of unit 1
+    const REQUEST: [u8; 12] = [
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x10, 0x00, 0x02,
+    ];
+    const RESPONSE: [u8; 13] = [
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x00, 0x64, 0x01, 0x2c,
+    ];
+
+    fn parse(input: &[u8], direction: Direction) -> Message {
+        Modbus::default()
+            .parse(input, direction)
+            .unwrap()
+            .1
+            .unwrap()
+    }
+
+    fn request(transaction_id: u8) -> Message {
+        let mut input = REQUEST;
+        input[1] = transaction_id;
+        parse(&input, Direction::ToServer)
+    }
+
+    fn response(transaction_id: u8) -> Message {
+        let mut input = RESPONSE;
+        input[1] = transaction_id;
+        parse(&input, Direction::ToClient)
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_matched() {
+        let mut transactions = Transactions::default();
+        assert_eq!(transactions.request(request(1), ms(0)), None);
+        assert_eq!(transactions.request(request(2), ms(1)), None);
+        assert_eq!(transactions.len(), 2);
+
+        // Responses may arrive out of order
+        assert_eq!(
+            transactions.response(response(2), ms(10)),
+            Event::Matched {
+                request: request(2),
+                response: response(2)
+            }
+        );
+        assert!(matches!(
+            transactions.response(response(1), ms(11)),
+            Event::Matched { .. }
+        ));
+        assert!(transactions.is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_data() {
+        let mut transactions = Transactions::default();
+        transactions.request(request(1), ms(0));
+
+        // 1 register instead of 2
+        let mut input = RESPONSE;
+        input[5] = 0x05;
+        input[8] = 0x02;
+        let response = parse(&input[..11], Direction::ToClient);
+        match transactions.response(response, ms(10)) {
+            Event::Matched { request, .. } => {
+                assert_eq!(request.error_flags, ErrorFlags::DATA_VALUE)
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_duplicate() {
+        let mut transactions = Transactions::default();
+        transactions.request(request(1), ms(0));
+        assert_eq!(
+            transactions.request(request(1), ms(5)),
+            Some(Event::Duplicate(request(1)))
+        );
+        assert_eq!(transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_unsolicited() {
+        let mut transactions = Transactions::default();
+        assert_eq!(
+            transactions.response(response(1), ms(0)),
+            Event::Unsolicited(response(1))
+        );
+
+        assert!(transactions.is_empty());
+    }
+
+    #[test]
+    fn test_late() {
+        let mut transactions = Transactions::default();
+        transactions.request(request(1), ms(0));
+
+        // A response after the timeout removes the request, which doesn't
+        // time out again
+        assert_eq!(
+            transactions.response(response(1), DEFAULT_TIMEOUT + ms(1)),
+            Event::Late {
+                request: request(1),
+                response: response(1)
+            }
+        );
+        assert!(transactions.is_empty());
+        assert_eq!(transactions.expire(DEFAULT_TIMEOUT + ms(2)), vec![]);
+    }
+
+    #[test]
+    fn test_mismatched() {
+        let mut transactions = Transactions::default();
+        transactions.request(request(1), ms(0));
+
+        // A response of another function doesn't match and removes the
+        // request
+        let mut input = RESPONSE;
+        input[7] = 0x04;
+        let other = parse(&input, Direction::ToClient);
+        assert_eq!(
+            transactions.response(other, ms(1)),
+            Event::Mismatched {
+                request: request(1),
+                response: parse(&input, Direction::ToClient)
+            }
+        );
+        assert!(transactions.is_empty());
+        assert_eq!(
+            transactions.response(response(1), ms(2)),
+            Event::Unsolicited(response(1))
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut transactions = Transactions {
+            timeout: ms(100),
+            ..Transactions::default()
+        };
+        transactions.request(request(2), ms(10));
+        transactions.request(request(1), ms(20));
+        transactions.request(request(3), ms(150));
+
+        assert_eq!(transactions.expire(ms(100)), vec![]);
+        assert_eq!(
+            transactions.expire(ms(200)),
+            vec![Event::TimedOut(request(2)), Event::TimedOut(request(1))]
+        );
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions.flush(), vec![Event::TimedOut(request(3))]);
+        assert!(transactions.is_empty());
+    }
+
+    #[test]
+    fn test_max_pending() {
+        let mut transactions = Transactions {
+            max_pending: 2,
+            ..Transactions::default()
+        };
+        transactions.request(request(1), ms(0));
+        transactions.request(request(2), ms(1));
+        assert_eq!(
+            transactions.request(request(3), ms(2)),
+            Some(Event::Dropped(request(1)))
+        );
+        assert_eq!(transactions.len(), 2);
+
+        // Without room, requests are dropped right away
+        transactions.max_pending = 0;
+        assert_eq!(
+            transactions.request(request(4), ms(3)),
+            Some(Event::Dropped(request(4)))
+        );
+        assert_eq!(transactions.len(), 2);
+    }
+}
@@ -0,0 +1,347 @@
+//! Typed interpretation of the coils and registers read or written.
+//!
+//! [`Message::read_data`] pairs a read response with its request and
+//! [`Message::write_data`] extracts the data of a write request, both
+//! returning a [`RegisterData`] which decodes the raw bytes per address as
+//! bits, 16-bit or 32-bit integers, floats or ASCII strings.
+//!
+//! Addresses are the coil/register numbers starting at 1, as returned by
+//! [`Message::get_address_range`].
+//!
+//! # Example
+//! ```
+//! use sawp::parser::{Direction, Parse};
+//! use sawp_modbus::values::WordOrder;
+//! use sawp_modbus::Modbus;
+//!
+//! let modbus = Modbus::default();
+//! // Read 2 holding registers at address 0 of unit 1
+//! let request = [0x00, 0x01, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x00, 0x00, 0x02];
+//! let response = [0x00, 0x01, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x42, 0x28, 0x00, 0x00];
+//! let (_, request) = modbus.parse(&request, Direction::ToServer).unwrap();
+//! let (_, response) = modbus.parse(&response, Direction::ToClient).unwrap();
+//!
+//! let response = response.unwrap();
+//! let data = response.read_data(&request.unwrap()).unwrap();
+//! assert_eq!(data.u16s(), vec![(1, 0x4228), (2, 0x0000)]);
+//! assert_eq!(data.f32s(WordOrder::BigEndian), vec![(1, 42.0)]);
+//! ```
+
+use crate::{AccessType, Data, Message, Read, Write};
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Order of the registers and of their bytes in values spanning two
+/// registers, named after the bytes `ABCD` of the big endian value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WordOrder {
+    /// `ABCD`: most significant register first, as defined by Modbus
+    BigEndian,
+    /// `CDAB`: least significant register first
+    LittleEndian,
+    /// `BADC`: most significant register first, bytes swapped
+    BigEndianByteSwap,
+    /// `DCBA`: least significant register first, bytes swapped
+    LittleEndianByteSwap,
+}
+
+impl WordOrder {
+    /// Reorders the bytes of two registers as a big endian value
+    fn arrange(self, bytes: [u8; 4]) -> [u8; 4] {
+        let [a, b, c, d] = bytes;
+        match self {
+            WordOrder::BigEndian => [a, b, c, d],
+            WordOrder::LittleEndian => [c, d, a, b],
+            WordOrder::BigEndianByteSwap => [b, a, d, c],
+            WordOrder::LittleEndianByteSwap => [d, c, b, a],
+        }
+    }
+}
+
+/// Coils or registers read or written from a starting address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegisterData<'a> {
+    /// Coil/register number of the first value
+    pub address: u16,
+    /// Number of coils or registers
+    pub quantity: u16,
+    /// Coils packed least significant bit first, or big endian registers
+    pub data: Cow<'a, [u8]>,
+}
+
+impl RegisterData<'_> {
+    /// The values as coils or discrete inputs
+    pub fn bits(&self) -> Vec<(u16, bool)> {
+        let bits = self
+            .data
+            .iter()
+            .flat_map(|byte| (0..8).map(move |bit| (byte >> bit) & 1 == 1));
+        (self.address..=u16::MAX)
+            .zip(bits)
+            .take(usize::from(self.quantity))
+            .collect()
+    }
+
+    /// The values as unsigned registers
+    pub fn u16s(&self) -> Vec<(u16, u16)> {
+        let registers = self
+            .data
+            .chunks_exact(2)
+            .map(|word| u16::from_be_bytes([word[0], word[1]]));
+        (self.address..=u16::MAX)
+            .zip(registers)
+            .take(usize::from(self.quantity))
+            .collect()
+    }
+
+    /// The values as signed registers
+    pub fn i16s(&self) -> Vec<(u16, i16)> {
+        self.u16s()
+            .into_iter()
+            .map(|(address, value)| (address, value as i16))
+            .collect()
+    }
+
+    /// Pairs of registers as unsigned 32-bit values, at the address of their
+    /// first register. An odd last register is ignored.
+    pub fn u32s(&self, order: WordOrder) -> Vec<(u16, u32)> {
+        let data = &self.data[..self.data.len().min(2 * usize::from(self.quantity))];
+        let values = data.chunks_exact(4).map(|bytes| {
+            u32::from_be_bytes(order.arrange([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        });
+        (self.address..=u16::MAX).step_by(2).zip(values).collect()
+    }
+
+    /// Pairs of registers as signed 32-bit values
+    pub fn i32s(&self, order: WordOrder) -> Vec<(u16, i32)> {
+        self.u32s(order)
+            .into_iter()
+            .map(|(address, value)| (address, value as i32))
+            .collect()
+    }
+
+    /// Pairs of registers as IEEE 754 single precision floats
+    pub fn f32s(&self, order: WordOrder) -> Vec<(u16, f32)> {
+        self.u32s(order)
+            .into_iter()
+            .map(|(address, value)| (address, f32::from_bits(value)))
+            .collect()
+    }
+
+    /// The registers as a string of two characters each, most significant
+    /// byte first, ending at the first NUL. Characters which aren't ASCII are
+    /// replaced by U+FFFD.
+    pub fn ascii(&self) -> String {
+        self.data
+            .iter()
+            .take(2 * usize::from(self.quantity))
+            .take_while(|byte| **byte != 0)
+            .map(|byte| {
+                if byte.is_ascii() {
+                    char::from(*byte)
+                } else {
+                    char::REPLACEMENT_CHARACTER
+                }
+            })
+            .collect()
+    }
+}
+
+/// Coil/register number of the first value, if all quantity values are
+/// addressable
+pub(crate) fn first_address(address: u16, quantity: u16) -> Option<u16> {
+    if quantity > 0 && quantity <= u16::MAX - address {
+        Some(address + 1)
+    } else {
+        None
+    }
+}
+
+impl Message {
+    /// Pairs this read response with its request, returning the values read.
+    ///
+    /// The messages should be paired with [`Message::matches`] first, only
+    /// their functions are compared here.
+    pub fn read_data(&self, request: &Message) -> Option<RegisterData<'_>> {
+        if self.function.code != request.function.code {
+            return None;
+        }
+
+        let (address, quantity) = match &request.data {
+            Data::Read(Read::Request { address, quantity })
+            | Data::ReadWrite {
+                read: Read::Request { address, quantity },
+                ..
+            } => (*address, *quantity),
+            _ => return None,
+        };
+        let data = match &self.data {
+            Data::Read(Read::Response(data)) => data,
+            _ => return None,
+        };
+
+        Some(RegisterData {
+            address: first_address(address, quantity)?,
+            quantity,
+            data: Cow::Borrowed(data),
+        })
+    }
+
+    /// The values written by this single or multiple write request
+    pub fn write_data(&self) -> Option<RegisterData<'_>> {
+        match &self.data {
+            // Multiple write responses echo the address and quantity
+            Data::Write(Write::Other { address, data })
+                if self.access_type.contains(AccessType::SINGLE) =>
+            {
+                Some(RegisterData {
+                    address: first_address(*address, 1)?,
+                    quantity: 1,
+                    data: Cow::Owned(data.to_be_bytes().to_vec()),
+                })
+            }
+            Data::Write(Write::MultReq {
+                address,
+                quantity,
+                data,
+            })
+            | Data::ReadWrite {
+                write:
+                    Write::MultReq {
+                        address,
+                        quantity,
+                        data,
+                    },
+                ..
+            } => Some(RegisterData {
+                address: first_address(*address, *quantity)?,
+                quantity: *quantity,
+                data: Cow::Borrowed(data),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Modbus;
+    use rstest::rstest;
+    use sawp::parser::{Direction, Parse};
+
+    fn registers(data: &[u8]) -> RegisterData<'_> {
+        RegisterData {
+            address: 1,
+            quantity: (data.len() / 2) as u16,
+            data: Cow::Borrowed(data),
+        }
+    }
+
+    #[rstest(
+        order,
+        expected,
+        case::big_endian(WordOrder::BigEndian, 0x1234_5678),
+        case::little_endian(WordOrder::LittleEndian, 0x5678_1234),
+        case::big_endian_byte_swap(WordOrder::BigEndianByteSwap, 0x3412_7856),
+        case::little_endian_byte_swap(WordOrder::LittleEndianByteSwap, 0x7856_3412)
+    )]
+    fn test_word_order(order: WordOrder, expected: u32) {
+        let data = registers(&[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc]);
+        assert_eq!(data.u32s(order), vec![(1, expected)]);
+    }
+
+    #[test]
+    fn test_registers() {
+        let data = registers(&[0xff, 0xfe, 0x00, 0x02, 0xc0, 0x49, 0x0f, 0xdb]);
+        assert_eq!(
+            data.u16s(),
+            vec![(1, 0xfffe), (2, 0x0002), (3, 0xc049), (4, 0x0fdb)]
+        );
+        assert_eq!(data.i16s()[0], (1, -2));
+        assert_eq!(
+            data.i32s(WordOrder::BigEndian),
+            vec![(1, -131_070), (3, -1_068_953_637)]
+        );
+        assert_eq!(
+            data.f32s(WordOrder::BigEndian)[1],
+            (3, -core::f32::consts::PI)
+        );
+        assert_eq!(registers(b"PLC-5\xff\x00\x00").ascii(), "PLC-5\u{fffd}");
+    }
+
+    #[test]
+    fn test_read_data() {
+        let modbus = Modbus::default();
+        let (_, request) = modbus
+            .parse(
+                &[
+                    0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                    0x01, // Unit ID: 1
+                    0x02, // Function Code: Read Discrete Inputs (2)
+                    0x00, 0xc4, // Starting Address: 196
+                    0x00, 0x0a, // Quantity: 10
+                ],
+                Direction::ToServer,
+            )
+            .unwrap();
+        let (_, response) = modbus
+            .parse(
+                &[
+                    0x00, 0x01, 0x00, 0x00, 0x00, 0x05, // MBAP: transaction 1, length 5
+                    0x01, // Unit ID: 1
+                    0x02, // Function Code: Read Discrete Inputs (2)
+                    0x02, // Byte Count: 2
+                    0xac, 0x02, // Inputs: 0011010101
+                ],
+                Direction::ToClient,
+            )
+            .unwrap();
+        let (request, response) = (request.unwrap(), response.unwrap());
+        let bits = response.read_data(&request).unwrap().bits();
+        assert_eq!(bits.len(), 10);
+        assert_eq!(bits[0], (197, false));
+        assert_eq!(bits[2], (199, true));
+        assert_eq!(bits[9], (206, true));
+
+        assert_eq!(request.read_data(&request), None);
+        assert_eq!(response.write_data(), None);
+    }
+
+    #[test]
+    fn test_write_data() {
+        let modbus = Modbus::default();
+        let (_, request) = modbus
+            .parse(
+                &[
+                    0x00, 0x01, 0x00, 0x00, 0x00, 0x0b, // MBAP: transaction 1, length 11
+                    0x01, // Unit ID: 1
+                    0x10, // Function Code: Write Multiple Registers (16)
+                    0x00, 0x01, // Starting Address: 1
+                    0x00, 0x02, // Quantity: 2
+                    0x04, // Byte Count: 4
+                    0x00, 0x0a, 0x01, 0x02, // Registers: 10, 258
+                ],
+                Direction::ToServer,
+            )
+            .unwrap();
+        let data = request.unwrap().write_data().unwrap().u16s();
+        assert_eq!(data, vec![(2, 10), (3, 258)]);
+
+        let (_, request) = modbus
+            .parse(
+                &[
+                    0x00, 0x01, 0x00, 0x00, 0x00, 0x06, // MBAP: transaction 1, length 6
+                    0x01, // Unit ID: 1
+                    0x06, // Function Code: Write Single Register (6)
+                    0xff, 0xff, // Register Address: 65535
+                    0x00, 0x03, // Register Value: 3
+                ],
+                Direction::ToServer,
+            )
+            .unwrap();
+        assert_eq!(request.unwrap().write_data(), None);
+    }
+}
@@ -16,7 +16,7 @@
 //! use sawp_pop3::{POP3, Message, InnerMessage, ErrorFlag};
 //!
 //! fn parse_bytes(input: &[u8]) -> std::result::Result<&[u8], Error> {
-//!     let pop3 = POP3 {};
+//!     let pop3 = POP3::default();
 //!     let mut bytes = input;
 //!     while bytes.len() > 0 {
 //!         match pop3.parse(bytes, Direction::Unknown) {
@@ -50,7 +50,9 @@
 /// in this crate.
 pub use sawp_flags::{Flag, Flags};
 
-use sawp::error::{Error, Result};
+use sawp::error::{Error, ErrorKind, Result};
+use sawp::limits::{Limits, Tracker};
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::Probe;
 use sawp::protocol::Protocol;
@@ -62,9 +64,11 @@ mod ffi;
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
-use nom::bytes::complete::{is_not, take_until};
-use nom::character::complete::{char, crlf};
+use nom::bytes::streaming::{is_not, take_until};
+use nom::character::streaming::{char, crlf};
 use nom::combinator::opt;
 use nom::multi::many_till;
 use nom::sequence::{pair, preceded, terminated};
@@ -76,6 +80,7 @@ pub const CLIENT_COMMAND_MAX_LEN: usize = 256;
 pub const SERVER_RESP_FIRST_LINE_MAX_LEN: usize = 512;
 
 /// The supported POP3 client commands
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_pop3"))]
 pub enum Keyword {
@@ -99,6 +104,7 @@ pub enum Keyword {
 }
 
 /// POP3 servers can respond with either an OK or Error response based on client input
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_pop3"))]
 pub enum Status {
@@ -118,6 +124,8 @@ pub enum ErrorFlag {
     UnknownKeyword = 0b0000_0100,
     /// First line of server response + CRLF must not exceed 512 octets (RFC 2449)
     ResponseTooLong = 0b0000_1000,
+    /// A resource limit was reached, arguments or data lines past the limit are dropped
+    LimitExceeded = 0b0001_0000,
 }
 
 impl TryFrom<&[u8]> for Keyword {
@@ -204,6 +212,7 @@ impl Status {
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_pop3"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Command {
     pub keyword: Keyword,
@@ -211,6 +220,7 @@ pub struct Command {
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_pop3"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Response {
     pub status: Status,
@@ -219,6 +229,7 @@ pub struct Response {
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_pop3"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum InnerMessage {
     Command(Command),
@@ -226,13 +237,19 @@ pub enum InnerMessage {
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_pop3"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub error_flags: Flags<ErrorFlag>,
     pub inner: InnerMessage,
 }
 
-pub struct POP3 {}
+#[derive(Default)]
+pub struct POP3 {
+    /// Resource limits applied to every message. `max_elements` bounds the
+    /// number of command arguments and response data lines.
+    pub limits: Limits,
+}
 
 impl<'a> Probe<'a> for POP3 {}
 
@@ -244,6 +261,12 @@ impl Protocol<'_> for POP3 {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
 impl POP3 {
     fn server_response_too_long(status_length: usize, payload_first_line_length: usize) -> bool {
         status_length + SPACE.len() + payload_first_line_length + CRLF.len()
@@ -254,28 +277,48 @@ impl POP3 {
         command_length + SPACE.len() + client_payload_length + CRLF.len() > CLIENT_COMMAND_MAX_LEN
     }
 
-    fn parse_response(input: &[u8]) -> Result<(&[u8], Message)> {
+    /// Copies the lines that fit within the limits of the tracker.
+    fn copy_lines(lines: &[&[u8]], tracker: &Tracker) -> Vec<Vec<u8>> {
+        lines
+            .iter()
+            .take_while(|line| tracker.element() && tracker.alloc(line.len()))
+            .map(|line| line.to_vec())
+            .collect()
+    }
+
+    fn parse_response<'a>(input: &'a [u8], tracker: &Tracker) -> Result<(&'a [u8], Message)> {
         let mut flags: Flags<ErrorFlag> = ErrorFlag::none();
 
         let (input, raw_status) = terminated(is_not(" \r"), opt(char(' ')))(input)?;
         let status = Status::try_from(raw_status)?;
-        let first_line = terminated(take_until(CRLF), crlf);
-        let additional_line = terminated(preceded(opt(char('.')), take_until(CRLF)), crlf);
-        let termination_line = pair(char('.'), crlf);
-        let (input, (header, data)) = pair(
-            first_line,
-            opt(many_till(additional_line, termination_line)),
-        )(input)?;
+        let (input, header) = terminated(take_until(CRLF), crlf)(input)?;
+        // Whether a response has data lines depends on the command it answers,
+        // so they are only parsed if the whole response is available
+        let additional_line = terminated(
+            preceded(
+                opt(nom::character::complete::char('.')),
+                nom::bytes::complete::take_until(CRLF),
+            ),
+            nom::character::complete::crlf,
+        );
+        let termination_line = pair(
+            nom::character::complete::char('.'),
+            nom::character::complete::crlf,
+        );
+        let (input, data) = opt(many_till(additional_line, termination_line))(input)?;
 
         let header = header.to_vec();
         let data: Vec<Vec<u8>> = match data {
             None => vec![],
-            Some((x, _)) => x.iter().map(|x| x.to_vec()).collect(),
+            Some((x, _)) => POP3::copy_lines(&x, tracker),
         };
 
         if POP3::server_response_too_long(raw_status.len(), header.len()) {
             flags |= ErrorFlag::ResponseTooLong;
         }
+        if tracker.exceeded() {
+            flags |= ErrorFlag::LimitExceeded;
+        }
 
         let message = Message {
             error_flags: flags,
@@ -289,17 +332,18 @@ impl POP3 {
         Ok((input, message))
     }
 
-    fn parse_command(input: &[u8]) -> Result<(&[u8], Message)> {
+    fn parse_command<'a>(input: &'a [u8], tracker: &Tracker) -> Result<(&'a [u8], Message)> {
         let mut flags: Flags<ErrorFlag> = ErrorFlag::none();
 
         let (input, raw_keyword) = terminated(is_not(" \r"), opt(char(' ')))(input)?;
         let keyword = Keyword::try_from(raw_keyword)?;
         let (input, raw_args) = terminated(take_until(CRLF), crlf)(input)?;
-        let args: Vec<Vec<u8>> = raw_args
+        let raw_args_len = raw_args.len();
+        let raw_args: Vec<&[u8]> = raw_args
             .split(|&x| x == b' ')
-            .map(|x| x.to_vec())
             .filter(|x| !x.is_empty())
             .collect();
+        let args = POP3::copy_lines(&raw_args, tracker);
 
         // Apply IncorrectArgumentNum flag if necessary, depending on the specific client command used
         match &keyword {
@@ -339,9 +383,12 @@ impl POP3 {
             Keyword::Unknown(_) => flags |= ErrorFlag::UnknownKeyword,
         }
 
-        if POP3::client_command_too_long(raw_keyword.len(), raw_args.len()) {
+        if POP3::client_command_too_long(raw_keyword.len(), raw_args_len) {
             flags |= ErrorFlag::CommandTooLong;
         }
+        if tracker.exceeded() {
+            flags |= ErrorFlag::LimitExceeded;
+        }
 
         let message = Message {
             error_flags: flags,
@@ -358,22 +405,26 @@ impl<'a> Parse<'a> for POP3 {
         input: &'a [u8],
         direction: Direction,
     ) -> Result<(&'a [u8], Option<Self::Message>)> {
+        let tracker = self.limits.tracker();
         match direction {
             Direction::ToServer => {
-                let (input, msg) = POP3::parse_command(input)?;
+                let (input, msg) = POP3::parse_command(input, &tracker)?;
                 Ok((input, Some(msg)))
             }
             Direction::ToClient => {
-                let (input, msg) = POP3::parse_response(input)?;
+                let (input, msg) = POP3::parse_response(input, &tracker)?;
                 Ok((input, Some(msg)))
             }
             Direction::Unknown => {
                 // Can't use nom::branch::alt since parse_* return sawp::error
-                if let Ok((input, msg)) = POP3::parse_command(input) {
-                    Ok((input, Some(msg)))
-                } else {
-                    let (input, msg) = POP3::parse_response(input)?;
-                    Ok((input, Some(msg)))
+                match POP3::parse_command(input, &tracker) {
+                    Ok((input, msg)) => Ok((input, Some(msg))),
+                    Err(err) if matches!(err.kind, ErrorKind::Incomplete(_)) => Err(err),
+                    Err(_) => {
+                        let tracker = self.limits.tracker();
+                        let (input, msg) = POP3::parse_response(input, &tracker)?;
+                        Ok((input, Some(msg)))
+                    }
                 }
             }
         }
@@ -383,9 +434,9 @@ impl<'a> Parse<'a> for POP3 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use nom::error::ErrorKind;
     use rstest::rstest;
-    use sawp::error::{Error, NomError};
+    use sawp::error::Error;
+    use sawp::testing::{assert_datagram, assert_incremental};
 
     #[test]
     fn test_name() {
@@ -395,8 +446,8 @@ mod tests {
     #[rstest(
         input,
         expected,
-        case::empty(b"", Err(Error::from(NomError::new(b"" as &[u8], ErrorKind::Many0)))),
-        case::hello_world(b"hello world", Err(Error::from(NomError::new(b"\x01\x02\x03\x04 world" as &[u8], ErrorKind::Tag)))),
+        case::empty(b"", Err(Error::incomplete_needed(1))),
+        case::hello_world(b"hello world", Err(Error::incomplete())),
         case::unknown_keyword(
             b"HELLO WORLD\r\n", 
             Ok((b"".as_ref(),
@@ -592,7 +643,77 @@ mod tests {
             Err(Error::parse(Some("Keyword is response".to_string())))),
     )]
     fn test_parse(input: &[u8], expected: Result<(&[u8], Option<Message>)>) {
-        let pop3 = POP3 {};
+        let pop3 = POP3::default();
         assert_eq!(pop3.parse(input, Direction::Unknown), expected);
+        check_incremental(Limits::default(), input, Direction::Unknown, &expected);
+    }
+
+    /// Checks the `Parse` contract over every split of `input`. Data lines are
+    /// only parsed once the whole response is available, so responses with
+    /// data lines are checked as datagrams.
+    fn check_incremental(
+        limits: Limits,
+        input: &[u8],
+        direction: Direction,
+        expected: &Result<(&[u8], Option<Message>)>,
+    ) {
+        match expected {
+            Ok((
+                _,
+                Some(Message {
+                    inner: InnerMessage::Response(response),
+                    ..
+                }),
+            )) if !response.data.is_empty() => {
+                assert_datagram(&POP3 { limits }, input, direction);
+            }
+            _ => {
+                assert_incremental(|| POP3 { limits }, input, direction);
+            }
+        }
+    }
+
+    #[rstest(
+        input,
+        direction,
+        limits,
+        expected,
+        case::command_max_elements(
+            b"TOP 1 2\r\n",
+            Direction::ToServer,
+            Limits { max_elements: 1, ..Limits::default() },
+            Ok((b"".as_ref(),
+                Some(Message {
+                    error_flags: ErrorFlag::IncorrectArgumentNum | ErrorFlag::LimitExceeded,
+                    inner: InnerMessage::Command(Command {
+                        keyword: Keyword::TOP,
+                        args: vec![b"1".to_vec()],
+                    }),
+                }),
+            ))),
+        case::response_max_alloc(
+            b"+OK\r\nfirst\r\nsecond\r\n.\r\n",
+            Direction::ToClient,
+            Limits { max_alloc: 8, ..Limits::default() },
+            Ok((b"".as_ref(),
+                Some(Message {
+                    error_flags: ErrorFlag::LimitExceeded.into(),
+                    inner: InnerMessage::Response(Response {
+                        status: Status::OK,
+                        header: b"".to_vec(),
+                        data: vec![b"first".to_vec()],
+                    }),
+                }),
+            ))),
+    )]
+    fn test_parse_limits(
+        input: &[u8],
+        direction: Direction,
+        limits: Limits,
+        expected: Result<(&[u8], Option<Message>)>,
+    ) {
+        let pop3 = POP3 { limits };
+        assert_eq!(pop3.parse(input, direction.clone()), expected);
+        check_incremental(limits, input, direction, &expected);
     }
 }
@@ -0,0 +1,176 @@
+//! Conversion of message trees into python objects.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use pyo3::IntoPyObjectExt;
+
+use sawp_visit::{Value, Visit, Visitor};
+
+/// Converts `root` into nested python dicts, lists and values.
+///
+/// - Structs become dicts keyed by field name.
+/// - Sequences become lists, except byte sequences which become `bytes`.
+/// - Unit enum variants become their name as a `str`.
+/// - Flags become their bits as an `int`.
+/// - IP addresses become `ipaddress` objects.
+/// - Missing optional values become `None`.
+pub fn to_python(py: Python<'_>, root: &dyn Visit) -> PyResult<PyObject> {
+    let mut builder = Builder {
+        py,
+        node: Node::Empty,
+        result: Ok(()),
+    };
+    root.visit(&mut builder);
+    builder.result?;
+    Ok(match builder.node {
+        Node::Empty => py.None(),
+        Node::Value(value) => value,
+        Node::Dict(dict) => dict.into_any().unbind(),
+        Node::List(list) => list.into_any().unbind(),
+    })
+}
+
+enum Node<'py> {
+    Empty,
+    Value(PyObject),
+    Dict(Bound<'py, PyDict>),
+    List(Bound<'py, PyList>),
+}
+
+struct Builder<'py> {
+    py: Python<'py>,
+    node: Node<'py>,
+    /// First error raised by python while building the node
+    result: PyResult<()>,
+}
+
+impl<'py> Builder<'py> {
+    fn check(&mut self, result: PyResult<()>) {
+        if self.result.is_ok() {
+            self.result = result;
+        }
+    }
+
+    fn list(&mut self) -> &Bound<'py, PyList> {
+        if !matches!(self.node, Node::List(_)) {
+            self.node = Node::List(PyList::empty(self.py));
+        }
+        match &self.node {
+            Node::List(list) => list,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a, 'py> Visitor<'a> for Builder<'py> {
+    fn value(&mut self, value: Value<'a>) {
+        let py = self.py;
+        let result = match value {
+            Value::Bool(value) => value.into_py_any(py),
+            Value::Uint(value) => value.into_py_any(py),
+            Value::Int(value) => value.into_py_any(py),
+            Value::Float(value) => value.into_py_any(py),
+            Value::Str(value) => value.into_py_any(py),
+            Value::Bytes(value) => PyBytes::new(py, value).into_py_any(py),
+            Value::Ip(value) => value.into_py_any(py),
+            Value::Variant(value) => value.into_py_any(py),
+            Value::Flags(value) => value.bits().into_py_any(py),
+        }
+        .map(|value| self.node = Node::Value(value));
+        self.check(result);
+    }
+
+    fn field(&mut self, name: &'a str, value: &'a dyn Visit) {
+        let result = to_python(self.py, value).and_then(|value| {
+            if !matches!(self.node, Node::Dict(_)) {
+                self.node = Node::Dict(PyDict::new(self.py));
+            }
+            match &self.node {
+                Node::Dict(dict) => dict.set_item(name, value),
+                _ => unreachable!(),
+            }
+        });
+        self.check(result);
+    }
+
+    fn element(&mut self, _index: usize, value: &'a dyn Visit) {
+        let result = to_python(self.py, value).and_then(|value| self.list().append(value));
+        self.check(result);
+    }
+
+    fn sequence(&mut self, _len: usize) {
+        self.list();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::ffi::c_str;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[derive(Visit)]
+    #[allow(dead_code)]
+    enum Kind {
+        Empty,
+        Number(u8),
+    }
+
+    #[derive(Visit)]
+    struct Inner {
+        kind: Kind,
+        address: Option<IpAddr>,
+    }
+
+    #[derive(Visit)]
+    struct Outer {
+        name: String,
+        data: Vec<u8>,
+        inner: Vec<Inner>,
+        empty: Vec<Inner>,
+    }
+
+    #[test]
+    fn test_to_python() {
+        let outer = Outer {
+            name: "outer".to_string(),
+            data: b"\x00\x01".to_vec(),
+            inner: vec![
+                Inner {
+                    kind: Kind::Empty,
+                    address: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                },
+                Inner {
+                    kind: Kind::Number(7),
+                    address: None,
+                },
+            ],
+            empty: vec![],
+        };
+
+        Python::with_gil(|py| {
+            let object = to_python(py, &outer).unwrap();
+            let locals = PyDict::new(py);
+            locals.set_item("outer", object).unwrap();
+            py.run(
+                c_str!(
+                    r#"
+import ipaddress
+assert outer == {
+    "name": "outer",
+    "data": b"\x00\x01",
+    "inner": [
+        {"kind": "Empty", "address": ipaddress.ip_address("127.0.0.1")},
+        {"kind": 7, "address": None},
+    ],
+    "empty": [],
+}, outer
+"#
+                ),
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+}
@@ -0,0 +1,137 @@
+//! Python access to the `sawp-file` format.
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::IntoPyObjectExt;
+
+use sawp_file::format::{self, Call};
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+use crate::Direction;
+
+fn io_error(err: sawp_file::error::Error) -> PyErr {
+    PyIOError::new_err(err.to_string())
+}
+
+impl From<format::Direction> for Direction {
+    fn from(direction: format::Direction) -> Self {
+        match direction {
+            format::Direction::ToClient => Direction::ToClient,
+            format::Direction::ToServer => Direction::ToServer,
+            format::Direction::Unknown => Direction::Unknown,
+        }
+    }
+}
+
+impl From<Direction> for format::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::ToClient => format::Direction::ToClient,
+            Direction::ToServer => format::Direction::ToServer,
+            Direction::Unknown => format::Direction::Unknown,
+        }
+    }
+}
+
+/// Iterates over the calls recorded in a file.
+///
+/// Each call is a tuple of `("parse", direction, data)` or
+/// `("gap", direction, size)`.
+#[pyclass(module = "sawp")]
+pub struct Reader {
+    inner: format::Reader<BufReader<File>>,
+}
+
+#[pymethods]
+impl Reader {
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::open(path)?;
+        let inner = format::Reader::new(BufReader::new(file)).map_err(io_error)?;
+        Ok(Self { inner })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(
+        &mut self,
+        py: Python<'_>,
+    ) -> PyResult<Option<(&'static str, Direction, PyObject)>> {
+        self.inner
+            .next()
+            .map(|call| match call {
+                Call::Parse(data) => Ok((
+                    "parse",
+                    data.direction.into(),
+                    PyBytes::new(py, &data.data).into_py_any(py)?,
+                )),
+                Call::Gap(gap) => Ok(("gap", gap.direction.into(), gap.gap.into_py_any(py)?)),
+            })
+            .transpose()
+    }
+}
+
+/// Records calls to a file.
+///
+/// The file is flushed when the writer is closed, either with `close()` or
+/// at the end of a `with` block.
+#[pyclass(module = "sawp")]
+pub struct Writer {
+    inner: Option<format::Writer<BufWriter<File>>>,
+}
+
+impl Writer {
+    fn inner(&mut self) -> PyResult<&mut format::Writer<BufWriter<File>>> {
+        self.inner
+            .as_mut()
+            .ok_or_else(|| PyValueError::new_err("writer is closed"))
+    }
+}
+
+#[pymethods]
+impl Writer {
+    #[new]
+    fn new(path: PathBuf) -> PyResult<Self> {
+        let file = File::create(path)?;
+        let inner = format::Writer::new(BufWriter::new(file)).map_err(io_error)?;
+        Ok(Self { inner: Some(inner) })
+    }
+
+    fn parse(&mut self, direction: Direction, data: &[u8]) -> PyResult<()> {
+        self.inner()?
+            .parse(direction.into(), data)
+            .map_err(io_error)
+    }
+
+    fn gap(&mut self, direction: Direction, size: usize) -> PyResult<()> {
+        self.inner()?.gap(direction.into(), size).map_err(io_error)
+    }
+
+    /// Flushes and closes the file. Does nothing if already closed.
+    fn close(&mut self) -> PyResult<()> {
+        match self.inner.take() {
+            Some(mut inner) => inner.flush().map_err(io_error),
+            None => Ok(()),
+        }
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) -> PyResult<bool> {
+        self.close()?;
+        Ok(false)
+    }
+}
@@ -0,0 +1,256 @@
+//! Python bindings for the SAWP parsers.
+//!
+//! The `sawp` python module exposes a class per parser with `parse` and
+//! `probe` methods. Parsed messages are converted to nested dicts and lists
+//! following their field names, see [`sawp_visit`].
+//!
+//! Recordings made with `sawp-file` can be read with `sawp.Reader` and
+//! written with `sawp.Writer`.
+//!
+//! Build the module with [maturin](https://www.maturin.rs), e.g.
+//! `maturin develop` from this directory.
+//!
+//! ```python
+//! import sawp
+//!
+//! parser = sawp.Modbus()
+//! for call, direction, data in sawp.Reader("capture.sawp"):
+//!     if call != "parse":
+//!         continue
+//!     while data:
+//!         try:
+//!             data, message = parser.parse(data, direction)
+//!         except sawp.IncompleteError:
+//!             break
+//!         if message is not None:
+//!             print(message["function"]["code"], message["unit_id"])
+//! ```
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use sawp::error::{ErrorKind, Needed};
+use sawp::parser::{self, Parse};
+use sawp::probe::{self, Probe};
+use sawp::protocol::Protocol;
+
+mod convert;
+mod file;
+
+create_exception!(sawp, Error, PyException, "The parser failed");
+create_exception!(
+    sawp,
+    IncompleteError,
+    Error,
+    "More data is needed, `args[1]` holds the number of bytes if known"
+);
+
+fn error(err: sawp::error::Error) -> PyErr {
+    match err.kind {
+        ErrorKind::Incomplete(Needed::Size(size)) => {
+            IncompleteError::new_err((err.to_string(), size.get()))
+        }
+        ErrorKind::Incomplete(Needed::Unknown) => {
+            IncompleteError::new_err((err.to_string(), None::<usize>))
+        }
+        _ => Error::new_err(err.to_string()),
+    }
+}
+
+/// Destination of the input byte stream.
+#[pyclass(eq, eq_int, module = "sawp")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    ToClient,
+    ToServer,
+    Unknown,
+}
+
+impl From<Direction> for parser::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::ToClient => parser::Direction::ToClient,
+            Direction::ToServer => parser::Direction::ToServer,
+            Direction::Unknown => parser::Direction::Unknown,
+        }
+    }
+}
+
+/// Result of probing input data.
+#[pyclass(eq, eq_int, module = "sawp")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Recognized,
+    Unrecognized,
+    Incomplete,
+}
+
+impl From<probe::Status> for Status {
+    fn from(status: probe::Status) -> Self {
+        match status {
+            probe::Status::Recognized => Status::Recognized,
+            probe::Status::Unrecognized => Status::Unrecognized,
+            probe::Status::Incomplete => Status::Incomplete,
+        }
+    }
+}
+
+/// Defines a python class wrapping a parser with its default settings.
+///
+/// Parsers holding state in cells must pass `unsendable`, python then raises
+/// an error if they are used from another thread than the one creating them.
+macro_rules! parser {
+    ($name:ident, $parser:ty $(, $option:ident)?) => {
+        #[pyclass(module = "sawp" $(, $option)?)]
+        pub struct $name {
+            parser: $parser,
+        }
+
+        #[pymethods]
+        impl $name {
+            #[new]
+            fn new() -> Self {
+                Self {
+                    parser: <$parser>::default(),
+                }
+            }
+
+            /// Protocol name.
+            #[classattr]
+            fn name() -> &'static str {
+                <$parser as Protocol>::name()
+            }
+
+            /// Parses one message from `data`.
+            ///
+            /// Returns the remaining data and the message, which is `None`
+            /// if the parser made progress without completing a message.
+            /// Raises `IncompleteError` if more data is needed and `Error`
+            /// on any other failure.
+            #[pyo3(signature = (data, direction = Direction::Unknown))]
+            fn parse<'py>(
+                &self,
+                py: Python<'py>,
+                data: &[u8],
+                direction: Direction,
+            ) -> PyResult<(Bound<'py, PyBytes>, Option<PyObject>)> {
+                let (rest, message) = self.parser.parse(data, direction.into()).map_err(error)?;
+                let message = message
+                    .map(|message| convert::to_python(py, &message))
+                    .transpose()?;
+                Ok((PyBytes::new(py, rest), message))
+            }
+
+            /// Probes `data` to recognize whether it is of this protocol.
+            #[pyo3(signature = (data, direction = Direction::Unknown))]
+            fn probe(&self, data: &[u8], direction: Direction) -> Status {
+                self.parser.probe(data, direction.into()).into()
+            }
+        }
+    };
+}
+
+parser!(Diameter, sawp_diameter::Diameter);
+parser!(Dns, sawp_dns::Dns);
+parser!(Gre, sawp_gre::Gre);
+parser!(Ike, sawp_ike::Ike, unsendable);
+parser!(Json, sawp_json::Json);
+parser!(Modbus, sawp_modbus::Modbus);
+parser!(Pop3, sawp_pop3::POP3);
+parser!(Resp, sawp_resp::Resp);
+parser!(Tftp, sawp_tftp::TFTP);
+
+#[pymodule]
+#[pyo3(name = "sawp")]
+fn sawp_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("Error", py.get_type::<Error>())?;
+    m.add("IncompleteError", py.get_type::<IncompleteError>())?;
+    m.add_class::<Direction>()?;
+    m.add_class::<Status>()?;
+    m.add_class::<Diameter>()?;
+    m.add_class::<Dns>()?;
+    m.add_class::<Gre>()?;
+    m.add_class::<Ike>()?;
+    m.add_class::<Json>()?;
+    m.add_class::<Modbus>()?;
+    m.add_class::<Pop3>()?;
+    m.add_class::<Resp>()?;
+    m.add_class::<Tftp>()?;
+    m.add_class::<file::Reader>()?;
+    m.add_class::<file::Writer>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::ffi::c_str;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_module() {
+        let dir = tempfile::tempdir().unwrap();
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(sawp_python)(py);
+            py.import("sys")
+                .and_then(|sys| sys.getattr("modules"))
+                .and_then(|modules| modules.set_item("sawp", module))
+                .unwrap();
+            let locals = PyDict::new(py);
+            locals
+                .set_item("path", dir.path().join("calls.sawp"))
+                .unwrap();
+            py.run(
+                c_str!(
+                    r#"
+import sawp
+
+request = b"\x00\x01\x00\x00\x00\x06\x01\x01\x00\x02\x00\x03"
+parser = sawp.Modbus()
+assert sawp.Modbus.name == "modbus"
+
+rest, message = parser.parse(request + b"\x00", sawp.Direction.ToServer)
+assert rest == b"\x00"
+assert message["transaction_id"] == 1
+assert message["unit_id"] == 1
+assert message["function"]["code"] == "RdCoils"
+assert message["error_flags"] == 0
+
+assert parser.probe(request) == sawp.Status.Recognized
+assert parser.probe(request[:4]) == sawp.Status.Incomplete
+
+try:
+    parser.parse(request[:4])
+    assert False
+except sawp.IncompleteError as e:
+    assert e.args[1] == 2
+
+try:
+    sawp.Json().parse(b"}")
+    assert False
+except sawp.IncompleteError:
+    assert False
+except sawp.Error:
+    pass
+
+with sawp.Writer(path) as writer:
+    writer.parse(sawp.Direction.ToServer, request)
+    writer.gap(sawp.Direction.ToClient, 10)
+
+calls = list(sawp.Reader(path))
+assert calls == [
+    ("parse", sawp.Direction.ToServer, request),
+    ("gap", sawp.Direction.ToClient, 10),
+], calls
+"#
+                ),
+                None,
+                Some(&locals),
+            )
+            .unwrap();
+        });
+    }
+}
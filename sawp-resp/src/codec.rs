@@ -0,0 +1,114 @@
+use crate::{Entry, Message, Resp, CRLF};
+
+use sawp::codec::Encode;
+use sawp::error::{Error, ErrorKind, Result};
+
+use bytes::BytesMut;
+
+impl Encode<Message> for Resp {
+    /// Strings are written as bulk strings and errors as simple errors.
+    ///
+    /// `Entry::Invalid` and errors containing CR or LF can't be represented
+    /// and fail with `ErrorKind::InvalidData`.
+    fn encode(&self, message: &Message, dst: &mut BytesMut) -> Result<()> {
+        encode_entry(&message.entry, dst)
+    }
+}
+
+fn encode_entry(entry: &Entry, dst: &mut BytesMut) -> Result<()> {
+    match entry {
+        Entry::Array(entries) => {
+            dst.extend_from_slice(format!("*{}\r\n", entries.len()).as_bytes());
+            for entry in entries {
+                encode_entry(entry, dst)?;
+            }
+        }
+        Entry::Error(message) => {
+            if message.iter().any(|&c| c == b'\r' || c == b'\n') {
+                return Err(Error::new(ErrorKind::InvalidData));
+            }
+            dst.extend_from_slice(b"-");
+            dst.extend_from_slice(message);
+            dst.extend_from_slice(CRLF);
+        }
+        Entry::Integer(value) => dst.extend_from_slice(format!(":{}\r\n", value).as_bytes()),
+        Entry::Invalid(_) => return Err(Error::new(ErrorKind::InvalidData)),
+        Entry::Nil => dst.extend_from_slice(b"$-1\r\n"),
+        Entry::String(data) => {
+            dst.extend_from_slice(format!("${}\r\n", data.len()).as_bytes());
+            dst.extend_from_slice(data);
+            dst.extend_from_slice(CRLF);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ErrorFlags;
+    use rstest::rstest;
+    use sawp::codec::SawpCodec;
+    use sawp::parser::Direction;
+    use sawp_flags::Flag;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[rstest(
+        entry,
+        expected,
+        case::string(Entry::String(b"a\r\nb".to_vec()), b"$4\r\na\r\nb\r\n".as_ref()),
+        case::error(Entry::Error(b"ERR unknown".to_vec()), b"-ERR unknown\r\n".as_ref()),
+        case::integer(Entry::Integer(-5), b":-5\r\n".as_ref()),
+        case::nil(Entry::Nil, b"$-1\r\n".as_ref()),
+        case::array(
+            Entry::Array(vec![
+                Entry::String(b"GET".to_vec()),
+                Entry::Array(vec![]),
+                Entry::Integer(1),
+            ]),
+            b"*3\r\n$3\r\nGET\r\n*0\r\n:1\r\n".as_ref()
+        )
+    )]
+    fn test_encode(entry: Entry, expected: &[u8]) {
+        let message = Message {
+            entry,
+            error_flags: ErrorFlags::none(),
+        };
+        let mut buffer = BytesMut::new();
+        Resp::default().encode(&message, &mut buffer).unwrap();
+        assert_eq!(&buffer[..], expected);
+
+        let mut codec = SawpCodec::new(Resp::default(), Direction::Unknown);
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(message));
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_encoder() {
+        let mut codec = SawpCodec::new(Resp::default(), Direction::ToServer);
+        let mut buffer = BytesMut::new();
+        let message = Message {
+            entry: Entry::Array(vec![Entry::String(b"PING".to_vec())]),
+            error_flags: ErrorFlags::none(),
+        };
+        Encoder::encode(&mut codec, message, &mut buffer).unwrap();
+        assert_eq!(&buffer[..], b"*1\r\n$4\r\nPING\r\n");
+    }
+
+    #[rstest(
+        entry,
+        case::invalid(Entry::Invalid(b"$x\r\n".to_vec())),
+        case::error_crlf(Entry::Error(b"a\r\nb".to_vec())),
+        case::nested(Entry::Array(vec![Entry::Invalid(vec![])]))
+    )]
+    fn test_encode_unrepresentable(entry: Entry) {
+        let message = Message {
+            entry,
+            error_flags: ErrorFlags::none(),
+        };
+        assert_eq!(
+            Resp::default().encode(&message, &mut BytesMut::new()),
+            Err(Error::new(ErrorKind::InvalidData))
+        );
+    }
+}
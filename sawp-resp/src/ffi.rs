@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 use super::*;
 use sawp::error::Error;
+use sawp::metrics::{self, Recorder, Snapshot};
 use sawp::parser::Parse;
 use sawp_ffi::*;
 
@@ -13,10 +14,39 @@ pub struct ParseResult {
 
 #[no_mangle]
 pub unsafe extern "C" fn sawp_resp_create() -> *mut Resp {
-    let parser = Resp {};
+    let parser = Resp::default();
     parser.into_ffi_ptr()
 }
 
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_resp_set_limits(parser: *mut Resp, limits: Limits) {
+    let parser = deref_mut!(parser);
+    parser.limits = limits;
+}
+
+/// Metrics of the parsers called through the FFI.
+static RECORDER: Recorder = Recorder::new();
+
+/// Copies the metrics collected by the resp parsers into `snapshot`.
+///
+/// Returns false if nothing was recorded, for instance when the library was
+/// built without the `metrics` feature.
+/// # Safety
+/// function will panic if called with null
+#[no_mangle]
+pub unsafe extern "C" fn sawp_resp_metrics(snapshot: *mut Snapshot) -> bool {
+    nullcheck!(snapshot);
+    match metrics::get(Resp::name()) {
+        Some(metrics) => {
+            *snapshot = metrics;
+            true
+        }
+        None => false,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn sawp_resp_destroy(r: *mut Resp) {
     if !r.is_null() {
@@ -34,7 +64,9 @@ pub unsafe extern "C" fn sawp_resp_parse(
     length: usize,
 ) -> *mut ParseResult {
     let input = std::slice::from_raw_parts(data, length);
-    match (*parser).parse(input, direction) {
+    let result = (*parser).parse(input, direction);
+    RECORDER.record::<Resp>(input, &result);
+    match result {
         Ok((sl, message)) => ParseResult {
             message: message.into_ffi_ptr(),
             size_read: length - sl.len(),
@@ -15,7 +15,7 @@
 //! use sawp_resp::{Resp, Message};
 //!
 //! fn parse_bytes(input: &[u8]) -> std::result::Result<&[u8], Error> {
-//!     let resp = Resp {};
+//!     let resp = Resp::default();
 //!     let mut bytes = input;
 //!     while bytes.len() > 0 {
 //!         // If we know that this is a request or response, change the Direction
@@ -42,6 +42,8 @@
 //! ```
 
 use sawp::error::Result;
+use sawp::limits::{Limits, Tracker};
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::{Probe, Status};
 use sawp::protocol::Protocol;
@@ -50,7 +52,7 @@ use sawp_flags::{BitFlags, Flag, Flags};
 use nom::bytes::streaming::{take, take_until};
 use nom::character::streaming::crlf;
 use nom::number::streaming::be_u8;
-use nom::{AsBytes, FindToken, InputTakeAtPosition};
+use nom::{AsBytes, FindToken};
 
 use num_enum::TryFromPrimitive;
 
@@ -60,8 +62,14 @@ use std::convert::TryFrom;
 #[cfg(feature = "ffi")]
 mod ffi;
 
+/// Encoding of messages for `sawp::codec`
+#[cfg(feature = "codec")]
+mod codec;
+
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 pub const CRLF: &[u8] = b"\r\n";
 pub const DATA_TYPE_TOKENS: &str = "$*+-:";
@@ -82,6 +90,9 @@ pub enum ErrorFlags {
     /// An array of arrays with > MAX_ARRAY_DEPTH depth was found. Message will truncate
     /// at the limit but futher bytes WILL NOT be consumed.
     MaxArrayDepthReached = 0b0000_0100,
+    /// A resource limit was reached. Arrays are truncated at the limit and bulk strings
+    /// larger than the allocation limit are returned empty.
+    LimitExceeded = 0b0000_1000,
 }
 
 /// RESP signals data types by prepending these one-character tokens
@@ -117,6 +128,7 @@ impl DataTypeToken {
 /// Entry types to return in the parsed message
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_resp"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Entry {
     /// Arrays of entries
@@ -149,6 +161,7 @@ pub enum StringResult<'a> {
 /// Breakdown of the parsed resp bytes
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_resp"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     pub entry: Entry,
@@ -159,7 +172,22 @@ pub struct Message {
 impl Message {}
 
 #[derive(Debug)]
-pub struct Resp {}
+pub struct Resp {
+    /// Resource limits applied to every message. `max_depth` defaults to
+    /// [`MAX_ARRAY_DEPTH`].
+    pub limits: Limits,
+}
+
+impl Default for Resp {
+    fn default() -> Self {
+        Resp {
+            limits: Limits {
+                max_depth: MAX_ARRAY_DEPTH,
+                ..Limits::default()
+            },
+        }
+    }
+}
 
 impl<'a> Protocol<'a> for Resp {
     type Message = Message;
@@ -169,6 +197,12 @@ impl<'a> Protocol<'a> for Resp {
     }
 }
 
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
 impl<'a> Probe<'a> for Resp {
     /// Probes the input to recognize if the underlying bytes likely match this
     /// protocol.
@@ -200,7 +234,35 @@ impl Resp {
             .unwrap_or(input)
     }
 
-    fn parse_integer(input: &[u8]) -> Result<(&[u8], IntegerResult, Flags<ErrorFlags>)> {
+    /// Advances past the CRLF ending a bulk string. Other data is left as is,
+    /// but input which may still become a CRLF is incomplete.
+    fn advance_crlf(input: &[u8]) -> Result<&[u8]> {
+        match input.strip_prefix(CRLF) {
+            Some(rem) => Ok(rem),
+            None if CRLF.starts_with(input) => Err(sawp::error::Error::incomplete_needed(
+                CRLF.len() - input.len(),
+            )),
+            None => Ok(input),
+        }
+    }
+
+    /// Takes invalid data up to the next data type token or the end of the
+    /// line, whichever comes first.
+    fn take_invalid(input: &[u8]) -> Result<(&[u8], &[u8])> {
+        let token = input.iter().position(|&c| DATA_TYPE_TOKENS.find_token(c));
+        let line = input
+            .windows(CRLF.len())
+            .position(|window| window == CRLF)
+            .map(|position| position + CRLF.len());
+        let end = match (token, line) {
+            (Some(token), Some(line)) => token.min(line),
+            (Some(end), None) | (None, Some(end)) => end,
+            (None, None) => return Err(sawp::error::Error::incomplete()),
+        };
+        Ok((&input[end..], &input[..end]))
+    }
+
+    fn parse_integer(input: &[u8]) -> Result<(&[u8], IntegerResult<'_>, Flags<ErrorFlags>)> {
         let (rem, raw_len) = take_until(CRLF)(input)?;
         // We don't know how long ret is but it is supposed to be valid text.
         match std::str::from_utf8(raw_len) {
@@ -227,7 +289,10 @@ impl Resp {
     /// The TypeOr looks a bit complicated but essentially means that if there are no errors we just return the parsed string data TypeOr::Left.
     /// If there is an error with the integer calculation we'll try to return the <integer data, string data> TyperOr::Right.
     /// Indicates a nil entry return for the caller via the returned bool
-    fn parse_bulk_string(input: &[u8]) -> Result<(&[u8], StringResult, Flags<ErrorFlags>)> {
+    fn parse_bulk_string<'a>(
+        input: &'a [u8],
+        tracker: &Tracker,
+    ) -> Result<(&'a [u8], StringResult<'a>, Flags<ErrorFlags>)> {
         let (rem, wrapped_length, mut error_flags) = Resp::parse_integer(input)?;
         match wrapped_length {
             IntegerResult::Integer(length) => {
@@ -235,31 +300,33 @@ impl Resp {
                     if length > MAX_BULK_STRING_LEN as i64 {
                         error_flags |= ErrorFlags::BulkStringExceedsMaxLen
                     }
+                    if !tracker.alloc(length as usize) {
+                        // Skip the data we won't keep so it isn't parsed as the next entry
+                        error_flags |= ErrorFlags::LimitExceeded;
+                        let (rem, _) = take(length as usize)(rem)?;
+                        return Ok((
+                            Resp::advance_crlf(rem)?,
+                            StringResult::String(b""),
+                            error_flags,
+                        ));
+                    }
                     let (rem, ret) = take(length as usize)(rem)?;
                     // The standard states that even bulk strings should end with CRLF, but it may not be strictly necessary based on implementation?
                     Ok((
-                        Resp::advance_if_crlf(rem),
+                        Resp::advance_crlf(rem)?,
                         StringResult::String(ret),
                         error_flags,
                     ))
                 } else {
                     // Whether the result is the NULL string (-1 length) or some negative number, we can pass an "empty" result back with the inner error_flags and let the caller handle it.
                     if length == -1 {
-                        return Ok((Resp::advance_if_crlf(rem), StringResult::Nil, error_flags));
+                        return Ok((rem, StringResult::Nil, error_flags));
                     }
                     error_flags |= ErrorFlags::InvalidData;
-                    Ok((
-                        Resp::advance_if_crlf(rem),
-                        StringResult::String(b""),
-                        error_flags,
-                    ))
+                    Ok((rem, StringResult::String(b""), error_flags))
                 }
             }
-            IntegerResult::Data(bytes) => Ok((
-                Resp::advance_if_crlf(rem),
-                StringResult::Invalid(bytes, b""),
-                error_flags,
-            )),
+            IntegerResult::Data(bytes) => Ok((rem, StringResult::Invalid(bytes, b""), error_flags)),
         }
     }
 
@@ -269,12 +336,62 @@ impl Resp {
         Ok((Resp::advance_if_crlf(rem), ret))
     }
 
-    fn parse_entry(input: &[u8], array_depth: usize) -> Result<(&[u8], Entry, Flags<ErrorFlags>)> {
+    /// Skips the data following the token of an entry without keeping it,
+    /// including the elements of arrays.
+    fn skip_data(input: &[u8], token: DataTypeToken) -> Result<&[u8]> {
+        let (mut input, mut pending) = Resp::skip_value(input, token)?;
+        while pending > 0 {
+            let (rem, raw_token) = be_u8(input)?;
+            let (rem, elements) = Resp::skip_value(rem, DataTypeToken::from_raw(raw_token))?;
+            input = rem;
+            pending = (pending - 1).saturating_add(elements);
+        }
+        Ok(input)
+    }
+
+    /// Skips `count` entries without keeping them.
+    fn skip_entries(input: &[u8], count: i64) -> Result<&[u8]> {
+        let mut input = input;
+        for _ in 0..count {
+            let (rem, raw_token) = be_u8(input)?;
+            input = Resp::skip_data(rem, DataTypeToken::from_raw(raw_token))?;
+        }
+        Ok(input)
+    }
+
+    /// Skips the value following a token the same way [`Resp::parse_entry`]
+    /// parses it. Returns the number of array elements following it.
+    fn skip_value(input: &[u8], token: DataTypeToken) -> Result<(&[u8], u64)> {
+        match token {
+            DataTypeToken::BulkString => match Resp::parse_integer(input)? {
+                (rem, IntegerResult::Integer(length), _) if length >= 0 => {
+                    let (rem, _) = take(length as usize)(rem)?;
+                    Ok((Resp::advance_crlf(rem)?, 0))
+                }
+                (rem, _, _) => Ok((rem, 0)),
+            },
+            DataTypeToken::Array => match Resp::parse_integer(input)? {
+                (rem, IntegerResult::Integer(length), _) if length > 0 => Ok((rem, length as u64)),
+                (rem, _, _) => Ok((rem, 0)),
+            },
+            DataTypeToken::SimpleString | DataTypeToken::Error => {
+                Ok((Resp::parse_simple_string(input)?.0, 0))
+            }
+            DataTypeToken::Integer => Ok((Resp::parse_integer(input)?.0, 0)),
+            DataTypeToken::Unknown => Ok((Resp::take_invalid(input)?.0, 0)),
+        }
+    }
+
+    fn parse_entry<'a>(
+        input: &'a [u8],
+        array_depth: usize,
+        tracker: &Tracker,
+    ) -> Result<(&'a [u8], Entry, Flags<ErrorFlags>)> {
         let (input, raw_token) = be_u8(input)?;
         let token = DataTypeToken::from_raw(raw_token);
         match token {
             DataTypeToken::BulkString => {
-                let (rem, parsed_data, error_flags) = Resp::parse_bulk_string(input)?;
+                let (rem, parsed_data, error_flags) = Resp::parse_bulk_string(input, tracker)?;
                 match parsed_data {
                     StringResult::String(string_data) => {
                         Ok((rem, Entry::String(string_data.to_vec()), error_flags))
@@ -286,18 +403,26 @@ impl Resp {
                 }
             }
             DataTypeToken::Array => {
-                if array_depth < MAX_ARRAY_DEPTH {
+                if tracker.depth(array_depth + 1) {
                     let (mut local_input, length, mut error_flags) = Resp::parse_integer(input)?;
                     match length {
                         IntegerResult::Integer(length) if length >= 0 => {
-                            let mut entries: Vec<Entry> = Vec::with_capacity(length as usize);
+                            let mut entries: Vec<Entry> = Vec::with_capacity(
+                                (length as usize).min(tracker.remaining_elements()),
+                            );
 
-                            for _ in 0..length {
+                            for index in 0..length {
+                                if !tracker.element() {
+                                    error_flags |= ErrorFlags::LimitExceeded;
+                                    let rem = Resp::skip_entries(local_input, length - index)?;
+                                    return Ok((rem, Entry::Array(entries), error_flags));
+                                }
                                 let (rem, entry, inner_error_flags) =
-                                    Resp::parse_entry(local_input, array_depth + 1)?;
+                                    Resp::parse_entry(local_input, array_depth + 1, tracker)?;
                                 error_flags |= inner_error_flags;
-                                if error_flags.contains(ErrorFlags::MaxArrayDepthReached) {
-                                    return Ok((input, Entry::Array(entries), error_flags));
+                                if tracker.exceeded() {
+                                    let rem = Resp::skip_entries(rem, length - index - 1)?;
+                                    return Ok((rem, Entry::Array(entries), error_flags));
                                 }
                                 entries.push(entry);
                                 local_input = rem;
@@ -307,14 +432,10 @@ impl Resp {
                         IntegerResult::Integer(-1) => Ok((local_input, Entry::Nil, error_flags)),
                         IntegerResult::Integer(_length) => {
                             error_flags |= ErrorFlags::InvalidData;
-                            Ok((
-                                Resp::advance_if_crlf(local_input),
-                                Entry::Array(vec![]),
-                                error_flags,
-                            ))
+                            Ok((local_input, Entry::Array(vec![]), error_flags))
                         }
                         IntegerResult::Data(invalid_length) => Ok((
-                            Resp::advance_if_crlf(local_input),
+                            local_input,
                             Entry::Invalid(
                                 [b"*", invalid_length].concat(), // include the token character in the returned value.
                             ),
@@ -323,9 +444,9 @@ impl Resp {
                     }
                 } else {
                     Ok((
-                        input,
+                        Resp::skip_data(input, token)?,
                         Entry::Invalid(vec![]),
-                        ErrorFlags::MaxArrayDepthReached.into(),
+                        ErrorFlags::MaxArrayDepthReached | ErrorFlags::LimitExceeded,
                     ))
                 }
             }
@@ -353,12 +474,11 @@ impl Resp {
                 }
             }
             DataTypeToken::Unknown => {
-                // Advance to the next possible data type token, returning the "in-between" as InvalidData
+                // Advance to the next possible data type token or line, returning the "in-between" as InvalidData
                 // Note we should include the first character in the returned value.
-                let (rem, data) =
-                    input.split_at_position_complete(|e: u8| DATA_TYPE_TOKENS.find_token(e))?;
+                let (rem, data) = Resp::take_invalid(input)?;
                 Ok((
-                    Resp::advance_if_crlf(rem),
+                    rem,
                     Entry::Invalid([&[raw_token], data].concat()),
                     ErrorFlags::InvalidData.into(),
                 ))
@@ -376,7 +496,7 @@ impl<'a> Parse<'a> for Resp {
         input: &'a [u8],
         _direction: Direction,
     ) -> Result<(&'a [u8], Option<Self::Message>)> {
-        let (rem, entry, error_flags) = Resp::parse_entry(input, 0)?;
+        let (rem, entry, error_flags) = Resp::parse_entry(input, 0, &self.limits.tracker())?;
 
         Ok((rem, Some(Message { entry, error_flags })))
     }
@@ -387,7 +507,9 @@ mod test {
     use crate::{Entry, ErrorFlags, Message, Resp};
     use rstest::rstest;
     use sawp::error::Result;
+    use sawp::limits::Limits;
     use sawp::parser::{Direction, Parse};
+    use sawp::testing::assert_incremental;
     use sawp_flags::Flag;
 
     #[rstest(
@@ -516,16 +638,16 @@ mod test {
     *1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n\
     *1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n\
     *1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n\
-    *1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n", // array depth 65
+    *1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n*1\r\n:1\r\n", // array depth 65
     Ok((
-        268,
+        0,
         Some(
             Message {
                 entry:
                 Entry::Array(vec![
                 Entry::String(b"foo".to_vec()),
                 ]),
-                error_flags: ErrorFlags::MaxArrayDepthReached.into(),
+                error_flags: ErrorFlags::MaxArrayDepthReached | ErrorFlags::LimitExceeded,
             }
         )
     ))
@@ -644,11 +766,85 @@ mod test {
     ),
     )]
     fn resp(input: &[u8], expected: Result<(usize, Option<Message>)>) {
-        let resp = Resp {};
+        let resp = Resp::default();
+        assert_eq!(
+            resp.parse(input, Direction::Unknown)
+                .map(|(rem, msg)| (rem.len(), msg)),
+            expected
+        );
+        assert_incremental(Resp::default, input, Direction::Unknown);
+    }
+
+    #[rstest(
+        input,
+        limits,
+        expected,
+        case::max_elements(
+            b"*3\r\n:1\r\n:2\r\n:3\r\n",
+            Limits {
+                max_elements: 2,
+                ..Limits::default()
+            },
+            Ok((
+                0,
+                Some(Message {
+                    entry: Entry::Array(vec![Entry::Integer(1), Entry::Integer(2)]),
+                    error_flags: ErrorFlags::LimitExceeded.into(),
+                })
+            ))
+        ),
+        case::max_depth(
+            b"*1\r\n*1\r\n:1\r\n",
+            Limits {
+                max_depth: 1,
+                ..Limits::default()
+            },
+            Ok((
+                0,
+                Some(Message {
+                    entry: Entry::Array(vec![]),
+                    error_flags: ErrorFlags::MaxArrayDepthReached | ErrorFlags::LimitExceeded,
+                })
+            ))
+        ),
+        case::max_alloc(
+            // The payload isn't parsed as a command
+            b"$18\r\n*1\r\n$8\r\nFLUSHALL\r\n\r\n",
+            Limits {
+                max_alloc: 10,
+                ..Limits::default()
+            },
+            Ok((
+                0,
+                Some(Message {
+                    entry: Entry::String(vec![]),
+                    error_flags: ErrorFlags::LimitExceeded.into(),
+                })
+            ))
+        ),
+        case::max_elements_skips_payload(
+            b"*2\r\n$3\r\nfoo\r\n$18\r\n*1\r\n$8\r\nFLUSHALL\r\n\r\n",
+            Limits {
+                max_elements: 1,
+                ..Limits::default()
+            },
+            Ok((
+                0,
+                Some(Message {
+                    entry: Entry::Array(vec![Entry::String(b"foo".to_vec())]),
+                    error_flags: ErrorFlags::LimitExceeded.into(),
+                })
+            ))
+        )
+    )]
+    fn resp_limits(input: &[u8], limits: Limits, expected: Result<(usize, Option<Message>)>) {
+        let resp = Resp { limits };
         assert_eq!(
             resp.parse(input, Direction::Unknown)
                 .map(|(rem, msg)| (rem.len(), msg)),
             expected
         );
+        let stream = assert_incremental(|| Resp { limits }, input, Direction::Unknown);
+        assert_eq!(stream.messages.len(), 1);
     }
 }
@@ -3,6 +3,10 @@
 //! inform the caller about what went wrong if no message is returned (see [`sawp::parser::Parse`]
 //! for details on possible return types). TFTP ignores Direction.
 //!
+//! TFTP runs over UDP and its packets don't carry their length, so the input
+//! must be a single datagram. `Data` packets take the rest of the input as
+//! their data, including any bytes of a following packet.
+//!
 //! The following protocol references were used to create this module:
 //!
 //! [TFTP Protocol (Revision 2)](https://tools.ietf.org/html/rfc1350)
@@ -15,7 +19,7 @@
 //! use sawp_tftp::{TFTP, Message};
 //!
 //! fn parse_bytes(input: &[u8]) -> std::result::Result<&[u8], Error> {
-//!     let parser = TFTP {};
+//!     let parser = TFTP::default();
 //!     let mut bytes = input;
 //!     while bytes.len() > 0 {
 //!         match parser.parse(bytes, Direction::Unknown) {
@@ -42,9 +46,12 @@
 #![allow(clippy::unneeded_field_pattern)]
 
 use sawp::error::{NomError, Result};
+use sawp::limits::{Limits, Tracker};
+use sawp::metrics::Flagged;
 use sawp::parser::{Direction, Parse};
 use sawp::probe::Probe;
 use sawp::protocol::Protocol;
+use sawp_flags::{BitFlags, Flag, Flags};
 
 use num_enum::TryFromPrimitive;
 use std::convert::TryFrom;
@@ -61,9 +68,12 @@ mod ffi;
 
 #[cfg(feature = "ffi")]
 use sawp_ffi::GenerateFFI;
+#[cfg(feature = "visit")]
+use sawp_visit::Visit;
 
 /// The TFTP header of a packet contains the  opcode  associated  with
 /// that packet. TFTP supports five types of packets
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum OpCode {
@@ -76,6 +86,7 @@ pub enum OpCode {
 }
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_tftp"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Mode {
     NetASCII,
@@ -85,6 +96,7 @@ pub enum Mode {
 }
 
 ///  The error code is an integer indicating the nature of the error.
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive)]
 #[repr(u16)]
 pub enum ErrorCode {
@@ -102,6 +114,7 @@ pub enum ErrorCode {
 
 #[cfg_attr(feature = "ffi", derive(GenerateFFI))]
 #[cfg_attr(feature = "ffi", sawp_ffi(prefix = "sawp_tftp"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct OptionExtension {
     pub name: String,
@@ -110,6 +123,7 @@ pub struct OptionExtension {
 
 /// Represents the various types of TFTP Packets
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_tftp"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Packet {
     ReadWriteRequest {
@@ -119,6 +133,7 @@ pub enum Packet {
     },
     Data {
         block_number: u16,
+        /// Rest of the datagram after the block number
         data: Vec<u8>,
     },
     Ack(u16),
@@ -130,17 +145,33 @@ pub enum Packet {
     OptAck(Vec<OptionExtension>),
 }
 
+/// Error flags raised while parsing TFTP - to be used in the returned Message
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BitFlags)]
+pub enum ErrorFlags {
+    /// A resource limit was reached. Options past the limit are dropped and
+    /// data larger than the allocation limit is not copied.
+    LimitExceeded = 0b0000_0001,
+}
+
 /// Breakdown of the parsed TFTP bytes
 #[cfg_attr(feature = "ffi", derive(GenerateFFI), sawp_ffi(prefix = "sawp_tftp"))]
+#[cfg_attr(feature = "visit", derive(Visit))]
 #[derive(Debug, PartialEq, Eq)]
 pub struct Message {
     #[cfg_attr(feature = "ffi", sawp_ffi(copy))]
     pub op_code: OpCode,
     pub packet: Packet,
+    #[cfg_attr(feature = "ffi", sawp_ffi(flag = "u8"))]
+    pub error_flags: Flags<ErrorFlags>,
 }
 
-#[derive(Debug)]
-pub struct TFTP {}
+#[derive(Debug, Default)]
+pub struct TFTP {
+    /// Resource limits applied to every message. `max_elements` bounds the
+    /// number of options.
+    pub limits: Limits,
+}
 
 impl<'a> Probe<'a> for TFTP {}
 
@@ -152,10 +183,23 @@ impl Protocol<'_> for TFTP {
     }
 }
 
-fn parse_options(input: &'_ [u8]) -> Result<(&'_ [u8], Vec<OptionExtension>)> {
+impl Flagged for Message {
+    fn error_flags(&self) -> u64 {
+        self.error_flags.bits().into()
+    }
+}
+
+fn parse_options<'a>(
+    input: &'a [u8],
+    tracker: &Tracker,
+) -> Result<(&'a [u8], Vec<OptionExtension>)> {
     let mut bytes = input;
     let mut options: Vec<OptionExtension> = Vec::new();
     while !bytes.is_empty() {
+        // Options past a limit are dropped along with the rest of the datagram
+        if !tracker.element() {
+            return Ok((&[], options));
+        }
         let (rest, name) = map_res(
             terminated(take_while(|c| c != 0), tag(&[0])),
             std::str::from_utf8,
@@ -164,6 +208,9 @@ fn parse_options(input: &'_ [u8]) -> Result<(&'_ [u8], Vec<OptionExtension>)> {
             terminated(take_while(|c| c != 0), tag(&[0])),
             std::str::from_utf8,
         )(rest)?;
+        if !tracker.alloc(name.len() + value.len()) {
+            return Ok((&[], options));
+        }
         options.push(OptionExtension {
             name: name.into(),
             value: value.into(),
@@ -181,6 +228,7 @@ impl<'a> Parse<'a> for TFTP {
         _direction: Direction,
     ) -> Result<(&'a [u8], Option<Self::Message>)> {
         let (input, op_code) = be_u16(input)?;
+        let tracker = self.limits.tracker();
         if let Ok(op_code) = OpCode::try_from(op_code) {
             let (input, packet) = match op_code {
                 OpCode::ReadRequest | OpCode::WriteRequest => {
@@ -199,7 +247,7 @@ impl<'a> Parse<'a> for TFTP {
                         _ => Mode::Unknown(mode.into()),
                     };
 
-                    let (input, options) = match parse_options(input) {
+                    let (input, options) = match parse_options(input, &tracker) {
                         Ok((input, options)) => (input, options),
                         _ => (input, Vec::new()),
                     };
@@ -215,13 +263,12 @@ impl<'a> Parse<'a> for TFTP {
                 }
                 OpCode::Data => {
                     let (input, block_number) = be_u16(input)?;
-                    (
-                        &[] as &[u8],
-                        Packet::Data {
-                            block_number,
-                            data: input.into(),
-                        },
-                    )
+                    let data = if tracker.alloc(input.len()) {
+                        input.into()
+                    } else {
+                        Vec::new()
+                    };
+                    (&[] as &[u8], Packet::Data { block_number, data })
                 }
                 OpCode::Acknowledgement => {
                     let (input, block_number) = be_u16(input)?;
@@ -244,12 +291,24 @@ impl<'a> Parse<'a> for TFTP {
                         },
                     )
                 }
-                OpCode::OptionAcknowledgement => match parse_options(input) {
+                OpCode::OptionAcknowledgement => match parse_options(input, &tracker) {
                     Ok((input, options)) => (input, Packet::OptAck(options)),
                     _ => (input, Packet::OptAck(Vec::new())),
                 },
             };
-            Ok((input, Some(Message { op_code, packet })))
+            let error_flags = if tracker.exceeded() {
+                ErrorFlags::LimitExceeded.into()
+            } else {
+                ErrorFlags::none()
+            };
+            Ok((
+                input,
+                Some(Message {
+                    op_code,
+                    packet,
+                    error_flags,
+                }),
+            ))
         } else {
             Err(NomError::new(input, ErrorKind::IsA).into())
         }
@@ -262,6 +321,7 @@ mod tests {
     use rstest::rstest;
     use sawp::error;
     use sawp::probe::Status;
+    use sawp::testing::assert_datagram;
 
     #[test]
     fn test_name() {
@@ -291,6 +351,7 @@ mod tests {
                         mode: Mode::NetASCII,
                         options: vec![],
                     },
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::opt_read(
             &[
@@ -320,6 +381,7 @@ mod tests {
                             }
                         ],
                     },
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::write(
             &[
@@ -339,6 +401,7 @@ mod tests {
                         mode: Mode::Octet,
                         options: vec![],
                     },
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::opt_write(
             &[
@@ -376,6 +439,7 @@ mod tests {
                             }
                         ],
                     },
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::unknown_mode(
             &[
@@ -395,6 +459,7 @@ mod tests {
                         mode: Mode::Unknown("StRaNgEr".into()),
                         options: vec![],
                     },
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::no_null(
             &[
@@ -424,6 +489,28 @@ mod tests {
                         block_number: 12,
                         data: vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
                     },
+                    error_flags: ErrorFlags::none(),
+                })))),
+        case::data_trailing(
+            &[
+                // OpCode: 3 (Data)
+                0x00, 0x03,
+                // Data
+                // Block Number: 1
+                0x00, 0x01,
+                // Data
+                0x61,
+                // Next packet, taken as data: Ack of block 1
+                0x00, 0x04, 0x00, 0x01,
+            ],
+            Ok((&[] as &[u8],
+                Some(Message {
+                    op_code: OpCode::Data,
+                    packet: Packet::Data {
+                        block_number: 1,
+                        data: vec![0x61, 0x00, 0x04, 0x00, 0x01],
+                    },
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::ack(
             &[
@@ -436,6 +523,7 @@ mod tests {
                 Some(Message {
                    op_code: OpCode::Acknowledgement,
                    packet: Packet::Ack(16),
+                   error_flags: ErrorFlags::none(),
                 })))),
         case::opt_ack(
             &[
@@ -455,7 +543,8 @@ mod tests {
                             name: String::from("tsize"),
                             value: String::from("0"),
                         }],
-                    )
+                    ),
+                    error_flags: ErrorFlags::none(),
                 })))),
         case::error(
             &[
@@ -475,11 +564,70 @@ mod tests {
                         code: ErrorCode::DiskFull,
                         message: String::from("Disk full"),
                     },
+                    error_flags: ErrorFlags::none(),
                 })))),
     )]
     fn test_parse(input: &[u8], expected: Result<(&[u8], Option<Message>)>) {
-        let tftp = TFTP {};
+        let tftp = TFTP::default();
+        assert_eq!(tftp.parse(input, Direction::Unknown), expected);
+        // Each datagram is a message, `Data` ends with the datagram
+        assert_datagram(&tftp, input, Direction::Unknown);
+    }
+
+    #[rstest(
+        input,
+        limits,
+        expected,
+        case::max_elements(
+            &[
+                // OpCode: 6 (OptionAcknowledgement)
+                0x00, 0x06,
+                // Options
+                // Option name: tsize
+                0x74, 0x73, 0x69, 0x7a, 0x65, 0x00,
+                // Option value: 0
+                0x30, 0x00,
+                // Option name: tsize
+                0x74, 0x73, 0x69, 0x7a, 0x65, 0x00,
+                // Option value: 1
+                0x31, 0x00,
+            ],
+            Limits { max_elements: 1, ..Limits::default() },
+            Ok((&[] as &[u8],
+                Some(Message {
+                    op_code: OpCode::OptionAcknowledgement,
+                    packet: Packet::OptAck(
+                        vec![OptionExtension {
+                            name: String::from("tsize"),
+                            value: String::from("0"),
+                        }],
+                    ),
+                    error_flags: ErrorFlags::LimitExceeded.into(),
+                })))),
+        case::max_alloc(
+            &[
+                // OpCode: 3 (Data)
+                0x00, 0x03,
+                // Block Number: 12
+                0x00, 0x0c,
+                // Data
+                0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08
+            ],
+            Limits { max_alloc: 4, ..Limits::default() },
+            Ok((&[] as &[u8],
+                Some(Message {
+                    op_code: OpCode::Data,
+                    packet: Packet::Data {
+                        block_number: 12,
+                        data: vec![],
+                    },
+                    error_flags: ErrorFlags::LimitExceeded.into(),
+                })))),
+    )]
+    fn test_parse_limits(input: &[u8], limits: Limits, expected: Result<(&[u8], Option<Message>)>) {
+        let tftp = TFTP { limits };
         assert_eq!(tftp.parse(input, Direction::Unknown), expected);
+        assert_datagram(&tftp, input, Direction::Unknown);
     }
 
     #[rstest(
@@ -501,7 +649,7 @@ mod tests {
         ),
     )]
     fn test_probe(input: &[u8], expected: Status) {
-        let tftp = TFTP {};
+        let tftp = TFTP::default();
 
         assert_eq!(tftp.probe(input, Direction::Unknown), expected);
     }
@@ -0,0 +1,153 @@
+//! Derive macro for `sawp_visit::Visit`.
+//!
+//! - Named fields are visited with their name, without any `r#` prefix.
+//! - Newtypes and enum variants with a single unnamed field are transparent.
+//! - Other unnamed fields are visited with their index as name.
+//! - Unit enum variants are visited as `Value::Variant`.
+//!
+//! Fields accept the following attributes:
+//! - `#[visit(skip)]` to leave the field out of the tree.
+//! - `#[visit(rename = "name")]` to visit the field under another name.
+//! - `#[visit(flatten)]` to visit the content of the field in place of the
+//!   field itself.
+
+extern crate proc_macro;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// Derive macro implementing `sawp_visit::Visit`.
+#[proc_macro_derive(Visit, attributes(visit))]
+pub fn derive_visit(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    impl_visit(&ast).into()
+}
+
+fn impl_visit(ast: &syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let mut generics = ast.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(::sawp_visit::Visit));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let body = match &ast.data {
+        syn::Data::Struct(data) => {
+            let (pattern, visits) = gen_fields(&data.fields);
+            quote! {
+                let Self #pattern = self;
+                #visits
+            }
+        }
+        syn::Data::Enum(data) => {
+            let arms = data.variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                if let syn::Fields::Unit = variant.fields {
+                    let variant_name = ident.to_string();
+                    quote! {
+                        Self::#ident => visitor.value(::sawp_visit::Value::Variant(#variant_name)),
+                    }
+                } else {
+                    let (pattern, visits) = gen_fields(&variant.fields);
+                    quote! {
+                        Self::#ident #pattern => { #visits }
+                    }
+                }
+            });
+            quote! {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+        syn::Data::Union(_) => panic!("syn::Data::Union not supported"),
+    };
+
+    quote! {
+        impl #impl_generics ::sawp_visit::Visit for #name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn visit<'visit>(
+                &'visit self,
+                visitor: &mut dyn ::sawp_visit::Visitor<'visit>,
+            ) {
+                #body
+            }
+        }
+    }
+}
+
+/// Generates the destructuring pattern binding `fields` and the visitor calls
+/// for each of them.
+fn gen_fields(fields: &syn::Fields) -> (TokenStream, TokenStream) {
+    let bindings: Vec<syn::Ident> = (0..fields.len())
+        .map(|index| format_ident!("field_{}", index))
+        .collect();
+    let pattern = match fields {
+        syn::Fields::Named(named) => {
+            let idents = named.named.iter().map(|field| &field.ident);
+            quote! { { #(#idents: #bindings),* } }
+        }
+        syn::Fields::Unnamed(_) => quote! { ( #(#bindings),* ) },
+        syn::Fields::Unit => quote! {},
+    };
+
+    let visits: Vec<TokenStream> = fields
+        .iter()
+        .zip(&bindings)
+        .enumerate()
+        .filter_map(|(index, (field, binding))| {
+            let attrs = VisitAttrs::parse(&field.attrs);
+            if attrs.skip {
+                return None;
+            }
+            if attrs.flatten || (fields.len() == 1 && field.ident.is_none()) {
+                return Some(quote! { ::sawp_visit::Visit::visit(#binding, visitor); });
+            }
+            let name = attrs.rename.unwrap_or_else(|| match &field.ident {
+                Some(ident) => ident.to_string().trim_start_matches("r#").to_string(),
+                None => index.to_string(),
+            });
+            Some(quote! { visitor.field(#name, #binding); })
+        })
+        .collect();
+
+    (pattern, quote! { #(#visits)* })
+}
+
+#[derive(Default)]
+struct VisitAttrs {
+    skip: bool,
+    flatten: bool,
+    rename: Option<String>,
+}
+
+impl VisitAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> Self {
+        let mut result = VisitAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path.is_ident("visit")) {
+            let metas = match attr.parse_meta() {
+                Ok(syn::Meta::List(list)) => list.nested,
+                _ => panic!("expected #[visit(...)]"),
+            };
+            for meta in metas {
+                match meta {
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("skip") => {
+                        result.skip = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("flatten") => {
+                        result.flatten = true;
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::NameValue(value))
+                        if value.path.is_ident("rename") =>
+                    {
+                        match value.lit {
+                            syn::Lit::Str(name) => result.rename = Some(name.value()),
+                            _ => panic!("visit(rename) expects string literal"),
+                        }
+                    }
+                    _ => panic!("unknown visit attribute"),
+                }
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,406 @@
+//! Generic field visitor for SAWP messages.
+//!
+//! Every protocol crate represents its messages with its own concrete types.
+//! This crate provides a uniform view over them: deriving [`Visit`] on a type
+//! exposes its content as a tree of named fields, sequences and leaf
+//! [`Value`]s which can be walked with a [`Visitor`] or queried with a dotted
+//! [`Path`] such as `queries[*].name`.
+//!
+//! The tree is built as follows:
+//! - struct fields are named fields, `r#` prefixes are removed.
+//! - enums are transparent: a variant with fields exposes the fields of its
+//!   content directly, a unit variant is a [`Value::Variant`] leaf.
+//! - newtypes are transparent.
+//! - `Vec` and slices are sequences, except for `u8` which are [`Value::Bytes`].
+//! - `Option` is absent when `None`.
+//! - numbers, strings, booleans, IP addresses and `sawp_flags::Flags` are
+//!   leaves. Flags can be tested by name with [`FlagBits::contains`].
+//!
+//! # Example
+//! ```
+//! use sawp_visit::{Path, Value, Visit};
+//!
+//! #[derive(Visit)]
+//! struct Question {
+//!     name: Vec<u8>,
+//!     #[visit(rename = "type")]
+//!     record_type: u16,
+//! }
+//!
+//! #[derive(Visit)]
+//! struct Message {
+//!     id: u16,
+//!     queries: Vec<Question>,
+//! }
+//!
+//! let message = Message {
+//!     id: 1,
+//!     queries: vec![
+//!         Question { name: b"example.com".to_vec(), record_type: 1 },
+//!         Question { name: b"example.org".to_vec(), record_type: 28 },
+//!     ],
+//! };
+//!
+//! let path: Path = "queries[*].name".parse().unwrap();
+//! assert_eq!(
+//!     path.query(&message),
+//!     vec![Value::Bytes(b"example.com"), Value::Bytes(b"example.org")]
+//! );
+//! assert_eq!(
+//!     sawp_visit::query(&message, "queries[1].type").unwrap(),
+//!     vec![Value::Uint(28)]
+//! );
+//! ```
+
+/// Derive macro implementing [`Visit`].
+///
+/// Fields accept the `#[visit(skip)]`, `#[visit(flatten)]` and
+/// `#[visit(rename = "name")]` attributes.
+pub use sawp_visit_derive::Visit;
+
+// Allows the derive macro to be used within this crate.
+extern crate self as sawp_visit;
+
+pub mod path;
+pub use path::{fields, query, Error, Path, Segment};
+
+use sawp_flags::{Flag, Flags};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// A leaf of the field tree.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value<'a> {
+    Bool(bool),
+    Uint(u64),
+    Int(i64),
+    Float(f64),
+    Str(&'a str),
+    Bytes(&'a [u8]),
+    Ip(IpAddr),
+    /// Name of a unit enum variant
+    Variant(&'static str),
+    Flags(FlagBits),
+}
+
+/// Value of a `sawp_flags::Flags`, which can resolve the names of its flags.
+#[derive(Clone, Copy)]
+pub struct FlagBits {
+    bits: u64,
+    lookup: fn(&str) -> Option<u64>,
+}
+
+impl FlagBits {
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Returns the bits of the flag called `name`, case insensitive.
+    pub fn flag(&self, name: &str) -> Option<u64> {
+        (self.lookup)(name)
+    }
+
+    /// Whether the flag called `name` is set. Unknown names are never set.
+    pub fn contains(&self, name: &str) -> bool {
+        matches!(self.flag(name), Some(flag) if flag != 0 && self.bits & flag == flag)
+    }
+}
+
+impl PartialEq for FlagBits {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl fmt::Debug for FlagBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FlagBits({:#b})", self.bits)
+    }
+}
+
+fn lookup_flag<E>(name: &str) -> Option<u64>
+where
+    E: Flag + FromStr,
+    E::Primitive: Into<u64>,
+{
+    E::from_str(name).ok().map(|flag| flag.bits().into())
+}
+
+/// Receives the content of a type implementing [`Visit`].
+pub trait Visitor<'a> {
+    /// A leaf value.
+    fn value(&mut self, value: Value<'a>);
+
+    /// A named field of a struct or enum variant.
+    fn field(&mut self, name: &'a str, value: &'a dyn Visit);
+
+    /// An element of a sequence.
+    fn element(&mut self, index: usize, value: &'a dyn Visit);
+
+    /// Start of a sequence of `len` elements, called before its elements.
+    fn sequence(&mut self, _len: usize) {}
+}
+
+/// Types which can be walked as a field tree.
+pub trait Visit {
+    /// Calls `visitor` for each field, element or value of `self`.
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>);
+
+    /// Visits a slice of `Self`. Used to visit byte slices as a single value.
+    #[doc(hidden)]
+    fn visit_slice<'a>(slice: &'a [Self], visitor: &mut dyn Visitor<'a>)
+    where
+        Self: Sized,
+    {
+        visitor.sequence(slice.len());
+        for (index, element) in slice.iter().enumerate() {
+            visitor.element(index, element);
+        }
+    }
+}
+
+macro_rules! impl_visit {
+    ($variant:ident, $into:ty, $($ty:ty),*) => {
+        $(
+        impl Visit for $ty {
+            fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+                visitor.value(Value::$variant(*self as $into));
+            }
+        }
+        )*
+    };
+}
+
+impl_visit!(Uint, u64, u16, u32, u64, usize);
+impl_visit!(Int, i64, i8, i16, i32, i64, isize);
+impl_visit!(Float, f64, f32, f64);
+
+impl Visit for u8 {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Uint(u64::from(*self)));
+    }
+
+    fn visit_slice<'a>(slice: &'a [Self], visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Bytes(slice));
+    }
+}
+
+impl Visit for bool {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Bool(*self));
+    }
+}
+
+impl Visit for str {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Str(self));
+    }
+}
+
+impl Visit for String {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Str(self));
+    }
+}
+
+impl Visit for IpAddr {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Ip(*self));
+    }
+}
+
+impl Visit for Ipv4Addr {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Ip(IpAddr::V4(*self)));
+    }
+}
+
+impl Visit for Ipv6Addr {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        visitor.value(Value::Ip(IpAddr::V6(*self)));
+    }
+}
+
+impl<T: Visit> Visit for [T] {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        T::visit_slice(self, visitor);
+    }
+}
+
+impl<T: Visit> Visit for Vec<T> {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        T::visit_slice(self, visitor);
+    }
+}
+
+impl<T: Visit> Visit for Option<T> {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        if let Some(value) = self {
+            value.visit(visitor);
+        }
+    }
+}
+
+impl<T: Visit + ?Sized> Visit for Box<T> {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        (**self).visit(visitor);
+    }
+}
+
+impl<T: Visit + ?Sized> Visit for &T {
+    fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+        (**self).visit(visitor);
+    }
+}
+
+macro_rules! impl_visit_flags {
+    ($($ty:ty),*) => {
+        $(
+        impl<E: Flag<Primitive = $ty> + FromStr> Visit for Flags<E, $ty> {
+            fn visit<'a>(&'a self, visitor: &mut dyn Visitor<'a>) {
+                visitor.value(Value::Flags(FlagBits {
+                    bits: u64::from(self.bits()),
+                    lookup: lookup_flag::<E>,
+                }));
+            }
+        }
+        )*
+    };
+}
+
+impl_visit_flags!(u8, u16, u32, u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sawp_flags::BitFlags;
+
+    #[derive(Debug, Clone, Copy, PartialEq, BitFlags)]
+    #[repr(u8)]
+    enum TestFlags {
+        A = 0b01,
+        B = 0b10,
+    }
+
+    #[derive(Visit)]
+    enum Kind {
+        Empty,
+        Number(u32),
+        Pair(u8, i8),
+        Named { r#type: String },
+    }
+
+    #[derive(Visit)]
+    struct Inner {
+        kind: Kind,
+        address: Option<Ipv4Addr>,
+    }
+
+    #[derive(Visit)]
+    struct Outer {
+        flags: Flags<TestFlags>,
+        inner: Vec<Inner>,
+        #[visit(flatten)]
+        last: Kind,
+        #[visit(skip)]
+        #[allow(dead_code)]
+        hidden: bool,
+    }
+
+    fn outer() -> Outer {
+        Outer {
+            flags: TestFlags::A | TestFlags::B,
+            inner: vec![
+                Inner {
+                    kind: Kind::Empty,
+                    address: Some(Ipv4Addr::LOCALHOST),
+                },
+                Inner {
+                    kind: Kind::Number(7),
+                    address: None,
+                },
+                Inner {
+                    kind: Kind::Pair(1, -1),
+                    address: None,
+                },
+                Inner {
+                    kind: Kind::Named {
+                        r#type: "name".to_string(),
+                    },
+                    address: None,
+                },
+            ],
+            last: Kind::Number(8),
+            hidden: true,
+        }
+    }
+
+    #[test]
+    fn test_flags() {
+        let outer = outer();
+        match query(&outer, "flags").unwrap()[..] {
+            [Value::Flags(flags)] => {
+                assert_eq!(flags.bits(), 3);
+                assert_eq!(flags.flag("b"), Some(2));
+                assert!(flags.contains("A"));
+                assert!(!flags.contains("C"));
+            }
+            ref other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        let outer = outer();
+        assert_eq!(
+            fields(&outer),
+            vec![
+                (
+                    "flags".to_string(),
+                    Value::Flags(FlagBits {
+                        bits: 3,
+                        lookup: lookup_flag::<TestFlags>,
+                    })
+                ),
+                ("inner[0].kind".to_string(), Value::Variant("Empty")),
+                (
+                    "inner[0].address".to_string(),
+                    Value::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST))
+                ),
+                ("inner[1].kind".to_string(), Value::Uint(7)),
+                ("inner[2].kind.0".to_string(), Value::Uint(1)),
+                ("inner[2].kind.1".to_string(), Value::Int(-1)),
+                ("inner[3].kind.type".to_string(), Value::Str("name")),
+                ("".to_string(), Value::Uint(8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequence() {
+        #[derive(Default)]
+        struct Sequences(Vec<usize>);
+
+        impl<'a> Visitor<'a> for Sequences {
+            fn value(&mut self, _value: Value<'a>) {}
+
+            fn field(&mut self, _name: &'a str, value: &'a dyn Visit) {
+                value.visit(self);
+            }
+
+            fn element(&mut self, _index: usize, value: &'a dyn Visit) {
+                value.visit(self);
+            }
+
+            fn sequence(&mut self, len: usize) {
+                self.0.push(len);
+            }
+        }
+
+        let mut visitor = Sequences::default();
+        vec![vec![1u16, 2], vec![]].visit(&mut visitor);
+        b"bytes".to_vec().visit(&mut visitor);
+        assert_eq!(visitor.0, vec![2, 2, 0]);
+    }
+}
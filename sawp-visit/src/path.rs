@@ -0,0 +1,316 @@
+//! Dotted paths used to query a field tree.
+//!
+//! A path is a list of field names separated by `.`, each optionally followed
+//! by one or more indexes: `[n]` selects the n-th element of a sequence and
+//! `[*]` selects all of them. Sequences which aren't indexed are flattened, so
+//! `queries.name` is equivalent to `queries[*].name`. A path ending on a struct
+//! selects all the values it contains.
+
+use crate::{Value, Visit, Visitor};
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Errors encountered while parsing a [`Path`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A field name is empty, e.g. `a..b`
+    EmptyField(usize),
+    /// An index is not a number or `*`, or a bracket isn't closed
+    InvalidIndex(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyField(position) => write!(f, "empty field name at {}", position),
+            Error::InvalidIndex(position) => write!(f, "invalid index at {}", position),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A single step of a [`Path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Named field
+    Field(String),
+    /// Element at a given index in a sequence
+    Index(usize),
+    /// All elements of a sequence
+    Any,
+}
+
+/// A parsed dotted path, e.g. `answers[*].data`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path {
+    segments: Vec<Segment>,
+}
+
+impl Path {
+    pub fn new(segments: Vec<Segment>) -> Self {
+        Self { segments }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Returns the rest of the path if it starts with the field `name`.
+    ///
+    /// Useful for paths prefixed with a protocol name, such as
+    /// `dns.queries[*].name`.
+    pub fn strip_prefix(&self, name: &str) -> Option<Path> {
+        match self.segments.first() {
+            Some(Segment::Field(field)) if field == name => Some(Path {
+                segments: self.segments[1..].to_vec(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns all values of `root` found at this path.
+    pub fn query<'a>(&self, root: &'a dyn Visit) -> Vec<Value<'a>> {
+        let mut values = Vec::new();
+        root.visit(&mut Query {
+            segments: &self.segments,
+            values: &mut values,
+        });
+        values
+    }
+}
+
+impl FromStr for Path {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut position = 0;
+        for field in input.split('.') {
+            let name_len = field.find('[').unwrap_or(field.len());
+            let (name, mut indexes) = field.split_at(name_len);
+            if name.is_empty() {
+                // Only the first segment can start with an index
+                if !segments.is_empty() || indexes.is_empty() {
+                    return Err(Error::EmptyField(position));
+                }
+            } else {
+                segments.push(Segment::Field(name.to_string()));
+            }
+            let mut index_position = position + name_len;
+            while !indexes.is_empty() {
+                let end = indexes
+                    .find(']')
+                    .filter(|_| indexes.starts_with('['))
+                    .ok_or(Error::InvalidIndex(index_position))?;
+                segments.push(match &indexes[1..end] {
+                    "*" => Segment::Any,
+                    index => Segment::Index(
+                        index
+                            .parse()
+                            .map_err(|_| Error::InvalidIndex(index_position))?,
+                    ),
+                });
+                indexes = &indexes[end + 1..];
+                index_position += end + 1;
+            }
+            position += field.len() + 1;
+        }
+        Ok(Path { segments })
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Field(name) if i == 0 => write!(f, "{}", name)?,
+                Segment::Field(name) => write!(f, ".{}", name)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+                Segment::Any => write!(f, "[*]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses `path` and returns all values of `root` found at it.
+pub fn query<'a>(root: &'a dyn Visit, path: &str) -> Result<Vec<Value<'a>>, Error> {
+    Ok(path.parse::<Path>()?.query(root))
+}
+
+/// Returns every value of `root` along with its full path.
+///
+/// Values are returned in visiting order, which is the declaration order of
+/// the fields.
+pub fn fields(root: &dyn Visit) -> Vec<(String, Value<'_>)> {
+    let mut fields = Vec::new();
+    root.visit(&mut Fields {
+        path: String::new(),
+        fields: &mut fields,
+    });
+    fields
+}
+
+struct Query<'p, 'a> {
+    segments: &'p [Segment],
+    values: &'p mut Vec<Value<'a>>,
+}
+
+impl<'p, 'a> Query<'p, 'a> {
+    fn descend(&mut self, segments: &[Segment], value: &'a dyn Visit) {
+        value.visit(&mut Query {
+            segments,
+            values: self.values,
+        });
+    }
+}
+
+impl<'p, 'a> Visitor<'a> for Query<'p, 'a> {
+    fn value(&mut self, value: Value<'a>) {
+        if self.segments.is_empty() {
+            self.values.push(value);
+        }
+    }
+
+    fn field(&mut self, name: &'a str, value: &'a dyn Visit) {
+        match self.segments.first() {
+            Some(Segment::Field(field)) if field == name => {
+                self.descend(&self.segments[1..], value)
+            }
+            Some(_) => (),
+            None => self.descend(self.segments, value),
+        }
+    }
+
+    fn element(&mut self, index: usize, value: &'a dyn Visit) {
+        match self.segments.first() {
+            Some(Segment::Index(i)) if *i == index => self.descend(&self.segments[1..], value),
+            Some(Segment::Any) => self.descend(&self.segments[1..], value),
+            Some(Segment::Field(_)) | None => self.descend(self.segments, value),
+            Some(Segment::Index(_)) => (),
+        }
+    }
+}
+
+struct Fields<'p, 'a> {
+    path: String,
+    fields: &'p mut Vec<(String, Value<'a>)>,
+}
+
+impl<'p, 'a> Fields<'p, 'a> {
+    fn descend(&mut self, path: String, value: &'a dyn Visit) {
+        value.visit(&mut Fields {
+            path,
+            fields: self.fields,
+        });
+    }
+}
+
+impl<'p, 'a> Visitor<'a> for Fields<'p, 'a> {
+    fn value(&mut self, value: Value<'a>) {
+        self.fields.push((self.path.clone(), value));
+    }
+
+    fn field(&mut self, name: &'a str, value: &'a dyn Visit) {
+        let path = if self.path.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}.{}", self.path, name)
+        };
+        self.descend(path, value);
+    }
+
+    fn element(&mut self, index: usize, value: &'a dyn Visit) {
+        self.descend(format!("{}[{}]", self.path, index), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        input,
+        expected,
+        case::field("a", Ok(vec![Segment::Field("a".into())])),
+        case::nested(
+            "a.b",
+            Ok(vec![Segment::Field("a".into()), Segment::Field("b".into())])
+        ),
+        case::indexes(
+            "a[*].b[2][*]",
+            Ok(vec![
+                Segment::Field("a".into()),
+                Segment::Any,
+                Segment::Field("b".into()),
+                Segment::Index(2),
+                Segment::Any,
+            ])
+        ),
+        case::leading_index("[0].a", Ok(vec![Segment::Index(0), Segment::Field("a".into())])),
+        case::empty("", Err(Error::EmptyField(0))),
+        case::empty_field("a..b", Err(Error::EmptyField(2))),
+        case::trailing_dot("a.", Err(Error::EmptyField(2))),
+        case::index_after_dot("a.[0]", Err(Error::EmptyField(2))),
+        case::unclosed("a[0", Err(Error::InvalidIndex(1))),
+        case::not_a_number("a.b[x]", Err(Error::InvalidIndex(3))),
+        case::trailing_characters("a[0]b", Err(Error::InvalidIndex(4)))
+    )]
+    fn test_parse(input: &str, expected: Result<Vec<Segment>, Error>) {
+        assert_eq!(input.parse::<Path>(), expected.map(Path::new));
+    }
+
+    #[test]
+    fn test_display() {
+        let path: Path = "a[*].b[2].c".parse().unwrap();
+        assert_eq!(path.to_string(), "a[*].b[2].c");
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        let path: Path = "dns.queries[*].name".parse().unwrap();
+        assert_eq!(
+            path.strip_prefix("dns"),
+            Some("queries[*].name".parse().unwrap())
+        );
+        assert_eq!(path.strip_prefix("ike"), None);
+    }
+
+    #[rstest(
+        path,
+        expected,
+        case::all("a[*][*]", vec![Value::Uint(1), Value::Uint(2), Value::Uint(3)]),
+        case::flattened("a", vec![Value::Uint(1), Value::Uint(2), Value::Uint(3)]),
+        case::index("a[1]", vec![Value::Uint(3)]),
+        case::nested_index("a[0][1]", vec![Value::Uint(2)]),
+        case::out_of_bounds("a[2]", vec![]),
+        case::bytes("b", vec![Value::Bytes(b"bytes")]),
+        case::missing("d", vec![]),
+        case::nested("c", vec![Value::Uint(4), Value::Bool(true)]),
+        case::field_of_value("b.c", vec![])
+    )]
+    fn test_query(path: &str, expected: Vec<Value>) {
+        #[derive(crate::Visit)]
+        struct Inner {
+            d: u8,
+            e: bool,
+        }
+
+        #[derive(crate::Visit)]
+        struct Test {
+            a: Vec<Vec<u16>>,
+            b: Vec<u8>,
+            c: Inner,
+        }
+
+        let test = Test {
+            a: vec![vec![1, 2], vec![3]],
+            b: b"bytes".to_vec(),
+            c: Inner { d: 4, e: true },
+        };
+        assert_eq!(query(&test, path), Ok(expected));
+    }
+}
@@ -0,0 +1,142 @@
+//! Conversion of message trees into JSON values.
+
+use serde_json::{Map, Number, Value as JsonValue};
+
+use sawp_visit::{Value, Visit, Visitor};
+
+/// Converts `root` into nested JSON objects, arrays and values.
+///
+/// - Structs become objects keyed by field name.
+/// - Sequences become arrays, byte sequences become arrays of numbers.
+/// - Unit enum variants become their name as a string.
+/// - Flags become their bits as a number.
+/// - IP addresses become strings.
+/// - Missing optional values and non-finite floats become `null`.
+pub fn to_json(root: &dyn Visit) -> JsonValue {
+    let mut builder = Builder {
+        node: JsonValue::Null,
+    };
+    root.visit(&mut builder);
+    builder.node
+}
+
+struct Builder {
+    node: JsonValue,
+}
+
+impl Builder {
+    fn array(&mut self) -> &mut Vec<JsonValue> {
+        if !self.node.is_array() {
+            self.node = JsonValue::Array(Vec::new());
+        }
+        match &mut self.node {
+            JsonValue::Array(array) => array,
+            _ => unreachable!(),
+        }
+    }
+
+    fn object(&mut self) -> &mut Map<String, JsonValue> {
+        if !self.node.is_object() {
+            self.node = JsonValue::Object(Map::new());
+        }
+        match &mut self.node {
+            JsonValue::Object(object) => object,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for Builder {
+    fn value(&mut self, value: Value<'a>) {
+        self.node = match value {
+            Value::Bool(value) => JsonValue::Bool(value),
+            Value::Uint(value) => JsonValue::from(value),
+            Value::Int(value) => JsonValue::from(value),
+            Value::Float(value) => {
+                Number::from_f64(value).map_or(JsonValue::Null, JsonValue::Number)
+            }
+            Value::Str(value) => JsonValue::from(value),
+            Value::Bytes(value) => JsonValue::from(value),
+            Value::Ip(value) => JsonValue::String(value.to_string()),
+            Value::Variant(value) => JsonValue::from(value),
+            Value::Flags(value) => JsonValue::from(value.bits()),
+        };
+    }
+
+    fn field(&mut self, name: &'a str, value: &'a dyn Visit) {
+        let value = to_json(value);
+        self.object().insert(name.to_string(), value);
+    }
+
+    fn element(&mut self, _index: usize, value: &'a dyn Visit) {
+        let value = to_json(value);
+        self.array().push(value);
+    }
+
+    fn sequence(&mut self, _len: usize) {
+        self.array();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[derive(Visit)]
+    #[allow(dead_code)]
+    enum Kind {
+        Empty,
+        Number(u8),
+    }
+
+    #[derive(Visit)]
+    struct Inner {
+        kind: Kind,
+        address: Option<IpAddr>,
+    }
+
+    #[derive(Visit)]
+    struct Outer {
+        name: String,
+        data: Vec<u8>,
+        ratio: f64,
+        inner: Vec<Inner>,
+        empty: Vec<Inner>,
+    }
+
+    #[test]
+    fn test_to_json() {
+        let outer = Outer {
+            name: "outer".to_string(),
+            data: b"\x00\x01".to_vec(),
+            ratio: f64::NAN,
+            inner: vec![
+                Inner {
+                    kind: Kind::Empty,
+                    address: Some(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                },
+                Inner {
+                    kind: Kind::Number(7),
+                    address: None,
+                },
+            ],
+            empty: vec![],
+        };
+
+        assert_eq!(
+            to_json(&outer),
+            json!({
+                "name": "outer",
+                "data": [0, 1],
+                "ratio": null,
+                "inner": [
+                    {"kind": "Empty", "address": "127.0.0.1"},
+                    {"kind": 7, "address": null},
+                ],
+                "empty": [],
+            })
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! WebAssembly bindings for the SAWP parsers.
+//!
+//! [`parse`] parses one message of a protocol, identified by its name, and
+//! returns the result as a JSON string. Messages are converted following
+//! their field names, see [`sawp_visit`] and [`json::to_json`].
+//!
+//! The result is `{"consumed": <bytes>, "message": <message or null>}`, or
+//! `{"needed": <bytes or null>}` if more data is needed. Any other error is
+//! thrown.
+//!
+//! Build the module with [wasm-pack](https://rustwasm.github.io/wasm-pack),
+//! e.g. `wasm-pack build --target web` from this directory.
+//!
+//! ```js
+//! import init, { parse, Direction } from "./pkg/sawp_wasm.js";
+//!
+//! await init();
+//! let data = new Uint8Array([0, 1, 0, 0, 0, 6, 1, 1, 0, 2, 0, 3]);
+//! while (data.length > 0) {
+//!     const result = JSON.parse(parse("modbus", Direction.ToServer, data));
+//!     if ("needed" in result) {
+//!         break;
+//!     }
+//!     data = data.subarray(result.consumed);
+//!     console.log(result.message);
+//! }
+//! ```
+
+pub mod json;
+
+use serde_json::{json, Value as JsonValue};
+use wasm_bindgen::prelude::*;
+
+use sawp::error::{ErrorKind, Needed};
+use sawp::parser::{self, Parse};
+use sawp::protocol::Protocol;
+
+use std::fmt;
+
+/// Destination of the input byte stream.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    ToClient,
+    ToServer,
+    Unknown,
+}
+
+impl From<Direction> for parser::Direction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::ToClient => parser::Direction::ToClient,
+            Direction::ToServer => parser::Direction::ToServer,
+            Direction::Unknown => parser::Direction::Unknown,
+        }
+    }
+}
+
+/// Errors thrown by [`parse`].
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// No parser has this name
+    UnknownProtocol(String),
+    /// The parser failed with an error other than `ErrorKind::Incomplete`
+    Parse(sawp::error::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownProtocol(name) => write!(f, "unknown protocol {}", name),
+            Error::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Calls the parser of the protocol called `$protocol` among `$parser`s,
+/// created with its default settings.
+macro_rules! dispatch {
+    ($protocol:expr, $data:expr, $direction:expr, $($parser:ty),+) => {
+        match $protocol {
+            $(
+                name if name == <$parser as Protocol>::name() => <$parser>::default()
+                    .parse($data, $direction)
+                    .map(|(rest, message)| {
+                        (rest, message.map(|message| json::to_json(&message)))
+                    }),
+            )+
+            name => return Err(Error::UnknownProtocol(name.to_string())),
+        }
+    };
+}
+
+/// Names of the protocols accepted by [`parse`].
+pub const PROTOCOLS: &[&str] = &[
+    "diameter", "dns", "gre", "ike", "json", "modbus", "pop3", "resp", "tftp",
+];
+
+/// Parses one message of `protocol` from `data` into a JSON value.
+///
+/// See the [crate documentation](crate) for the format of the value.
+pub fn parse_value(protocol: &str, direction: Direction, data: &[u8]) -> Result<JsonValue, Error> {
+    let result = dispatch!(
+        protocol,
+        data,
+        direction.into(),
+        sawp_diameter::Diameter,
+        sawp_dns::Dns,
+        sawp_gre::Gre,
+        sawp_ike::Ike,
+        sawp_json::Json,
+        sawp_modbus::Modbus,
+        sawp_pop3::POP3,
+        sawp_resp::Resp,
+        sawp_tftp::TFTP
+    );
+    match result {
+        Ok((rest, message)) => Ok(json!({
+            "consumed": data.len() - rest.len(),
+            "message": message,
+        })),
+        Err(sawp::error::Error {
+            kind: ErrorKind::Incomplete(needed),
+        }) => Ok(json!({
+            "needed": match needed {
+                Needed::Size(size) => Some(size.get()),
+                Needed::Unknown => None,
+            },
+        })),
+        Err(err) => Err(Error::Parse(err)),
+    }
+}
+
+/// Parses one message of `protocol` from `data` and returns the result as
+/// a JSON string.
+///
+/// See the [crate documentation](crate) for the format of the result.
+#[wasm_bindgen]
+pub fn parse(protocol: &str, direction: Direction, data: &[u8]) -> Result<String, JsError> {
+    parse_value(protocol, direction, data)
+        .map(|value| value.to_string())
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+/// Names of the protocols accepted by [`parse`].
+#[wasm_bindgen]
+pub fn protocols() -> Vec<JsValue> {
+    PROTOCOLS
+        .iter()
+        .map(|&name| JsValue::from_str(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest(
+        protocol,
+        data,
+        expected,
+        case::message(
+            "modbus",
+            b"\x00\x01\x00\x00\x00\x06\x01\x01\x00\x02\x00\x03\x00",
+            Ok(json!({"consumed": 12}))
+        ),
+        case::incomplete("modbus", b"\x00\x01\x00\x00", Ok(json!({"needed": 2}))),
+        case::error(
+            "json",
+            b"]",
+            Err(Error::Parse(sawp::error::Error::new(ErrorKind::InvalidData)))
+        ),
+        case::unknown("http", b"", Err(Error::UnknownProtocol("http".to_string())))
+    )]
+    fn test_parse_value(protocol: &str, data: &[u8], expected: Result<JsonValue, Error>) {
+        let result = parse_value(protocol, Direction::ToServer, data).map(|mut value| {
+            // Only compare the message of the first case by a few fields
+            if let Some(message) = value.get_mut("message").map(JsonValue::take) {
+                assert_eq!(message["transaction_id"], json!(1));
+                assert_eq!(message["function"]["code"], json!("RdCoils"));
+                value.as_object_mut().unwrap().remove("message");
+            }
+            value
+        });
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_protocols() {
+        for name in PROTOCOLS {
+            assert!(!matches!(
+                parse_value(name, Direction::Unknown, b""),
+                Err(Error::UnknownProtocol(_))
+            ));
+        }
+    }
+}
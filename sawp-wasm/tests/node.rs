@@ -0,0 +1,43 @@
+//! Tests of the JS-facing API, run under Node with
+//! `wasm-pack test --node` or `cargo test --target wasm32-unknown-unknown`
+//! with `wasm-bindgen-test-runner` as the runner.
+#![cfg(target_arch = "wasm32")]
+
+use serde_json::{json, Value};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use sawp_wasm::{parse, protocols, Direction};
+
+/// Calls `parse` and decodes its result. `JsError` isn't `Debug`.
+fn parse_json(protocol: &str, data: &[u8]) -> Value {
+    match parse(protocol, Direction::ToServer, data) {
+        Ok(result) => serde_json::from_str(&result).unwrap(),
+        Err(_) => panic!("parse failed"),
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_parse() {
+    let data = b"\x00\x01\x00\x00\x00\x06\x01\x01\x00\x02\x00\x03";
+    let result = parse_json("modbus", data);
+    assert_eq!(result["consumed"], json!(12));
+    assert_eq!(result["message"]["unit_id"], json!(1));
+    assert_eq!(result["message"]["function"]["code"], json!("RdCoils"));
+
+    assert_eq!(parse_json("modbus", &data[..4]), json!({"needed": 2}));
+}
+
+#[wasm_bindgen_test]
+fn test_parse_error() {
+    assert!(parse("json", Direction::Unknown, b"]").is_err());
+    assert!(parse("http", Direction::Unknown, b"").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_protocols() {
+    let names: Vec<String> = protocols()
+        .into_iter()
+        .map(|name| name.as_string().unwrap())
+        .collect();
+    assert!(names.contains(&"modbus".to_string()));
+}
@@ -0,0 +1,324 @@
+//! Adapters to use parsers with async streams.
+//!
+//! [`SawpCodec`] implements [`tokio_util::codec::Decoder`] for any parser so
+//! it can be used with `FramedRead` or `Framed`, which implement the
+//! `futures` `Stream` and `Sink` traits. It also implements
+//! [`tokio_util::codec::Encoder`] for parsers which implement [`Encode`],
+//! such as `sawp_resp::Resp` with its `codec` feature.
+//!
+//! `ErrorKind::Incomplete` is reported to the framing as "need more data".
+//! Any other error is returned as [`Error::Parse`] and ends the stream.
+//!
+//! # Example
+//! ```
+//! use bytes::BytesMut;
+//! use sawp::codec::SawpCodec;
+//! use sawp::error::{Error, Result};
+//! use sawp::parser::{Direction, Parse};
+//! use sawp::protocol::Protocol;
+//! use tokio_util::codec::Decoder;
+//!
+//! /// Messages are lines ending with `\n`
+//! struct Lines;
+//!
+//! impl<'a> Protocol<'a> for Lines {
+//!     type Message = Vec<u8>;
+//!
+//!     fn name() -> &'static str {
+//!         "lines"
+//!     }
+//! }
+//!
+//! impl<'a> Parse<'a> for Lines {
+//!     fn parse(&self, input: &'a [u8], _: Direction) -> Result<(&'a [u8], Option<Vec<u8>>)> {
+//!         match input.iter().position(|&c| c == b'\n') {
+//!             Some(end) => Ok((&input[end + 1..], Some(input[..end].to_vec()))),
+//!             None => Err(Error::incomplete()),
+//!         }
+//!     }
+//! }
+//!
+//! let mut codec = SawpCodec::new(Lines, Direction::Unknown);
+//! let mut buffer = BytesMut::from(&b"first\nsec"[..]);
+//! assert_eq!(codec.decode(&mut buffer).unwrap(), Some(b"first".to_vec()));
+//! assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+//!
+//! buffer.extend_from_slice(b"ond\n");
+//! assert_eq!(codec.decode(&mut buffer).unwrap(), Some(b"second".to_vec()));
+//! ```
+
+use crate::error::{self, ErrorKind, Needed};
+use crate::parser::{Direction, Parse};
+use crate::protocol::Protocol;
+
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use std::fmt;
+use std::io;
+
+/// Errors returned by [`SawpCodec`].
+#[derive(Debug)]
+pub enum Error {
+    /// Error of the underlying stream
+    Io(io::Error),
+    /// The parser or encoder failed with an error other than
+    /// `ErrorKind::Incomplete`
+    Parse(error::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Parse(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Parse(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<error::Error> for Error {
+    fn from(err: error::Error) -> Self {
+        Error::Parse(err)
+    }
+}
+
+/// Protocols which can serialize their messages.
+pub trait Encode<M> {
+    /// Appends the wire representation of `message` to `dst`.
+    fn encode(&self, message: &M, dst: &mut BytesMut) -> error::Result<()>;
+}
+
+/// Decodes the messages of the parser `P` from a byte stream.
+///
+/// Input passed to the parser is always in the direction given at creation.
+/// Use separate codecs for each direction of a connection.
+#[derive(Debug)]
+pub struct SawpCodec<P> {
+    parser: P,
+    direction: Direction,
+}
+
+impl<P> SawpCodec<P> {
+    pub fn new(parser: P, direction: Direction) -> Self {
+        Self { parser, direction }
+    }
+
+    /// The wrapped parser.
+    pub fn inner(&self) -> &P {
+        &self.parser
+    }
+
+    /// The wrapped parser, for instance to change its settings.
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.parser
+    }
+
+    pub fn into_inner(self) -> P {
+        self.parser
+    }
+
+    pub fn direction(&self) -> &Direction {
+        &self.direction
+    }
+}
+
+impl<P, M> Decoder for SawpCodec<P>
+where
+    P: for<'a> Parse<'a> + for<'a> Protocol<'a, Message = M>,
+{
+    type Item = M;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<M>, Error> {
+        while !src.is_empty() {
+            match self.parser.parse(src, self.direction.clone()) {
+                Ok((rest, message)) => {
+                    let consumed = src.len() - rest.len();
+                    src.advance(consumed);
+                    // Progress without a message means the parser buffered
+                    // the input internally, try again with what is left.
+                    if message.is_some() || consumed == 0 {
+                        return Ok(message);
+                    }
+                }
+                Err(error::Error {
+                    kind: ErrorKind::Incomplete(needed),
+                }) => {
+                    if let Needed::Size(size) = needed {
+                        src.reserve(size.get());
+                    }
+                    return Ok(None);
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<P, M> Encoder<M> for SawpCodec<P>
+where
+    P: Encode<M>,
+{
+    type Error = Error;
+
+    fn encode(&mut self, message: M, dst: &mut BytesMut) -> Result<(), Error> {
+        self.parser.encode(&message, dst).map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use futures::{SinkExt, StreamExt};
+    use rstest::rstest;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    /// Messages are a length byte followed by the payload. A length of 0xff
+    /// is invalid and a length of 0 is skipped without returning a message.
+    struct Test;
+
+    impl<'a> Protocol<'a> for Test {
+        type Message = Vec<u8>;
+
+        fn name() -> &'static str {
+            "codec_test"
+        }
+    }
+
+    impl<'a> Parse<'a> for Test {
+        fn parse(
+            &self,
+            input: &'a [u8],
+            _direction: Direction,
+        ) -> Result<(&'a [u8], Option<Self::Message>)> {
+            match input {
+                [] => Err(error::Error::incomplete_needed(1)),
+                [0xff, ..] => Err(error::Error::new(ErrorKind::InvalidData)),
+                [0, rest @ ..] => Ok((rest, None)),
+                [len, rest @ ..] if rest.len() < usize::from(*len) => Err(
+                    error::Error::incomplete_needed(usize::from(*len) - rest.len()),
+                ),
+                [len, rest @ ..] => {
+                    let (payload, rest) = rest.split_at(usize::from(*len));
+                    Ok((rest, Some(payload.to_vec())))
+                }
+            }
+        }
+    }
+
+    impl Encode<Vec<u8>> for Test {
+        fn encode(&self, message: &Vec<u8>, dst: &mut BytesMut) -> Result<()> {
+            let len = u8::try_from(message.len())
+                .ok()
+                .filter(|&len| len != 0xff)
+                .ok_or_else(|| error::Error::new(ErrorKind::InvalidData))?;
+            dst.extend_from_slice(&[len]);
+            dst.extend_from_slice(message);
+            Ok(())
+        }
+    }
+
+    #[rstest(
+        input,
+        expected,
+        expected_rest,
+        case::empty(b"", Ok(None), 0),
+        case::message(b"\x02ab\x01", Ok(Some(b"ab".to_vec())), 1),
+        case::incomplete(b"\x02a", Ok(None), 2),
+        case::skipped(b"\x00\x00\x01a", Ok(Some(b"a".to_vec())), 0),
+        case::skipped_incomplete(b"\x00\x02", Ok(None), 1),
+        case::invalid(b"\xff", Err(()), 1)
+    )]
+    fn test_decode(
+        input: &[u8],
+        expected: std::result::Result<Option<Vec<u8>>, ()>,
+        expected_rest: usize,
+    ) {
+        let mut codec = SawpCodec::new(Test, Direction::Unknown);
+        let mut buffer = BytesMut::from(input);
+        assert_eq!(codec.decode(&mut buffer).map_err(|_| ()), expected);
+        assert_eq!(buffer.len(), expected_rest);
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut codec = SawpCodec::new(Test, Direction::Unknown);
+        let mut buffer = BytesMut::new();
+        codec.encode(b"ab".to_vec(), &mut buffer).unwrap();
+        assert_eq!(&buffer[..], b"\x02ab");
+        assert!(matches!(
+            codec.encode(vec![0; 0xff], &mut buffer),
+            Err(Error::Parse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplex() {
+        let (client, server) = tokio::io::duplex(4);
+        let mut writer = FramedWrite::new(client, SawpCodec::new(Test, Direction::ToServer));
+        let mut reader = FramedRead::new(server, SawpCodec::new(Test, Direction::ToServer));
+
+        let messages = vec![b"hello".to_vec(), b"".to_vec(), b"world!".to_vec()];
+        let expected = messages.clone();
+        let write = tokio::spawn(async move {
+            for message in messages {
+                writer.send(message).await.unwrap();
+            }
+        });
+
+        let mut received = Vec::new();
+        while received.len() < 2 {
+            received.push(reader.next().await.unwrap().unwrap());
+        }
+        write.await.unwrap();
+        // The empty message is skipped by the parser
+        assert_eq!(received, vec![expected[0].clone(), expected[2].clone()]);
+        assert!(reader.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_duplex_error() {
+        let (mut client, server) = tokio::io::duplex(16);
+        let mut reader = FramedRead::new(server, SawpCodec::new(Test, Direction::ToServer));
+
+        tokio::io::AsyncWriteExt::write_all(&mut client, b"\x01a\xff")
+            .await
+            .unwrap();
+        assert_eq!(reader.next().await.unwrap().unwrap(), b"a".to_vec());
+        assert!(matches!(
+            reader.next().await,
+            Some(Err(Error::Parse(error::Error {
+                kind: ErrorKind::InvalidData
+            })))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplex_eof() {
+        let (mut client, server) = tokio::io::duplex(16);
+        let mut reader = FramedRead::new(server, SawpCodec::new(Test, Direction::ToServer));
+
+        tokio::io::AsyncWriteExt::write_all(&mut client, b"\x03ab")
+            .await
+            .unwrap();
+        drop(client);
+        assert!(matches!(reader.next().await, Some(Err(Error::Io(_)))));
+    }
+}
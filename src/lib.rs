@@ -24,13 +24,32 @@ referring to the protocol parser you wish to use:
 
 The following utility packages also exist:
 - [File](/sawp-file) Serializes API calls for debugging
+
+## `no_std`
+
+The base library and the Modbus, DNS, GRE and IKE parsers can be used
+without the standard library by disabling the default `std` feature. They
+still require `alloc`. The `ffi` and `metrics` features require `std`.
 */
 
+#![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::unneeded_field_pattern)]
 
+extern crate alloc;
+
+/// Async stream adapters
+#[cfg(feature = "codec")]
+pub mod codec;
+
 /// Return common errors
 pub mod error;
 
+/// Resource limits
+pub mod limits;
+
+/// Parser metrics
+pub mod metrics;
+
 /// Parse Messages
 pub mod parser;
 
@@ -40,5 +59,9 @@ pub mod probe;
 /// Describe a Protocol
 pub mod protocol;
 
+/// Parser test helpers
+#[cfg(feature = "testing")]
+pub mod testing;
+
 #[cfg(feature = "ffi")]
 pub mod ffi;
@@ -0,0 +1,211 @@
+use core::cell::Cell;
+
+/// Default maximum number of bytes a parser may allocate for a single message.
+pub const DEFAULT_MAX_ALLOC: usize = 16 * 1024 * 1024;
+/// Default maximum nesting depth of recursive structures.
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+/// Default maximum number of elements in a single message.
+pub const DEFAULT_MAX_ELEMENTS: usize = 65535;
+
+/// Resource limits applied by a parser to every message it parses.
+///
+/// Every parser accepts a `Limits` so a hostile input on one protocol cannot
+/// exhaust the memory of the caller. When a limit is reached, the parser
+/// stops allocating, returns what it parsed so far and sets the protocol's
+/// "limit exceeded" error flag on the message.
+///
+/// Parsers may use different defaults when the protocol defines tighter
+/// bounds than the ones below.
+///
+/// # Example
+/// ```
+/// use sawp::limits::Limits;
+///
+/// let limits = Limits {
+///     max_depth: 8,
+///     ..Limits::default()
+/// };
+/// assert_eq!(limits.max_depth, 8);
+/// ```
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum number of bytes allocated for a single message.
+    pub max_alloc: usize,
+    /// Maximum nesting depth when parsing recursive structures.
+    pub max_depth: usize,
+    /// Maximum number of elements (entries, records, options...) in a single
+    /// message.
+    pub max_elements: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_alloc: DEFAULT_MAX_ALLOC,
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_elements: DEFAULT_MAX_ELEMENTS,
+        }
+    }
+}
+
+impl Limits {
+    /// Returns a tracker to account for the resources used by one message.
+    pub fn tracker(&self) -> Tracker {
+        Tracker::new(self)
+    }
+}
+
+/// Tracks the resources used while parsing a single message.
+///
+/// A parser creates one tracker per message and charges its allocations and
+/// elements against it. Interior mutability is used so the tracker can be
+/// shared by nested parsing functions and closures.
+///
+/// Once any limit is reached, [`Tracker::exceeded`] will return `true` for the
+/// rest of the message.
+#[derive(Debug)]
+pub struct Tracker {
+    limits: Limits,
+    allocated: Cell<usize>,
+    elements: Cell<usize>,
+    exceeded: Cell<bool>,
+}
+
+impl Tracker {
+    /// Creates a tracker with nothing charged against `limits` yet.
+    pub fn new(limits: &Limits) -> Self {
+        Self {
+            limits: *limits,
+            allocated: Cell::new(0),
+            elements: Cell::new(0),
+            exceeded: Cell::new(false),
+        }
+    }
+
+    /// Limits this tracker was created with.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Charges an allocation of `size` bytes.
+    ///
+    /// Returns `false` if the allocation would go over `max_alloc`, in which
+    /// case nothing is charged and the caller should not allocate.
+    pub fn alloc(&self, size: usize) -> bool {
+        match self.allocated.get().checked_add(size) {
+            Some(total) if total <= self.limits.max_alloc => {
+                self.allocated.set(total);
+                true
+            }
+            _ => self.exceed(),
+        }
+    }
+
+    /// Charges `count` elements.
+    ///
+    /// Returns `false` if the elements would go over `max_elements`, in which
+    /// case nothing is charged and the caller should stop parsing elements.
+    pub fn elements(&self, count: usize) -> bool {
+        match self.elements.get().checked_add(count) {
+            Some(total) if total <= self.limits.max_elements => {
+                self.elements.set(total);
+                true
+            }
+            _ => self.exceed(),
+        }
+    }
+
+    /// Charges a single element. See [`Tracker::elements`].
+    pub fn element(&self) -> bool {
+        self.elements(1)
+    }
+
+    /// Checks that `depth` is within `max_depth`.
+    pub fn depth(&self, depth: usize) -> bool {
+        depth <= self.limits.max_depth || self.exceed()
+    }
+
+    /// Number of elements that can still be charged.
+    ///
+    /// Useful to bound preallocations based on untrusted counts.
+    pub fn remaining_elements(&self) -> usize {
+        self.limits.max_elements.saturating_sub(self.elements.get())
+    }
+
+    /// Number of bytes that can still be charged.
+    pub fn remaining_alloc(&self) -> usize {
+        self.limits.max_alloc.saturating_sub(self.allocated.get())
+    }
+
+    /// Whether any limit was reached.
+    pub fn exceeded(&self) -> bool {
+        self.exceeded.get()
+    }
+
+    /// Marks a limit as reached, for bounds specific to a protocol.
+    ///
+    /// Always returns `false`, like a failed check.
+    pub fn exceed(&self) -> bool {
+        self.exceeded.set(true);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alloc() {
+        let limits = Limits {
+            max_alloc: 10,
+            ..Limits::default()
+        };
+        let tracker = limits.tracker();
+        assert!(tracker.alloc(6));
+        assert!(tracker.alloc(4));
+        assert!(!tracker.exceeded());
+        assert_eq!(tracker.remaining_alloc(), 0);
+        assert!(!tracker.alloc(1));
+        assert!(tracker.exceeded());
+        assert!(!tracker.alloc(usize::MAX));
+    }
+
+    #[test]
+    fn test_elements() {
+        let limits = Limits {
+            max_elements: 3,
+            ..Limits::default()
+        };
+        let tracker = limits.tracker();
+        assert!(tracker.elements(2));
+        assert_eq!(tracker.remaining_elements(), 1);
+        assert!(!tracker.elements(2));
+        assert_eq!(tracker.remaining_elements(), 1);
+        assert!(tracker.element());
+        assert!(!tracker.element());
+        assert!(tracker.exceeded());
+    }
+
+    #[test]
+    fn test_depth() {
+        let limits = Limits {
+            max_depth: 2,
+            ..Limits::default()
+        };
+        let tracker = limits.tracker();
+        assert!(tracker.depth(2));
+        assert!(!tracker.exceeded());
+        assert!(!tracker.depth(3));
+        assert!(tracker.exceeded());
+    }
+
+    #[test]
+    fn test_exceed() {
+        let tracker = Limits::default().tracker();
+        assert!(!tracker.exceeded());
+        assert!(!tracker.exceed());
+        assert!(tracker.exceeded());
+    }
+}
@@ -0,0 +1,446 @@
+//! Per-protocol parser statistics.
+//!
+//! Counters are kept per protocol, keyed by
+//! [`Protocol::name`](crate::protocol::Protocol::name), and updated every time
+//! a parse result is recorded. They can be read at any time with
+//! [`get`](crate::metrics::get) or through the FFI.
+//!
+//! Recording is only done when the `metrics` feature is enabled. Without it
+//! [`Recorder`](crate::metrics::Recorder) and [`Metered`](crate::metrics::Metered)
+//! compile down to the wrapped parser and [`get`](crate::metrics::get) always
+//! returns `None`.
+//!
+//! # Example
+//! ```
+//! use sawp::error::Result;
+//! use sawp::metrics::{self, Flagged, Metered};
+//! use sawp::parser::{Direction, Parse};
+//! use sawp::protocol::Protocol;
+//!
+//! struct Echo;
+//!
+//! struct Message;
+//!
+//! impl Flagged for Message {
+//!     fn error_flags(&self) -> u64 {
+//!         0
+//!     }
+//! }
+//!
+//! impl<'a> Protocol<'a> for Echo {
+//!     type Message = Message;
+//!
+//!     fn name() -> &'static str {
+//!         "echo"
+//!     }
+//! }
+//!
+//! impl<'a> Parse<'a> for Echo {
+//!     fn parse(&self, input: &'a [u8], _: Direction) -> Result<(&'a [u8], Option<Message>)> {
+//!         Ok((&input[input.len()..], Some(Message)))
+//!     }
+//! }
+//!
+//! let parser = Metered::new(Echo);
+//! parser.parse(b"hello", Direction::Unknown).unwrap();
+//!
+//! if let Some(snapshot) = metrics::get("echo") {
+//!     assert_eq!(snapshot.messages, 1);
+//!     assert_eq!(snapshot.bytes, 5);
+//! }
+//! ```
+
+use crate::error::Result;
+use crate::parser::{Direction, Parse};
+use crate::probe::{Probe, Status};
+use crate::protocol::Protocol;
+
+#[cfg(feature = "metrics")]
+use crate::error::ErrorKind;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+#[cfg(feature = "metrics")]
+use std::sync::Mutex;
+
+/// Number of error flag bits tracked individually.
+pub const ERROR_FLAG_BITS: usize = 16;
+
+/// Messages which can report the error flags raised while parsing them.
+///
+/// Protocols expose their error flags as bit flags of various widths, this
+/// trait widens them to `u64` so they can be counted per bit.
+pub trait Flagged {
+    /// Raw bits of the error flags raised on this message.
+    fn error_flags(&self) -> u64;
+}
+
+/// Point in time copy of the counters of a protocol.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Messages returned by the parser.
+    pub messages: u64,
+    /// Parse attempts which needed more data.
+    pub incomplete: u64,
+    /// Parse attempts which failed with an error other than `Incomplete`.
+    pub errors: u64,
+    /// Errors of kind `ErrorKind::Unimplemented`.
+    pub unimplemented: u64,
+    /// Errors of kind `ErrorKind::InvalidData`.
+    pub invalid_data: u64,
+    /// Errors of kind `ErrorKind::ParseError`.
+    pub parse_errors: u64,
+    /// Messages with at least one error flag raised.
+    pub flagged: u64,
+    /// Number of times each error flag bit was raised, indexed by bit position.
+    pub error_flags: [u64; ERROR_FLAG_BITS],
+    /// Bytes consumed by the parser.
+    pub bytes: u64,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+struct Counters {
+    messages: AtomicU64,
+    incomplete: AtomicU64,
+    errors: AtomicU64,
+    unimplemented: AtomicU64,
+    invalid_data: AtomicU64,
+    parse_errors: AtomicU64,
+    flagged: AtomicU64,
+    error_flags: [AtomicU64; ERROR_FLAG_BITS],
+    bytes: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Counters {
+    fn add(counter: &AtomicU64, value: u64) {
+        counter.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn record<M: Flagged>(&self, input: &[u8], result: &Result<(&[u8], Option<M>)>) {
+        match result {
+            Ok((rest, message)) => {
+                Self::add(&self.bytes, (input.len() - rest.len()) as u64);
+                if let Some(message) = message {
+                    Self::add(&self.messages, 1);
+                    let flags = message.error_flags();
+                    if flags != 0 {
+                        Self::add(&self.flagged, 1);
+                    }
+                    for (bit, counter) in self.error_flags.iter().enumerate() {
+                        if flags & (1 << bit) != 0 {
+                            Self::add(counter, 1);
+                        }
+                    }
+                }
+            }
+            Err(error) => match error.kind {
+                ErrorKind::Incomplete(_) => Self::add(&self.incomplete, 1),
+                ErrorKind::Unimplemented => {
+                    Self::add(&self.errors, 1);
+                    Self::add(&self.unimplemented, 1);
+                }
+                ErrorKind::InvalidData => {
+                    Self::add(&self.errors, 1);
+                    Self::add(&self.invalid_data, 1);
+                }
+                ErrorKind::ParseError(_) => {
+                    Self::add(&self.errors, 1);
+                    Self::add(&self.parse_errors, 1);
+                }
+            },
+        }
+    }
+
+    fn reset(&self) {
+        let counters = [
+            &self.messages,
+            &self.incomplete,
+            &self.errors,
+            &self.unimplemented,
+            &self.invalid_data,
+            &self.parse_errors,
+            &self.flagged,
+            &self.bytes,
+        ];
+        for counter in counters.into_iter().chain(self.error_flags.iter()) {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut error_flags = [0; ERROR_FLAG_BITS];
+        for (value, counter) in error_flags.iter_mut().zip(self.error_flags.iter()) {
+            *value = load(counter);
+        }
+        Snapshot {
+            messages: load(&self.messages),
+            incomplete: load(&self.incomplete),
+            errors: load(&self.errors),
+            unimplemented: load(&self.unimplemented),
+            invalid_data: load(&self.invalid_data),
+            parse_errors: load(&self.parse_errors),
+            flagged: load(&self.flagged),
+            error_flags,
+            bytes: load(&self.bytes),
+        }
+    }
+}
+
+/// Counters are never freed so [`Recorder`]s can keep a reference to them.
+#[cfg(feature = "metrics")]
+type Registry = Vec<(&'static str, &'static Counters)>;
+
+#[cfg(feature = "metrics")]
+static REGISTRY: Mutex<Registry> = Mutex::new(Vec::new());
+
+/// Returns the counters of `protocol`, registering them on first use.
+#[cfg(feature = "metrics")]
+fn counters(protocol: &'static str) -> &'static Counters {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    match registry.iter().find(|(name, _)| *name == protocol) {
+        Some((_, counters)) => counters,
+        None => {
+            let counters: &'static Counters = Box::leak(Box::default());
+            registry.push((protocol, counters));
+            counters
+        }
+    }
+}
+
+/// Records parse results into the counters of a single protocol.
+///
+/// The counters are looked up in the registry on the first call only, so a
+/// recorder should be kept for as long as its protocol is parsed. Parse paths
+/// which don't go through [`Metered`], such as the FFI, can use a `static`
+/// recorder:
+///
+/// ```ignore
+/// static RECORDER: Recorder = Recorder::new();
+///
+/// let result = parser.parse(input, direction);
+/// RECORDER.record::<Dns>(input, &result);
+/// ```
+#[derive(Debug, Default)]
+pub struct Recorder {
+    #[cfg(feature = "metrics")]
+    counters: AtomicPtr<Counters>,
+}
+
+impl Recorder {
+    #[cfg(feature = "metrics")]
+    pub const fn new() -> Self {
+        Self {
+            counters: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    pub const fn new() -> Self {
+        Self {}
+    }
+
+    /// Records the result of parsing `input` with the protocol `P`.
+    ///
+    /// A recorder must only be used with one protocol.
+    #[cfg(feature = "metrics")]
+    pub fn record<'a, P>(&self, input: &[u8], result: &Result<(&[u8], Option<P::Message>)>)
+    where
+        P: Protocol<'a>,
+        P::Message: Flagged,
+    {
+        self.counters(P::name()).record(input, result);
+    }
+
+    /// Records the result of parsing `input` with the protocol `P`.
+    ///
+    /// A recorder must only be used with one protocol.
+    #[cfg(not(feature = "metrics"))]
+    #[inline(always)]
+    pub fn record<'a, P>(&self, _input: &[u8], _result: &Result<(&[u8], Option<P::Message>)>)
+    where
+        P: Protocol<'a>,
+        P::Message: Flagged,
+    {
+    }
+
+    #[cfg(feature = "metrics")]
+    fn counters(&self, protocol: &'static str) -> &'static Counters {
+        let cached = self.counters.load(Ordering::Acquire);
+        if !cached.is_null() {
+            // SAFETY: only ever set from a `&'static Counters` below.
+            return unsafe { &*cached };
+        }
+        let counters = counters(protocol);
+        self.counters.store(
+            counters as *const Counters as *mut Counters,
+            Ordering::Release,
+        );
+        counters
+    }
+}
+
+/// Returns the counters of `protocol`, if any result was recorded for it.
+#[cfg(feature = "metrics")]
+pub fn get(protocol: &str) -> Option<Snapshot> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry
+        .iter()
+        .find(|(name, _)| *name == protocol)
+        .map(|(_, counters)| counters.snapshot())
+}
+
+/// Returns the counters of `protocol`, if any result was recorded for it.
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub fn get(_protocol: &str) -> Option<Snapshot> {
+    None
+}
+
+/// Resets the counters of every protocol.
+#[cfg(feature = "metrics")]
+pub fn reset() {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    for (_, counters) in registry.iter() {
+        counters.reset();
+    }
+}
+
+/// Resets the counters of every protocol.
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub fn reset() {}
+
+/// Parser wrapper recording metrics for every call to `parse`.
+///
+/// Probing is forwarded to the wrapped parser without being recorded.
+#[derive(Debug)]
+pub struct Metered<P> {
+    parser: P,
+    recorder: Recorder,
+}
+
+impl<P> Metered<P> {
+    pub fn new(parser: P) -> Self {
+        Self {
+            parser,
+            recorder: Recorder::new(),
+        }
+    }
+
+    /// The wrapped parser.
+    pub fn inner(&self) -> &P {
+        &self.parser
+    }
+
+    /// The wrapped parser, for instance to change its settings.
+    pub fn inner_mut(&mut self) -> &mut P {
+        &mut self.parser
+    }
+
+    pub fn into_inner(self) -> P {
+        self.parser
+    }
+}
+
+impl<'a, P: Protocol<'a>> Protocol<'a> for Metered<P> {
+    type Message = P::Message;
+
+    fn name() -> &'static str {
+        P::name()
+    }
+}
+
+impl<'a, P> Parse<'a> for Metered<P>
+where
+    P: Parse<'a>,
+    P::Message: Flagged,
+{
+    fn parse(
+        &self,
+        input: &'a [u8],
+        direction: Direction,
+    ) -> Result<(&'a [u8], Option<Self::Message>)> {
+        let result = self.parser.parse(input, direction);
+        self.recorder.record::<P>(input, &result);
+        result
+    }
+}
+
+impl<'a, P> Probe<'a> for Metered<P>
+where
+    P: Probe<'a>,
+    P::Message: Flagged,
+{
+    fn probe(&self, input: &'a [u8], direction: Direction) -> Status {
+        self.parser.probe(input, direction)
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    struct Test;
+
+    struct Message(u64);
+
+    impl Flagged for Message {
+        fn error_flags(&self) -> u64 {
+            self.0
+        }
+    }
+
+    impl<'a> Protocol<'a> for Test {
+        type Message = Message;
+
+        fn name() -> &'static str {
+            "metrics_test"
+        }
+    }
+
+    impl<'a> Parse<'a> for Test {
+        fn parse(
+            &self,
+            input: &'a [u8],
+            _direction: Direction,
+        ) -> Result<(&'a [u8], Option<Self::Message>)> {
+            match input {
+                [] => Err(Error::incomplete_needed(1)),
+                [0xff, ..] => Err(Error::new(ErrorKind::InvalidData)),
+                [flags, rest @ ..] => Ok((rest, Some(Message(u64::from(*flags))))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_metered() {
+        let parser = Metered::new(Test);
+        assert!(parser.parse(b"", Direction::Unknown).is_err());
+        assert!(parser.parse(b"\xff", Direction::Unknown).is_err());
+        assert!(parser.parse(b"\x00rest", Direction::Unknown).is_ok());
+        assert!(parser.parse(b"\x05", Direction::Unknown).is_ok());
+
+        let snapshot = get("metrics_test").unwrap();
+        assert_eq!(snapshot.messages, 2);
+        assert_eq!(snapshot.incomplete, 1);
+        assert_eq!(snapshot.errors, 1);
+        assert_eq!(snapshot.invalid_data, 1);
+        assert_eq!(snapshot.flagged, 1);
+        assert_eq!(snapshot.error_flags[0], 1);
+        assert_eq!(snapshot.error_flags[1], 0);
+        assert_eq!(snapshot.error_flags[2], 1);
+        assert_eq!(snapshot.bytes, 2);
+        assert_eq!(get("unknown"), None);
+
+        reset();
+        assert_eq!(get("metrics_test"), Some(Snapshot::default()));
+        assert!(parser.parse(b"\x00", Direction::Unknown).is_ok());
+        let snapshot = get("metrics_test").unwrap();
+        assert_eq!(snapshot.messages, 1);
+        assert_eq!(snapshot.bytes, 1);
+    }
+}
@@ -31,6 +31,9 @@ pub trait Parse<'a>: Protocol<'a> {
     ///
     /// `Result::Err(ErrorKind::Incomplete(_))` must be used instead of `Result::Ok(None)`
     /// when no progress was made parsing the input.
+    ///
+    /// Parsers can be checked against these semantics with `sawp::testing`,
+    /// enabled by the `testing` feature.
     fn parse(
         &self,
         input: &'a [u8],
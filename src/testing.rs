@@ -0,0 +1,269 @@
+//! Checks of the [`Parse`] contract for parser tests.
+//!
+//! [`assert_incremental`] feeds a test vector to a parser as a stream: whole,
+//! in two chunks split at every offset and one byte at a time. Each call to
+//! `parse` must follow the contract of [`Parse::parse`]:
+//!
+//! - `Ok` consumes at least one byte and returns a suffix of its input.
+//! - `Err(Incomplete)` makes no progress, parsing restarts from the same
+//!   bytes once more data is available.
+//!
+//! The messages parsed, the number of bytes consumed and how the stream ended
+//! must be the same however the input is split. A parser returning another
+//! error or a different message on a truncated input fails the check.
+//!
+//! Datagram protocols consume whatever input they are given, so a truncated
+//! datagram is a different datagram. [`assert_datagram`] only checks each call
+//! on every prefix of the input instead.
+//!
+//! Enabled with the `testing` feature, usually as a dev-dependency.
+//!
+//! # Example
+//! ```
+//! use sawp::error::{Error, Result};
+//! use sawp::parser::{Direction, Parse};
+//! use sawp::protocol::Protocol;
+//! use sawp::testing::assert_incremental;
+//!
+//! /// Messages are lines ending with `\n`
+//! struct Lines;
+//!
+//! impl<'a> Protocol<'a> for Lines {
+//!     type Message = Vec<u8>;
+//!
+//!     fn name() -> &'static str {
+//!         "lines"
+//!     }
+//! }
+//!
+//! impl<'a> Parse<'a> for Lines {
+//!     fn parse(&self, input: &'a [u8], _: Direction) -> Result<(&'a [u8], Option<Vec<u8>>)> {
+//!         match input.iter().position(|&c| c == b'\n') {
+//!             Some(end) => Ok((&input[end + 1..], Some(input[..end].to_vec()))),
+//!             None => Err(Error::incomplete()),
+//!         }
+//!     }
+//! }
+//!
+//! assert_incremental(|| Lines, b"first\nsecond\nthi", Direction::Unknown);
+//! ```
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::parser::{Direction, Parse};
+use crate::protocol::Protocol;
+
+use std::fmt::Debug;
+use std::vec::Vec;
+
+/// Parses `input` once and panics if the result breaks the contract of
+/// [`Parse::parse`].
+pub fn check_parse<'a, P>(
+    parser: &P,
+    input: &'a [u8],
+    direction: Direction,
+) -> Result<(&'a [u8], Option<<P as Protocol<'a>>::Message>)>
+where
+    P: Parse<'a>,
+{
+    let result = parser.parse(input, direction);
+    if let Ok((rest, _)) = &result {
+        assert!(
+            rest.len() < input.len(),
+            "Ok must consume input: {} bytes left of {}",
+            rest.len(),
+            input.len()
+        );
+        // Parsers may return any empty slice once all the input is consumed
+        assert!(
+            rest.is_empty()
+                || core::ptr::eq(rest.as_ptr(), input[input.len() - rest.len()..].as_ptr()),
+            "Ok must return a suffix of its input"
+        );
+    }
+    result
+}
+
+/// How a stream of messages ended.
+#[derive(Debug, PartialEq)]
+pub enum End {
+    /// All the input was consumed
+    Exhausted,
+    /// More input is needed to parse the next message
+    Incomplete,
+    Error(ErrorKind),
+}
+
+/// Messages parsed from a stream.
+#[derive(Debug, PartialEq)]
+pub struct Stream<M> {
+    /// Messages along with the offset of the end of each one
+    pub messages: Vec<(usize, M)>,
+    /// Number of bytes consumed by the parser
+    pub consumed: usize,
+    pub end: End,
+}
+
+/// Parses `chunks` message by message as they arrive, checking every call
+/// with [`check_parse`]. Data left over after `Incomplete` is kept and the
+/// next chunk is appended to it.
+pub fn parse_stream<P, M>(parser: &P, chunks: &[&[u8]], direction: Direction) -> Stream<M>
+where
+    P: for<'a> Parse<'a> + for<'a> Protocol<'a, Message = M>,
+{
+    let mut chunks = chunks.iter();
+    let mut buffer = Vec::new();
+    let mut stream = Stream {
+        messages: Vec::new(),
+        consumed: 0,
+        end: End::Exhausted,
+    };
+
+    loop {
+        let input = &buffer[stream.consumed..];
+        let result = if input.is_empty() {
+            Err(Error::incomplete())
+        } else {
+            check_parse(parser, input, direction.clone())
+        };
+        match result {
+            Ok((rest, message)) => {
+                stream.consumed += input.len() - rest.len();
+                if let Some(message) = message {
+                    stream.messages.push((stream.consumed, message));
+                }
+            }
+            Err(Error {
+                kind: ErrorKind::Incomplete(_),
+            }) => match chunks.next() {
+                Some(chunk) => buffer.extend_from_slice(chunk),
+                None => {
+                    if !input.is_empty() {
+                        stream.end = End::Incomplete;
+                    }
+                    return stream;
+                }
+            },
+            Err(err) => {
+                stream.end = End::Error(err.kind);
+                return stream;
+            }
+        }
+    }
+}
+
+/// Panics unless `input` gives the same [`Stream`] when fed whole, split in
+/// two chunks at every offset and one byte at a time. Each stream uses a new
+/// parser from `new`. Returns the stream of the whole input.
+pub fn assert_incremental<P, M>(
+    new: impl Fn() -> P,
+    input: &[u8],
+    direction: Direction,
+) -> Stream<M>
+where
+    P: for<'a> Parse<'a> + for<'a> Protocol<'a, Message = M>,
+    M: Debug + PartialEq,
+{
+    let whole = parse_stream(&new(), &[input], direction.clone());
+
+    for split in 0..=input.len() {
+        let chunked = parse_stream(
+            &new(),
+            &[&input[..split], &input[split..]],
+            direction.clone(),
+        );
+        assert_eq!(whole, chunked, "input split at {}", split);
+    }
+
+    let bytes: Vec<&[u8]> = input.chunks(1).collect();
+    let chunked = parse_stream(&new(), &bytes, direction);
+    assert_eq!(whole, chunked, "input fed one byte at a time");
+
+    whole
+}
+
+/// Parses every prefix of `input` with [`check_parse`], as datagrams of
+/// their own.
+pub fn assert_datagram<'a, P>(parser: &P, input: &'a [u8], direction: Direction)
+where
+    P: Parse<'a>,
+{
+    for end in 0..=input.len() {
+        let mut rest = &input[..end];
+        while let Ok((next, _)) = check_parse(parser, rest, direction.clone()) {
+            rest = next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Messages are a length byte followed by the payload. If `lenient`, a
+    /// truncated payload is returned as is instead of waiting for more data.
+    struct Test {
+        lenient: bool,
+    }
+
+    impl<'a> Protocol<'a> for Test {
+        type Message = Vec<u8>;
+
+        fn name() -> &'static str {
+            "test"
+        }
+    }
+
+    impl<'a> Parse<'a> for Test {
+        fn parse(
+            &self,
+            input: &'a [u8],
+            _direction: Direction,
+        ) -> Result<(&'a [u8], Option<Vec<u8>>)> {
+            match input {
+                [] => Err(Error::incomplete_needed(1)),
+                [0xff, ..] => Err(Error::parse(None)),
+                [len, rest @ ..] if rest.len() < usize::from(*len) && self.lenient => {
+                    Ok((&[], Some(rest.to_vec())))
+                }
+                [len, rest @ ..] if rest.len() < usize::from(*len) => {
+                    Err(Error::incomplete_needed(usize::from(*len) - rest.len()))
+                }
+                [len, rest @ ..] => {
+                    let (payload, rest) = rest.split_at(usize::from(*len));
+                    Ok((rest, Some(payload.to_vec())))
+                }
+            }
+        }
+    }
+
+    #[rstest(
+        input,
+        expected,
+        expected_consumed,
+        case::empty(b"", End::Exhausted, 0),
+        case::messages(b"\x01a\x02bc", End::Exhausted, 5),
+        case::incomplete(b"\x01a\x02b", End::Incomplete, 2),
+        case::error(b"\x01a\xff", End::Error(ErrorKind::ParseError(None)), 2)
+    )]
+    fn test_assert_incremental(input: &[u8], expected: End, expected_consumed: usize) {
+        let stream = assert_incremental(|| Test { lenient: false }, input, Direction::Unknown);
+        assert_eq!(stream.end, expected);
+        assert_eq!(stream.consumed, expected_consumed);
+    }
+
+    #[test]
+    #[should_panic(expected = "input split at 1")]
+    fn test_assert_incremental_lenient() {
+        assert_incremental(
+            || Test { lenient: true },
+            b"\x01a\x02bc",
+            Direction::Unknown,
+        );
+    }
+
+    #[test]
+    fn test_assert_datagram() {
+        assert_datagram(&Test { lenient: true }, b"\x01a\x02bc", Direction::Unknown);
+    }
+}